@@ -0,0 +1,113 @@
+//! Derive macros for Rust-FNA3D. Use them through `fna3d` with the `derive` feature
+//!
+//! See `fna3d::vertex` for the usage.
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::{Span, TokenStream as TokenStream2},
+    quote::quote,
+    syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitInt, Result, Token},
+};
+
+/// Implements `fna3d::vertex::VertexLayout` from `#[vertex(Usage)]` or `#[vertex(Usage, index)]`
+/// field attributes
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    self::impl_vertex(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn impl_vertex(input: &DeriveInput) -> Result<TokenStream2> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "`Vertex` can't be derived for generic types",
+        ));
+    }
+
+    if !self::is_repr_c(input)? {
+        return Err(Error::new_spanned(
+            name,
+            "`Vertex` requires `#[repr(C)]` to keep the field order",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "`Vertex` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "`Vertex` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut elements = Vec::new();
+    for field in fields {
+        let attr = match field.attrs.iter().find(|a| a.path().is_ident("vertex")) {
+            Some(attr) => attr,
+            None => {
+                return Err(Error::new_spanned(
+                    field,
+                    "missing `#[vertex(Usage)]` or `#[vertex(Usage, index)]`",
+                ))
+            }
+        };
+
+        let (usage, index) = attr.parse_args_with(|input: syn::parse::ParseStream| {
+            let usage: Ident = input.parse()?;
+            let index = if input.parse::<Option<Token![,]>>()?.is_some() {
+                input.parse::<LitInt>()?
+            } else {
+                LitInt::new("0", Span::call_site())
+            };
+            Ok((usage, index))
+        })?;
+
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        elements.push(quote! {
+            ::fna3d::vertex::element(
+                ::core::mem::offset_of!(#name, #ident),
+                <#ty as ::fna3d::vertex::VertexAttribute>::FORMAT,
+                ::fna3d::VertexElementUsage::#usage,
+                #index,
+            )
+        });
+    }
+
+    Ok(quote! {
+        impl ::fna3d::vertex::VertexLayout for #name {
+            const ELEMENTS: &'static [::fna3d::VertexElement] = &[#(#elements),*];
+        }
+    })
+}
+
+fn is_repr_c(input: &DeriveInput) -> Result<bool> {
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            }
+            // skip the arguments of `align(N)` and `packed(N)`
+            if meta.input.peek(syn::token::Paren) {
+                let _ = meta.input.parse::<proc_macro2::Group>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
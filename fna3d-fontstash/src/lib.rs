@@ -5,10 +5,32 @@
 pub use fontstash::{self, FontStash};
 
 use {
+    fna3d::staging::StagingBelt,
     fontstash::FonsTextIter,
-    std::os::raw::{c_int, c_uchar, c_void},
+    std::{
+        fmt,
+        os::raw::{c_int, c_uchar, c_void},
+    },
 };
 
+/// Error of fontstash. Converts into [`fna3d::Error::Backend`]
+#[derive(Debug)]
+pub struct FontStashError(pub fontstash::ErrorCode);
+
+impl fmt::Display for FontStashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fontstash error: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for FontStashError {}
+
+impl From<FontStashError> for fna3d::Error {
+    fn from(e: FontStashError) -> Self {
+        fna3d::Error::backend(e)
+    }
+}
+
 /// The shared ownership of [`FontBookInternal`]
 ///
 /// It is required to use the internal variable so that the memory position is fixed.
@@ -39,6 +61,7 @@ impl FontBook {
             w,
             h,
             is_dirty: true,
+            staging: StagingBelt::new(),
         });
 
         let inner_ptr = inner.as_ref() as *const _ as *mut FontBookInternal;
@@ -82,6 +105,8 @@ pub struct FontBookInternal {
     h: u32,
     /// Shall we update the texture data?
     is_dirty: bool,
+    /// Reused for expanding the atlas to RGBA
+    staging: StagingBelt,
 }
 
 impl Drop for FontBookInternal {
@@ -112,8 +137,8 @@ impl FontBookInternal {
         self.stash.clone()
     }
 
-    pub fn text_iter(&mut self, text: &str) -> fontstash::Result<FonsTextIter> {
-        self.stash.text_iter(text)
+    pub fn text_iter(&mut self, text: &str) -> Result<FonsTextIter, FontStashError> {
+        self.stash.text_iter(text).map_err(FontStashError)
     }
 }
 
@@ -187,7 +212,7 @@ unsafe impl fontstash::Renderer for FontBookInternal {
 
 impl FontBookInternal {
     /// Updates GPU texure. Call it whenever drawing text
-    fn maybe_update_texture(&mut self) {
+    pub fn maybe_update_texture(&mut self) {
         if !self.is_dirty {
             // TODO: this looks very odd but works
             self.is_dirty = true;
@@ -195,25 +220,20 @@ impl FontBookInternal {
         }
         self.is_dirty = false;
 
+        let (device, texture, staging) = (&self.device, self.texture, &mut self.staging);
         self.stash.with_pixels(|pixels, w, h| {
-            let data = {
-                log::trace!("fontbook: [{}, {}] update GPU texture", w, h);
-
-                // FIXME: address boundary error
-                let area = (w * h) as usize;
-                // four channels (RGBA)
-                let mut data = Vec::<u8>::with_capacity(4 * area);
-                for i in 0..area {
-                    data.push(255);
-                    data.push(255);
-                    data.push(255);
-                    data.push(pixels[i]);
-                }
-                data
-            };
+            log::trace!("fontbook: [{}, {}] update GPU texture", w, h);
+
+            // FIXME: address boundary error
+            let area = (w * h) as usize;
+            // four channels (RGBA), white with premultiplied alpha
+            staging.clear();
+            staging.reserve(4 * area);
+            for &a in &pixels[0..area] {
+                staging.push(&[a, a, a, a]);
+            }
 
-            self.device
-                .set_texture_data_2d(self.texture, 0, 0, w, h, 0, &data);
+            device.set_texture_data_2d(texture, 0, 0, w, h, 0, staging.as_bytes());
 
             log::trace!("<after upload>");
         });
@@ -22,6 +22,14 @@ pub enum ImGuiRendererError {
 /// Result<T, ImGuiRendererError>
 pub type Result<T> = std::result::Result<T, ImGuiRendererError>;
 
+impl From<ImGuiRendererError> for fna3d::Error {
+    fn from(e: ImGuiRendererError) -> Self {
+        match e {
+            ImGuiRendererError::BadTexture(_) => fna3d::Error::invalid_data(e),
+        }
+    }
+}
+
 /// GPU texture with size
 pub struct TextureData2d {
     pub raw: *mut fna3d::Texture,
@@ -119,7 +127,7 @@ impl ImGuiRenderer {
         })
     }
 
-    /// Be warned that the font texture is  non-premultiplied alpha
+    /// Be warned that the font texture is non-premultiplied alpha (as vertex colors of ImGUI)
     fn load_font_texture(
         device: &fna3d::Device,
         mut fonts: imgui::FontAtlasRefMut,
@@ -159,17 +167,21 @@ impl ImGuiRenderer {
         &mut self.textures
     }
 
-    /// Be warned that the font texture is  non-premultiplied alpha
+    /// Be warned that the font texture is non-premultiplied alpha (as vertex colors of ImGUI)
     pub fn font_texture(&self) -> &TextureData2d {
         &self.font_texture.texture
     }
 
     /// Set render target to FNA3D device before/after calling this method
+    ///
+    /// ImGUI draws with straight alpha, so this method sets
+    /// [`fna3d::BlendState::non_premultiplied`] and then restores
+    /// [`fna3d::BlendState::premultiplied`], the convention of Rust-FNA3D.
     pub fn render(&mut self, draw_data: &imgui::DrawData, device: &fna3d::Device) -> Result<()> {
         // TODO: restore/restore previous state
         device.set_blend_state(&fna3d::BlendState::non_premultiplied());
         let res = self.render_impl(draw_data, device);
-        device.set_blend_state(&fna3d::BlendState::alpha_blend());
+        device.set_blend_state(&fna3d::BlendState::premultiplied());
         // SamplerState.LinearWrap;
         // DepthStencilState.None;
         // RasterizerState = RasterizerState.CullNone;
@@ -248,12 +260,12 @@ impl ImGuiRenderer {
                             };
 
                             // FIXME:
-                            let scissors_rect = fna3d::Rect {
-                                x: f32::max(0.0, clip_rect[0]).floor() as i32,
-                                y: f32::max(0.0, clip_rect[1]).floor() as i32,
-                                w: (clip_rect[2] - clip_rect[0]).abs().ceil() as i32,
-                                h: (clip_rect[3] - clip_rect[1]).abs().ceil() as i32,
-                            };
+                            let scissors_rect = fna3d::Rect::from([
+                                f32::max(0.0, clip_rect[0]).floor() as i32,
+                                f32::max(0.0, clip_rect[1]).floor() as i32,
+                                (clip_rect[2] - clip_rect[0]).abs().ceil() as i32,
+                                (clip_rect[3] - clip_rect[1]).abs().ceil() as i32,
+                            ]);
 
                             self.batch.prepare_draw(
                                 device,
@@ -370,7 +382,7 @@ impl Batch {
             vertexOffset: 0, // FIXME:
             instanceFrequency: 0,
         };
-        device.apply_vertex_buffer_bindings(&[bind], true, vtx_offset);
+        device.apply_vertex_buffer_bindings_auto(&[bind], vtx_offset);
     }
 }
 
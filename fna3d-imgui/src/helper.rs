@@ -2,7 +2,7 @@ use ::sdl2::{event::Event, video::Window};
 
 use crate::{
     fna3d_renderer::{ImGuiRenderer, RcTexture2d, TextureData2d},
-    sdl2_backend::ImguiSdl2,
+    sdl2_backend::{Composition, ImguiSdl2},
 };
 
 /// Just holds both ImGUI context and backend/renderer of it
@@ -62,6 +62,11 @@ impl Fna3dImgui {
         self.part.backend.handle_event(&mut self.icx, ev)
     }
 
+    /// IME composition in progress. See [`Composition`]
+    pub fn composition(&self) -> Option<&Composition> {
+        self.part.backend.composition()
+    }
+
     pub fn frame(
         &mut self,
         window: &impl AsRef<Window>,
@@ -15,6 +15,7 @@ mod sdl2_backend;
 pub use crate::{
     fna3d_renderer::{ImGuiRendererError, RcTexture2d, Result, TextureData2d},
     helper::Fna3dImgui,
+    sdl2_backend::Composition,
 };
 
 /// `SpriteEffect.fxb`
@@ -14,6 +14,19 @@ use ::{
     },
 };
 
+/// Text being composed with an IME, not committed yet
+///
+/// ImGUI can't show it, so draw it over the focused text field yourself (e.g. with
+/// `fna3d_toolkit::batch::Batcher::push_composition`, which uses the font book).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Composition {
+    pub text: String,
+    /// Caret position in characters
+    pub cursor: usize,
+    /// Number of characters selected after the caret (the clause being converted)
+    pub selection: usize,
+}
+
 /// SDL2 backend for ImGUI
 pub struct ImguiSdl2 {
     mouse_press: [bool; 5],
@@ -21,6 +34,8 @@ pub struct ImguiSdl2 {
     ignore_keyboard: bool,
     cursor: Option<MouseCursor>,
     sdl_cursor: Option<Cursor>,
+    /// From `TextEditing` events. Cleared when the text is committed (`TextInput`)
+    composition: Option<Composition>,
 }
 
 struct Sdl2ClipboardBackend(sdl2::clipboard::ClipboardUtil);
@@ -71,9 +86,15 @@ impl ImguiSdl2 {
             ignore_mouse: false,
             cursor: None,
             sdl_cursor: None,
+            composition: None,
         }
     }
 
+    /// IME composition in progress, if any
+    pub fn composition(&self) -> Option<&Composition> {
+        self.composition.as_ref()
+    }
+
     /// Return if the event is captured by ImGUI
     pub fn handle_event(&mut self, imgui: &mut Context, event: &Event) -> bool {
         use sdl2::keyboard;
@@ -108,7 +129,25 @@ impl ImguiSdl2 {
                     self.mouse_press[index] = true;
                 }
             }
+            Event::TextEditing {
+                ref text,
+                start,
+                length,
+                ..
+            } => {
+                self.composition = if text.is_empty() {
+                    None
+                } else {
+                    Some(Composition {
+                        text: text.clone(),
+                        cursor: start.max(0) as usize,
+                        selection: length.max(0) as usize,
+                    })
+                };
+            }
             Event::TextInput { ref text, .. } => {
+                // the composition is committed
+                self.composition = None;
                 for chr in text.chars() {
                     imgui.io_mut().add_input_character(chr);
                 }
@@ -15,7 +15,12 @@ fn main() {
     // FIXME: somehow reruns too often?
     self::compile();
     self::gen_bindings("wrappers/fna3d_wrapper.h", "fna3d_bindings.rs");
-    self::gen_bindings("wrappers/mojoshader_wrapper.h", "mojoshader_bindings.rs");
+    if env::var_os("CARGO_FEATURE_MOJOSHADER").is_some() {
+        self::gen_bindings("wrappers/mojoshader_wrapper.h", "mojoshader_bindings.rs");
+    }
+    if env::var_os("CARGO_FEATURE_RUSTIFIED_ENUMS").is_some() {
+        self::gen_rustified_enums("wrappers/fna3d_wrapper.h", "fna3d_rustified.rs");
+    }
 }
 
 /// Add `mojoshader_version.h` to `FNA3D/MojoShader`
@@ -91,3 +96,35 @@ fn gen_bindings(wrapper: impl AsRef<Path>, dst_file_name: impl AsRef<Path>) {
     // it's `ok` to fail conidering crates.io
     bindings.write_to_file(&dst).ok();
 }
+
+/// Generates FNA3D types with enums as `#[repr(u32)]` Rust enums (`rustified` module)
+///
+/// They're always generated into `OUT_DIR` (unlike the checked-in bindings) since the feature is
+/// opt-in.
+fn gen_rustified_enums(wrapper: impl AsRef<Path>, dst_file_name: impl AsRef<Path>) {
+    let wrapper = wrapper.as_ref();
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    let bindings = bindgen::Builder::default()
+        .header(format!("{}", wrapper.display()))
+        .derive_default(true)
+        .rustified_enum("FNA3D_.*")
+        // functions are in the crate root
+        .blacklist_function(".*")
+        .whitelist_type("FNA3D_.*")
+        .clang_arg(format!("-I{}", root.join("FNA3D/include").display()))
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks))
+        .generate()
+        .unwrap_or_else(|err| {
+            panic!(
+                "Unable to generate rustified enums for `{}`. Original error {:?}",
+                wrapper.display(),
+                err
+            )
+        });
+
+    bindings
+        .write_to_file(out_dir.join(dst_file_name.as_ref()))
+        .unwrap();
+}
@@ -0,0 +1,100 @@
+// Conversions and arithmetic of plain structs. They're defined here because of the orphan rule;
+// the constructors are in `fna3d`.
+
+impl From<(i32, i32, i32, i32)> for FNA3D_Rect {
+    fn from((x, y, w, h): (i32, i32, i32, i32)) -> Self {
+        Self { x, y, w, h }
+    }
+}
+
+impl From<[i32; 4]> for FNA3D_Rect {
+    fn from([x, y, w, h]: [i32; 4]) -> Self {
+        Self { x, y, w, h }
+    }
+}
+
+impl From<[f32; 4]> for FNA3D_Vec4 {
+    fn from([x, y, z, w]: [f32; 4]) -> Self {
+        Self { x, y, z, w }
+    }
+}
+
+impl From<(f32, f32, f32, f32)> for FNA3D_Vec4 {
+    fn from((x, y, z, w): (f32, f32, f32, f32)) -> Self {
+        Self { x, y, z, w }
+    }
+}
+
+impl From<FNA3D_Vec4> for [f32; 4] {
+    fn from(v: FNA3D_Vec4) -> Self {
+        [v.x, v.y, v.z, v.w]
+    }
+}
+
+impl PartialEq for FNA3D_Vec4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y && self.z == other.z && self.w == other.w
+    }
+}
+
+// Element-wise arithmetic of `FNA3D_Vec4`
+macro_rules! impl_vec4_ops {
+    ($($op:ident $f:ident $op_assign:ident $f_assign:ident),* $(,)?) => {
+        $(
+            impl core::ops::$op for FNA3D_Vec4 {
+                type Output = Self;
+                fn $f(self, rhs: Self) -> Self {
+                    Self {
+                        x: core::ops::$op::$f(self.x, rhs.x),
+                        y: core::ops::$op::$f(self.y, rhs.y),
+                        z: core::ops::$op::$f(self.z, rhs.z),
+                        w: core::ops::$op::$f(self.w, rhs.w),
+                    }
+                }
+            }
+
+            impl core::ops::$op<f32> for FNA3D_Vec4 {
+                type Output = Self;
+                fn $f(self, rhs: f32) -> Self {
+                    Self {
+                        x: core::ops::$op::$f(self.x, rhs),
+                        y: core::ops::$op::$f(self.y, rhs),
+                        z: core::ops::$op::$f(self.z, rhs),
+                        w: core::ops::$op::$f(self.w, rhs),
+                    }
+                }
+            }
+
+            impl core::ops::$op_assign for FNA3D_Vec4 {
+                fn $f_assign(&mut self, rhs: Self) {
+                    *self = core::ops::$op::$f(*self, rhs);
+                }
+            }
+
+            impl core::ops::$op_assign<f32> for FNA3D_Vec4 {
+                fn $f_assign(&mut self, rhs: f32) {
+                    *self = core::ops::$op::$f(*self, rhs);
+                }
+            }
+        )*
+    };
+}
+
+impl_vec4_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign,
+);
+
+impl core::ops::Neg for FNA3D_Vec4 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: -self.w,
+        }
+    }
+}
@@ -2,8 +2,9 @@
 
 WARNING: Probablly it doesn't compile on Window. I need to buy one.
 
-I'm using `bindgen` with  default settings, but if you're interested, see the API documentation
-of [`Builder`] to configure enum generation.
+I'm using `bindgen` with  default settings (enum variants are constants). Enable the
+`rustified-enums` feature to also get Rust enums in the [`rustified`] module. See the API
+documentation of [`Builder`] for the enum generation styles.
 
 [bindgen]:  https://github.com/rust-lang/rust-bindgen
 [`Builder`]: https://docs.rs/bindgen/newest/bindgen/struct.Builder.html
@@ -14,14 +15,30 @@ of [`Builder`] to configure enum generation.
 
 // Include generated bindings
 include!("ffi/fna3d_bindings.rs");
+include!("conv.rs");
 
+#[cfg(feature = "mojoshader")]
 pub mod mojo {
-    //! MojoShader types
+    //! MojoShader types (`mojoshader` feature, enabled by default)
     //!
     //! These types are concrete while mojoshader types under `crate::` don't tell the field types.
+    //! Use them instead of the opaque ones.
     include!("ffi/mojoshader_bindings.rs");
 }
 
+#[cfg(feature = "rustified-enums")]
+pub mod rustified {
+    //! FNA3D types with enums generated as Rust enums (`rustified-enums` feature)
+    //!
+    //! Enums are `#[repr(u32)]` and have the same discriminants as the constants in the crate
+    //! root. The `fna3d` enums convert from/to them with `From`. Structs are
+    //! layout-compatible with the ones in the crate root, but their enum fields are typed.
+    //!
+    //! NOTE: Rust enums must only hold declared values. Don't use them for values from unknown
+    //! sources.
+    include!(concat!(env!("OUT_DIR"), "/fna3d_rustified.rs"));
+}
+
 #[cfg(test)]
 mod test {
     /// Makes sure we can link to FNA3D. Fails if we can't
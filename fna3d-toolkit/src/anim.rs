@@ -0,0 +1,181 @@
+//! Sprite animation
+//!
+//! ```ignore
+//! let frames = texture.full_region().split_grid(4, 1);
+//! let mut anim = Animation::uniform(frames, Duration::from_millis(100), LoopMode::Loop);
+//!
+//! // update
+//! anim.update(time.dt);
+//! // render
+//! batcher.push_animation(&anim, [x, y], Color::white());
+//! ```
+
+use std::time::Duration;
+
+use crate::gfx::TextureRegion;
+
+/// How an [`Animation`] behaves after the last frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Stops at the last frame
+    Once,
+    /// Goes back to the first frame
+    Loop,
+    /// Plays backwards, then forwards again (the first and last frames are not repeated)
+    PingPong,
+}
+
+/// Frames of [`TextureRegion`]s with durations
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<TextureRegion>,
+    durations: Vec<Duration>,
+    mode: LoopMode,
+    /// Time elapsed in the current cycle
+    elapsed: Duration,
+    is_finished: bool,
+}
+
+impl Animation {
+    /// `frames.len()` must be equal to `durations.len()` and not zero
+    pub fn new(frames: Vec<TextureRegion>, durations: Vec<Duration>, mode: LoopMode) -> Self {
+        assert!(!frames.is_empty(), "animation without frames");
+        assert_eq!(frames.len(), durations.len());
+
+        Self {
+            frames,
+            durations,
+            mode,
+            elapsed: Duration::default(),
+            is_finished: false,
+        }
+    }
+
+    /// Every frame has the same duration
+    pub fn uniform(frames: Vec<TextureRegion>, frame_duration: Duration, mode: LoopMode) -> Self {
+        let durations = vec![frame_duration; frames.len()];
+        Self::new(frames, durations, mode)
+    }
+
+    pub fn mode(&self) -> LoopMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: LoopMode) {
+        self.mode = mode;
+        self.is_finished = false;
+    }
+
+    /// Only [`LoopMode::Once`] animations finish
+    pub fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+
+    /// Rewinds to the first frame
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::default();
+        self.is_finished = false;
+    }
+
+    /// Advances the animation by `dt`
+    pub fn update(&mut self, dt: Duration) {
+        if self.is_finished {
+            return;
+        }
+
+        self.elapsed += dt;
+
+        let cycle = self.cycle_duration();
+        if cycle == Duration::default() {
+            return;
+        }
+
+        if self.elapsed >= cycle {
+            match self.mode {
+                LoopMode::Once => {
+                    self.elapsed = cycle;
+                    self.is_finished = true;
+                }
+                LoopMode::Loop | LoopMode::PingPong => {
+                    let nanos = self.elapsed.as_nanos() % cycle.as_nanos();
+                    self.elapsed = Duration::from_nanos(nanos as u64);
+                }
+            }
+        }
+    }
+
+    pub fn current_frame(&self) -> &TextureRegion {
+        &self.frames[self.current_index()]
+    }
+
+    /// Index of the current frame
+    pub fn current_index(&self) -> usize {
+        let n = self.frames.len();
+        if self.is_finished {
+            return n - 1;
+        }
+
+        // walk the frames of the cycle (forward, then backward for ping-pong)
+        let mut t = self.elapsed;
+        for i in self.cycle_indices() {
+            if t < self.durations[i] {
+                return i;
+            }
+            t -= self.durations[i];
+        }
+
+        n - 1
+    }
+
+    /// Frame indices in one cycle
+    fn cycle_indices(&self) -> impl Iterator<Item = usize> {
+        let n = self.frames.len();
+        let back = if self.mode == LoopMode::PingPong && n > 2 {
+            (1..n - 1).rev()
+        } else {
+            // empty
+            (0..0).rev()
+        };
+        (0..n).chain(back)
+    }
+
+    fn cycle_duration(&self) -> Duration {
+        self.cycle_indices().map(|i| self.durations[i]).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn anim(n: usize, mode: LoopMode) -> Animation {
+        let frames = (0..n)
+            .map(|_| TextureRegion::new(std::ptr::null_mut(), [0.0; 4], [1.0, 1.0]))
+            .collect();
+        Animation::uniform(frames, Duration::from_millis(100), mode)
+    }
+
+    fn step(anim: &mut Animation, ms: u64) -> usize {
+        anim.update(Duration::from_millis(ms));
+        anim.current_index()
+    }
+
+    #[test]
+    fn test_loop_modes() {
+        let mut once = anim(3, LoopMode::Once);
+        assert_eq!(once.current_index(), 0);
+        assert_eq!(step(&mut once, 150), 1);
+        assert_eq!(step(&mut once, 1000), 2);
+        assert!(once.is_finished());
+
+        let mut looping = anim(3, LoopMode::Loop);
+        assert_eq!(step(&mut looping, 250), 2);
+        assert_eq!(step(&mut looping, 100), 0);
+
+        // 0 1 2 1 0 1 ..
+        let mut ping_pong = anim(3, LoopMode::PingPong);
+        assert_eq!(step(&mut ping_pong, 250), 2);
+        assert_eq!(step(&mut ping_pong, 100), 1);
+        assert_eq!(step(&mut ping_pong, 100), 0);
+    }
+}
@@ -0,0 +1,276 @@
+//! Texture atlas packing, offline baking and runtime loading
+//!
+//! The same packer is used by asset pipelines and at runtime. Bake a directory of PNG files in a
+//! build script (with the `bake` feature):
+//!
+//! ```ignore
+//! // build.rs
+//! fna3d_toolkit::atlas::bake_dir("assets/sprites", "assets/sprites.png", "assets/sprites.json", 1024, 1)?;
+//! ```
+//!
+//! and load the regions at runtime:
+//!
+//! ```ignore
+//! let texture = Texture2dDrop::from_encoded_bytes(&device, include_bytes!("sprites.png"));
+//! let meta = AtlasMeta::from_json(include_str!("sprites.json"))?;
+//! let regions = meta.regions(texture.raw);
+//! batcher.push_region(&regions["player"], [x, y, 32.0, 32.0], Color::WHITE);
+//! ```
+
+use {
+    anyhow::{anyhow, Result},
+    serde::{Deserialize, Serialize},
+    std::collections::HashMap,
+};
+
+use crate::gfx::TextureRegion;
+
+/// Named rectangle in an atlas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AtlasEntry {
+    pub name: String,
+    /// `[x, y, w, h]` in pixels
+    pub rect: [u32; 4],
+}
+
+/// Metadata of a baked atlas, saved as JSON next to the atlas image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AtlasMeta {
+    /// `[w, h]` of the atlas image
+    pub size: [u32; 2],
+    pub entries: Vec<AtlasEntry>,
+}
+
+impl AtlasMeta {
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AtlasEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Region of the entry in the atlas texture
+    pub fn region(&self, texture: *mut fna3d::Texture, entry: &AtlasEntry) -> TextureRegion {
+        let [x, y, w, h] = entry.rect;
+        let (aw, ah) = (self.size[0] as f32, self.size[1] as f32);
+        TextureRegion::new(
+            texture,
+            [x as f32 / aw, y as f32 / ah, w as f32 / aw, h as f32 / ah],
+            [w as f32, h as f32],
+        )
+    }
+
+    /// Regions of all the entries by name. `texture` is the loaded atlas image
+    pub fn regions(&self, texture: *mut fna3d::Texture) -> HashMap<String, TextureRegion> {
+        self.entries
+            .iter()
+            .map(|e| (e.name.clone(), self.region(texture, e)))
+            .collect()
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Packing
+
+/// Result of [`pack`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packing {
+    /// `[w, h]` of the atlas
+    pub size: [u32; 2],
+    /// `[x, y, w, h]` of each input, in the input order
+    pub rects: Vec<[u32; 4]>,
+}
+
+/// Packs rectangles of `[w, h]` into shelves of at most `max_width` pixels wide
+///
+/// Rectangles are placed from the tallest, with `padding` pixels between them. Returns `None` if
+/// a rectangle is wider than `max_width`.
+pub fn pack(sizes: &[[u32; 2]], max_width: u32, padding: u32) -> Option<Packing> {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    // stable, so that equal heights keep the input order
+    order.sort_by_key(|i| std::cmp::Reverse(sizes[*i][1]));
+
+    let mut rects = vec![[0; 4]; sizes.len()];
+    let (mut x, mut y, mut shelf_h, mut w_used) = (0, 0, 0, 0);
+    for i in order {
+        let [w, h] = sizes[i];
+        if w > max_width {
+            return None;
+        }
+
+        if x > 0 && x + w > max_width {
+            // next shelf
+            y += shelf_h + padding;
+            x = 0;
+            shelf_h = 0;
+        }
+
+        rects[i] = [x, y, w, h];
+        w_used = w_used.max(x + w);
+        shelf_h = shelf_h.max(h);
+        x += w + padding;
+    }
+
+    Some(Packing {
+        size: [w_used, y + shelf_h],
+        rects,
+    })
+}
+
+/// RGBA8 image to bake into an atlas
+#[derive(Debug, Clone)]
+pub struct AtlasImage {
+    pub name: String,
+    pub w: u32,
+    pub h: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Packs the images and copies them into one RGBA8 image. Returns the metadata and the pixels
+pub fn bake(images: &[AtlasImage], max_width: u32, padding: u32) -> Result<(AtlasMeta, Vec<u8>)> {
+    for img in images {
+        if img.pixels.len() != img.w as usize * img.h as usize * 4 {
+            return Err(anyhow!("`{}` is not {}x{} RGBA8", img.name, img.w, img.h));
+        }
+    }
+
+    let sizes = images.iter().map(|i| [i.w, i.h]).collect::<Vec<_>>();
+    let packing = self::pack(&sizes, max_width, padding)
+        .ok_or_else(|| anyhow!("an image is wider than the atlas ({} px)", max_width))?;
+
+    let [aw, ah] = packing.size;
+    let mut pixels = vec![0; aw as usize * ah as usize * 4];
+    for (img, rect) in images.iter().zip(packing.rects.iter()) {
+        let row_len = img.w as usize * 4;
+        for (row, src) in img.pixels.chunks_exact(row_len.max(1)).enumerate() {
+            let start = ((rect[1] as usize + row) * aw as usize + rect[0] as usize) * 4;
+            pixels[start..start + row_len].copy_from_slice(src);
+        }
+    }
+
+    let entries = images
+        .iter()
+        .zip(packing.rects)
+        .map(|(img, rect)| AtlasEntry {
+            name: img.name.clone(),
+            rect,
+        })
+        .collect();
+
+    Ok((
+        AtlasMeta {
+            size: packing.size,
+            entries,
+        },
+        pixels,
+    ))
+}
+
+/// Bakes the PNG files in `dir` into `out_png` and `out_meta` (JSON). Entries are named by the
+/// file stems
+///
+/// Pure Rust, so it can run in build scripts. The pixels are not premultiplied;
+/// [`crate::gfx::Texture2dDrop::from_encoded_bytes`] does it on load.
+#[cfg(feature = "bake")]
+pub fn bake_dir(
+    dir: impl AsRef<std::path::Path>,
+    out_png: impl AsRef<std::path::Path>,
+    out_meta: impl AsRef<std::path::Path>,
+    max_width: u32,
+    padding: u32,
+) -> Result<AtlasMeta> {
+    use std::fs;
+
+    let mut paths = fs::read_dir(dir.as_ref())?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")));
+    paths.sort();
+
+    let images = paths
+        .iter()
+        .map(|p| {
+            let (w, h, pixels) = self::decode_png(&fs::read(p)?)
+                .map_err(|e| anyhow!("failed to decode {}: {}", p.display(), e))?;
+            let name = p.file_stem().unwrap().to_string_lossy().into_owned();
+            Ok(AtlasImage { name, w, h, pixels })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (meta, pixels) = self::bake(&images, max_width, padding)?;
+
+    let file = std::io::BufWriter::new(fs::File::create(out_png)?);
+    let mut encoder = png::Encoder::new(file, meta.size[0], meta.size[1]);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    fs::write(out_meta, meta.to_json())?;
+    Ok(meta)
+}
+
+/// Decodes a PNG file into RGBA8 pixels
+#[cfg(feature = "bake")]
+fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+
+    let n_pixels = info.width as usize * info.height as usize;
+    let buf = &buf[..info.buffer_size()];
+    let n_channels = buf.len() / n_pixels.max(1);
+
+    let mut pixels = Vec::with_capacity(n_pixels * 4);
+    for px in buf.chunks_exact(n_channels.max(1)) {
+        let rgba = match *px {
+            [l] => [l, l, l, 255],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => return Err(anyhow!("unexpected number of channels: {}", n_channels)),
+        };
+        pixels.extend_from_slice(&rgba);
+    }
+
+    Ok((info.width, info.height, pixels))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_and_bake() {
+        let p = pack(&[[4, 2], [3, 5], [4, 4]], 8, 1).unwrap();
+        assert_eq!(p.rects, vec![[0, 6, 4, 2], [0, 0, 3, 5], [4, 0, 4, 4]]);
+        assert_eq!(p.size, [8, 8]);
+        assert!(pack(&[[9, 1]], 8, 0).is_none());
+
+        let img = |name: &str, w, h, v| AtlasImage {
+            name: name.to_string(),
+            w,
+            h,
+            pixels: vec![v; (w * h * 4) as usize],
+        };
+        let (meta, pixels) = bake(&[img("a", 1, 1, 10), img("b", 2, 2, 20)], 3, 0).unwrap();
+        assert_eq!(meta.size, [3, 2]);
+        assert_eq!(meta.get("a").unwrap().rect, [2, 0, 1, 1]);
+        // second row: `b`, then empty
+        assert_eq!(
+            pixels[3 * 4..],
+            [20, 20, 20, 20, 20, 20, 20, 20, 0, 0, 0, 0]
+        );
+
+        let meta = AtlasMeta::from_json(&meta.to_json()).unwrap();
+        let b = meta.regions(std::ptr::null_mut())["b"];
+        assert_eq!(b.uv_rect, [0.0, 0.0, 2.0 / 3.0, 1.0]);
+        assert_eq!(b.size, [2.0, 2.0]);
+    }
+}
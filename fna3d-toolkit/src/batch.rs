@@ -0,0 +1,700 @@
+//! Quad-based draw call batcher
+//!
+//! Based on FNA's `SpriteBatch`. You would want to make some wrapper that provides a fluent API.
+//!
+//! Like `SpriteBatch`, it draws with premultiplied alpha: textures are premultiplied on load
+//! ([`crate::gfx::Texture2dDrop::from_encoded_bytes`]) and translucent colors should be made with
+//! [`Color::from_non_premultiplied`].
+//!
+//! # Overdraw visualization
+//!
+//! [`Batcher::set_overdraw`] draws every quad as a constant color with additive blending
+//! ([`Pipeline::overdraw`]), without changing the code pushing quads. Each layer adds
+//! [`OVERDRAW_COLOR`], so pixels go from black to red, yellow and white as they are drawn more
+//! often. Clear the screen to black in this mode.
+//!
+//! # Clipping
+//!
+//! [`Batcher::push_clip`] clips following quads to a rectangle (e.g. a scrollable UI panel) with
+//! the scissor test. Clips nest: each one is intersected with the current one (see
+//! [`crate::clip`]). Push and pop clips and pipelines in nested order, since the first clip pushes
+//! a pipeline enabling the scissor test.
+
+use {
+    anyhow::Result,
+    fna3d::{vertex::VertexLayout, Color},
+    fna3d_fontstash::FontBook,
+    std::mem,
+};
+
+use crate::{
+    anim::Animation,
+    camera::Camera2d,
+    clip::{self, ClipStack},
+    gfx::{Shader2d, TextureRegion, Vertex},
+    pipeline::{Pipeline, PipelineStack},
+};
+
+/// RGBA added per layer in overdraw mode. Red saturates after 8 layers, green after 32
+pub const OVERDRAW_COLOR: [u8; 4] = [32, 8, 2, 255];
+
+#[derive(Debug, Clone, Default)]
+pub struct QuadData(pub [Vertex; 4]);
+
+/// Corner colors of a quad: top-left, top-right, bottom-left and bottom-right
+///
+/// The GPU interpolates colors between the corners, so gradients need no custom shader.
+pub type QuadColors = [Color; 4];
+
+/// Corner colors fading from `left` to `right`
+pub fn horizontal_gradient(left: Color, right: Color) -> QuadColors {
+    [left, right, left, right]
+}
+
+/// Corner colors fading from `top` to `bottom`
+pub fn vertical_gradient(top: Color, bottom: Color) -> QuadColors {
+    [top, top, bottom, bottom]
+}
+
+impl QuadData {
+    /// Creates a quad from destination and UV rectangles (`[x, y, w, h]`)
+    pub fn from_rects(dst: [f32; 4], uv: [f32; 4], color: Color) -> Self {
+        Self::from_rects_colored(dst, uv, [color; 4])
+    }
+
+    /// [`QuadData::from_rects`] with a color per corner
+    pub fn from_rects_colored(dst: [f32; 4], uv: [f32; 4], colors: QuadColors) -> Self {
+        let [x, y, w, h] = dst;
+        let [u, v, uw, vh] = uv;
+        let [tl, tr, bl, br] = colors;
+
+        Self([
+            Vertex::new([x, y, 0.0], [u, v], tl),
+            Vertex::new([x + w, y, 0.0], [u + uw, v], tr),
+            Vertex::new([x, y + h, 0.0], [u, v + vh], bl),
+            Vertex::new([x + w, y + h, 0.0], [u + uw, v + vh], br),
+        ])
+    }
+
+    pub fn colors(&self) -> QuadColors {
+        [
+            self.0[0].color,
+            self.0[1].color,
+            self.0[2].color,
+            self.0[3].color,
+        ]
+    }
+
+    pub fn set_colors(&mut self, colors: QuadColors) {
+        for (v, c) in self.0.iter_mut().zip(colors.iter()) {
+            v.color = *c;
+        }
+    }
+}
+
+/// Creates an iterator of draw calls.
+///
+/// It's wrapped into [`Batcher`] so that it can be flushed running draw calls.
+#[derive(Debug)]
+struct Batch {
+    device: fna3d::Device,
+    vbuf: *mut fna3d::Buffer,
+    vbind: fna3d::VertexBufferBinding,
+    ibuf: *mut fna3d::Buffer,
+    quads: Vec<QuadData>,
+    /// The number of quads stored in this batch
+    n_quads: usize,
+    /// `track.len() == quads.len()`
+    track: Vec<*mut fna3d::Texture>,
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        self.device.add_dispose_vertex_buffer(self.vbuf);
+        self.device.add_dispose_index_buffer(self.ibuf);
+    }
+}
+
+/// Creates index buffer for quadliterals
+///
+/// Each index element has 16 bits length
+macro_rules! gen_quad_indices {
+    ( $n_quads:expr ) => {{
+        let mut indices = [0; 6 * $n_quads as usize];
+
+        for q in 0..$n_quads as i16 {
+            let (i, v) = (q * 6, q * 4);
+            indices[i as usize] = v as i16;
+            indices[(i + 1) as usize] = v + 1 as i16;
+            indices[(i + 2) as usize] = v + 2 as i16;
+            indices[(i + 3) as usize] = v + 3 as i16;
+            indices[(i + 4) as usize] = v + 2 as i16;
+            indices[(i + 5) as usize] = v + 1 as i16;
+        }
+
+        indices
+    }};
+}
+
+impl Batch {
+    pub fn new(device: &fna3d::Device) -> Result<Self> {
+        // number of quads to allocate
+        const N_QUADS: u32 = 2048;
+
+        // GPU vertex buffer (marked as "dynamic")
+        let n_verts = 4 * N_QUADS;
+        let vbuf = device.gen_vertex_buffer(
+            true, // dynamic
+            fna3d::BufferUsage::WriteOnly,
+            n_verts * mem::size_of::<Vertex>() as u32,
+        );
+
+        // GPU index buffer (marked as "static")
+        let n_indices = 6 * N_QUADS;
+        // each index element has 16 bits length
+        let ibuf = device.gen_index_buffer(false, fna3d::BufferUsage::WriteOnly, n_indices * 16);
+        device.set_index_buffer_data(
+            ibuf,
+            0, // offset (in bytes)
+            &gen_quad_indices!(N_QUADS),
+            fna3d::SetDataOptions::None,
+        );
+
+        // vertex attributes
+        // META: such types are just re-exported from FFI to FNA3D and don't have snake_case fields
+        let vbind = fna3d::VertexBufferBinding {
+            vertexBuffer: vbuf,
+            vertexDeclaration: Vertex::DECLARATION,
+            vertexOffset: 0,
+            instanceFrequency: 0,
+        };
+
+        let quads = vec![QuadData::default(); N_QUADS as usize];
+        let track = vec![std::ptr::null_mut(); N_QUADS as usize];
+
+        Ok(Self {
+            device: device.clone(),
+            vbuf,
+            vbind,
+            ibuf,
+            quads,
+            n_quads: 0,
+            track,
+        })
+    }
+
+    pub fn next_quad_mut(&mut self) -> &mut QuadData {
+        let quad = &mut self.quads[self.n_quads];
+        self.n_quads += 1;
+        quad
+    }
+
+    /// Make sure the [`Batch`] is not yet satured before calling this method
+    pub fn push_quad(&mut self, quad: &QuadData, tex: *mut fna3d::Texture) {
+        self.quads[self.n_quads] = quad.clone();
+        self.track[self.n_quads] = tex;
+        self.n_quads += 1;
+    }
+
+    pub fn draw_calls(&self) -> DrawCallIterator<'_> {
+        DrawCallIterator::from_batch(self)
+    }
+}
+
+/// Quad span [lo, hi) with texture
+#[derive(Debug)]
+pub struct DrawCall {
+    pub tex: *mut fna3d::Texture,
+    /// low quad index (inclusive)
+    pub lo: usize,
+    /// high quad index (exclusive)
+    pub hi: usize,
+}
+
+impl DrawCall {
+    pub fn n_quads(&self) -> usize {
+        self.hi - self.lo
+    }
+
+    pub fn n_verts(&self) -> usize {
+        4 * self.n_quads()
+    }
+
+    pub fn n_indices(&self) -> usize {
+        6 * self.n_quads()
+    }
+
+    pub fn n_triangles(&self) -> usize {
+        2 * self.n_quads()
+    }
+
+    pub fn base_vtx(&self) -> usize {
+        4 * self.lo
+    }
+
+    pub fn base_idx(&self) -> usize {
+        6 * self.lo
+    }
+}
+
+pub struct DrawCallIterator<'a> {
+    /// Source
+    batch: &'a Batch,
+    /// Index of next quad
+    ix: usize,
+}
+
+impl<'a> DrawCallIterator<'a> {
+    fn from_batch(batch: &'a Batch) -> Self {
+        Self { batch, ix: 0 }
+    }
+}
+
+impl<'a> Iterator for DrawCallIterator<'a> {
+    type Item = DrawCall;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ix >= self.batch.n_quads {
+            return None;
+        }
+
+        let lo = self.ix;
+        let tex = self.batch.track[lo];
+
+        for hi in lo..self.batch.n_quads {
+            let new_tex = self.batch.track[hi];
+            if new_tex != tex {
+                self.ix = hi;
+                return Some(DrawCall { lo, hi, tex });
+            }
+        }
+
+        let hi = self.batch.n_quads;
+        self.ix = hi;
+        Some(DrawCall { lo, hi, tex })
+    }
+}
+
+/// Batches draw calls
+pub struct Batcher {
+    batch: Batch,
+    shader: Shader2d,
+    sampler: fna3d::SamplerState,
+    pipelines: PipelineStack,
+    /// White texture used in overdraw mode (owned by the device)
+    overdraw: Option<*mut fna3d::Texture>,
+    clips: ClipStack,
+}
+
+impl Batcher {
+    pub fn new(device: &fna3d::Device, shader: Shader2d) -> Result<Self> {
+        Ok(Self {
+            batch: Batch::new(device)?,
+            shader,
+            sampler: fna3d::SamplerState::default(),
+            pipelines: PipelineStack::new(Pipeline::default()),
+            overdraw: None,
+            clips: ClipStack::new(),
+        })
+    }
+
+    pub fn shader(&self) -> &Shader2d {
+        &self.shader
+    }
+
+    /// Sets the sampler state for following quads (sampler slot `0`). Flushes the batch
+    ///
+    /// The sampler state of the current [`Pipeline`] is used instead if it has one.
+    pub fn set_sampler(&mut self, sampler: fna3d::SamplerState) {
+        self.flush();
+        self.sampler = sampler;
+    }
+
+    pub fn pipelines(&self) -> &PipelineStack {
+        &self.pipelines
+    }
+
+    /// Applies the render states for following quads. Flushes the batch
+    pub fn push_pipeline(&mut self, pipeline: Pipeline) {
+        self.flush();
+        self.pipelines.push(&self.batch.device, pipeline);
+    }
+
+    /// Restores the previous render states. Flushes the batch
+    pub fn pop_pipeline(&mut self) -> Option<Pipeline> {
+        self.flush();
+        self.pipelines.pop(&self.batch.device)
+    }
+
+    pub fn is_overdraw(&self) -> bool {
+        self.overdraw.is_some()
+    }
+
+    /// Toggles overdraw visualization (see the module document). Flushes the batch
+    pub fn set_overdraw(&mut self, enable: bool) {
+        if enable == self.is_overdraw() {
+            return;
+        }
+
+        if enable {
+            self.push_pipeline(Pipeline::overdraw());
+            self.overdraw = Some(self.batch.device.defaults().white_texture());
+        } else {
+            self.pop_pipeline();
+            self.overdraw = None;
+        }
+    }
+
+    /// Current clip rectangle (`[x, y, w, h]` in render target pixels)
+    pub fn clip(&self) -> Option<[f32; 4]> {
+        self.clips.current()
+    }
+
+    /// Clips following quads to `rect` (`[x, y, w, h]` in render target pixels) intersected with
+    /// the current clip rectangle. Flushes the batch
+    pub fn push_clip(&mut self, rect: [f32; 4]) {
+        self.flush();
+
+        if self.clips.is_empty() {
+            let mut pipeline = self.pipelines.current().clone();
+            pipeline.rasterizer.set_is_scissor_test_enabled(true);
+            self.pipelines.push(&self.batch.device, pipeline);
+        }
+
+        let rect = self.clips.push(rect);
+        self.batch.device.set_scissor_rect(&clip::to_scissor(rect));
+    }
+
+    /// Restores the previous clip rectangle. Flushes the batch
+    ///
+    /// Returns the popped rectangle (intersected with the ones below it), or `None` if no clip was
+    /// pushed.
+    pub fn pop_clip(&mut self) -> Option<[f32; 4]> {
+        if self.clips.is_empty() {
+            return None;
+        }
+        self.flush();
+
+        let popped = self.clips.pop();
+        match self.clips.current() {
+            Some(rect) => self.batch.device.set_scissor_rect(&clip::to_scissor(rect)),
+            None => {
+                self.pipelines.pop(&self.batch.device);
+            }
+        }
+        popped
+    }
+
+    /// Sets the camera for following quads. Flushes the batch
+    pub fn set_camera(&mut self, camera: &Camera2d, w: u32, h: u32) -> Result<()> {
+        self.flush();
+        self.shader.set_camera(camera, w, h)
+    }
+
+    pub fn next_quad_mut(&mut self) -> &mut QuadData {
+        self.flush_if_satured();
+
+        self.batch.next_quad_mut()
+    }
+
+    pub fn push_quad(&mut self, quad: &QuadData, tex: *mut fna3d::Texture) {
+        self.flush_if_satured();
+
+        self.batch.push_quad(quad, tex);
+    }
+
+    /// Pushes a texture region stretched to `dst` (`[x, y, w, h]` in pixels)
+    pub fn push_region(&mut self, region: &TextureRegion, dst: [f32; 4], color: Color) {
+        let quad = QuadData::from_rects(dst, region.uv_rect, color);
+        self.push_quad(&quad, region.texture);
+    }
+
+    /// [`Batcher::push_region`] with a color per corner (see [`QuadColors`])
+    ///
+    /// Colors multiply the texture, so use a white region for plain gradient fills.
+    pub fn push_region_colored(
+        &mut self,
+        region: &TextureRegion,
+        dst: [f32; 4],
+        colors: QuadColors,
+    ) {
+        let quad = QuadData::from_rects_colored(dst, region.uv_rect, colors);
+        self.push_quad(&quad, region.texture);
+    }
+
+    /// Pushes the current frame of the animation at its pixel size
+    pub fn push_animation(&mut self, anim: &Animation, pos: [f32; 2], color: Color) {
+        let frame = anim.current_frame();
+        let [w, h] = frame.size;
+        self.push_region(frame, [pos[0], pos[1], w, h], color);
+    }
+
+    /// Pushes a scalable panel split into (up to) nine quads
+    ///
+    /// * `rect`: destination `[x, y, w, h]` in pixels
+    /// * `margins`: `[left, right, top, bottom]` in texture pixels; corners are drawn unscaled
+    pub fn push_nine_slice(&mut self, region: &TextureRegion, rect: [f32; 4], margins: [f32; 4]) {
+        let [tw, th] = region.size;
+        let [x, y, w, h] = rect;
+        let [l, r, t, b] = margins;
+
+        // shrink the margins if the destination is smaller than them
+        let sx = if l + r > w { w / (l + r) } else { 1.0 };
+        let sy = if t + b > h { h / (t + b) } else { 1.0 };
+
+        // (position, size) of the columns/rows in pixels and in texture pixels
+        let cols = [
+            (x, l * sx, 0.0, l),
+            (x + l * sx, w - (l + r) * sx, l, tw - l - r),
+            (x + w - r * sx, r * sx, tw - r, r),
+        ];
+        let rows = [
+            (y, t * sy, 0.0, t),
+            (y + t * sy, h - (t + b) * sy, t, th - t - b),
+            (y + h - b * sy, b * sy, th - b, b),
+        ];
+
+        for &(dy, dh, src_y, src_h) in rows.iter() {
+            for &(dx, dw, src_x, src_w) in cols.iter() {
+                if dw <= 0.0 || dh <= 0.0 {
+                    continue;
+                }
+                let src = region.sub_region([src_x, src_y, src_w, src_h]);
+                self.push_region(&src, [dx, dy, dw, dh], Color::white());
+            }
+        }
+    }
+
+    /// Fills `rect` (`[x, y, w, h]`) by repeating the texture region
+    ///
+    /// Each tile is `region size / uv_scale` pixels. Tiles are emitted as separate quads, clipping
+    /// the last column/row, so it works without a wrapping sampler (and with atlas regions).
+    pub fn push_tiled(&mut self, region: &TextureRegion, rect: [f32; 4], uv_scale: [f32; 2]) {
+        let [x, y, w, h] = rect;
+        let tile_w = region.size[0] / uv_scale[0];
+        let tile_h = region.size[1] / uv_scale[1];
+        if tile_w <= 0.0 || tile_h <= 0.0 {
+            return;
+        }
+
+        let mut ty = 0.0;
+        while ty < h {
+            let dh = (h - ty).min(tile_h);
+            let mut tx = 0.0;
+            while tx < w {
+                let dw = (w - tx).min(tile_w);
+                let src = region.sub_region([
+                    0.0,
+                    0.0,
+                    region.size[0] * dw / tile_w,
+                    region.size[1] * dh / tile_h,
+                ]);
+                self.push_region(&src, [x + tx, y + ty, dw, dh], Color::white());
+                tx += tile_w;
+            }
+            ty += tile_h;
+        }
+    }
+
+    /// Pushes quads of the glyphs of `text`, using the font book's atlas texture
+    ///
+    /// `pos` is the origin of the text given to fontstash (top-left or baseline depending on the
+    /// alignment set to the stash).
+    pub fn push_text(&mut self, book: &mut FontBook, pos: [f32; 2], color: Color, text: &str) {
+        if let Some(glyphs) = self::text_glyphs(book, text) {
+            self.push_glyphs(book, pos, color, &glyphs);
+        }
+    }
+
+    fn push_glyphs(&mut self, book: &mut FontBook, pos: [f32; 2], color: Color, glyphs: &[Glyph]) {
+        book.maybe_update_texture();
+        let tex = book.texture();
+
+        for g in glyphs.iter() {
+            let [x, y, w, h] = g.dst;
+            let glyph = TextureRegion::new(tex, g.uv, [w, h]);
+            self.push_region(&glyph, [pos[0] + x, pos[1] + y, w, h], color);
+        }
+    }
+
+    /// Pushes text being composed with an IME, underlined, with a caret
+    ///
+    /// `cursor` and `selection` are in characters (`fna3d_imgui::Composition`). The selected
+    /// clause gets a thicker underline. Returns the width of the text, e.g. for placing the IME
+    /// candidate window.
+    pub fn push_composition(
+        &mut self,
+        book: &mut FontBook,
+        pos: [f32; 2],
+        color: Color,
+        text: &str,
+        cursor: usize,
+        selection: usize,
+    ) -> f32 {
+        let glyphs = match self::text_glyphs(book, text) {
+            Some(glyphs) if !glyphs.is_empty() => glyphs,
+            _ => return 0.0,
+        };
+        self.push_glyphs(book, pos, color, &glyphs);
+
+        // one glyph per character
+        let spans = glyphs
+            .iter()
+            .map(|g| [g.dst[0], g.dst[0] + g.dst[2]])
+            .collect::<Vec<_>>();
+        let top = glyphs.iter().map(|g| g.dst[1]).fold(f32::MAX, f32::min);
+        let bottom = glyphs
+            .iter()
+            .map(|g| g.dst[1] + g.dst[3])
+            .fold(f32::MIN, f32::max);
+        let (left, right) = (self::caret_x(&spans, 0), self::caret_x(&spans, spans.len()));
+
+        let [x, y] = [pos[0], pos[1] + bottom + 1.0];
+        self.push_rect([x + left, y, right - left, 1.0], color);
+        if selection > 0 {
+            let from = self::caret_x(&spans, cursor);
+            let to = self::caret_x(&spans, cursor + selection);
+            self.push_rect([x + from, y, to - from, 2.0], color);
+        }
+
+        let caret = self::caret_x(&spans, cursor);
+        self.push_rect([pos[0] + caret, pos[1] + top, 1.0, bottom - top], color);
+
+        right - left
+    }
+
+    /// Pushes a solid rectangle with the device's white texture
+    fn push_rect(&mut self, rect: [f32; 4], color: Color) {
+        let white = self.batch.device.defaults().white_texture();
+        let region = TextureRegion::new(white, [0.0, 0.0, 1.0, 1.0], [1.0, 1.0]);
+        self.push_region(&region, rect, color);
+    }
+
+    fn flush_if_satured(&mut self) {
+        if self.batch.n_quads >= self.batch.quads.len() {
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if self.batch.n_quads == 0 {
+            return;
+        }
+
+        // we have to update the shader's orthographic offcenter matrix in real application where we
+        // can resize window
+        self.shader.apply_to_device();
+
+        if self.overdraw.is_some() {
+            let [r, g, b, a] = OVERDRAW_COLOR;
+            let color = Color::rgba(r, g, b, a);
+            for quad in self.batch.quads[0..self.batch.n_quads].iter_mut() {
+                for v in quad.0.iter_mut() {
+                    v.color = color;
+                }
+            }
+        }
+
+        // upload the CPU vertices to the GPU vertices
+        self.batch.device.set_vertex_buffer_data(
+            self.batch.vbuf,
+            0, // vertex offset
+            &self.batch.quads[0..self.batch.n_quads],
+            fna3d::SetDataOptions::None,
+        );
+
+        for call in self.batch.draw_calls() {
+            log::trace!("draw call: {:?}", call);
+            self.draw(&call);
+        }
+
+        self.batch.n_quads = 0;
+    }
+
+    fn draw(&self, call: &DrawCall) {
+        let device = &self.batch.device;
+
+        let tex = self.overdraw.unwrap_or(call.tex);
+        let sampler = self.pipelines.current().sampler.as_ref();
+        device.verify_sampler(0, tex, sampler.unwrap_or(&self.sampler));
+        device.apply_vertex_buffer_bindings_auto(&[self.batch.vbind], call.base_vtx() as u32);
+
+        device.draw_indexed_primitives(
+            fna3d::PrimitiveType::TriangleList,
+            call.base_vtx() as u32,
+            0,
+            call.n_verts() as u32,
+            call.base_idx() as u32,
+            call.n_triangles() as u32,
+            self.batch.ibuf,
+            fna3d::IndexElementSize::Bits16,
+        );
+    }
+}
+
+/// Glyph from fontstash: destination rectangle relative to the text origin and UV rectangle
+struct Glyph {
+    dst: [f32; 4],
+    uv: [f32; 4],
+}
+
+/// Collects the glyphs first, since the atlas texture can be re-created while rasterizing them
+fn text_glyphs(book: &mut FontBook, text: &str) -> Option<Vec<Glyph>> {
+    match book.text_iter(text) {
+        Ok(iter) => Some(
+            iter.map(|q| Glyph {
+                dst: [q.x0, q.y0, q.x1 - q.x0, q.y1 - q.y0],
+                uv: [q.s0, q.t0, q.s1 - q.s0, q.t1 - q.t0],
+            })
+            .collect(),
+        ),
+        Err(why) => {
+            log::warn!("unable to iterate text {:?}: {:?}", text, why);
+            None
+        }
+    }
+}
+
+/// Horizontal offset of the caret before the `i`th of the `[x0, x1]` glyphs (after the last one
+/// if `i` is out of range)
+fn caret_x(glyphs: &[[f32; 2]], i: usize) -> f32 {
+    match glyphs.get(i) {
+        Some(g) => g[0],
+        None => glyphs.last().map_or(0.0, |g| g[1]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_caret_x() {
+        let glyphs = [[0.0, 8.0], [9.0, 16.0]];
+        assert_eq!(caret_x(&glyphs, 0), 0.0);
+        assert_eq!(caret_x(&glyphs, 1), 9.0);
+        assert_eq!(caret_x(&glyphs, 2), 16.0);
+        assert_eq!(caret_x(&glyphs, 5), 16.0);
+        assert_eq!(caret_x(&[], 0), 0.0);
+    }
+
+    #[test]
+    fn test_quad_corner_colors() {
+        let (a, b) = (Color::rgba(255, 0, 0, 255), Color::rgba(0, 0, 255, 255));
+
+        let quad = QuadData::from_rects_colored(
+            [0.0, 0.0, 10.0, 10.0],
+            [0.0, 0.0, 1.0, 1.0],
+            horizontal_gradient(a, b),
+        );
+        // the right vertices have the right color
+        for v in quad.0.iter() {
+            let expected = if v.dst[0] > 0.0 { b } else { a };
+            assert_eq!(v.color, expected);
+        }
+
+        let mut quad = QuadData::from_rects([0.0; 4], [0.0; 4], a);
+        quad.set_colors(vertical_gradient(a, b));
+        assert_eq!(quad.colors(), [a, a, b, b]);
+    }
+}
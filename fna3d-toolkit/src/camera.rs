@@ -0,0 +1,75 @@
+//! 2D camera
+
+use fna3d::mojo::{self, ClipSpaceInfo};
+
+/// 2D camera that makes projection matrices for [`crate::gfx::Shader2d`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera2d {
+    /// World position at the top-left corner of the screen
+    pub pos: [f32; 2],
+    /// Scale of world pixels (`2.0` shows world pixels twice as large)
+    pub zoom: f32,
+    /// Rounds the translation to the pixel grid so that sprites don't shimmer while scrolling
+    pub pixel_snap: bool,
+}
+
+impl Default for Camera2d {
+    fn default() -> Self {
+        Self {
+            pos: [0.0, 0.0],
+            zoom: 1.0,
+            pixel_snap: true,
+        }
+    }
+}
+
+impl Camera2d {
+    /// Projection matrix for a `w` x `h` viewport
+    ///
+    /// Applies the half-pixel offset if the clip space requires it.
+    pub fn projection(&self, w: u32, h: u32, clip: &ClipSpaceInfo) -> [f32; 16] {
+        let mut mat = mojo::pixel_projection(w, h, clip);
+
+        // world -> screen: (p - pos) * zoom
+        let mut offset = [-self.pos[0] * self.zoom, -self.pos[1] * self.zoom];
+        if self.pixel_snap {
+            offset = [offset[0].round(), offset[1].round()];
+        }
+
+        // scale the input, then translate (both in pixels)
+        for i in 0..4 {
+            let row = 4 * i;
+            mat[row + 3] += mat[row] * offset[0] + mat[row + 1] * offset[1];
+            mat[row] *= self.zoom;
+            mat[row + 1] *= self.zoom;
+        }
+
+        mat
+    }
+
+    /// World rectangle `[x, y, w, h]` visible in a `w` x `h` viewport (for culling)
+    pub fn visible_rect(&self, w: u32, h: u32) -> [f32; 4] {
+        [
+            self.pos[0],
+            self.pos[1],
+            w as f32 / self.zoom,
+            h as f32 / self.zoom,
+        ]
+    }
+
+    /// Screen position in pixels to world position
+    pub fn screen_to_world(&self, screen: [f32; 2]) -> [f32; 2] {
+        [
+            screen[0] / self.zoom + self.pos[0],
+            screen[1] / self.zoom + self.pos[1],
+        ]
+    }
+
+    /// World position to screen position in pixels
+    pub fn world_to_screen(&self, world: [f32; 2]) -> [f32; 2] {
+        [
+            (world[0] - self.pos[0]) * self.zoom,
+            (world[1] - self.pos[1]) * self.zoom,
+        ]
+    }
+}
@@ -0,0 +1,142 @@
+//! Secondary camera views rendered into textures (minimaps, picture-in-picture)
+//!
+//! [`CaptureRegion`] renders a view of `view` screen pixels into a render target of reduced
+//! resolution. The camera is zoomed out by the scale, so the captured view shows the same world
+//! rectangle as it would on the screen:
+//!
+//! ```ignore
+//! // a 320x180 minimap at half resolution
+//! let mut minimap = CaptureRegion::new(&device, [320, 180], 0.5)?;
+//!
+//! minimap.render(&device, &overview_camera, |proj| {
+//!     shader.set_projection(proj)?;
+//!     draw_world(&mut batcher, &shader);
+//!     Ok(())
+//! })?;
+//!
+//! // composite it at its view size
+//! batcher.push_region(&minimap.region(), [16.0, 16.0, 320.0, 180.0], Color::white());
+//! ```
+
+use {anyhow::Result, std::fmt};
+
+use fna3d::res::RenderTarget2d;
+
+use crate::{camera::Camera2d, gfx::TextureRegion};
+
+/// Render target for a secondary camera view
+pub struct CaptureRegion {
+    target: RenderTarget2d,
+    /// Size of the view in screen pixels
+    view: [u32; 2],
+    clear_color: fna3d::Color,
+}
+
+impl fmt::Debug for CaptureRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaptureRegion")
+            .field("texture", &self.target.texture())
+            .field("texture_size", &self.target.size())
+            .field("view", &self.view)
+            .field("clear_color", &self.clear_color)
+            .finish()
+    }
+}
+
+impl CaptureRegion {
+    /// * `view`: size of the captured view in screen pixels
+    /// * `scale`: resolution of the texture relative to the view (e.g. `0.5`)
+    pub fn new(device: &fna3d::Device, view: [u32; 2], scale: f32) -> Result<Self> {
+        let [w, h] = self::texture_size(view, scale);
+        let target = RenderTarget2d::new(
+            device,
+            w,
+            h,
+            fna3d::SurfaceFormat::Color,
+            fna3d::DepthFormat::None,
+            0,
+        )?;
+
+        Ok(Self {
+            target,
+            view,
+            clear_color: fna3d::Color::transparent(),
+        })
+    }
+
+    /// Color the texture is cleared with before drawing (transparent by default)
+    pub fn with_clear_color(mut self, color: fna3d::Color) -> Self {
+        self.clear_color = color;
+        self
+    }
+
+    /// Draws the view of the camera into the texture
+    ///
+    /// `draw` is given the projection matrix of the view. Set it to your shader and flush the
+    /// batch before returning. The backbuffer is bound again after this, even on error.
+    pub fn render(
+        &mut self,
+        device: &fna3d::Device,
+        camera: &Camera2d,
+        draw: impl FnOnce(&[f32; 16]) -> Result<()>,
+    ) -> Result<()> {
+        let (w, h) = self.target.size();
+        let mut camera = camera.clone();
+        camera.zoom *= w as f32 / self.view[0] as f32;
+        let proj = camera.projection(w, h, &device.clip_space_info());
+
+        self.target.bind();
+        device.clear(
+            fna3d::ClearOptions::TARGET,
+            self.clear_color.to_vec4(),
+            0.0,
+            0,
+        );
+        let res = draw(&proj);
+        self.target.unbind();
+
+        res
+    }
+
+    /// Region of the whole texture, sized as the view
+    pub fn region(&self) -> TextureRegion {
+        TextureRegion::new(
+            self.target.texture(),
+            [0.0, 0.0, 1.0, 1.0],
+            [self.view[0] as f32, self.view[1] as f32],
+        )
+    }
+
+    pub fn texture(&self) -> *mut fna3d::Texture {
+        self.target.texture()
+    }
+
+    /// Size of the view in screen pixels
+    pub fn view_size(&self) -> [u32; 2] {
+        self.view
+    }
+
+    /// Size of the texture in pixels
+    pub fn texture_size(&self) -> [u32; 2] {
+        let (w, h) = self.target.size();
+        [w, h]
+    }
+}
+
+/// Texture size for the view, at least one pixel
+fn texture_size(view: [u32; 2], scale: f32) -> [u32; 2] {
+    let f = |x: u32| ((x as f32 * scale).ceil() as u32).max(1);
+    [f(view[0]), f(view[1])]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_texture_size() {
+        assert_eq!(texture_size([320, 180], 0.5), [160, 90]);
+        assert_eq!(texture_size([321, 181], 0.5), [161, 91]);
+        assert_eq!(texture_size([4, 4], 0.0), [1, 1]);
+    }
+}
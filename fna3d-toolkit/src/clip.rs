@@ -0,0 +1,99 @@
+//! Nested clip rectangles implemented with the scissor test
+//!
+//! UI panels clip their contents to their bounds, and a scrollable panel inside another panel is
+//! clipped by both. [`ClipStack`] intersects each pushed rectangle with the current one and
+//! returns the scissor rectangle to set. See [`crate::batch::Batcher::push_clip`] for the sprite
+//! batcher integration.
+//!
+//! Rectangles are `[x, y, w, h]` in pixels of the render target (not camera space).
+
+/// Intersection of two `[x, y, w, h]` rectangles. The size is zero if they don't overlap
+pub fn intersect(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    let x0 = a[0].max(b[0]);
+    let y0 = a[1].max(b[1]);
+    let x1 = (a[0] + a[2]).min(b[0] + b[2]);
+    let y1 = (a[1] + a[3]).min(b[1] + b[3]);
+    [x0, y0, (x1 - x0).max(0.0), (y1 - y0).max(0.0)]
+}
+
+/// Scissor rectangle covering every pixel touched by `rect`
+pub fn to_scissor(rect: [f32; 4]) -> fna3d::Rect {
+    let [x, y, w, h] = rect;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (x1, y1) = ((x + w).ceil(), (y + h).ceil());
+    fna3d::Rect::from([
+        x0 as i32,
+        y0 as i32,
+        (x1 - x0).max(0.0) as i32,
+        (y1 - y0).max(0.0) as i32,
+    ])
+}
+
+/// Stack of clip rectangles, each clipped by the previous ones
+#[derive(Debug, Clone, Default)]
+pub struct ClipStack {
+    /// Effective (intersected) rectangles
+    stack: Vec<[f32; 4]>,
+}
+
+impl ClipStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The effective clip rectangle, or `None` if nothing is clipped
+    pub fn current(&self) -> Option<[f32; 4]> {
+        self.stack.last().copied()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Pushes a rectangle and returns the new effective clip rectangle
+    pub fn push(&mut self, rect: [f32; 4]) -> [f32; 4] {
+        let rect = match self.current() {
+            Some(current) => self::intersect(current, rect),
+            None => rect,
+        };
+        self.stack.push(rect);
+        rect
+    }
+
+    /// Pops the last rectangle. Returns `None` if the stack was empty
+    pub fn pop(&mut self) -> Option<[f32; 4]> {
+        self.stack.pop()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nested_clips() {
+        let mut clips = ClipStack::new();
+        assert_eq!(clips.current(), None);
+
+        clips.push([0.0, 0.0, 100.0, 100.0]);
+        assert_eq!(
+            clips.push([50.0, -10.0, 100.0, 30.0]),
+            [50.0, 0.0, 50.0, 20.0]
+        );
+        // disjoint rectangles clip everything
+        assert_eq!(clips.push([200.0, 0.0, 10.0, 10.0])[2], 0.0);
+
+        clips.pop();
+        clips.pop();
+        assert_eq!(clips.current(), Some([0.0, 0.0, 100.0, 100.0]));
+        clips.pop();
+        assert!(clips.pop().is_none());
+
+        let r = to_scissor([0.5, 1.0, 10.0, 2.5]);
+        assert_eq!((r.x, r.y, r.w, r.h), (0, 1, 11, 3));
+    }
+}
@@ -0,0 +1,265 @@
+//! In-game debug console showing recent `log` records
+//!
+//! On platforms without a terminal (consoles, phones, a game launched from a file manager), driver
+//! warnings are otherwise invisible. [`ConsoleLogger`] keeps recent `log` records, including the
+//! FNA3D messages routed by [`fna3d::utils::hook_log_functions_log_crate`] and the warnings of the
+//! wrapper, and [`DebugConsole`] draws them with the font book:
+//!
+//! ```ignore
+//! let log = ConsoleLog::new(64);
+//! ConsoleLogger::new(log.clone(), log::LevelFilter::Info).install()?;
+//! let mut console = DebugConsole::new(log);
+//!
+//! init.run(|frame| {
+//!     for ev in &frame.events {
+//!         console.handle_event(ev);
+//!     }
+//!     // .. draw the game
+//!     console.render(&mut batcher, &mut book, [8.0, 8.0]);
+//!     batcher.flush();
+//!     Ok(())
+//! })
+//! ```
+//!
+//! The console is toggled with the backquote key or the `Back` button of a game controller.
+
+use {
+    fna3d::Color,
+    fna3d_fontstash::FontBook,
+    log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError},
+    sdl2::{controller::Button, event::Event, keyboard::Keycode},
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    },
+};
+
+use crate::batch::Batcher;
+
+/// A `log` record kept by [`ConsoleLog`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Shared ring buffer of [`ConsoleLine`]s. Old lines are dropped when it's full
+#[derive(Debug, Clone)]
+pub struct ConsoleLog {
+    lines: Arc<Mutex<VecDeque<ConsoleLine>>>,
+    capacity: usize,
+}
+
+impl ConsoleLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn push(&self, line: ConsoleLine) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().unwrap();
+        while lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Copy of the lines, oldest first
+    pub fn lines(&self) -> Vec<ConsoleLine> {
+        self.lines.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+}
+
+/// `log` implementation pushing records to a [`ConsoleLog`], optionally forwarding them to
+/// another logger
+pub struct ConsoleLogger {
+    log: ConsoleLog,
+    level: LevelFilter,
+    inner: Option<Box<dyn Log>>,
+}
+
+impl ConsoleLogger {
+    pub fn new(log: ConsoleLog, level: LevelFilter) -> Self {
+        Self {
+            log,
+            level,
+            inner: None,
+        }
+    }
+
+    /// Also passes records to `inner` (e.g. a terminal logger)
+    pub fn with_inner(mut self, inner: Box<dyn Log>) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    /// Sets this logger as the global logger. Fails if a logger is already set
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(inner) = self.inner.as_ref() {
+            inner.log(record);
+        }
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.log.push(ConsoleLine {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = self.inner.as_ref() {
+            inner.flush();
+        }
+    }
+}
+
+/// Overlay drawing the last lines of a [`ConsoleLog`]. Hidden by default
+#[derive(Debug, Clone)]
+pub struct DebugConsole {
+    log: ConsoleLog,
+    visible: bool,
+    /// Maximum number of lines drawn
+    pub max_lines: usize,
+    /// Distance between lines in pixels
+    pub line_height: f32,
+}
+
+impl DebugConsole {
+    pub fn new(log: ConsoleLog) -> Self {
+        Self {
+            log,
+            visible: false,
+            max_lines: 16,
+            line_height: 20.0,
+        }
+    }
+
+    pub fn log(&self) -> &ConsoleLog {
+        &self.log
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Toggles the console on the backquote key or the `Back` button. Returns true if the event
+    /// is consumed
+    pub fn handle_event(&mut self, ev: &Event) -> bool {
+        match ev {
+            Event::KeyDown {
+                keycode: Some(Keycode::Backquote),
+                repeat: false,
+                ..
+            }
+            | Event::ControllerButtonDown {
+                button: Button::Back,
+                ..
+            } => {
+                self.toggle();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Pushes the last lines to the batcher if the console is visible. `pos` is the origin of
+    /// the first line
+    pub fn render(&self, batcher: &mut Batcher, book: &mut FontBook, pos: [f32; 2]) {
+        if !self.visible {
+            return;
+        }
+
+        let lines = self.log.lines();
+        let skip = lines.len().saturating_sub(self.max_lines);
+        for (i, line) in lines.iter().skip(skip).enumerate() {
+            let text = format!("[{}] {}: {}", line.level, line.target, line.message);
+            let y = pos[1] + i as f32 * self.line_height;
+            batcher.push_text(book, [pos[0], y], self::level_color(line.level), &text);
+        }
+    }
+}
+
+/// Text color of the lines
+pub fn level_color(level: Level) -> Color {
+    match level {
+        Level::Error => Color::RED,
+        Level::Warn => Color::YELLOW,
+        Level::Info => Color::WHITE,
+        Level::Debug => Color::LIGHT_GRAY,
+        Level::Trace => Color::GRAY,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_console_log() {
+        let log = ConsoleLog::new(2);
+        let logger = ConsoleLogger::new(log.clone(), LevelFilter::Warn);
+
+        for (level, msg) in [
+            (Level::Warn, "a"),
+            (Level::Info, "filtered"),
+            (Level::Error, "b"),
+            (Level::Warn, "c"),
+        ]
+        .iter()
+        {
+            logger.log(
+                &Record::builder()
+                    .level(*level)
+                    .target("FNA3D")
+                    .args(format_args!("{}", msg))
+                    .build(),
+            );
+        }
+
+        let lines = log.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            (lines[0].level, lines[0].message.as_str()),
+            (Level::Error, "b")
+        );
+        assert_eq!(lines[1].target, "FNA3D");
+        assert_eq!(lines[1].message, "c");
+    }
+}
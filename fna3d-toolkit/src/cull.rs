@@ -0,0 +1,235 @@
+//! CPU culling: view frustum and bounding volumes
+//!
+//! Test the bounding volumes of your meshes against the [`Frustum`] of the camera and skip the
+//! draw calls of invisible ones.
+//!
+//! ```ignore
+//! let frustum = Frustum::from_matrix(&view_proj);
+//! for mesh in meshes.iter().filter(|m| frustum.intersects_aabb(&m.bounds)) {
+//!     mesh.draw(&device);
+//! }
+//! ```
+//!
+//! Matrices are in the layout of [`fna3d::mojo::orthographic_off_center`]: element `4 * i + j` is
+//! the coefficient of input component `j` for output component `i`.
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    pub fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box containing the points. Returns `None` if there's no point
+    pub fn from_points(points: impl IntoIterator<Item = [f32; 3]>) -> Option<Self> {
+        let mut iter = points.into_iter();
+        let first = iter.next()?;
+        Some(iter.fold(Self::new(first, first), |b, p| {
+            let f = |i: usize| (b.min[i].min(p[i]), b.max[i].max(p[i]));
+            let (x, y, z) = (f(0), f(1), f(2));
+            Self::new([x.0, y.0, z.0], [x.1, y.1, z.1])
+        }))
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+            (self.min[2] + self.max[2]) / 2.0,
+        ]
+    }
+
+    pub fn half_extents(&self) -> [f32; 3] {
+        [
+            (self.max[0] - self.min[0]) / 2.0,
+            (self.max[1] - self.min[1]) / 2.0,
+            (self.max[2] - self.min[2]) / 2.0,
+        ]
+    }
+
+    pub fn contains_point(&self, p: [f32; 3]) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    /// Bounding sphere of the box
+    pub fn bounding_sphere(&self) -> Sphere {
+        let [x, y, z] = self.half_extents();
+        Sphere::new(self.center(), (x * x + y * y + z * z).sqrt())
+    }
+}
+
+/// Bounding sphere
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: [f32; 3], radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains_point(&self, p: [f32; 3]) -> bool {
+        self::distance_sq(self.center, p) <= self.radius * self.radius
+    }
+
+    pub fn intersects(&self, other: &Sphere) -> bool {
+        let r = self.radius + other.radius;
+        self::distance_sq(self.center, other.center) <= r * r
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        // closest point on the box
+        let mut d = 0.0;
+        for i in 0..3 {
+            let v = self.center[i].max(aabb.min[i]).min(aabb.max[i]) - self.center[i];
+            d += v * v;
+        }
+        d <= self.radius * self.radius
+    }
+}
+
+fn distance_sq(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]) * (a[i] - b[i])).sum()
+}
+
+/// Six planes `[a, b, c, d]` facing inside: a point is inside if `a*x + b*y + c*z + d >= 0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near, far
+    pub planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a view-projection matrix with the D3D depth range `[0, 1]`
+    /// (which FNA3D uses on every backend; see [`fna3d::mojo::ClipSpaceInfo`])
+    pub fn from_matrix(m: &[f32; 16]) -> Self {
+        Self::from_matrix_with_depth_range(m, [0.0, 1.0])
+    }
+
+    /// Extracts the planes from a view-projection matrix with the NDC `depth_range`, e.g.
+    /// `[-1.0, 1.0]` for matrices made for OpenGL
+    pub fn from_matrix_with_depth_range(m: &[f32; 16], depth_range: [f32; 2]) -> Self {
+        let row = |i: usize| [m[4 * i], m[4 * i + 1], m[4 * i + 2], m[4 * i + 3]];
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let [lo, hi] = depth_range;
+
+        // -w <= x <= w, -w <= y <= w, lo * w <= z <= hi * w
+        let combine = |a: [f32; 4], s: f32, b: [f32; 4]| {
+            [
+                a[0] + s * b[0],
+                a[1] + s * b[1],
+                a[2] + s * b[2],
+                a[3] + s * b[3],
+            ]
+        };
+        let near = combine(z, -lo, w);
+        let far = combine([-z[0], -z[1], -z[2], -z[3]], hi, w);
+
+        let mut planes = [
+            combine(w, 1.0, x),
+            combine(w, -1.0, x),
+            combine(w, 1.0, y),
+            combine(w, -1.0, y),
+            near,
+            far,
+        ];
+
+        for p in planes.iter_mut() {
+            let len = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+            if len > 0.0 {
+                for v in p.iter_mut() {
+                    *v /= len;
+                }
+            }
+        }
+
+        Self { planes }
+    }
+
+    fn distance(plane: &[f32; 4], p: [f32; 3]) -> f32 {
+        plane[0] * p[0] + plane[1] * p[1] + plane[2] * p[2] + plane[3]
+    }
+
+    pub fn contains_point(&self, p: [f32; 3]) -> bool {
+        self.planes.iter().all(|pl| Self::distance(pl, p) >= 0.0)
+    }
+
+    /// Conservative: may return `true` for some spheres just outside the corners
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|pl| Self::distance(pl, sphere.center) >= -sphere.radius)
+    }
+
+    /// Conservative: may return `true` for some boxes just outside the corners
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|pl| {
+            // the corner furthest along the plane normal
+            let pick = |i: usize| {
+                if pl[i] >= 0.0 {
+                    aabb.max[i]
+                } else {
+                    aabb.min[i]
+                }
+            };
+            let p = [pick(0), pick(1), pick(2)];
+            Self::distance(pl, p) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_frustum_culling() {
+        #[rustfmt::skip]
+        let identity = [
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        // x, y in [-1, 1] and z in [0, 1]
+        let frustum = Frustum::from_matrix(&identity);
+
+        assert!(frustum.contains_point([0.0, 0.0, 0.5]));
+        assert!(!frustum.contains_point([0.0, 0.0, -0.5]));
+        assert!(!frustum.contains_point([1.5, 0.0, 0.5]));
+
+        assert!(frustum.intersects_sphere(&Sphere::new([1.5, 0.0, 0.5], 0.6)));
+        assert!(!frustum.intersects_sphere(&Sphere::new([1.5, 0.0, 0.5], 0.4)));
+
+        let outside = Aabb::new([2.0, -1.0, 0.0], [3.0, 1.0, 1.0]);
+        assert!(!frustum.intersects_aabb(&outside));
+        let crossing = Aabb::new([0.5, -1.0, 0.0], [3.0, 1.0, 1.0]);
+        assert!(frustum.intersects_aabb(&crossing));
+
+        let gl = Frustum::from_matrix_with_depth_range(&identity, [-1.0, 1.0]);
+        assert!(gl.contains_point([0.0, 0.0, -0.5]));
+    }
+
+    #[test]
+    fn test_bounding_volumes() {
+        let b = Aabb::from_points(vec![[0.0, 0.0, 0.0], [2.0, -2.0, 1.0]]).unwrap();
+        assert_eq!(b, Aabb::new([0.0, -2.0, 0.0], [2.0, 0.0, 1.0]));
+        assert!(b.intersects(&Aabb::new([1.0, -1.0, 0.5], [5.0, 5.0, 5.0])));
+        assert!(b.bounding_sphere().contains_point([2.0, -2.0, 1.0]));
+
+        let s = Sphere::new([0.0, 0.0, 0.0], 1.0);
+        assert!(s.intersects(&Sphere::new([1.5, 0.0, 0.0], 0.6)));
+        assert!(!s.intersects_aabb(&Aabb::new([1.0, 1.0, 1.0], [2.0, 2.0, 2.0])));
+    }
+}
@@ -0,0 +1,47 @@
+sampler IndexSampler : register(s0);
+sampler PaletteSampler : register(s1);
+float4x4 MatrixTransform;
+float PaletteSize;
+
+
+struct VertexShaderOutput
+{
+	float4 position : POSITION;
+	float4 color : COLOR0;
+	float2 texCoord : TEXCOORD0;
+};
+
+
+VertexShaderOutput spriteVert( float4 position: POSITION0, float4 color: COLOR0, float2 texCoord: TEXCOORD0 )
+{
+	VertexShaderOutput output;
+	output.position = mul( position, MatrixTransform );
+	output.color = color;
+	output.texCoord = texCoord;
+
+	return output;
+}
+
+
+float4 palettePixel( VertexShaderOutput input ) : COLOR
+{
+	// `Alpha8` index texture: the index is in the alpha channel as `i / 255`
+	float index = tex2D( IndexSampler, input.texCoord ).a * 255.0;
+	// center of the texel
+	float u = (index + 0.5) / PaletteSize;
+
+	float4 color = tex2D( PaletteSampler, float2(u, 0.5) ) * input.color;
+	color.rgb *= input.color.a;
+
+	return color;
+}
+
+
+technique PaletteDrawing
+{
+	pass P0
+	{
+		VertexShader = compile vs_2_0 spriteVert();
+		PixelShader = compile ps_2_0 palettePixel();
+	}
+};
@@ -0,0 +1,12 @@
+//! Embedded files
+
+/// SpriteEffect shader
+///
+/// It has `MatrixTransform` uniform, i.e. orthograpihc projection matrix.
+pub const SHADER: &[u8] = include_bytes!("SpriteEffect.fxb");
+
+/// Source of the palette swap effect (see [`crate::palette`])
+///
+/// It's not compiled in; build it with `fxc /T fx_2_0 PaletteEffect.fx /Fo PaletteEffect.fxb` and
+/// load the output with [`crate::gfx::Shader2d::from_bytes`].
+pub const PALETTE_SHADER_SOURCE: &str = include_str!("PaletteEffect.fx");
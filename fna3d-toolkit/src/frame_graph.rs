@@ -0,0 +1,251 @@
+//! Frame graph description for debugging pass setups
+//!
+//! This crate has no render graph executor; passes are plain code. [`FrameGraph`] is a record of
+//! what a frame did: declare the passes with the targets they read and write while rendering a
+//! frame, then export the description as Graphviz or JSON:
+//!
+//! ```ignore
+//! let mut graph = FrameGraph::new();
+//! let scene = graph.add_render_target("scene", &scene_target);
+//! let bloom = graph.add_render_target("bloom", &bloom_target);
+//! let screen = graph.add_target("backbuffer", Some([1280, 720]));
+//!
+//! graph.add_pass("sprites", &[], &[scene]);
+//! graph.add_pass("bloom", &[scene], &[bloom]);
+//! graph.add_pass("composite", &[scene, bloom], &[screen]);
+//!
+//! std::fs::write("frame.dot", graph.to_dot())?;
+//! ```
+//!
+//! Render it with `dot -Tsvg frame.dot -o frame.svg`. Passes are boxes and targets are ellipses.
+
+use {serde::Serialize, std::fmt::Write};
+
+/// Index of a target in a [`FrameGraph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct TargetId(pub usize);
+
+/// Index of a pass in a [`FrameGraph`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct PassId(pub usize);
+
+/// Render target (or the backbuffer) in a [`FrameGraph`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TargetNode {
+    pub name: String,
+    /// `[w, h]` in pixels if known
+    pub size: Option<[u32; 2]>,
+    /// Debug name of the surface format if known
+    pub format: Option<String>,
+}
+
+/// Pass in a [`FrameGraph`], in submission order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PassNode {
+    pub name: String,
+    pub reads: Vec<TargetId>,
+    pub writes: Vec<TargetId>,
+}
+
+/// Pass `to` reads `target` last written by pass `from`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub from: PassId,
+    pub to: PassId,
+    pub target: TargetId,
+}
+
+/// Passes and targets of a captured frame. See the module document
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FrameGraph {
+    pub targets: Vec<TargetNode>,
+    pub passes: Vec<PassNode>,
+}
+
+impl FrameGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes everything, e.g. to capture the next frame
+    pub fn clear(&mut self) {
+        self.targets.clear();
+        self.passes.clear();
+    }
+
+    /// Adds a target, or returns the existing one with the same name
+    pub fn add_target(&mut self, name: &str, size: Option<[u32; 2]>) -> TargetId {
+        if let Some(i) = self.targets.iter().position(|t| t.name == name) {
+            return TargetId(i);
+        }
+
+        self.targets.push(TargetNode {
+            name: name.to_string(),
+            size,
+            format: None,
+        });
+        TargetId(self.targets.len() - 1)
+    }
+
+    /// [`FrameGraph::add_target`] with the size and format of the render target
+    pub fn add_render_target(
+        &mut self,
+        name: &str,
+        target: &fna3d::res::RenderTarget2d,
+    ) -> TargetId {
+        let (w, h) = target.size();
+        let id = self.add_target(name, Some([w, h]));
+        self.targets[id.0].format = Some(format!("{:?}", target.format()));
+        id
+    }
+
+    /// Adds a pass. Call in the order the passes are submitted
+    pub fn add_pass(&mut self, name: &str, reads: &[TargetId], writes: &[TargetId]) -> PassId {
+        self.passes.push(PassNode {
+            name: name.to_string(),
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+        });
+        PassId(self.passes.len() - 1)
+    }
+
+    /// Edges from the pass that last wrote a target to each pass reading it
+    ///
+    /// Reads of targets not written earlier in the frame (e.g. the previous frame's output) have
+    /// no dependency.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut last_writer = vec![None; self.targets.len()];
+        let mut deps = Vec::new();
+
+        for (i, pass) in self.passes.iter().enumerate() {
+            for target in &pass.reads {
+                if let Some(from) = last_writer.get(target.0).copied().flatten() {
+                    deps.push(Dependency {
+                        from,
+                        to: PassId(i),
+                        target: *target,
+                    });
+                }
+            }
+            for target in &pass.writes {
+                if let Some(w) = last_writer.get_mut(target.0) {
+                    *w = Some(PassId(i));
+                }
+            }
+        }
+
+        deps
+    }
+
+    /// Graphviz (`dot`) source. Edges go from passes to the targets they write and from targets
+    /// to the passes reading them
+    pub fn to_dot(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "digraph frame {{").unwrap();
+        writeln!(s, "    rankdir=LR;").unwrap();
+
+        for (i, t) in self.targets.iter().enumerate() {
+            let mut label = self::escape(&t.name);
+            if let Some([w, h]) = t.size {
+                write!(label, "\\n{}x{}", w, h).unwrap();
+            }
+            if let Some(format) = &t.format {
+                write!(label, "\\n{}", self::escape(format)).unwrap();
+            }
+            writeln!(s, "    t{} [shape=ellipse, label=\"{}\"];", i, label).unwrap();
+        }
+
+        for (i, p) in self.passes.iter().enumerate() {
+            writeln!(
+                s,
+                "    p{} [shape=box, label=\"{}: {}\"];",
+                i,
+                i,
+                self::escape(&p.name)
+            )
+            .unwrap();
+        }
+
+        for (i, p) in self.passes.iter().enumerate() {
+            for t in &p.reads {
+                writeln!(s, "    t{} -> p{};", t.0, i).unwrap();
+            }
+            for t in &p.writes {
+                writeln!(s, "    p{} -> t{};", i, t.0).unwrap();
+            }
+        }
+
+        writeln!(s, "}}").unwrap();
+        s
+    }
+
+    /// JSON with `targets`, `passes` and `dependencies` (indices into the arrays)
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Export<'a> {
+            targets: &'a [TargetNode],
+            passes: &'a [PassNode],
+            dependencies: Vec<Dependency>,
+        }
+
+        let export = Export {
+            targets: &self.targets,
+            passes: &self.passes,
+            dependencies: self.dependencies(),
+        };
+        serde_json::to_string_pretty(&export).unwrap()
+    }
+}
+
+/// Escapes a string for a quoted `dot` label
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_frame_graph_export() {
+        let mut graph = FrameGraph::new();
+        let scene = graph.add_target("scene", Some([640, 360]));
+        let bloom = graph.add_target("bloom", None);
+        let screen = graph.add_target("screen", None);
+        assert_eq!(graph.add_target("scene", None), scene);
+
+        let sprites = graph.add_pass("sprites", &[], &[scene]);
+        let blur = graph.add_pass("bloom", &[scene], &[bloom]);
+        let composite = graph.add_pass("composite", &[scene, bloom], &[screen]);
+
+        assert_eq!(
+            graph.dependencies(),
+            vec![
+                Dependency {
+                    from: sprites,
+                    to: blur,
+                    target: scene
+                },
+                Dependency {
+                    from: sprites,
+                    to: composite,
+                    target: scene
+                },
+                Dependency {
+                    from: blur,
+                    to: composite,
+                    target: bloom
+                },
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("t0 [shape=ellipse, label=\"scene\\n640x360\"];"));
+        assert!(dot.contains("t1 -> p2;"));
+        assert!(dot.contains("p2 -> t2;"));
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+        assert_eq!(json["dependencies"].as_array().unwrap().len(), 3);
+        assert_eq!(json["passes"][1]["reads"][0], 0);
+    }
+}
@@ -0,0 +1,175 @@
+//! Game loop with fixed timestep update and variable timestep render
+//!
+//! `update` is called with a fixed `dt` (as many times as required to catch up with the real
+//! time), then `render` is called once per frame with `alpha`, the interpolation factor between
+//! the previous and the current update state. See [Fix Your Timestep!].
+//!
+//! [Fix Your Timestep!]: https://gafferongames.com/post/fix_your_timestep/
+
+use {
+    anyhow::{Error, Result},
+    sdl2::event::Event,
+    std::time::{Duration, Instant},
+};
+
+use fna3d::ViewportExt;
+
+use crate::Init;
+
+/// Return value of [`Game`] callbacks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// Stops the game loop (after the current frame)
+    Quit,
+}
+
+/// Time given to [`Game::update`]
+#[derive(Debug, Clone, Copy)]
+pub struct UpdateTime {
+    /// Fixed delta time
+    pub dt: Duration,
+    /// Total simulated time
+    pub total: Duration,
+}
+
+/// Time given to [`Game::render`]
+#[derive(Debug, Clone, Copy)]
+pub struct RenderTime {
+    /// Real delta time since the last render
+    pub dt: Duration,
+    /// Interpolation factor in range `[0, 1)`: how far we are from the last update to the next one
+    pub alpha: f32,
+}
+
+/// User game driven by [`GameLoop`]
+pub trait Game {
+    /// Called for each SDL event. `Event::Quit` stops the loop anyways
+    fn event(&mut self, _ev: &Event) -> Flow {
+        Flow::Continue
+    }
+
+    fn update(&mut self, time: &UpdateTime) -> Result<Flow>;
+
+    /// Called after the backbuffer is recreated to match the window. The viewport is already reset
+    ///
+    /// Recreate window-sized render targets and update projection matrices here.
+    fn backbuffer_reset(
+        &mut self,
+        _device: &fna3d::Device,
+        _event: &fna3d::BackbufferEvent,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Render the game; the back buffer is presented by the [`GameLoop`]
+    fn render(&mut self, device: &fna3d::Device, time: &RenderTime) -> Result<()>;
+}
+
+/// Drives a [`Game`] with fixed timestep
+#[derive(Debug, Clone)]
+pub struct GameLoop {
+    /// Fixed delta time of update
+    pub dt: Duration,
+    /// Maximum number of updates per frame. We give up catching up when we're too slow
+    pub max_updates: u32,
+    /// Minimum frame duration. Set it when vsync is off so that we don't burn CPU
+    pub min_frame: Option<Duration>,
+}
+
+impl Default for GameLoop {
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
+
+impl GameLoop {
+    /// Creates a game loop that updates `update_fps` times per second
+    pub fn new(update_fps: u32) -> Self {
+        Self {
+            dt: Duration::from_nanos(1_000_000_000 / update_fps as u64),
+            max_updates: 5,
+            min_frame: None,
+        }
+    }
+
+    /// Limits render frame rate
+    pub fn max_fps(mut self, fps: Option<u32>) -> Self {
+        self.min_frame = fps.map(|fps| Duration::from_nanos(1_000_000_000 / fps as u64));
+        self
+    }
+
+    /// Runs the game until the window is closed or the game returns [`Flow::Quit`]
+    pub fn run(&self, init: &Init, game: &mut impl Game) -> Result<()> {
+        let mut pump = init.sdl.event_pump().map_err(Error::msg)?;
+
+        let mut update_time = UpdateTime {
+            dt: self.dt,
+            total: Duration::default(),
+        };
+
+        let mut last = Instant::now();
+        let mut acc = Duration::default();
+
+        loop {
+            let frame_start = Instant::now();
+            let frame_dt = frame_start - last;
+            last = frame_start;
+
+            let mut flow = Flow::Continue;
+
+            for ev in pump.poll_iter() {
+                if let Event::Quit { .. } = ev {
+                    flow = Flow::Quit;
+                }
+                if game.event(&ev) == Flow::Quit {
+                    flow = Flow::Quit;
+                }
+            }
+            if flow == Flow::Quit {
+                return Ok(());
+            }
+
+            if let Some(ev) = init.device.handle_backbuffer_invalidation() {
+                let (w, h) = ev.size();
+                init.device
+                    .set_viewport(&fna3d::Viewport::new(0, 0, w as i32, h as i32));
+                game.backbuffer_reset(&init.device, &ev)?;
+            }
+
+            // fixed timestep update
+            acc += frame_dt;
+            let mut n_updates = 0;
+            while acc >= self.dt {
+                if n_updates >= self.max_updates {
+                    // spiral of death; drop the remaining time
+                    acc = Duration::default();
+                    break;
+                }
+
+                if game.update(&update_time)? == Flow::Quit {
+                    return Ok(());
+                }
+                update_time.total += self.dt;
+                acc -= self.dt;
+                n_updates += 1;
+            }
+
+            // variable timestep render
+            let render_time = RenderTime {
+                dt: frame_dt,
+                alpha: acc.as_secs_f32() / self.dt.as_secs_f32(),
+            };
+            game.render(&init.device, &render_time)?;
+            init.device
+                .swap_buffers(None, None, init.raw_window() as *mut _);
+
+            if let Some(min_frame) = self.min_frame {
+                let elapsed = frame_start.elapsed();
+                if elapsed < min_frame {
+                    std::thread::sleep(min_frame - elapsed);
+                }
+            }
+        }
+    }
+}
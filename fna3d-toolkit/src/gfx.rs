@@ -0,0 +1,304 @@
+//! Grapics data types
+
+use {
+    anyhow::{Error, Result},
+    fna3d::Color,
+};
+
+use crate::{camera::Camera2d, embedded};
+
+/// GPU texture disposed automatically
+#[derive(Debug, Clone)]
+pub struct Texture2dDrop {
+    device: fna3d::Device,
+    pub raw: *mut fna3d::Texture,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Drop for Texture2dDrop {
+    fn drop(&mut self) {
+        self.device.add_dispose_texture(self.raw);
+    }
+}
+
+impl Texture2dDrop {
+    /// For use with `include_bytes!`. The pixels are premultiplied by alpha
+    pub fn from_encoded_bytes(device: &fna3d::Device, bytes: &[u8]) -> Self {
+        let (mut pixels, w, h) = fna3d::img::load_from_memory(bytes)
+            .unwrap_or_else(|e| panic!("Unable to read the encoded bytes as an image: {}", e));
+        fna3d::img::premultiply(&mut pixels);
+        Self::from_decoded_bytes(device, w, h, &pixels)
+    }
+
+    /// Creates an `Alpha8` texture of color indices (one byte per pixel)
+    ///
+    /// Sample it with a point sampler; see [`crate::palette`].
+    pub fn from_indices(device: &fna3d::Device, w: u32, h: u32, indices: &[u8]) -> Self {
+        let raw = device.create_texture_2d(fna3d::SurfaceFormat::Alpha8, w, h, 1, false);
+        device.set_texture_data_2d(raw, 0, 0, w, h, 0, indices);
+
+        Self {
+            device: device.clone(),
+            raw,
+            w,
+            h,
+        }
+    }
+
+    /// Creates a texture from RGBA8 pixels, which should be premultiplied by alpha (see
+    /// [`fna3d::img::premultiply`])
+    pub fn from_decoded_bytes(device: &fna3d::Device, w: u32, h: u32, pixels: &[u8]) -> Self {
+        let raw = device.create_texture_2d(fna3d::SurfaceFormat::Color, w, h, 1, false);
+        device.set_texture_data_2d(raw, 0, 0, w, h, 0, pixels);
+
+        Self {
+            device: device.clone(),
+            raw,
+            w,
+            h,
+        }
+    }
+}
+
+/// Sub rectangle of a texture
+///
+/// The shared representation of sprites, atlas entries, nine-slice panels and glyphs. It doesn't
+/// own the texture, so keep the [`Texture2dDrop`] alive while using the region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureRegion {
+    pub texture: *mut fna3d::Texture,
+    /// Normalized `[u, v, w, h]`
+    pub uv_rect: [f32; 4],
+    /// Size in pixels
+    pub size: [f32; 2],
+}
+
+impl From<&Texture2dDrop> for TextureRegion {
+    fn from(texture: &Texture2dDrop) -> Self {
+        texture.full_region()
+    }
+}
+
+impl TextureRegion {
+    pub fn new(texture: *mut fna3d::Texture, uv_rect: [f32; 4], size: [f32; 2]) -> Self {
+        Self {
+            texture,
+            uv_rect,
+            size,
+        }
+    }
+
+    /// Sub region in pixels relative to this region
+    pub fn sub_region(&self, rect: [f32; 4]) -> Self {
+        let [u, v, uw, vh] = self.uv_rect;
+        let [x, y, w, h] = rect;
+        let (su, sv) = (uw / self.size[0], vh / self.size[1]);
+
+        Self {
+            texture: self.texture,
+            uv_rect: [u + x * su, v + y * sv, w * su, h * sv],
+            size: [w, h],
+        }
+    }
+
+    /// Splits the region into `cols` x `rows` cells in row-major order (e.g. a sprite sheet)
+    pub fn split_grid(&self, cols: u32, rows: u32) -> Vec<Self> {
+        let (w, h) = (self.size[0] / cols as f32, self.size[1] / rows as f32);
+
+        (0..rows)
+            .flat_map(|r| (0..cols).map(move |c| (c, r)))
+            .map(|(c, r)| self.sub_region([c as f32 * w, r as f32 * h, w, h]))
+            .collect()
+    }
+}
+
+impl Texture2dDrop {
+    /// Region of the whole texture
+    pub fn full_region(&self) -> TextureRegion {
+        TextureRegion::new(
+            self.raw,
+            [0.0, 0.0, 1.0, 1.0],
+            [self.w as f32, self.h as f32],
+        )
+    }
+
+    /// Region of `[x, y, w, h]` in pixels
+    pub fn region(&self, rect: [f32; 4]) -> TextureRegion {
+        self.full_region().sub_region(rect)
+    }
+}
+
+// use std::{ops::Deref, rc::Rc};
+//
+// pub struct TextureData2d {
+//     inner: Rc<Texture2dDrop>,
+// }
+//
+// impl Deref for TextureData2d {
+//     type Target = Texture2dDrop;
+//
+//     fn deref(&self) -> &Self::Target {
+//         &self.inner
+//     }
+// }
+
+/// The vertex data
+///
+/// `#[repr(C)]` is required. The declaration is [`fna3d::vertex::VertexLayout::DECLARATION`].
+#[derive(Debug, Clone, fna3d::vertex::Vertex)]
+#[repr(C)]
+pub struct Vertex {
+    /// Destination position in pixels
+    ///
+    /// We don't need the z coordinate but the shader (`SpriteEffect.fxb`) requires it.
+    ///
+    /// TODO: really? setup 2D only vertices
+    #[vertex(Position)]
+    pub dst: [f32; 3],
+    /// Color of the vertex
+    #[vertex(Color)]
+    pub color: Color,
+    /// Texture coordinates in normalized range [0, 1] (or wraps if it's out of the range)
+    #[vertex(TextureCoordinate)]
+    pub uv: [f32; 2],
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Self {
+            dst: [0.0, 0.0, 0.0],
+            color: Color::rgba(255, 255, 255, 255),
+            uv: [0.0, 0.0],
+        }
+    }
+}
+
+impl Vertex {
+    pub fn new(dst: [f32; 3], uv: [f32; 2], color: Color) -> Self {
+        Self { dst, uv, color }
+    }
+}
+
+/// `SpriteEffect`, one of "Effects" in XNA
+///
+/// It's a combination of vertex/fragment shaders. I hear that it's not a good abstraction though.
+///
+/// Disposed automatically.
+#[derive(Debug)]
+pub struct Shader2d {
+    device: fna3d::Device,
+    effect: *mut fna3d::Effect,
+    effect_data: *mut fna3d::mojo::Effect,
+}
+
+impl Drop for Shader2d {
+    fn drop(&mut self) {
+        // frees both `effect` and `effect_data`
+        self.device.add_dispose_effect(self.effect);
+    }
+}
+
+impl Shader2d {
+    /// Create SpriteEffect from FNA3D device and the screen size
+    pub fn new(device: &fna3d::Device, w: u32, h: u32) -> Result<Self> {
+        Self::from_bytes(device, embedded::SHADER, w, h)
+    }
+
+    /// Creates a shader from compiled effect bytes (`.fxb`)
+    ///
+    /// The effect has to have the `MatrixTransform` parameter and take [`Vertex`] as input, like
+    /// `SpriteEffect`.
+    pub fn from_bytes(device: &fna3d::Device, bytes: &[u8], w: u32, h: u32) -> Result<Self> {
+        let (effect, effect_data) = fna3d::mojo::from_bytes(device, bytes).map_err(Error::msg)?;
+
+        let mut me = Self {
+            device: device.clone(),
+            effect,
+            effect_data,
+        };
+        me.set_camera(&Camera2d::default(), w, h)?;
+
+        Ok(me)
+    }
+
+    /// Set the matrix parameter of the SpriteEffect shader
+    fn set_mat(
+        data: *mut fna3d::mojo::Effect,
+        mat: &[f32; 16],
+    ) -> std::result::Result<(), std::ffi::NulError> {
+        // the name is hardcoded to the original shader source file (`SpriteEffect.fx`)
+        let name = "MatrixTransform";
+        unsafe {
+            let name = std::ffi::CString::new(name)?;
+            if !fna3d::mojo::set_param(data, &name, mat) {
+                log::warn!(
+                    "failed to set the MatrixTransform shader parameter. Probably the effect is not `SpriteEffect.fxb`"
+                );
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Sets the projection matrix
+    pub fn set_projection(&mut self, mat: &[f32; 16]) -> Result<()> {
+        Self::set_mat(self.effect_data, mat)?;
+        Ok(())
+    }
+
+    /// Sets the projection matrix of the camera for a `w` x `h` viewport
+    pub fn set_camera(&mut self, camera: &Camera2d, w: u32, h: u32) -> Result<()> {
+        let mat = camera.projection(w, h, &self.device.clip_space_info());
+        self.set_projection(&mat)
+    }
+
+    /// Updates the projection matrix. Call it when the window is resized
+    pub fn on_update_viewport_size(&mut self, w: u32, h: u32) -> Result<()> {
+        self.set_camera(&Camera2d::default(), w, h)
+    }
+
+    /// For setting other effect parameters with [`fna3d::mojo::set_param`]
+    pub fn effect_data(&self) -> *mut fna3d::mojo::Effect {
+        self.effect_data
+    }
+
+    pub fn apply_to_device(&self) {
+        let pass = 0;
+        self.device
+            .apply_effect(self.effect, pass, &fna3d::utils::no_change_effect());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, fna3d::vertex::VertexLayout};
+
+    #[test]
+    fn test_vertex_layout() {
+        let offsets = Vertex::ELEMENTS
+            .iter()
+            .map(|e| e.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![0, 12, 16]);
+        assert_eq!(Vertex::DECLARATION.vertexStride, 24);
+        assert_eq!(Vertex::DECLARATION.elementCount, 3);
+        assert_eq!(
+            Vertex::ELEMENTS[2].vertexElementUsage,
+            fna3d::VertexElementUsage::TextureCoordinate as u32
+        );
+    }
+
+    #[test]
+    fn test_vertex_repr_args() {
+        #[derive(Debug, Clone, fna3d::vertex::Vertex)]
+        #[repr(C, align(16))]
+        struct Aligned {
+            #[vertex(Position)]
+            pos: [f32; 3],
+        }
+
+        assert_eq!(Aligned::DECLARATION.vertexStride, 16);
+        assert_eq!(Aligned::ELEMENTS.len(), 1);
+    }
+}
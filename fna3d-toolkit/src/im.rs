@@ -0,0 +1,183 @@
+//! Immediate-mode primitive API
+//!
+//! `begin`, `vertex`s and `end`, like the good old OpenGL. Handy for prototyping and debug
+//! visualization without setting up meshes:
+//!
+//! ```ignore
+//! im.begin(fna3d::PrimitiveType::LineStrip);
+//! im.vertex([0.0, 0.0], Color::white(), [0.0, 0.0]);
+//! im.vertex([100.0, 100.0], Color::white(), [0.0, 0.0]);
+//! im.end(&device);
+//! ```
+//!
+//! Vertices are uploaded to a dynamic vertex buffer used as a ring; we append with `NoOverwrite`
+//! and `Discard` the whole buffer when it's full, so the GPU never stalls on vertices in flight.
+
+use {
+    fna3d::{vertex::VertexLayout, Color},
+    std::mem,
+};
+
+use crate::gfx::{Shader2d, Vertex};
+
+/// Dynamic vertex buffer written as a ring
+#[derive(Debug)]
+struct VertexRing {
+    device: fna3d::Device,
+    buf: *mut fna3d::Buffer,
+    /// Capacity in vertices
+    cap: usize,
+    /// Next vertex index to write
+    pos: usize,
+}
+
+impl Drop for VertexRing {
+    fn drop(&mut self) {
+        self.device.add_dispose_vertex_buffer(self.buf);
+    }
+}
+
+impl VertexRing {
+    pub fn new(device: &fna3d::Device, cap: usize) -> Self {
+        let buf = device.gen_vertex_buffer(
+            true, // dynamic
+            fna3d::BufferUsage::WriteOnly,
+            (cap * mem::size_of::<Vertex>()) as u32,
+        );
+
+        Self {
+            device: device.clone(),
+            buf,
+            cap,
+            pos: 0,
+        }
+    }
+
+    /// Uploads the vertices and returns the index of the first one
+    ///
+    /// Make sure `verts.len() <= cap`.
+    pub fn push(&mut self, verts: &[Vertex]) -> usize {
+        let opts = if self.pos + verts.len() > self.cap {
+            // wrap around
+            self.pos = 0;
+            fna3d::SetDataOptions::Discard
+        } else {
+            fna3d::SetDataOptions::NoOverwrite
+        };
+
+        let base = self.pos;
+        self.device.set_vertex_buffer_data(
+            self.buf,
+            (base * mem::size_of::<Vertex>()) as u32,
+            verts,
+            opts,
+        );
+        self.pos += verts.len();
+
+        base
+    }
+}
+
+/// Immediate-mode renderer
+///
+/// Vertex positions are in pixels (with the matrix of [`Shader2d`]).
+#[derive(Debug)]
+pub struct Im {
+    ring: VertexRing,
+    vbind: fna3d::VertexBufferBinding,
+    shader: Shader2d,
+    /// 1x1 white texture used when no texture is set (owned by the device)
+    white: *mut fna3d::Texture,
+    texture: *mut fna3d::Texture,
+    /// Primitive type between `begin` and `end`
+    prim: Option<fna3d::PrimitiveType>,
+    verts: Vec<Vertex>,
+}
+
+impl Im {
+    /// Number of vertices the ring can hold
+    const N_VERTS: usize = 4096;
+
+    pub fn new(device: &fna3d::Device, shader: Shader2d) -> Self {
+        let ring = VertexRing::new(device, Self::N_VERTS);
+
+        let vbind = fna3d::VertexBufferBinding {
+            vertexBuffer: ring.buf,
+            vertexDeclaration: Vertex::DECLARATION,
+            vertexOffset: 0,
+            instanceFrequency: 0,
+        };
+
+        let white = device.defaults().white_texture();
+
+        Self {
+            ring,
+            vbind,
+            shader,
+            texture: white,
+            white,
+            prim: None,
+            verts: Vec::with_capacity(Self::N_VERTS),
+        }
+    }
+
+    /// Sets the texture used by following draws. Pass null to use a white texture
+    pub fn set_texture(&mut self, texture: *mut fna3d::Texture) {
+        self.texture = if texture.is_null() {
+            self.white
+        } else {
+            texture
+        };
+    }
+
+    pub fn begin(&mut self, prim: fna3d::PrimitiveType) {
+        assert!(
+            self.prim.is_none(),
+            "im: `begin` called twice without `end`"
+        );
+        self.prim = Some(prim);
+        self.verts.clear();
+    }
+
+    pub fn vertex(&mut self, pos: [f32; 2], color: Color, uv: [f32; 2]) {
+        assert!(
+            self.prim.is_some(),
+            "im: `vertex` called outside `begin`/`end`"
+        );
+        if self.verts.len() >= Self::N_VERTS {
+            log::warn!("im: too many vertices; ignored");
+            return;
+        }
+        self.verts
+            .push(Vertex::new([pos[0], pos[1], 0.0], uv, color));
+    }
+
+    /// Uploads the vertices and draws them
+    pub fn end(&mut self, device: &fna3d::Device) {
+        let prim = self.prim.take().expect("im: `end` called without `begin`");
+
+        let n_prims = self::n_primitives(prim, self.verts.len());
+        if n_prims == 0 {
+            return;
+        }
+
+        let base = self.ring.push(&self.verts);
+
+        self.shader.apply_to_device();
+        device.verify_sampler(0, self.texture, &fna3d::SamplerState::default());
+        device.apply_vertex_buffer_bindings_auto(&[self.vbind], 0);
+        device.draw_primitives(prim, base as u32, n_prims as u32);
+    }
+}
+
+/// Number of primitives made of `n_verts` vertices
+fn n_primitives(prim: fna3d::PrimitiveType, n_verts: usize) -> usize {
+    use fna3d::PrimitiveType as P;
+    match prim {
+        P::TriangleList => n_verts / 3,
+        P::TriangleStrip => n_verts.saturating_sub(2),
+        P::LineList => n_verts / 2,
+        P::LineStrip => n_verts.saturating_sub(1),
+        P::PointListExt => n_verts,
+    }
+}
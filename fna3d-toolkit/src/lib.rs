@@ -0,0 +1,219 @@
+//! Scaffolding for Rust-FNA3D applications
+//!
+//! Promoted from `fna3d/examples/common`: SDL2 window creation, device setup with sane default
+//! render states, the quad batcher and the immediate-mode primitive API.
+//!
+//! ```no_run
+//! use fna3d_toolkit::Init;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let mut init = Init::new("My game", (1280, 720))?;
+//!     init.run(|frame| {
+//!         frame.device.clear(
+//!             fna3d::ClearOptions::TARGET,
+//!             fna3d::Color::cornflower_blue().to_vec4(),
+//!             0.0,
+//!             0,
+//!         );
+//!         Ok(())
+//!     })
+//! }
+//! ```
+//!
+//! This is a small crate. For real games, you would need a bunch of things more: 2D geometry
+//! types, sub textures, input and maybe an asset manager. Check out ANF framework if you're
+//! interested!
+
+pub mod anim;
+pub mod atlas;
+pub mod batch;
+pub mod camera;
+pub mod capture;
+pub mod clip;
+pub mod console;
+pub mod cull;
+pub mod embedded;
+pub mod frame_graph;
+pub mod game_loop;
+pub mod gfx;
+pub mod im;
+pub mod lut;
+pub mod material;
+pub mod palette;
+pub mod particle;
+pub mod pipeline;
+pub mod prepass;
+pub mod sort;
+pub mod tilemap;
+
+pub use crate::game_loop::{Flow, Game, GameLoop, RenderTime, UpdateTime};
+
+pub use {anyhow, fna3d, sdl2};
+
+use {
+    anyhow::{Error, Result},
+    fna3d::ViewportExt,
+    sdl2::event::Event,
+    std::time::{Duration, Instant},
+};
+
+/// Lifetime of the application
+pub struct Init {
+    pub sdl: sdl2::Sdl,
+    pub vid: sdl2::VideoSubsystem,
+    pub win: sdl2::video::Window,
+    pub params: fna3d::PresentationParameters,
+    pub device: fna3d::Device,
+}
+
+impl Init {
+    /// Initializes the FNA3D device and the SDL2 window
+    ///
+    /// Viewport, rasterizer state and (alpha) blend state are set up.
+    pub fn new(title: &str, size: (u32, u32)) -> Result<Self> {
+        log::info!("FNA3D linked version: {}", fna3d::linked_version());
+        fna3d::utils::hook_log_functions_log_crate();
+
+        let (sdl, vid, win) = {
+            let flags = fna3d::prepare_window_attributes();
+
+            let sdl = sdl2::init().map_err(Error::msg)?;
+            let vid = sdl.video().map_err(Error::msg)?;
+            let win = vid
+                .window(title, size.0, size.1)
+                .set_window_flags(flags.0)
+                .position_centered()
+                .build()
+                .map_err(|e| e.to_string())
+                .map_err(Error::msg)?;
+
+            let size = fna3d::get_drawable_size(win.raw() as *mut _);
+            log::info!("FNA3D drawable size: [{}, {}]", size.0, size.1);
+
+            (sdl, vid, win)
+        };
+
+        let (params, device) = {
+            let mut params = fna3d::utils::default_params_from_window_handle(win.raw() as *mut _);
+            let do_debug = true;
+            // `params` are updated to the actual backbuffer configuration
+            let device = fna3d::Device::try_from_params(&mut params, do_debug)?;
+
+            {
+                let (max_tx, max_v_tx) = device.get_max_texture_slots();
+                log::info!("device max textures: {}", max_tx);
+                log::info!("device max vertex textures: {}", max_v_tx);
+            }
+
+            let vp = fna3d::Viewport::new(0, 0, params.backBufferWidth, params.backBufferHeight);
+            device.set_viewport(&vp);
+
+            let rst = fna3d::RasterizerState::default();
+            device.apply_rasterizer_state(&rst);
+
+            let bst = fna3d::BlendState::premultiplied();
+            device.set_blend_state(&bst);
+
+            (params, device)
+        };
+
+        Ok(Self {
+            sdl,
+            vid,
+            win,
+            params,
+            device,
+        })
+    }
+
+    /// Use it when calling [`fna3d::Device::swap_buffers`]
+    pub fn raw_window(&self) -> *mut sdl2::sys::SDL_Window {
+        self.win.raw()
+    }
+
+    /// Runs the main loop at 60 FPS until the window is closed or [`Frame::quit`] is called
+    ///
+    /// The back buffer is presented after each call of `tick`. Use [`GameLoop`] to separate update
+    /// from render.
+    pub fn run(&mut self, tick: impl FnMut(&mut Frame) -> Result<()>) -> Result<()> {
+        self.run_with_fps(60, tick)
+    }
+
+    /// Runs the main loop with fixed timestep
+    ///
+    /// `tick` is called once per `1 / fps` seconds with a fixed `dt`. If we fall behind, `tick` is
+    /// called multiple times (up to [`MAX_CATCH_UP`]) without presenting in between.
+    pub fn run_with_fps(
+        &mut self,
+        fps: u32,
+        mut tick: impl FnMut(&mut Frame) -> Result<()>,
+    ) -> Result<()> {
+        let dt = Duration::from_nanos(1_000_000_000 / fps as u64);
+        let mut pump = self.sdl.event_pump().map_err(Error::msg)?;
+
+        let mut next = Instant::now();
+        let mut frame = Frame {
+            device: self.device.clone(),
+            dt,
+            events: Vec::new(),
+            quit: false,
+        };
+
+        loop {
+            frame.events.clear();
+            for ev in pump.poll_iter() {
+                if let Event::Quit { .. } = ev {
+                    frame.quit = true;
+                }
+                frame.events.push(ev);
+            }
+            if frame.quit {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now < next {
+                std::thread::sleep(next - now);
+            }
+
+            let mut n_ticks = 0;
+            while next <= Instant::now() && n_ticks < MAX_CATCH_UP {
+                tick(&mut frame)?;
+                frame.events.clear();
+                next += dt;
+                n_ticks += 1;
+            }
+            if n_ticks == MAX_CATCH_UP {
+                // too slow; give up catching up
+                next = Instant::now() + dt;
+            }
+
+            if frame.quit {
+                return Ok(());
+            }
+
+            self.device
+                .swap_buffers(None, None, self.raw_window() as *mut _);
+        }
+    }
+}
+
+/// Maximum number of ticks run in one loop iteration
+pub const MAX_CATCH_UP: u32 = 5;
+
+/// Per-tick context given to the [`Init::run`] closure
+pub struct Frame {
+    pub device: fna3d::Device,
+    /// Fixed delta time
+    pub dt: Duration,
+    /// SDL events polled since the last tick
+    pub events: Vec<Event>,
+    quit: bool,
+}
+
+impl Frame {
+    /// Stops the main loop after this tick
+    pub fn quit(&mut self) {
+        self.quit = true;
+    }
+}
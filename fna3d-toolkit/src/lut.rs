@@ -0,0 +1,170 @@
+//! Color gradient look-up textures
+//!
+//! A gradient is baked into an `N` x 1 `Color` texture and sampled in effects with the particle
+//! age (or any other scalar in `[0, 1]`) as the U coordinate. It's a cheap way to animate colors
+//! on the GPU.
+//!
+//! # Sampling
+//!
+//! Two details matter on this API:
+//!
+//! * The sampler has to clamp: wrapping makes `t = 1.0` blend with the first texel.
+//! * `t` has to be mapped to texel centers: `u = t * (N - 1) / N + 0.5 / N`, or the ends of the
+//!   gradient are blended with half a texel of nothing. [`GradientLut::uv_scale_offset`] gives the
+//!   `(scale, offset)` for `u = t * scale + offset`.
+//!
+//! ```hlsl
+//! sampler LutSampler : register(s1);
+//! float2 LutScaleOffset;
+//! // ..
+//! float4 color = tex2D(LutSampler, float2(t * LutScaleOffset.x + LutScaleOffset.y, 0.5));
+//! ```
+
+use std::ffi::CStr;
+
+use fna3d::Color;
+
+use crate::gfx::Texture2dDrop;
+
+/// Color stops on `[0, 1]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// `(position, color)` sorted by position
+    stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /// Stops are sorted by position ([`f32::total_cmp`], so NaN positions don't panic)
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Self {
+        assert!(!stops.is_empty(), "gradient without stops");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// Linear gradient between two colors
+    pub fn linear(from: Color, to: Color) -> Self {
+        Self::new(vec![(0.0, from), (1.0, to)])
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Linearly interpolated color at `t` (clamped to the first/last stop)
+    pub fn sample(&self, t: f32) -> Color {
+        let first = self.stops[0];
+        if t <= first.0 {
+            return first.1;
+        }
+
+        for w in self.stops.windows(2) {
+            let ((t0, c0), (t1, c1)) = (w[0], w[1]);
+            if t <= t1 {
+                let x = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                let (a, b) = (c0.to_vec4(), c1.to_vec4());
+                let mut v = a;
+                v.x += (b.x - a.x) * x;
+                v.y += (b.y - a.y) * x;
+                v.z += (b.z - a.z) * x;
+                v.w += (b.w - a.w) * x;
+                return Color::from_vec4(v);
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+
+    /// RGBA pixels of `width` samples from `0.0` to `1.0` (both inclusive)
+    pub fn bake(&self, width: u32) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(4 * width as usize);
+        for i in 0..width {
+            let t = if width > 1 {
+                i as f32 / (width - 1) as f32
+            } else {
+                0.0
+            };
+            let c = self.sample(t).raw();
+            pixels.extend_from_slice(&[c.r, c.g, c.b, c.a]);
+        }
+        pixels
+    }
+}
+
+/// Gradient baked into a `width` x 1 texture with a clamping sampler
+#[derive(Debug)]
+pub struct GradientLut {
+    texture: Texture2dDrop,
+    sampler: fna3d::SamplerState,
+}
+
+impl GradientLut {
+    /// 256 is enough for 8 bits per channel
+    pub fn new(device: &fna3d::Device, gradient: &Gradient, width: u32) -> Self {
+        let pixels = gradient.bake(width);
+        let texture = Texture2dDrop::from_decoded_bytes(device, width, 1, &pixels);
+
+        // no mipmaps, so the sampler never blends with smaller levels
+        let sampler = fna3d::SamplerState::linear_clamp();
+
+        Self { texture, sampler }
+    }
+
+    /// Re-bakes the gradient into the texture (the width is kept)
+    pub fn update(&mut self, device: &fna3d::Device, gradient: &Gradient) {
+        let w = self.texture.w;
+        device.set_texture_data_2d(self.texture.raw, 0, 0, w, 1, 0, &gradient.bake(w));
+    }
+
+    pub fn texture(&self) -> &Texture2dDrop {
+        &self.texture
+    }
+
+    pub fn sampler(&self) -> &fna3d::SamplerState {
+        &self.sampler
+    }
+
+    /// `(scale, offset)` that maps `t` in `[0, 1]` to the centers of the first and last texels
+    pub fn uv_scale_offset(&self) -> [f32; 2] {
+        let n = self.texture.w as f32;
+        [(n - 1.0) / n, 0.5 / n]
+    }
+
+    /// Binds the texture and the clamping sampler to the sampler `slot` of effects
+    pub fn bind(&self, device: &fna3d::Device, slot: u32) {
+        device.verify_sampler(slot, self.texture.raw, &self.sampler);
+    }
+
+    /// Sets [`GradientLut::uv_scale_offset`] to a `float2` effect parameter
+    ///
+    /// Returns false if the parameter is not found.
+    ///
+    /// # Safety
+    ///
+    /// `effect_data` has to be a valid effect (see [`fna3d::mojo::set_param`]).
+    pub unsafe fn set_param(&self, effect_data: *mut fna3d::mojo::Effect, name: &CStr) -> bool {
+        fna3d::mojo::set_param(effect_data, name, &self.uv_scale_offset())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gradient_sample() {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::white();
+        let grad = Gradient::new(vec![(1.0, white), (0.0, black)]);
+
+        assert_eq!(grad.sample(-1.0), black);
+        assert_eq!(grad.sample(2.0), white);
+        assert_eq!(grad.sample(0.5).raw().r, 127);
+
+        let pixels = grad.bake(3);
+        assert_eq!(&pixels[0..4], &[0, 0, 0, 255]);
+        assert_eq!(&pixels[8..12], &[255, 255, 255, 255]);
+
+        let grad = Gradient::new(vec![(f32::NAN, white), (0.0, black)]);
+        assert_eq!(grad.stops()[0], (0.0, black));
+    }
+}
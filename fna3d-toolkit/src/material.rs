@@ -0,0 +1,218 @@
+//! Material: a pipeline, effect parameters and textures applied together
+//!
+//! ```ignore
+//! let effect = Rc::new(EffectHandle::from_bytes(&device, include_bytes!("lit.fxb"))?);
+//!
+//! let mut material = Material::new(effect, Pipeline::from_file("assets/lit.ron")?);
+//! material.params.set_vec4("Tint", [1.0, 0.8, 0.8, 1.0]);
+//! material.set_texture(0, albedo.raw(), None);
+//!
+//! material.apply(&device)?;
+//! // draw calls
+//! ```
+
+use {
+    anyhow::{anyhow, Result},
+    fna3d::{mojo, res::EffectHandle, SamplerState},
+    std::{ffi::CString, rc::Rc},
+};
+
+use crate::pipeline::Pipeline;
+
+/// Value of an effect parameter
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec4([f32; 4]),
+    /// Column-major 4x4 matrix
+    Matrix([f32; 16]),
+}
+
+/// Named effect parameter values, written to the effect on [`EffectParams::apply`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectParams {
+    values: Vec<(CString, ParamValue)>,
+}
+
+impl EffectParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a parameter, replacing the previous value of the name
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name contains a nul byte.
+    pub fn set(&mut self, name: &str, value: ParamValue) {
+        let name = CString::new(name).expect("parameter name with a nul byte");
+        match self.values.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.values.push((name, value)),
+        }
+    }
+
+    pub fn set_f32(&mut self, name: &str, value: f32) {
+        self.set(name, ParamValue::Float(value));
+    }
+
+    pub fn set_vec2(&mut self, name: &str, value: [f32; 2]) {
+        self.set(name, ParamValue::Vec2(value));
+    }
+
+    pub fn set_vec4(&mut self, name: &str, value: [f32; 4]) {
+        self.set(name, ParamValue::Vec4(value));
+    }
+
+    pub fn set_matrix(&mut self, name: &str, value: [f32; 16]) {
+        self.set(name, ParamValue::Matrix(value));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.values
+            .iter()
+            .find(|(n, _)| n.as_bytes() == name.as_bytes())
+            .map(|(_, v)| v)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<ParamValue> {
+        let i = self
+            .values
+            .iter()
+            .position(|(n, _)| n.as_bytes() == name.as_bytes())?;
+        Some(self.values.remove(i).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Writes the values to the effect. Fails on the first parameter the effect doesn't have
+    pub fn apply(&self, effect: &EffectHandle) -> Result<()> {
+        for (name, value) in &self.values {
+            let found = unsafe {
+                match value {
+                    ParamValue::Float(v) => mojo::set_param(effect.data(), name, v),
+                    ParamValue::Vec2(v) => mojo::set_param(effect.data(), name, v),
+                    ParamValue::Vec4(v) => mojo::set_param(effect.data(), name, v),
+                    ParamValue::Matrix(v) => mojo::set_param(effect.data(), name, v),
+                }
+            };
+            if !found {
+                return Err(anyhow!(
+                    "the effect doesn't have parameter `{}`",
+                    name.to_string_lossy()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Texture bound to a sampler slot by [`Material::apply`]
+#[derive(Debug, Clone)]
+pub struct TextureSlot {
+    pub texture: *mut fna3d::Texture,
+    /// `None` to use the sampler state of the pipeline (or the default one)
+    pub sampler: Option<SamplerState>,
+}
+
+/// Effect, render states, parameters and textures
+///
+/// The effect is shared (`Rc`) so that materials can be variations of the same effect. Parameters
+/// are written to the effect on every [`Material::apply`], so sharing is fine.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub effect: Rc<EffectHandle>,
+    pub pipeline: Pipeline,
+    pub params: EffectParams,
+    /// Indexed by sampler slot
+    textures: Vec<Option<TextureSlot>>,
+}
+
+impl Material {
+    pub fn new(effect: Rc<EffectHandle>, pipeline: Pipeline) -> Self {
+        Self {
+            effect,
+            pipeline,
+            params: EffectParams::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    /// Sets the texture of the sampler `slot`. Pass null to unset it
+    pub fn set_texture(
+        &mut self,
+        slot: u32,
+        texture: *mut fna3d::Texture,
+        sampler: Option<SamplerState>,
+    ) {
+        let slot = slot as usize;
+        if slot >= self.textures.len() {
+            self.textures.resize(slot + 1, None);
+        }
+        self.textures[slot] = if texture.is_null() {
+            None
+        } else {
+            Some(TextureSlot { texture, sampler })
+        };
+    }
+
+    pub fn texture(&self, slot: u32) -> Option<&TextureSlot> {
+        self.textures.get(slot as usize).and_then(|t| t.as_ref())
+    }
+
+    /// [`Material::apply_pass`] with the first pass
+    pub fn apply(&self, device: &fna3d::Device) -> Result<()> {
+        self.apply_pass(device, 0)
+    }
+
+    /// Applies everything for following draw calls:
+    ///
+    /// 1. the render states of the pipeline
+    /// 2. the technique of the pipeline (if any)
+    /// 3. the effect parameters
+    /// 4. the pass, which uploads the parameters
+    /// 5. the textures and sampler states
+    pub fn apply_pass(&self, device: &fna3d::Device, pass: u32) -> Result<()> {
+        self.pipeline.apply(device);
+        self.pipeline.select_technique(&self.effect)?;
+        self.params.apply(&self.effect)?;
+
+        device.apply_effect(self.effect.raw(), pass, &fna3d::utils::no_change_effect());
+
+        let fallback = SamplerState::default();
+        let pipeline_sampler = self.pipeline.sampler.as_ref().unwrap_or(&fallback);
+        for (slot, tex) in self.textures.iter().enumerate() {
+            if let Some(tex) = tex {
+                let sampler = tex.sampler.as_ref().unwrap_or(pipeline_sampler);
+                device.verify_sampler(slot as u32, tex.texture, sampler);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_effect_params() {
+        let mut params = EffectParams::new();
+        params.set_f32("Time", 1.0);
+        params.set_vec4("Tint", [1.0; 4]);
+        params.set_f32("Time", 2.0);
+
+        assert_eq!(params.len(), 2);
+        assert_eq!(params.get("Time"), Some(&ParamValue::Float(2.0)));
+        assert_eq!(params.remove("Tint"), Some(ParamValue::Vec4([1.0; 4])));
+        assert_eq!(params.get("Tint"), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Palette swap (index color) rendering
+//!
+//! Sprites are stored as `Alpha8` textures of color indices and colored through a palette texture
+//! on the GPU, so swapping palettes is just swapping a 256 x 1 texture.
+//!
+//! The effect source is [`crate::embedded::PALETTE_SHADER_SOURCE`]. Compile it into `.fxb` with
+//! `fxc /T fx_2_0 PaletteEffect.fx /Fo PaletteEffect.fxb`.
+//!
+//! ```ignore
+//! let shader = Shader2d::from_bytes(&device, include_bytes!("PaletteEffect.fxb"), w, h)?;
+//! let mut batcher = Batcher::new(&device, shader)?;
+//! // index textures must not be filtered
+//! batcher.set_sampler(Palette::index_sampler());
+//!
+//! let palette = Palette::new(&device, &colors);
+//! palette.set_param(batcher.shader());
+//! let sprite = Texture2dDrop::from_indices(&device, w, h, &indices);
+//!
+//! // render
+//! palette.bind(&device);
+//! batcher.push_region(&sprite.full_region(), [x, y, w, h], Color::white());
+//! batcher.flush();
+//! ```
+//!
+//! # Exact lookups
+//!
+//! Both textures are sampled with [`fna3d::SamplerState::point_clamp`]: linear filtering would
+//! blend neighboring indices (which are unrelated colors), and mipmaps would average them.
+
+use std::ffi::CString;
+
+use fna3d::Color;
+
+use crate::gfx::{Shader2d, Texture2dDrop};
+
+/// Sampler slot of the palette texture in `PaletteEffect.fx`
+pub const PALETTE_SLOT: u32 = 1;
+
+/// Number of colors in a palette texture (indices are bytes)
+pub const PALETTE_SIZE: u32 = 256;
+
+/// 256 x 1 color texture
+#[derive(Debug)]
+pub struct Palette {
+    texture: Texture2dDrop,
+}
+
+impl Palette {
+    /// Unused entries (up to 256) are transparent
+    pub fn new(device: &fna3d::Device, colors: &[Color]) -> Self {
+        let texture = Texture2dDrop::from_decoded_bytes(
+            device,
+            PALETTE_SIZE,
+            1,
+            &self::palette_pixels(colors),
+        );
+        Self { texture }
+    }
+
+    /// Point sampler for index textures
+    pub fn index_sampler() -> fna3d::SamplerState {
+        fna3d::SamplerState::point_clamp()
+    }
+
+    /// Replaces the colors (palette swap)
+    pub fn set_colors(&mut self, device: &fna3d::Device, colors: &[Color]) {
+        let pixels = self::palette_pixels(colors);
+        device.set_texture_data_2d(self.texture.raw, 0, 0, PALETTE_SIZE, 1, 0, &pixels);
+    }
+
+    pub fn texture(&self) -> &Texture2dDrop {
+        &self.texture
+    }
+
+    /// Binds the palette to [`PALETTE_SLOT`] with a point clamp sampler
+    ///
+    /// The batcher only touches slot `0`, so it stays bound until something else is bound there.
+    pub fn bind(&self, device: &fna3d::Device) {
+        device.verify_sampler(
+            PALETTE_SLOT,
+            self.texture.raw,
+            &fna3d::SamplerState::point_clamp(),
+        );
+    }
+
+    /// Sets the `PaletteSize` parameter of the effect. Returns false if it's not found
+    pub fn set_param(&self, shader: &Shader2d) -> bool {
+        let name = CString::new("PaletteSize").unwrap();
+        unsafe { fna3d::mojo::set_param(shader.effect_data(), &name, &(PALETTE_SIZE as f32)) }
+    }
+}
+
+fn palette_pixels(colors: &[Color]) -> Vec<u8> {
+    if colors.len() > PALETTE_SIZE as usize {
+        log::warn!(
+            "palette has {} colors; only the first {} are used",
+            colors.len(),
+            PALETTE_SIZE
+        );
+    }
+
+    let mut pixels = vec![0; 4 * PALETTE_SIZE as usize];
+    for (px, c) in pixels.chunks_mut(4).zip(colors.iter()) {
+        let c = c.raw();
+        px.copy_from_slice(&[c.r, c.g, c.b, c.a]);
+    }
+    pixels
+}
@@ -0,0 +1,279 @@
+//! CPU-simulated particles rendered with hardware instancing or batched quads
+//!
+//! [`ParticleBuffer::draw`] uses instancing if it's enabled with
+//! [`ParticleBuffer::enable_instancing`] and the device supports it. Otherwise particles are pushed
+//! to the [`Batcher`] as quads, so the same code runs everywhere.
+//!
+//! # Instancing effect
+//!
+//! `SpriteEffect` doesn't read per-instance data, so instancing requires your own effect. It's
+//! given two vertex streams:
+//!
+//! ```hlsl
+//! void VS(
+//!     // per vertex (unit quad centered at the origin)
+//!     float4 corner : POSITION0, float4 color : COLOR0, float2 uv : TEXCOORD0,
+//!     // per instance ([`InstanceData`])
+//!     float3 pos_scale : TEXCOORD1, float4 tint : COLOR1,
+//!     out float4 outColor : COLOR0, out float2 outUv : TEXCOORD0, out float4 outPos : SV_Position)
+//! {
+//!     float2 world = pos_scale.xy + corner.xy * pos_scale.z;
+//!     outPos = mul(float4(world, 0, 1), MatrixTransform);
+//!     outColor = color * tint;
+//!     outUv = uv;
+//! }
+//! ```
+//!
+//! Set the effect parameters (e.g. the projection matrix) yourself before drawing.
+
+use {
+    fna3d::{vertex::VertexLayout, Color},
+    std::mem,
+    std::time::Duration,
+};
+
+use crate::{
+    batch::Batcher,
+    gfx::{TextureRegion, Vertex},
+};
+
+/// Simulated on CPU
+#[derive(Debug, Clone)]
+pub struct Particle {
+    /// Center position in pixels
+    pub pos: [f32; 2],
+    /// Pixels per second
+    pub vel: [f32; 2],
+    pub color: Color,
+    /// Size relative to the texture region
+    pub scale: f32,
+    /// Remaining lifetime in seconds
+    pub life: f32,
+}
+
+/// Per-instance vertex data
+#[derive(Debug, Clone, fna3d::vertex::Vertex)]
+#[repr(C)]
+pub struct InstanceData {
+    /// `[x, y, scale]`
+    #[vertex(TextureCoordinate, 1)]
+    pub pos_scale: [f32; 3],
+    #[vertex(Color, 1)]
+    pub color: Color,
+}
+
+/// GPU resources for the instancing path
+#[derive(Debug)]
+struct Instancing {
+    device: fna3d::Device,
+    effect: *mut fna3d::Effect,
+    /// Four vertices of the unit quad (re-uploaded when the texture region changes)
+    quad: *mut fna3d::Buffer,
+    quad_uv: [f32; 4],
+    ibuf: *mut fna3d::Buffer,
+    instances: *mut fna3d::Buffer,
+    /// Staging buffer of the instance data
+    data: Vec<InstanceData>,
+}
+
+impl Drop for Instancing {
+    fn drop(&mut self) {
+        self.device.add_dispose_vertex_buffer(self.quad);
+        self.device.add_dispose_vertex_buffer(self.instances);
+        self.device.add_dispose_index_buffer(self.ibuf);
+    }
+}
+
+impl Instancing {
+    fn new(device: &fna3d::Device, effect: *mut fna3d::Effect, capacity: usize) -> Self {
+        let quad = device.gen_vertex_buffer(
+            true,
+            fna3d::BufferUsage::WriteOnly,
+            4 * mem::size_of::<Vertex>() as u32,
+        );
+
+        let ibuf = device.gen_index_buffer(
+            false,
+            fna3d::BufferUsage::WriteOnly,
+            6 * mem::size_of::<u16>() as u32,
+        );
+        device.set_index_buffer_data(ibuf, 0, &[0u16, 1, 2, 3, 2, 1], fna3d::SetDataOptions::None);
+
+        let instances = device.gen_vertex_buffer(
+            true,
+            fna3d::BufferUsage::WriteOnly,
+            (capacity * mem::size_of::<InstanceData>()) as u32,
+        );
+
+        Self {
+            device: device.clone(),
+            effect,
+            quad,
+            // invalid so that the first draw uploads the quad
+            quad_uv: [-1.0; 4],
+            ibuf,
+            instances,
+            data: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn draw(&mut self, particles: &[Particle], region: &TextureRegion) {
+        let device = &self.device;
+
+        if self.quad_uv != region.uv_rect {
+            self.quad_uv = region.uv_rect;
+            let [w, h] = region.size;
+            let quad = crate::batch::QuadData::from_rects(
+                [-w / 2.0, -h / 2.0, w, h],
+                region.uv_rect,
+                Color::white(),
+            );
+            device.set_vertex_buffer_data(self.quad, 0, &quad.0, fna3d::SetDataOptions::Discard);
+        }
+
+        self.data.clear();
+        self.data.extend(particles.iter().map(|p| InstanceData {
+            pos_scale: [p.pos[0], p.pos[1], p.scale],
+            color: p.color,
+        }));
+        device.set_vertex_buffer_data(
+            self.instances,
+            0,
+            &self.data,
+            fna3d::SetDataOptions::Discard,
+        );
+
+        device.apply_effect(self.effect, 0, &fna3d::utils::no_change_effect());
+        device.verify_sampler(0, region.texture, &fna3d::SamplerState::default());
+
+        let bindings = [
+            fna3d::VertexBufferBinding {
+                vertexBuffer: self.quad,
+                vertexDeclaration: Vertex::DECLARATION,
+                vertexOffset: 0,
+                instanceFrequency: 0,
+            },
+            fna3d::VertexBufferBinding {
+                vertexBuffer: self.instances,
+                vertexDeclaration: InstanceData::DECLARATION,
+                vertexOffset: 0,
+                instanceFrequency: 1,
+            },
+        ];
+        device.apply_vertex_buffer_bindings(&bindings, true, 0);
+
+        device.draw_instanced_primitives(
+            fna3d::PrimitiveType::TriangleList,
+            0,
+            0,
+            4,
+            0,
+            2,
+            particles.len() as u32,
+            self.ibuf,
+            fna3d::IndexElementSize::Bits16,
+        );
+    }
+}
+
+/// Fixed capacity set of particles
+#[derive(Debug)]
+pub struct ParticleBuffer {
+    particles: Vec<Particle>,
+    capacity: usize,
+    /// Acceleration in pixels per second squared
+    pub gravity: [f32; 2],
+    instancing: Option<Instancing>,
+}
+
+impl ParticleBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            capacity,
+            gravity: [0.0, 0.0],
+            instancing: None,
+        }
+    }
+
+    /// Draws with instancing using the `effect` (see the module document for its inputs)
+    ///
+    /// Returns `false` (and keeps using batched quads) if the device doesn't support hardware
+    /// instancing. The effect is not owned by the buffer.
+    pub fn enable_instancing(
+        &mut self,
+        device: &fna3d::Device,
+        effect: *mut fna3d::Effect,
+    ) -> bool {
+        if !device.supports_hardware_instancing() {
+            log::info!("hardware instancing is not supported; particles fall back to batching");
+            return false;
+        }
+
+        self.instancing = Some(Instancing::new(device, effect, self.capacity));
+        true
+    }
+
+    pub fn is_instancing(&self) -> bool {
+        self.instancing.is_some()
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Returns `false` if the buffer is full
+    pub fn emit(&mut self, particle: Particle) -> bool {
+        if self.particles.len() >= self.capacity {
+            return false;
+        }
+        self.particles.push(particle);
+        true
+    }
+
+    /// Integrates positions and removes dead particles
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        let gravity = self.gravity;
+
+        for p in self.particles.iter_mut() {
+            p.vel[0] += gravity[0] * dt;
+            p.vel[1] += gravity[1] * dt;
+            p.pos[0] += p.vel[0] * dt;
+            p.pos[1] += p.vel[1] * dt;
+            p.life -= dt;
+        }
+
+        // order doesn't matter
+        let mut i = 0;
+        while i < self.particles.len() {
+            if self.particles[i].life <= 0.0 {
+                self.particles.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Draws the particles with the texture `region` centered at their positions
+    ///
+    /// With instancing, the batcher is flushed first so that draw order is kept.
+    pub fn draw(&mut self, batcher: &mut Batcher, region: &TextureRegion) {
+        if self.particles.is_empty() {
+            return;
+        }
+
+        if let Some(instancing) = self.instancing.as_mut() {
+            batcher.flush();
+            instancing.draw(&self.particles, region);
+            return;
+        }
+
+        let [w, h] = region.size;
+        for p in self.particles.iter() {
+            let (pw, ph) = (w * p.scale, h * p.scale);
+            let dst = [p.pos[0] - pw / 2.0, p.pos[1] - ph / 2.0, pw, ph];
+            batcher.push_region(region, dst, p.color);
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Render states applied together, and a stack of them
+//!
+//! FNA3D doesn't let us read back the current states, so [`PipelineStack`] remembers what it has
+//! applied: pushing a pipeline applies it, and popping it re-applies the previous one.
+//!
+//! Pipelines can be described in data files ([`PipelineDesc`]), so that render states can be
+//! tweaked without recompiling. Every field is optional and defaults to [`Pipeline::default`]:
+//!
+//! ```ron
+//! // outline.ron
+//! (
+//!     blend: (color_src: One, alpha_src: One),
+//!     depth_stencil: (depth_test: true, depth_function: LessEqual),
+//!     rasterizer: (cull: None),
+//!     sampler: Some((filter: Point, address_u: Clamp, address_v: Clamp)),
+//!     technique: Some("Outline"),
+//! )
+//! ```
+//!
+//! ```ignore
+//! let pipeline = Pipeline::from_file("assets/outline.ron")?;
+//! pipeline.select_technique(&effect)?;
+//! batcher.push_pipeline(pipeline);
+//! ```
+//!
+//! JSON files are always supported. RON and TOML require the `ron` and `toml` features.
+
+use {
+    anyhow::{anyhow, Result},
+    fna3d::{
+        context::GraphicsContext, Blend, BlendFunction, BlendState, Color, ColorWriteChannels,
+        CompareFunction, CullMode, DepthStencilState, FillMode, RasterizerState, SamplerState,
+        StencilOperation, TextureAddressMode, TextureFilter,
+    },
+    serde::{Deserialize, Serialize},
+    std::path::Path,
+};
+
+/// Blend, depth/stencil and rasterizer states, and optionally the sampler state and the effect
+/// technique
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    pub blend: BlendState,
+    pub depth_stencil: DepthStencilState,
+    pub rasterizer: RasterizerState,
+    /// Sampler state of slot `0`. [`crate::batch::Batcher`] uses it in place of its own
+    pub sampler: Option<SamplerState>,
+    /// Name of the effect technique (see [`Pipeline::select_technique`])
+    pub technique: Option<String>,
+}
+
+/// Premultiplied alpha blending and the default rasterizer state (as set up by [`crate::Init`]),
+/// no depth test
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            blend: BlendState::premultiplied(),
+            depth_stencil: DepthStencilState::none(),
+            rasterizer: RasterizerState::default(),
+            sampler: None,
+            technique: None,
+        }
+    }
+}
+
+impl Pipeline {
+    /// Additive blending without depth test, for overdraw visualization
+    ///
+    /// Every fragment adds its color to the render target regardless of what's behind it, so
+    /// drawing a constant small color results in the number of layers per pixel.
+    pub fn overdraw() -> Self {
+        Self {
+            blend: BlendState::with_blend(
+                fna3d::Blend::One,
+                fna3d::Blend::One,
+                fna3d::Blend::One,
+                fna3d::Blend::One,
+            ),
+            depth_stencil: DepthStencilState::none(),
+            ..Self::default()
+        }
+    }
+
+    /// Loads a [`PipelineDesc`] file (see the module document)
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(PipelineDesc::from_file(path)?.to_pipeline())
+    }
+
+    /// Applies the render states. The sampler state and the technique are not applied because
+    /// they need a texture and an effect
+    pub fn apply(&self, device: &impl GraphicsContext) {
+        device.set_blend_state(&self.blend);
+        device.set_depth_stencil_state(&self.depth_stencil);
+        device.apply_rasterizer_state(&self.rasterizer);
+    }
+
+    /// Sets the technique of the effect by name. Does nothing if the pipeline doesn't have one
+    pub fn select_technique(&self, effect: &fna3d::res::EffectHandle) -> Result<()> {
+        let name = match self.technique.as_ref() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let tech = effect
+            .technique_by_name(name)
+            .ok_or_else(|| anyhow!("the effect doesn't have technique `{}`", name))?;
+        effect.set_technique(tech)?;
+        Ok(())
+    }
+}
+
+/// Stack of [`Pipeline`]s over a base pipeline, which is never popped
+#[derive(Debug, Clone)]
+pub struct PipelineStack {
+    stack: Vec<Pipeline>,
+}
+
+impl PipelineStack {
+    pub fn new(base: Pipeline) -> Self {
+        Self { stack: vec![base] }
+    }
+
+    /// The last applied pipeline
+    pub fn current(&self) -> &Pipeline {
+        self.stack.last().unwrap()
+    }
+
+    /// Number of pushed pipelines (the base is not counted)
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    /// Applies the pipeline and pushes it
+    pub fn push(&mut self, device: &impl GraphicsContext, pipeline: Pipeline) {
+        pipeline.apply(device);
+        self.stack.push(pipeline);
+    }
+
+    /// Pops the last pipeline and re-applies the previous one. Returns `None` if only the base
+    /// is left
+    pub fn pop(&mut self, device: &impl GraphicsContext) -> Option<Pipeline> {
+        if self.stack.len() <= 1 {
+            return None;
+        }
+        let popped = self.stack.pop();
+        self.current().apply(device);
+        popped
+    }
+
+    /// Re-applies the current pipeline, e.g. after the states are changed directly on the device
+    pub fn reapply(&self, device: &impl GraphicsContext) {
+        self.current().apply(device);
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Pipeline files
+
+/// Serializable [`Pipeline`]. Missing fields default to [`Pipeline::default`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipelineDesc {
+    pub blend: BlendDesc,
+    pub depth_stencil: DepthStencilDesc,
+    pub rasterizer: RasterizerDesc,
+    pub sampler: Option<SamplerDesc>,
+    pub technique: Option<String>,
+}
+
+impl Default for PipelineDesc {
+    fn default() -> Self {
+        Self::from_pipeline(&Pipeline::default())
+    }
+}
+
+impl PipelineDesc {
+    pub fn from_pipeline(p: &Pipeline) -> Self {
+        Self {
+            blend: BlendDesc::from_state(&p.blend),
+            depth_stencil: DepthStencilDesc::from_state(&p.depth_stencil),
+            rasterizer: RasterizerDesc::from_state(&p.rasterizer),
+            sampler: p.sampler.as_ref().map(SamplerDesc::from_state),
+            technique: p.technique.clone(),
+        }
+    }
+
+    pub fn to_pipeline(&self) -> Pipeline {
+        Pipeline {
+            blend: self.blend.to_state(),
+            depth_stencil: self.depth_stencil.to_state(),
+            rasterizer: self.rasterizer.to_state(),
+            sampler: self.sampler.as_ref().map(SamplerDesc::to_state),
+            technique: self.technique.clone(),
+        }
+    }
+
+    pub fn from_json(s: &str) -> Result<Self> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    #[cfg(feature = "ron")]
+    pub fn from_ron(s: &str) -> Result<Self> {
+        Ok(ron::from_str(s)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Loads a `.json`, `.ron` or `.toml` file, selected by the extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+        let desc = match ext {
+            "json" => Self::from_json(&text),
+            #[cfg(feature = "ron")]
+            "ron" => Self::from_ron(&text),
+            #[cfg(feature = "toml")]
+            "toml" => Self::from_toml(&text),
+            _ => Err(anyhow!(
+                "unsupported pipeline file (enable the `ron` or `toml` feature?)"
+            )),
+        };
+        desc.map_err(|e| anyhow!("failed to load {}: {}", path.display(), e))
+    }
+}
+
+/// Serializable [`BlendState`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlendDesc {
+    pub color_src: Blend,
+    pub color_dest: Blend,
+    pub color_fn: BlendFunction,
+    pub alpha_src: Blend,
+    pub alpha_dest: Blend,
+    pub alpha_fn: BlendFunction,
+    pub color_write: ColorWriteChannels,
+    pub blend_factor: Color,
+}
+
+impl Default for BlendDesc {
+    fn default() -> Self {
+        Self::from_state(&Pipeline::default().blend)
+    }
+}
+
+impl BlendDesc {
+    pub fn from_state(b: &BlendState) -> Self {
+        let f = b.raw().blendFactor;
+        Self {
+            color_src: b.color_src_blend(),
+            color_dest: b.color_dest_blend(),
+            color_fn: b.color_blend_fn(),
+            alpha_src: b.alpha_src_blend(),
+            alpha_dest: b.alpha_dest_blend(),
+            alpha_fn: b.alpha_blend_fn(),
+            color_write: b.color_write_enable(),
+            blend_factor: Color::rgba(f.r, f.g, f.b, f.a),
+        }
+    }
+
+    pub fn to_state(&self) -> BlendState {
+        let mut b = BlendState::default();
+        b.set_color_src_blend(self.color_src);
+        b.set_color_dest_blend(self.color_dest);
+        b.set_color_blend_fn(self.color_fn);
+        b.set_alpha_src_blend(self.alpha_src);
+        b.set_alpha_dest_blend(self.alpha_dest);
+        b.set_alpha_blend_fn(self.alpha_fn);
+        b.set_color_write_enable(self.color_write);
+        b.raw_mut().blendFactor = self.blend_factor.raw();
+        b
+    }
+}
+
+/// Serializable [`DepthStencilState`] (the counter-clockwise stencil settings are the same as the
+/// clockwise ones)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DepthStencilDesc {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_function: CompareFunction,
+    pub stencil: bool,
+    pub stencil_function: CompareFunction,
+    pub stencil_pass: StencilOperation,
+    pub stencil_fail: StencilOperation,
+    pub stencil_depth_fail: StencilOperation,
+    pub stencil_mask: i32,
+    pub stencil_write_mask: i32,
+    pub reference_stencil: i32,
+}
+
+impl Default for DepthStencilDesc {
+    fn default() -> Self {
+        Self::from_state(&Pipeline::default().depth_stencil)
+    }
+}
+
+impl DepthStencilDesc {
+    pub fn from_state(d: &DepthStencilState) -> Self {
+        let raw = d.raw();
+        Self {
+            depth_test: d.is_depth_buffer_enabled(),
+            depth_write: d.is_depth_buffer_write_enabled(),
+            depth_function: d.depth_buffer_function(),
+            stencil: d.is_stencil_enabled(),
+            stencil_function: CompareFunction::from_raw(raw.stencilFunction)
+                .unwrap_or(CompareFunction::Always),
+            stencil_pass: d.stencil_pass(),
+            stencil_fail: d.stencil_fail(),
+            stencil_depth_fail: d.stencil_depth_buffer_fail(),
+            stencil_mask: raw.stencilMask,
+            stencil_write_mask: raw.stencilWriteMask,
+            reference_stencil: raw.referenceStencil,
+        }
+    }
+
+    pub fn to_state(&self) -> DepthStencilState {
+        let mut d = DepthStencilState::default();
+        d.set_is_depth_buffer_enabled(self.depth_test);
+        d.set_is_depth_buffer_write_enabled(self.depth_write);
+        d.set_depth_buffer_function(self.depth_function);
+        d.set_is_stencil_enabled(self.stencil);
+
+        let raw = d.raw_mut();
+        raw.stencilFunction = self.stencil_function as u32;
+        raw.ccwStencilFunction = self.stencil_function as u32;
+        raw.stencilPass = self.stencil_pass as u32;
+        raw.ccwStencilPass = self.stencil_pass as u32;
+        raw.stencilFail = self.stencil_fail as u32;
+        raw.ccwStencilFail = self.stencil_fail as u32;
+        raw.stencilDepthBufferFail = self.stencil_depth_fail as u32;
+        raw.ccwStencilDepthBufferFail = self.stencil_depth_fail as u32;
+        raw.stencilMask = self.stencil_mask;
+        raw.stencilWriteMask = self.stencil_write_mask;
+        raw.referenceStencil = self.reference_stencil;
+        d
+    }
+}
+
+/// Serializable [`RasterizerState`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RasterizerDesc {
+    pub fill: FillMode,
+    pub cull: CullMode,
+    pub depth_bias: f32,
+    pub slope_scale_depth_bias: f32,
+    pub scissor_test: bool,
+    pub msaa: bool,
+}
+
+impl Default for RasterizerDesc {
+    fn default() -> Self {
+        Self::from_state(&Pipeline::default().rasterizer)
+    }
+}
+
+impl RasterizerDesc {
+    pub fn from_state(r: &RasterizerState) -> Self {
+        Self {
+            fill: r.fill_mode(),
+            cull: r.cull_mode(),
+            depth_bias: r.depth_bias(),
+            slope_scale_depth_bias: r.slope_scale_depth_bias(),
+            scissor_test: r.is_scissor_test_enabled(),
+            msaa: r.is_multi_sample_anti_alias_enabled(),
+        }
+    }
+
+    pub fn to_state(&self) -> RasterizerState {
+        let mut r = RasterizerState::default();
+        r.set_fill_mode(self.fill);
+        r.set_cull_mode(self.cull);
+        r.set_depth_bias(self.depth_bias);
+        r.set_slope_scale_depth_bias(self.slope_scale_depth_bias);
+        r.set_is_scissor_test_enabled(self.scissor_test);
+        r.set_is_multi_sample_anti_alias_enabled(self.msaa);
+        r
+    }
+}
+
+/// Serializable [`SamplerState`]. Missing fields default to [`SamplerState::default`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplerDesc {
+    pub filter: TextureFilter,
+    pub address_u: TextureAddressMode,
+    pub address_v: TextureAddressMode,
+    pub address_w: TextureAddressMode,
+    pub max_anisotropy: i32,
+    pub max_mip_level: i32,
+    pub lod_bias: f32,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self::from_state(&SamplerState::default())
+    }
+}
+
+impl SamplerDesc {
+    pub fn from_state(s: &SamplerState) -> Self {
+        Self {
+            filter: s.filter(),
+            address_u: s.address_u(),
+            address_v: s.address_v(),
+            address_w: s.address_w(),
+            max_anisotropy: s.max_anisotropy(),
+            max_mip_level: s.max_mip_level(),
+            lod_bias: s.mip_map_level_of_detail_bias(),
+        }
+    }
+
+    pub fn to_state(&self) -> SamplerState {
+        let mut s = SamplerState::default();
+        s.set_filter(self.filter);
+        s.set_address_u(self.address_u);
+        s.set_address_v(self.address_v);
+        s.set_address_w(self.address_w);
+        s.set_max_anisotropy(self.max_anisotropy);
+        s.set_max_mip_level(self.max_mip_level);
+        s.set_mip_map_level_of_detail_bias(self.lod_bias);
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_desc() {
+        let desc = PipelineDesc::from_json(
+            r#"{
+                "blend": { "color_src": "One", "blend_factor": "Red" },
+                "depth_stencil": { "depth_test": true, "depth_function": "LessEqual" },
+                "sampler": { "filter": "Point" },
+                "technique": "Outline"
+            }"#,
+        )
+        .unwrap();
+
+        let p = desc.to_pipeline();
+        assert_eq!(p.blend.color_src_blend(), Blend::One);
+        // defaults to the premultiplied alpha blending
+        assert_eq!(p.blend.color_dest_blend(), Blend::InverseSourceAlpha);
+        assert_eq!(p.blend.raw().blendFactor.r, 255);
+        assert!(p.depth_stencil.is_depth_buffer_enabled());
+        assert_eq!(
+            p.depth_stencil.depth_buffer_function(),
+            CompareFunction::LessEqual
+        );
+        assert_eq!(
+            p.rasterizer.cull_mode(),
+            RasterizerState::default().cull_mode()
+        );
+        assert_eq!(p.sampler.as_ref().unwrap().filter(), TextureFilter::Point);
+        assert_eq!(p.technique.as_deref(), Some("Outline"));
+
+        assert_eq!(PipelineDesc::from_pipeline(&p), desc);
+        assert_eq!(
+            PipelineDesc::default()
+                .to_pipeline()
+                .blend
+                .color_src_blend(),
+            Pipeline::default().blend.color_src_blend()
+        );
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn test_pipeline_desc_ron() {
+        let desc =
+            PipelineDesc::from_ron("(rasterizer: (cull: None), technique: Some(\"A\"))").unwrap();
+        assert_eq!(desc.rasterizer.cull, CullMode::None);
+        assert_eq!(desc.technique.as_deref(), Some("A"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_pipeline_desc_toml() {
+        let desc = PipelineDesc::from_toml("[rasterizer]\ncull = \"None\"\n").unwrap();
+        assert_eq!(desc.rasterizer.cull, CullMode::None);
+    }
+}
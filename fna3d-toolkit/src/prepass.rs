@@ -0,0 +1,89 @@
+//! Depth pre-pass for opaque meshes
+//!
+//! Fill-rate-bound scenes shade hidden pixels many times. [`DepthPrePass`] draws opaque meshes
+//! twice:
+//!
+//! 1. [`Phase::Depth`]: depth test and write, color writes off ([`fna3d::BlendState::depth_only`]).
+//!    Use the cheapest effect you have.
+//! 2. [`Phase::Shading`]: depth test with `CompareFunction::Equal` and no depth write
+//!    ([`fna3d::DepthStencilState::depth_equal`]), so only the visible fragment of each pixel is
+//!    shaded.
+//!
+//! ```ignore
+//! let prepass = DepthPrePass::new(stack.current());
+//! prepass.render(&mut stack, &device, |phase| {
+//!     let effect = match phase {
+//!         Phase::Depth => &depth_effect,
+//!         Phase::Shading => &lit_effect,
+//!     };
+//!     for mesh in &opaque_meshes {
+//!         mesh.draw(&device, effect);
+//!     }
+//! });
+//! ```
+//!
+//! Both phases have to produce exactly the same depth, so use the same vertex transformation
+//! (same matrices and same shader math for positions) in both effects.
+
+use fna3d::{BlendState, DepthStencilState};
+
+use crate::pipeline::{Pipeline, PipelineStack};
+
+/// Phase given to the draw function of [`DepthPrePass::render`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Only the depth buffer is written
+    Depth,
+    /// Shades pixels whose depth equals the depth buffer
+    Shading,
+}
+
+/// Pipelines of the two phases
+#[derive(Debug, Clone)]
+pub struct DepthPrePass {
+    depth: Pipeline,
+    shading: Pipeline,
+}
+
+impl DepthPrePass {
+    /// Derives the pipelines from `base` (its rasterizer and, for shading, the rest are kept)
+    pub fn new(base: &Pipeline) -> Self {
+        let depth = Pipeline {
+            blend: BlendState::depth_only(),
+            depth_stencil: DepthStencilState::default(),
+            rasterizer: base.rasterizer.clone(),
+            ..Pipeline::default()
+        };
+
+        let shading = Pipeline {
+            blend: base.blend.clone(),
+            depth_stencil: DepthStencilState::depth_equal(),
+            ..base.clone()
+        };
+
+        Self { depth, shading }
+    }
+
+    pub fn pipeline(&self, phase: Phase) -> &Pipeline {
+        match phase {
+            Phase::Depth => &self.depth,
+            Phase::Shading => &self.shading,
+        }
+    }
+
+    /// Calls `draw` for each phase with the pipeline pushed, then restores the previous one
+    ///
+    /// The render target needs a depth buffer.
+    pub fn render(
+        &self,
+        stack: &mut PipelineStack,
+        device: &fna3d::Device,
+        mut draw: impl FnMut(Phase),
+    ) {
+        for phase in [Phase::Depth, Phase::Shading].iter().copied() {
+            stack.push(device, self.pipeline(phase).clone());
+            draw(phase);
+            stack.pop(device);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Sorted submission of draw calls
+//!
+//! Changing the material (effect and render states) or the texture between draw calls is
+//! expensive. Push the draw calls to a [`DrawQueue`] with a [`SortKey`] and submit them in the key
+//! order, so that draw calls sharing a material and a texture are made next to each other:
+//!
+//! ```ignore
+//! let mut queue = DrawQueue::new();
+//! for mesh in &meshes {
+//!     let key = SortKey::new()
+//!         .with_layer(mesh.layer)
+//!         .with_material(mesh.material_id)
+//!         .with_depth(mesh.view_depth / far)
+//!         .with_texture(mesh.texture_id);
+//!     queue.push(key, mesh);
+//! }
+//!
+//! let stats = queue.submit(&device, |mesh, change| {
+//!     if change.material {
+//!         materials[mesh.material_id as usize].apply(&device)?;
+//!     }
+//!     mesh.draw(&device);
+//!     Ok(())
+//! })?;
+//! log::debug!("{} draw calls", stats.draw_calls);
+//! ```
+//!
+//! # Key layout
+//!
+//! From the most significant bits: layer (8 bits), material (16 bits), depth (24 bits) and texture
+//! (16 bits). So draw calls are grouped by layer, then by material, and sorted by depth within a
+//! material. Use [`SortKey::with_depth_back_to_front`] for translucent layers.
+
+use anyhow::Result;
+
+const TEXTURE_BITS: u32 = 16;
+const DEPTH_BITS: u32 = 24;
+const MATERIAL_BITS: u32 = 16;
+
+const TEXTURE_SHIFT: u32 = 0;
+const DEPTH_SHIFT: u32 = TEXTURE_SHIFT + TEXTURE_BITS;
+const MATERIAL_SHIFT: u32 = DEPTH_SHIFT + DEPTH_BITS;
+const LAYER_SHIFT: u32 = MATERIAL_SHIFT + MATERIAL_BITS;
+
+const DEPTH_MAX: u32 = (1 << DEPTH_BITS) - 1;
+
+/// 64-bit key of a draw call. Smaller keys are submitted first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct SortKey(pub u64);
+
+impl SortKey {
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    fn with_bits(self, shift: u32, bits: u32, value: u64) -> Self {
+        let mask = ((1u64 << bits) - 1) << shift;
+        Self((self.0 & !mask) | ((value << shift) & mask))
+    }
+
+    fn bits(&self, shift: u32, bits: u32) -> u64 {
+        (self.0 >> shift) & ((1u64 << bits) - 1)
+    }
+
+    pub fn with_layer(self, layer: u8) -> Self {
+        self.with_bits(LAYER_SHIFT, 8, layer as u64)
+    }
+
+    pub fn with_material(self, material: u16) -> Self {
+        self.with_bits(MATERIAL_SHIFT, MATERIAL_BITS, material as u64)
+    }
+
+    /// Sorts front to back (for opaque draw calls). `depth` is clamped to `[0.0, 1.0]`
+    pub fn with_depth(self, depth: f32) -> Self {
+        self.with_bits(DEPTH_SHIFT, DEPTH_BITS, self::quantize_depth(depth) as u64)
+    }
+
+    /// Sorts back to front (for translucent draw calls). `depth` is clamped to `[0.0, 1.0]`
+    pub fn with_depth_back_to_front(self, depth: f32) -> Self {
+        let d = DEPTH_MAX - self::quantize_depth(depth);
+        self.with_bits(DEPTH_SHIFT, DEPTH_BITS, d as u64)
+    }
+
+    pub fn with_texture(self, texture: u16) -> Self {
+        self.with_bits(TEXTURE_SHIFT, TEXTURE_BITS, texture as u64)
+    }
+
+    pub fn layer(&self) -> u8 {
+        self.bits(LAYER_SHIFT, 8) as u8
+    }
+
+    pub fn material(&self) -> u16 {
+        self.bits(MATERIAL_SHIFT, MATERIAL_BITS) as u16
+    }
+
+    /// Quantized depth as stored in the key (reversed if set with
+    /// [`SortKey::with_depth_back_to_front`])
+    pub fn depth_bits(&self) -> u32 {
+        self.bits(DEPTH_SHIFT, DEPTH_BITS) as u32
+    }
+
+    pub fn texture(&self) -> u16 {
+        self.bits(TEXTURE_SHIFT, TEXTURE_BITS) as u16
+    }
+}
+
+fn quantize_depth(depth: f32) -> u32 {
+    // NaN is mapped to zero
+    let d = if depth.is_nan() {
+        0.0
+    } else {
+        depth.clamp(0.0, 1.0)
+    };
+    (d * DEPTH_MAX as f32).round() as u32
+}
+
+/// State changed since the previous draw call in [`DrawQueue::submit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StateChange {
+    /// The layer differs (always `true` for the first draw call)
+    pub layer: bool,
+    /// The material differs (always `true` for the first draw call)
+    pub material: bool,
+    /// The texture differs (always `true` for the first draw call)
+    pub texture: bool,
+}
+
+impl StateChange {
+    fn between(prev: Option<SortKey>, key: SortKey) -> Self {
+        match prev {
+            None => Self {
+                layer: true,
+                material: true,
+                texture: true,
+            },
+            Some(prev) => Self {
+                layer: prev.layer() != key.layer(),
+                material: prev.material() != key.material(),
+                texture: prev.texture() != key.texture(),
+            },
+        }
+    }
+}
+
+/// Draw calls sorted by [`SortKey`]. See the module document
+#[derive(Debug, Clone)]
+pub struct DrawQueue<T> {
+    items: Vec<(SortKey, T)>,
+}
+
+impl<T> Default for DrawQueue<T> {
+    fn default() -> Self {
+        Self { items: Vec::new() }
+    }
+}
+
+impl<T> DrawQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, key: SortKey, item: T) {
+        self.items.push((key, item));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Sorts the items by key. Items with the same key keep the push order
+    pub fn sort(&mut self) {
+        self.items.sort_by_key(|(key, _)| *key);
+    }
+
+    /// Items in the current order
+    pub fn iter(&self) -> impl Iterator<Item = (SortKey, &T)> {
+        self.items.iter().map(|(key, item)| (*key, item))
+    }
+
+    /// Number of material and texture changes in the current order. Call after
+    /// [`DrawQueue::sort`] to see how much sorting saves
+    pub fn count_state_changes(&self) -> (usize, usize) {
+        let mut prev = None;
+        let (mut materials, mut textures) = (0, 0);
+        for (key, _) in &self.items {
+            let change = StateChange::between(prev, *key);
+            materials += change.material as usize;
+            textures += change.texture as usize;
+            prev = Some(*key);
+        }
+        (materials, textures)
+    }
+
+    /// Sorts the items and calls `draw` on each, then clears the queue
+    ///
+    /// `draw` is told what changed since the previous item, so it can skip applying the same
+    /// material or texture again. Returns the draw calls made, counted by the device. The queue is
+    /// cleared even on error.
+    pub fn submit(
+        &mut self,
+        device: &fna3d::Device,
+        mut draw: impl FnMut(&T, StateChange) -> Result<()>,
+    ) -> Result<fna3d::stats::DrawStats> {
+        self.sort();
+        let before = device.draw_stats();
+
+        let mut prev = None;
+        let mut res = Ok(());
+        for (key, item) in self.items.drain(..) {
+            if let Err(e) = draw(&item, StateChange::between(prev, key)) {
+                res = Err(e);
+                break;
+            }
+            prev = Some(key);
+        }
+
+        res.map(|()| device.draw_stats().since(&before))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sort_key() {
+        let key = SortKey::new()
+            .with_layer(3)
+            .with_material(0xABCD)
+            .with_depth(1.0)
+            .with_texture(7);
+        assert_eq!(key.layer(), 3);
+        assert_eq!(key.material(), 0xABCD);
+        assert_eq!(key.depth_bits(), DEPTH_MAX);
+        assert_eq!(key.texture(), 7);
+        assert_eq!(key.with_material(1).material(), 1);
+
+        // layer first, then material, then depth
+        let k = |layer, material, depth| {
+            SortKey::new()
+                .with_layer(layer)
+                .with_material(material)
+                .with_depth(depth)
+        };
+        assert!(k(0, 9, 1.0) < k(1, 0, 0.0));
+        assert!(k(1, 0, 1.0) < k(1, 1, 0.0));
+        assert!(k(1, 1, 0.2) < k(1, 1, 0.8));
+
+        let far = SortKey::new().with_depth_back_to_front(0.8);
+        let near = SortKey::new().with_depth_back_to_front(0.2);
+        assert!(far < near);
+    }
+
+    #[test]
+    fn test_draw_queue_sort() {
+        let mut queue = DrawQueue::new();
+        for (i, material) in [2u16, 1, 2, 1].iter().enumerate() {
+            queue.push(SortKey::new().with_material(*material), i);
+        }
+        assert_eq!(queue.count_state_changes(), (4, 1));
+
+        queue.sort();
+        let order = queue.iter().map(|(_, i)| *i).collect::<Vec<_>>();
+        assert_eq!(order, vec![1, 3, 0, 2]);
+        assert_eq!(queue.count_state_changes(), (2, 1));
+    }
+}
@@ -0,0 +1,272 @@
+//! Tilemap rendering with static buffers
+//!
+//! The fast path for large 2D worlds: the map is split into chunks, each chunk uploads its quads
+//! into a static vertex buffer once, and all the chunks share one static index buffer. Rendering
+//! is one indexed draw call per visible chunk, without touching the CPU-side tiles.
+//!
+//! ```ignore
+//! let atlas = TileAtlas::from_grid(&texture, 8, 8);
+//! let tilemap = Tilemap::new(&device, &grid, &atlas, [16.0, 16.0], 32);
+//!
+//! // render
+//! shader.set_camera(&camera, w, h)?;
+//! tilemap.draw(&shader, camera.visible_rect(w, h));
+//! ```
+
+use {
+    fna3d::{vertex::VertexLayout, Color},
+    std::mem,
+};
+
+use crate::gfx::{Shader2d, Texture2dDrop, TextureRegion, Vertex};
+
+/// Grid of tile indices into a [`TileAtlas`]
+#[derive(Debug, Clone)]
+pub struct TileGrid {
+    pub w: u32,
+    pub h: u32,
+    /// Row-major tiles. `None` for empty cells
+    pub tiles: Vec<Option<u32>>,
+}
+
+impl TileGrid {
+    /// Empty grid
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            w,
+            h,
+            tiles: vec![None; (w * h) as usize],
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<u32> {
+        self.tiles[(x + y * self.w) as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, tile: Option<u32>) {
+        self.tiles[(x + y * self.w) as usize] = tile;
+    }
+}
+
+/// Tile images. All the regions have to be in one texture
+#[derive(Debug, Clone)]
+pub struct TileAtlas {
+    pub texture: *mut fna3d::Texture,
+    pub regions: Vec<TextureRegion>,
+}
+
+impl TileAtlas {
+    /// Splits the texture into `cols` x `rows` tiles (tile index = `col + row * cols`)
+    pub fn from_grid(texture: &Texture2dDrop, cols: u32, rows: u32) -> Self {
+        Self {
+            texture: texture.raw,
+            regions: texture.full_region().split_grid(cols, rows),
+        }
+    }
+}
+
+/// Chunk of a [`Tilemap`]
+#[derive(Debug)]
+struct Chunk {
+    vbuf: *mut fna3d::Buffer,
+    /// Number of non-empty tiles
+    n_quads: u32,
+    /// `[x, y, w, h]` in pixels
+    bounds: [f32; 4],
+}
+
+/// Renders a [`TileGrid`] with one draw call per visible chunk
+#[derive(Debug)]
+pub struct Tilemap {
+    device: fna3d::Device,
+    grid: TileGrid,
+    atlas: TileAtlas,
+    tile_size: [f32; 2],
+    /// Chunk size in tiles
+    chunk_size: u32,
+    /// Chunks in row-major order
+    chunks: Vec<Chunk>,
+    chunks_w: u32,
+    /// Shared by all the chunks
+    ibuf: *mut fna3d::Buffer,
+    sampler: fna3d::SamplerState,
+}
+
+impl Drop for Tilemap {
+    fn drop(&mut self) {
+        for chunk in self.chunks.iter() {
+            self.device.add_dispose_vertex_buffer(chunk.vbuf);
+        }
+        self.device.add_dispose_index_buffer(self.ibuf);
+    }
+}
+
+impl Tilemap {
+    /// * `tile_size`: size of a tile in pixels
+    /// * `chunk_size`: width (and height) of a chunk in tiles. Up to `128` (16 bit indices)
+    pub fn new(
+        device: &fna3d::Device,
+        grid: &TileGrid,
+        atlas: &TileAtlas,
+        tile_size: [f32; 2],
+        chunk_size: u32,
+    ) -> Self {
+        assert!(
+            chunk_size > 0 && 4 * chunk_size * chunk_size <= u16::MAX as u32 + 1,
+            "chunk size {} doesn't fit in 16 bit indices",
+            chunk_size
+        );
+
+        let quads_per_chunk = chunk_size * chunk_size;
+        let ibuf = device.gen_index_buffer(
+            false,
+            fna3d::BufferUsage::WriteOnly,
+            6 * quads_per_chunk * mem::size_of::<u16>() as u32,
+        );
+        device.set_index_buffer_data(
+            ibuf,
+            0,
+            &self::quad_indices(quads_per_chunk),
+            fna3d::SetDataOptions::None,
+        );
+
+        let chunks_w = grid.w.div_ceil(chunk_size);
+        let chunks_h = grid.h.div_ceil(chunk_size);
+
+        let mut me = Self {
+            device: device.clone(),
+            grid: grid.clone(),
+            atlas: atlas.clone(),
+            tile_size,
+            chunk_size,
+            chunks: Vec::with_capacity((chunks_w * chunks_h) as usize),
+            chunks_w,
+            ibuf,
+            sampler: fna3d::SamplerState::point_clamp(),
+        };
+
+        let chunk_w = chunk_size as f32 * tile_size[0];
+        let chunk_h = chunk_size as f32 * tile_size[1];
+        for cy in 0..chunks_h {
+            for cx in 0..chunks_w {
+                // allocated for a full chunk so that `set_tile` never reallocates
+                let vbuf = device.gen_vertex_buffer(
+                    false,
+                    fna3d::BufferUsage::WriteOnly,
+                    4 * quads_per_chunk * mem::size_of::<Vertex>() as u32,
+                );
+                me.chunks.push(Chunk {
+                    vbuf,
+                    n_quads: 0,
+                    bounds: [cx as f32 * chunk_w, cy as f32 * chunk_h, chunk_w, chunk_h],
+                });
+                me.upload_chunk(cx, cy);
+            }
+        }
+
+        me
+    }
+
+    pub fn grid(&self) -> &TileGrid {
+        &self.grid
+    }
+
+    /// Sampler used to draw tiles. Point clamp by default
+    pub fn set_sampler(&mut self, sampler: fna3d::SamplerState) {
+        self.sampler = sampler;
+    }
+
+    /// Changes a tile and re-uploads the chunk containing it
+    pub fn set_tile(&mut self, x: u32, y: u32, tile: Option<u32>) {
+        self.grid.set(x, y, tile);
+        self.upload_chunk(x / self.chunk_size, y / self.chunk_size);
+    }
+
+    fn upload_chunk(&mut self, cx: u32, cy: u32) {
+        let (x0, y0) = (cx * self.chunk_size, cy * self.chunk_size);
+        let x1 = (x0 + self.chunk_size).min(self.grid.w);
+        let y1 = (y0 + self.chunk_size).min(self.grid.h);
+        let [tw, th] = self.tile_size;
+
+        let mut verts = Vec::new();
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let region = match self.grid.get(x, y) {
+                    Some(i) => &self.atlas.regions[i as usize],
+                    None => continue,
+                };
+
+                let (px, py) = (x as f32 * tw, y as f32 * th);
+                let [u, v, uw, vh] = region.uv_rect;
+                let color = Color::white();
+                verts.push(Vertex::new([px, py, 0.0], [u, v], color));
+                verts.push(Vertex::new([px + tw, py, 0.0], [u + uw, v], color));
+                verts.push(Vertex::new([px, py + th, 0.0], [u, v + vh], color));
+                verts.push(Vertex::new(
+                    [px + tw, py + th, 0.0],
+                    [u + uw, v + vh],
+                    color,
+                ));
+            }
+        }
+
+        let chunk = &mut self.chunks[(cx + cy * self.chunks_w) as usize];
+        chunk.n_quads = verts.len() as u32 / 4;
+        if !verts.is_empty() {
+            self.device.set_vertex_buffer_data(
+                chunk.vbuf,
+                0,
+                &verts,
+                fna3d::SetDataOptions::Discard,
+            );
+        }
+    }
+
+    /// Draws chunks overlapping with `view` (`[x, y, w, h]` in world pixels)
+    ///
+    /// Set the projection matrix of the `shader` before calling this.
+    pub fn draw(&self, shader: &Shader2d, view: [f32; 4]) {
+        shader.apply_to_device();
+        self.device
+            .verify_sampler(0, self.atlas.texture, &self.sampler);
+
+        for chunk in self.chunks.iter() {
+            if chunk.n_quads == 0 || !self::overlaps(chunk.bounds, view) {
+                continue;
+            }
+
+            let bind = fna3d::VertexBufferBinding {
+                vertexBuffer: chunk.vbuf,
+                vertexDeclaration: Vertex::DECLARATION,
+                vertexOffset: 0,
+                instanceFrequency: 0,
+            };
+            self.device.apply_vertex_buffer_bindings(&[bind], true, 0);
+
+            self.device.draw_indexed_primitives(
+                fna3d::PrimitiveType::TriangleList,
+                0,
+                0,
+                4 * chunk.n_quads,
+                0,
+                2 * chunk.n_quads,
+                self.ibuf,
+                fna3d::IndexElementSize::Bits16,
+            );
+        }
+    }
+}
+
+fn overlaps(a: [f32; 4], b: [f32; 4]) -> bool {
+    a[0] < b[0] + b[2] && b[0] < a[0] + a[2] && a[1] < b[1] + b[3] && b[1] < a[1] + a[3]
+}
+
+/// Index buffer data of `n_quads` quads
+fn quad_indices(n_quads: u32) -> Vec<u16> {
+    let mut indices = Vec::with_capacity(6 * n_quads as usize);
+    for q in 0..n_quads {
+        let v = (4 * q) as u16;
+        indices.extend_from_slice(&[v, v + 1, v + 2, v + 3, v + 2, v + 1]);
+    }
+    indices
+}
@@ -5,17 +5,16 @@
 mod common;
 
 use {
-    anyhow::{Error, Result},
+    anyhow::Result,
     fna3d::Color,
-    sdl2::{event::Event, EventPump},
-    std::time::Duration,
+    fna3d_toolkit::{
+        batch::{Batcher, QuadData},
+        gfx::{Shader2d, Texture2dDrop, Vertex},
+        Flow, Game, GameLoop, Init, RenderTime, UpdateTime,
+    },
 };
 
-use self::common::{
-    batch::{Batcher, QuadData},
-    embedded,
-    gfx::{Shader2d, Texture2dDrop, Vertex},
-};
+use self::common::embedded;
 
 const W: u32 = 1280;
 const H: u32 = 720;
@@ -25,81 +24,58 @@ pub fn main() -> Result<()> {
 
     let title = "Rust-FNA3D batcher example";
 
-    let init = common::init(title, (W, H))?;
-    let pump = init.sdl.event_pump().map_err(Error::msg)?;
-
-    self::run(pump, init)
-}
-
-fn run(mut pump: EventPump, init: common::Init) -> Result<()> {
+    let init = Init::new(title, (W, H))?;
     let shader = Shader2d::new(&init.device, W, H)?;
-    let mut game = self::GameData::new(init, shader)?;
-
-    'running: loop {
-        // Rustified enums are the biggest benefit when using Rust-SDL2 (not Rust-SDL2-sys)!
-        for ev in pump.poll_iter() {
-            match ev {
-                Event::Quit { .. } => break 'running,
-                _ => {}
-            }
-        }
-
-        // something like 30 FPS. do not use it for real applications
-        std::thread::sleep(Duration::from_nanos(1_000_000_000 / 30));
-
-        // update and render our game
-        println!("---- new frame");
-        game.tick()?;
-    }
+    let mut game = self::GameData::new(&init.device, shader)?;
 
-    Ok(())
+    // fixed timestep update, variable timestep render
+    GameLoop::new(60).run(&init, &mut game)
 }
 
 pub struct GameData {
-    /// Lifetime of the application
-    init: common::Init,
     /// Batcher of draw calls
     batcher: Batcher,
     deadly_strike: Texture2dDrop,
     castle: Texture2dDrop,
 }
 
-impl GameData {
-    pub fn new(init: common::Init, shader: Shader2d) -> Result<Self> {
-        // GPU texture
-        let deadly_strike = Texture2dDrop::from_encoded_bytes(&init.device, embedded::ICON);
-        let castle = Texture2dDrop::from_encoded_bytes(&init.device, embedded::CASTLE);
-
-        let batcher = Batcher::new(&init.device, shader)?;
-
-        Ok(Self {
-            init,
-            batcher,
-            deadly_strike,
-            castle,
-        })
+impl Game for GameData {
+    fn update(&mut self, _time: &UpdateTime) -> Result<Flow> {
+        Ok(Flow::Continue)
     }
 
-    pub fn tick(&mut self) -> Result<()> {
-        self.init.device.clear(
+    fn render(&mut self, device: &fna3d::Device, _time: &RenderTime) -> Result<()> {
+        device.clear(
             fna3d::ClearOptions::TARGET,
             Color::rgb(120, 180, 140).to_vec4(),
             0.0, // depth
             0,   // stencil
         );
 
-        self.render();
+        self.push_quads();
 
         self.batcher.flush();
 
-        self.init
-            .device
-            .swap_buffers(None, None, self.init.raw_window() as *mut _);
-
         Ok(())
     }
+}
+
+impl GameData {
+    pub fn new(device: &fna3d::Device, shader: Shader2d) -> Result<Self> {
+        // GPU texture
+        let deadly_strike = Texture2dDrop::from_encoded_bytes(device, embedded::ICON);
+        let castle = Texture2dDrop::from_encoded_bytes(device, embedded::CASTLE);
+
+        let batcher = Batcher::new(device, shader)?;
+
+        Ok(Self {
+            batcher,
+            deadly_strike,
+            castle,
+        })
+    }
 
-    fn render(&mut self) {
+    fn push_quads(&mut self) {
         let size = [200.0, 200.0];
 
         for i in 0..4 {
@@ -0,0 +1,21 @@
+//! Draws a rectangle, a sprite and text with `fna3d::easy`, without any setup code
+//!
+//! Run with: `cargo run --example easy --features sdl2`
+
+use fna3d::Color;
+
+pub fn main() -> Result<(), fna3d::easy::Error> {
+    env_logger::init();
+
+    let mut x = 0.0;
+
+    fna3d::easy::run("Rust-FNA3D easy example", (640, 360), |ctx| {
+        ctx.clear(Color::CORNFLOWER_BLUE);
+
+        x = (x + 120.0 * ctx.dt().as_secs_f32()) % 640.0;
+        ctx.rect([x, 300.0, 32.0, 32.0], Color::ORANGE);
+
+        ctx.image(include_bytes!("common/embedded/castle.png"), [32.0, 64.0]);
+        ctx.text([32.0, 16.0], Color::WHITE, "Hello, FNA3D!");
+    })
+}
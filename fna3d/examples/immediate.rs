@@ -0,0 +1,87 @@
+//! Draws primitives with the immediate-mode API (`fna3d_toolkit::im`)
+//!
+//! The triangle is rotated in fixed timestep update and interpolated on render.
+
+use {
+    anyhow::Result,
+    fna3d::Color,
+    fna3d_toolkit::{gfx::Shader2d, im::Im, Flow, Game, GameLoop, Init, RenderTime, UpdateTime},
+};
+
+const W: u32 = 640;
+const H: u32 = 360;
+
+pub fn main() -> Result<()> {
+    env_logger::init();
+
+    let title = "Rust-FNA3D immediate-mode example";
+
+    let init = Init::new(title, (W, H))?;
+    let mut game = Demo {
+        im: Im::new(&init.device, Shader2d::new(&init.device, W, H)?),
+        angle: 0.0,
+        prev_angle: 0.0,
+    };
+
+    GameLoop::new(60).run(&init, &mut game)
+}
+
+struct Demo {
+    im: Im,
+    /// Rotation in radians
+    angle: f32,
+    /// Rotation at the previous update, used for interpolation
+    prev_angle: f32,
+}
+
+impl Game for Demo {
+    fn update(&mut self, time: &UpdateTime) -> Result<Flow> {
+        self.prev_angle = self.angle;
+        self.angle += std::f32::consts::PI * time.dt.as_secs_f32();
+        Ok(Flow::Continue)
+    }
+
+    fn render(&mut self, device: &fna3d::Device, time: &RenderTime) -> Result<()> {
+        device.clear(
+            fna3d::ClearOptions::TARGET,
+            Color::cornflower_blue().to_vec4(),
+            0.0,
+            0,
+        );
+
+        let angle = self.prev_angle + (self.angle - self.prev_angle) * time.alpha;
+        let (center, radius) = ([320.0, 180.0], 120.0);
+        let point = |i: usize| {
+            let rad = angle + 2.0 * std::f32::consts::PI * i as f32 / 3.0;
+            [
+                center[0] + radius * rad.cos(),
+                center[1] + radius * rad.sin(),
+            ]
+        };
+
+        // a triangle with vertex colors
+        let im = &mut self.im;
+        im.begin(fna3d::PrimitiveType::TriangleList);
+        im.vertex(point(0), Color::rgb(255, 0, 0), [0.0, 0.0]);
+        im.vertex(point(1), Color::rgb(0, 255, 0), [0.0, 0.0]);
+        im.vertex(point(2), Color::rgb(0, 0, 255), [0.0, 0.0]);
+        im.end(device);
+
+        // a frame around it
+        im.begin(fna3d::PrimitiveType::LineStrip);
+        for &pos in [
+            [140.0, 40.0],
+            [500.0, 40.0],
+            [500.0, 320.0],
+            [140.0, 320.0],
+            [140.0, 40.0],
+        ]
+        .iter()
+        {
+            im.vertex(pos, Color::white(), [0.0, 0.0]);
+        }
+        im.end(device);
+
+        Ok(())
+    }
+}
@@ -2,21 +2,21 @@
 //!
 //! Rust-FNA3D is not so easy t use, unfortunatelly.
 //!
-//! Utilities came from `examples/common/mod.rs`.
+//! Utilities came from `fna3d-toolkit`. The main loop is `fna3d_toolkit::GameLoop`.
 
 mod common;
 
 use {
-    anyhow::{Error, Result},
-    fna3d::Color,
-    sdl2::{event::Event, EventPump},
-    std::{mem, time::Duration},
+    anyhow::Result,
+    fna3d::{vertex::VertexLayout, Color},
+    fna3d_toolkit::{
+        gfx::{Shader2d, Texture2dDrop, Vertex},
+        Flow, Game, GameLoop, Init, RenderTime, UpdateTime,
+    },
+    std::mem,
 };
 
-use self::common::{
-    embedded,
-    gfx::{Shader2d, Texture2dDrop, Vertex},
-};
+use self::common::embedded;
 
 const W: u32 = 1280;
 const H: u32 = 720;
@@ -26,37 +26,14 @@ pub fn main() -> Result<()> {
 
     let title = "Rust-FNA3D texture example";
 
-    let init = common::init(title, (W, H))?;
-    let pump = init.sdl.event_pump().map_err(Error::msg)?;
+    let init = Init::new(title, (W, H))?;
+    let mut game = self::GameData::new(&init.device)?;
 
-    self::run(pump, init)
-}
-
-fn run(mut pump: EventPump, init: common::Init) -> Result<()> {
-    let mut game = self::GameData::new(init)?;
-
-    'running: loop {
-        // Rustified enums are the biggest benefit when using Rust-SDL2 (not Rust-SDL2-sys)!
-        for ev in pump.poll_iter() {
-            match ev {
-                Event::Quit { .. } => break 'running,
-                _ => {}
-            }
-        }
-
-        // something like 30 FPS. do not use it for real applications
-        std::thread::sleep(Duration::from_nanos(1_000_000_000 / 30));
-
-        // update and render our game
-        game.tick()?;
-    }
-
-    Ok(())
+    // fixed timestep update, variable timestep render
+    GameLoop::new(60).run(&init, &mut game)
 }
 
 pub struct GameData {
-    /// Lifetime of the application
-    init: common::Init,
     /// GPU side of things: shader and GPU vertices with attributes
     draw: DrawData,
     /// CPU vertices
@@ -66,9 +43,9 @@ pub struct GameData {
 }
 
 impl GameData {
-    pub fn new(init: common::Init) -> Result<Self> {
+    pub fn new(device: &fna3d::Device) -> Result<Self> {
         // GPU texture
-        let tex = Texture2dDrop::from_encoded_bytes(&init.device, embedded::ICON);
+        let tex = Texture2dDrop::from_encoded_bytes(device, embedded::ICON);
 
         // CPU vertex buffer
         let color = Color::rgb(255, 255, 255);
@@ -86,18 +63,19 @@ impl GameData {
             ]
         };
 
-        let draw = DrawData::new(init.device.clone(), verts.len() as u32)?;
+        let draw = DrawData::new(device.clone(), verts.len() as u32)?;
 
-        Ok(Self {
-            init,
-            draw,
-            verts,
-            tex,
-        })
+        Ok(Self { draw, verts, tex })
     }
+}
 
-    pub fn tick(&mut self) -> Result<()> {
-        self.init.device.clear(
+impl Game for GameData {
+    fn update(&mut self, _time: &UpdateTime) -> Result<Flow> {
+        Ok(Flow::Continue)
+    }
+
+    fn render(&mut self, device: &fna3d::Device, _time: &RenderTime) -> Result<()> {
+        device.clear(
             fna3d::ClearOptions::TARGET,
             Color::rgb(120, 180, 140).to_vec4(),
             0.0, // depth
@@ -106,10 +84,6 @@ impl GameData {
 
         self.draw.draw_quads(&self.verts, self.tex.raw)?;
 
-        self.init
-            .device
-            .swap_buffers(None, None, self.init.raw_window() as *mut _);
-
         Ok(())
     }
 }
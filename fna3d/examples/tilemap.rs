@@ -0,0 +1,87 @@
+//! Scrolls a large tilemap drawn from static buffers (`fna3d_toolkit::tilemap`)
+//!
+//! 512x512 tiles are split into 32x32 chunks; only the visible chunks are drawn, one draw call each.
+
+mod common;
+
+use {
+    anyhow::Result,
+    fna3d::Color,
+    fna3d_toolkit::{
+        camera::Camera2d,
+        gfx::{Shader2d, Texture2dDrop},
+        tilemap::{TileAtlas, TileGrid, Tilemap},
+        Flow, Game, GameLoop, Init, RenderTime, UpdateTime,
+    },
+};
+
+use self::common::embedded;
+
+const W: u32 = 1280;
+const H: u32 = 720;
+
+pub fn main() -> Result<()> {
+    env_logger::init();
+
+    let title = "Rust-FNA3D tilemap example";
+
+    let init = Init::new(title, (W, H))?;
+    let mut game = Demo::new(&init.device)?;
+
+    GameLoop::new(60).run(&init, &mut game)
+}
+
+struct Demo {
+    shader: Shader2d,
+    tilemap: Tilemap,
+    camera: Camera2d,
+    /// Kept alive while the tilemap is used
+    _texture: Texture2dDrop,
+}
+
+impl Demo {
+    fn new(device: &fna3d::Device) -> Result<Self> {
+        let texture = Texture2dDrop::from_encoded_bytes(device, embedded::CASTLE);
+        let atlas = TileAtlas::from_grid(&texture, 4, 4);
+
+        // some pattern
+        let mut grid = TileGrid::new(512, 512);
+        for y in 0..grid.h {
+            for x in 0..grid.w {
+                let tile = (x * 7 + y * 13) % 17;
+                grid.set(x, y, if tile < 16 { Some(tile) } else { None });
+            }
+        }
+
+        Ok(Self {
+            shader: Shader2d::new(device, W, H)?,
+            tilemap: Tilemap::new(device, &grid, &atlas, [32.0, 32.0], 32),
+            camera: Camera2d::default(),
+            _texture: texture,
+        })
+    }
+}
+
+impl Game for Demo {
+    fn update(&mut self, time: &UpdateTime) -> Result<Flow> {
+        let speed = 240.0 * time.dt.as_secs_f32();
+        self.camera.pos[0] += speed;
+        self.camera.pos[1] += speed * 0.5;
+        Ok(Flow::Continue)
+    }
+
+    fn render(&mut self, device: &fna3d::Device, _time: &RenderTime) -> Result<()> {
+        device.clear(
+            fna3d::ClearOptions::TARGET,
+            Color::rgb(40, 40, 60).to_vec4(),
+            0.0,
+            0,
+        );
+
+        self.shader.set_camera(&self.camera, W, H)?;
+        self.tilemap
+            .draw(&self.shader, self.camera.visible_rect(W, H));
+
+        Ok(())
+    }
+}
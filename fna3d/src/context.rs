@@ -0,0 +1,284 @@
+//! [`GraphicsContext`], the subset of [`Device`] used by rendering crates
+//!
+//! Renderers (sprite batchers, imgui and fontstash renderers) only need to upload textures and
+//! buffers, set render states and make draw calls. Writing them against this trait instead of
+//! [`Device`] lets them run on other implementations, e.g. a headless mock recording the calls
+//! in tests.
+//!
+//! ```no_run
+//! use fna3d::{context::GraphicsContext, BlendState};
+//!
+//! fn setup_2d(cx: &impl GraphicsContext) {
+//!     cx.set_blend_state(&BlendState::alpha_blend());
+//! }
+//! ```
+//!
+//! Methods have the same signatures as the ones of [`Device`], so switching a function from
+//! `&Device` to `&impl GraphicsContext` doesn't affect callers.
+
+use crate::fna3d::{fna3d_device::Device, fna3d_enums as enums, fna3d_structs::*};
+
+#[cfg(feature = "mojoshader")]
+use crate::mojo;
+
+/// Textures, buffers, render states and draw calls. See the module document
+pub trait GraphicsContext {
+    // Render states
+
+    fn set_viewport(&self, viewport: &Viewport);
+    fn set_scissor_rect(&self, scissor: &Rect);
+    fn set_blend_state(&self, blend_state: &BlendState);
+    fn set_depth_stencil_state(&self, depth_stencil_state: &DepthStencilState);
+    fn apply_rasterizer_state(&self, rst: &RasterizerState);
+    fn verify_sampler(&self, index: u32, texture: *mut Texture, sampler: &SamplerState);
+
+    // Textures
+
+    fn create_texture_2d(
+        &self,
+        fmt: enums::SurfaceFormat,
+        w: u32,
+        h: u32,
+        level_count: u32,
+        is_render_target: bool,
+    ) -> *mut Texture;
+
+    #[allow(clippy::too_many_arguments)]
+    fn set_texture_data_2d(
+        &self,
+        texture: *mut Texture,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        level: u32,
+        data: &[u8],
+    );
+
+    fn add_dispose_texture(&self, texture: *mut Texture);
+
+    // Buffers
+
+    fn gen_vertex_buffer(
+        &self,
+        is_dynamic: bool,
+        usage: enums::BufferUsage,
+        size_in_bytes: u32,
+    ) -> *mut Buffer;
+
+    fn set_vertex_buffer_data<T>(
+        &self,
+        buf: *mut Buffer,
+        buf_offset_in_bytes: u32,
+        data: &[T],
+        opts: enums::SetDataOptions,
+    );
+
+    fn add_dispose_vertex_buffer(&self, buffer: *mut Buffer);
+
+    fn gen_index_buffer(
+        &self,
+        is_dynamic: bool,
+        usage: enums::BufferUsage,
+        size_in_bytes: u32,
+    ) -> *mut Buffer;
+
+    fn set_index_buffer_data<T>(
+        &self,
+        buf: *mut Buffer,
+        buf_offset_in_bytes: u32,
+        data: &[T],
+        opts: enums::SetDataOptions,
+    );
+
+    fn add_dispose_index_buffer(&self, buf: *mut Buffer);
+
+    // Effects and draw calls
+
+    #[cfg(feature = "mojoshader")]
+    fn apply_effect(
+        &self,
+        effect: *mut Effect,
+        pass: u32,
+        state_changes: &mojo::EffectStateChanges,
+    );
+
+    fn add_dispose_effect(&self, effect: *mut Effect);
+
+    fn apply_vertex_buffer_bindings(
+        &self,
+        bindings: &[VertexBufferBinding],
+        is_bindings_updated: bool,
+        base_vertex: u32,
+    );
+
+    fn apply_vertex_buffer_bindings_auto(&self, bindings: &[VertexBufferBinding], base_vertex: u32);
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_indexed_primitives(
+        &self,
+        type_: enums::PrimitiveType,
+        base_vtx: u32,
+        min_vert_idx: u32,
+        n_verts: u32,
+        base_idx: u32,
+        n_primitives: u32,
+        ibuf: *mut Buffer,
+        index_elem_size: enums::IndexElementSize,
+    );
+}
+
+impl GraphicsContext for Device {
+    fn set_viewport(&self, viewport: &Viewport) {
+        Device::set_viewport(self, viewport)
+    }
+
+    fn set_scissor_rect(&self, scissor: &Rect) {
+        Device::set_scissor_rect(self, scissor)
+    }
+
+    fn set_blend_state(&self, blend_state: &BlendState) {
+        Device::set_blend_state(self, blend_state)
+    }
+
+    fn set_depth_stencil_state(&self, depth_stencil_state: &DepthStencilState) {
+        Device::set_depth_stencil_state(self, depth_stencil_state)
+    }
+
+    fn apply_rasterizer_state(&self, rst: &RasterizerState) {
+        Device::apply_rasterizer_state(self, rst)
+    }
+
+    fn verify_sampler(&self, index: u32, texture: *mut Texture, sampler: &SamplerState) {
+        Device::verify_sampler(self, index, texture, sampler)
+    }
+
+    fn create_texture_2d(
+        &self,
+        fmt: enums::SurfaceFormat,
+        w: u32,
+        h: u32,
+        level_count: u32,
+        is_render_target: bool,
+    ) -> *mut Texture {
+        Device::create_texture_2d(self, fmt, w, h, level_count, is_render_target)
+    }
+
+    fn set_texture_data_2d(
+        &self,
+        texture: *mut Texture,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        level: u32,
+        data: &[u8],
+    ) {
+        Device::set_texture_data_2d(self, texture, x, y, w, h, level, data)
+    }
+
+    fn add_dispose_texture(&self, texture: *mut Texture) {
+        Device::add_dispose_texture(self, texture)
+    }
+
+    fn gen_vertex_buffer(
+        &self,
+        is_dynamic: bool,
+        usage: enums::BufferUsage,
+        size_in_bytes: u32,
+    ) -> *mut Buffer {
+        Device::gen_vertex_buffer(self, is_dynamic, usage, size_in_bytes)
+    }
+
+    fn set_vertex_buffer_data<T>(
+        &self,
+        buf: *mut Buffer,
+        buf_offset_in_bytes: u32,
+        data: &[T],
+        opts: enums::SetDataOptions,
+    ) {
+        Device::set_vertex_buffer_data(self, buf, buf_offset_in_bytes, data, opts)
+    }
+
+    fn add_dispose_vertex_buffer(&self, buffer: *mut Buffer) {
+        Device::add_dispose_vertex_buffer(self, buffer)
+    }
+
+    fn gen_index_buffer(
+        &self,
+        is_dynamic: bool,
+        usage: enums::BufferUsage,
+        size_in_bytes: u32,
+    ) -> *mut Buffer {
+        Device::gen_index_buffer(self, is_dynamic, usage, size_in_bytes)
+    }
+
+    fn set_index_buffer_data<T>(
+        &self,
+        buf: *mut Buffer,
+        buf_offset_in_bytes: u32,
+        data: &[T],
+        opts: enums::SetDataOptions,
+    ) {
+        Device::set_index_buffer_data(self, buf, buf_offset_in_bytes, data, opts)
+    }
+
+    fn add_dispose_index_buffer(&self, buf: *mut Buffer) {
+        Device::add_dispose_index_buffer(self, buf)
+    }
+
+    #[cfg(feature = "mojoshader")]
+    fn apply_effect(
+        &self,
+        effect: *mut Effect,
+        pass: u32,
+        state_changes: &mojo::EffectStateChanges,
+    ) {
+        Device::apply_effect(self, effect, pass, state_changes)
+    }
+
+    fn add_dispose_effect(&self, effect: *mut Effect) {
+        Device::add_dispose_effect(self, effect)
+    }
+
+    fn apply_vertex_buffer_bindings(
+        &self,
+        bindings: &[VertexBufferBinding],
+        is_bindings_updated: bool,
+        base_vertex: u32,
+    ) {
+        Device::apply_vertex_buffer_bindings(self, bindings, is_bindings_updated, base_vertex)
+    }
+
+    fn apply_vertex_buffer_bindings_auto(
+        &self,
+        bindings: &[VertexBufferBinding],
+        base_vertex: u32,
+    ) {
+        Device::apply_vertex_buffer_bindings_auto(self, bindings, base_vertex)
+    }
+
+    fn draw_indexed_primitives(
+        &self,
+        type_: enums::PrimitiveType,
+        base_vtx: u32,
+        min_vert_idx: u32,
+        n_verts: u32,
+        base_idx: u32,
+        n_primitives: u32,
+        ibuf: *mut Buffer,
+        index_elem_size: enums::IndexElementSize,
+    ) {
+        Device::draw_indexed_primitives(
+            self,
+            type_,
+            base_vtx,
+            min_vert_idx,
+            n_verts,
+            base_idx,
+            n_primitives,
+            ibuf,
+            index_elem_size,
+        )
+    }
+}
@@ -0,0 +1,143 @@
+//! Default resources owned by [`Device`]
+//!
+//! Tiny resources every renderer needs (a white texture, common samplers and a sprite effect).
+//! They're created on first access and disposed with the device, so that crates built on `fna3d`
+//! share them instead of creating duplicates:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device) {
+//! let defaults = device.defaults();
+//! device.verify_sampler(0, defaults.white_texture(), defaults.point_sampler());
+//! # }
+//! ```
+//!
+//! The handles are raw pointers owned by the device. Don't dispose them.
+
+use std::cell::Cell;
+
+use crate::{Device, SamplerState, SurfaceFormat, Texture};
+
+#[cfg(feature = "mojoshader")]
+use {
+    crate::{mojo, Effect},
+    std::ffi::CStr,
+};
+
+/// Compiled `SpriteEffect` (the effect of XNA's `SpriteBatch`)
+///
+/// It has the `MatrixTransform` parameter (4x4 column-major matrix).
+pub const SPRITE_EFFECT: &[u8] = include_bytes!("embedded/SpriteEffect.fxb");
+
+/// Lazily created resources, stored in the device and disposed before the device
+#[derive(Debug)]
+pub(crate) struct DefaultStore {
+    white: Cell<*mut Texture>,
+    #[cfg(feature = "mojoshader")]
+    sprite_effect: Cell<Option<(*mut Effect, *mut mojo::Effect)>>,
+    point: SamplerState,
+    linear: SamplerState,
+}
+
+impl DefaultStore {
+    pub fn new() -> Self {
+        Self {
+            white: Cell::new(std::ptr::null_mut()),
+            #[cfg(feature = "mojoshader")]
+            sprite_effect: Cell::new(None),
+            point: SamplerState::point_clamp(),
+            linear: SamplerState::linear_clamp(),
+        }
+    }
+
+    /// Adds the created resources to the dispose queue. Called on dropping the device
+    pub fn dispose(&self, device: *mut fna3d_sys::FNA3D_Device) {
+        let white = self.white.replace(std::ptr::null_mut());
+        if !white.is_null() {
+            unsafe {
+                fna3d_sys::FNA3D_AddDisposeTexture(device, white);
+            }
+        }
+
+        #[cfg(feature = "mojoshader")]
+        if let Some((effect, _data)) = self.sprite_effect.take() {
+            unsafe {
+                fna3d_sys::FNA3D_AddDisposeEffect(device, effect);
+            }
+        }
+    }
+}
+
+/// Access to the default resources. Returned from [`Device::defaults`]
+#[derive(Debug, Clone, Copy)]
+pub struct Defaults<'a> {
+    device: &'a Device,
+    store: &'a DefaultStore,
+}
+
+impl<'a> Defaults<'a> {
+    pub(crate) fn new(device: &'a Device, store: &'a DefaultStore) -> Self {
+        Self { device, store }
+    }
+
+    /// 1x1 opaque white texture (`Color` format)
+    pub fn white_texture(&self) -> *mut Texture {
+        let white = self.store.white.get();
+        if !white.is_null() {
+            return white;
+        }
+
+        let white = self
+            .device
+            .create_texture_2d(SurfaceFormat::Color, 1, 1, 1, false);
+        self.device
+            .set_texture_data_2d(white, 0, 0, 1, 1, 0, &[255u8; 4]);
+        self.store.white.set(white);
+        white
+    }
+
+    /// Point filtering with clamped addressing
+    pub fn point_sampler(&self) -> &'a SamplerState {
+        &self.store.point
+    }
+
+    /// Linear filtering with clamped addressing
+    pub fn linear_sampler(&self) -> &'a SamplerState {
+        &self.store.linear
+    }
+
+    /// [`SPRITE_EFFECT`] with identity `MatrixTransform`, i.e. vertex positions are in clip space
+    ///
+    /// Returns `(effect, effect_data)` as [`mojo::from_bytes`] does. Apply it with
+    /// [`Device::apply_effect`]. The parameter is shared by every user of the defaults; load your
+    /// own effect to draw with other matrices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend fails to load the effect.
+    #[cfg(feature = "mojoshader")]
+    pub fn sprite_effect(&self) -> (*mut Effect, *mut mojo::Effect) {
+        if let Some(effect) = self.store.sprite_effect.get() {
+            return effect;
+        }
+
+        let (effect, data) = mojo::from_bytes(self.device, SPRITE_EFFECT)
+            .unwrap_or_else(|e| panic!("failed to load the default sprite effect: {}", e));
+
+        let name = CStr::from_bytes_with_nul(b"MatrixTransform\0").unwrap();
+        unsafe {
+            mojo::set_param(data, name, &IDENTITY);
+        }
+
+        self.store.sprite_effect.set(Some((effect, data)));
+        (effect, data)
+    }
+}
+
+/// Column-major identity matrix
+#[cfg(feature = "mojoshader")]
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0, //
+    0.0, 1.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0, //
+];
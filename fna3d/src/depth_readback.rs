@@ -0,0 +1,224 @@
+//! Depth and stencil readback for debugging
+//!
+//! FNA3D can't read depth/stencil buffers, and they can't be sampled by shaders either. So
+//! [`Device::read_depth_region`] and [`Device::read_stencil_region`] reconstruct the values with
+//! the depth/stencil test: they draw 255 full-screen quads into a scratch color target sharing the
+//! depth buffer of your render target, each adding `1 / 255` where it passes the test. The color
+//! target then holds the values, which are read back.
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, scene: &fna3d::res::RenderTarget2d) -> fna3d::res::Result<()> {
+//! // after drawing the scene into the target
+//! let depth = device.read_depth_region(scene, [0, 0, 64, 64])?;
+//! log::debug!("depth at (0, 0): {}", depth[0]);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Warnings
+//!
+//! These are debugging tools; don't call them every frame:
+//!
+//! * Values are quantized to 8 bits. Depth is rounded to the nearest multiple of `1 / 255`.
+//! * They make hundreds of draw calls and stall the CPU until the GPU finishes (readback).
+//! * The backbuffer is bound afterwards. Blend, depth/stencil and rasterizer states, the viewport,
+//!   the scissor rectangle and the sampler state of slot `0` are restored if they were set
+//!   through the [`Device`]. Vertex buffer bindings and the applied effect are not.
+
+use crate::{
+    fna3d::{fna3d_device::Device, fna3d_enums as enums, fna3d_structs::*},
+    res,
+    spritebatch::SpriteVertex,
+    Color,
+};
+
+/// Number of quads (and the precision of the result)
+const N_SLICES: u32 = 255;
+
+/// Depth/stencil readback
+/// ---
+///
+/// See [`crate::depth_readback`].
+impl Device {
+    /// Reads the depth buffer of the render target in the `[x, y, w, h]` region
+    ///
+    /// Returns `w * h` depth values in `[0.0, 1.0]` (row-major), quantized to 8 bits.
+    pub fn read_depth_region(
+        &self,
+        target: &res::RenderTarget2d,
+        rect: [u32; 4],
+    ) -> res::Result<Vec<f32>> {
+        let levels = self::read_levels(self, target, rect, false)?;
+        Ok(levels.iter().map(|&l| l as f32 / N_SLICES as f32).collect())
+    }
+
+    /// Reads the stencil buffer of the render target in the `[x, y, w, h]` region
+    ///
+    /// Returns `w * h` stencil values (row-major). The depth format has to be `D24S8`.
+    pub fn read_stencil_region(
+        &self,
+        target: &res::RenderTarget2d,
+        rect: [u32; 4],
+    ) -> res::Result<Vec<u8>> {
+        self::read_levels(self, target, rect, true)
+    }
+}
+
+/// Counts the slices passing the depth (or stencil) test for each pixel
+fn read_levels(
+    device: &Device,
+    target: &res::RenderTarget2d,
+    rect: [u32; 4],
+    stencil: bool,
+) -> res::Result<Vec<u8>> {
+    let (w, h) = target.size();
+    res::validate_region(enums::SurfaceFormat::Color, [w, h], rect)?;
+    let [x, y, rw, rh] = rect;
+
+    let depth_format = target.depth_format();
+    let depth = target.depth_buffer();
+    let has_data = match depth_format {
+        enums::DepthFormat::None => false,
+        enums::DepthFormat::D24S8 => true,
+        _ => !stencil,
+    };
+    if depth.is_null() || !has_data {
+        return Err(res::ResourceError::NoDepthStencil(depth_format));
+    }
+
+    // the sample counts of the color and the depth buffers have to match
+    let mut scratch = res::RenderTarget2d::new(
+        device,
+        w,
+        h,
+        enums::SurfaceFormat::Color,
+        enums::DepthFormat::None,
+        target.multi_sample_count(),
+    )?;
+
+    let snapshot = device.snapshot_states();
+
+    device.set_render_targets(
+        Some(scratch.binding_mut()),
+        1,
+        Some(unsafe { &mut *depth }),
+        depth_format,
+        false,
+    );
+    let full = Rect::new(0, 0, w as i32, h as i32);
+    device.set_viewport(&Viewport::from_rect(full));
+    device.set_scissor_rect(&full);
+    device.clear(
+        enums::ClearOptions::TARGET,
+        Color::TRANSPARENT.to_vec4(),
+        0.0,
+        0,
+    );
+
+    device.set_blend_state(&BlendState::with_blend(
+        enums::Blend::One,
+        enums::Blend::One,
+        enums::Blend::One,
+        enums::Blend::One,
+    ));
+    device.apply_rasterizer_state(&RasterizerState::from_cull_mode(enums::CullMode::None));
+
+    let vertices = self::slice_vertices();
+    let vbuf = device.gen_vertex_buffer(
+        false,
+        enums::BufferUsage::WriteOnly,
+        (vertices.len() * std::mem::size_of::<SpriteVertex>()) as u32,
+    );
+    device.set_vertex_buffer_data(vbuf, 0, &vertices, enums::SetDataOptions::None);
+
+    // positions are in clip space
+    let (effect, _) = device.defaults().sprite_effect();
+    device.apply_effect_no_change(effect, 0);
+    let white = device.defaults().white_texture();
+    device.verify_sampler(0, white, device.defaults().point_sampler());
+
+    let binding = VertexBufferBinding {
+        vertexBuffer: vbuf,
+        vertexDeclaration: <SpriteVertex as crate::vertex::VertexLayout>::DECLARATION,
+        vertexOffset: 0,
+        instanceFrequency: 0,
+    };
+    device.apply_vertex_buffer_bindings(&[binding], true, 0);
+
+    if stencil {
+        // the `i`th quad passes where `i <= stencil`
+        let mut dss = DepthStencilState::none();
+        dss.set_is_stencil_enabled(true);
+        dss.set_stencil_function(enums::CompareFunction::LessEqual);
+        for i in 1..=N_SLICES {
+            dss.set_renference_stencil(i as i32);
+            device.set_depth_stencil_state(&dss);
+            device.draw_primitives(enums::PrimitiveType::TriangleList, 6 * (i - 1), 2);
+        }
+    } else {
+        // the `i`th quad passes where its depth is less than or equal to the depth buffer
+        let mut dss = DepthStencilState::depth_read();
+        dss.set_depth_buffer_function(enums::CompareFunction::LessEqual);
+        device.set_depth_stencil_state(&dss);
+        device.draw_primitives(enums::PrimitiveType::TriangleList, 0, 2 * N_SLICES);
+    }
+
+    device.add_dispose_vertex_buffer(vbuf);
+    scratch.unbind();
+
+    let mut pixels = vec![0u8; rw as usize * rh as usize * 4];
+    device.get_texture_data_2d(scratch.texture(), x, y, rw, rh, 0, &mut pixels);
+
+    device.restore_states(&snapshot);
+
+    Ok(pixels.chunks_exact(4).map(|px| px[0]).collect())
+}
+
+/// Full-screen quads (two triangles each) at the depth of each slice center
+fn slice_vertices() -> Vec<SpriteVertex> {
+    // adds `1 / 255` to an 8-bit channel with additive blending
+    let color = Color::rgba(1, 1, 1, 1);
+    let corners = [
+        [-1.0, 1.0],
+        [1.0, 1.0],
+        [-1.0, -1.0],
+        [1.0, -1.0],
+        [-1.0, -1.0],
+        [1.0, 1.0],
+    ];
+
+    (0..N_SLICES)
+        .flat_map(|i| {
+            let z = self::slice_depth(i);
+            corners.iter().map(move |&[x, y]| SpriteVertex {
+                pos: [x, y, z],
+                color,
+                uv: [0.0, 0.0],
+            })
+        })
+        .collect()
+}
+
+/// Depth of the `i`th slice. Depth `d` passes `round(d * 255)` slices
+fn slice_depth(i: u32) -> f32 {
+    (i as f32 + 0.5) / N_SLICES as f32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_depth_slices() {
+        let vertices = slice_vertices();
+        assert_eq!(vertices.len(), 6 * N_SLICES as usize);
+        assert_eq!(vertices[6].pos[2], slice_depth(1));
+
+        // number of slices at or in front of the depth
+        let passed = |d: f32| (0..N_SLICES).filter(|&i| slice_depth(i) <= d).count();
+        assert_eq!(passed(0.0), 0);
+        assert_eq!(passed(1.0), 255);
+        assert_eq!(passed(0.5), 128);
+        assert_eq!(passed(100.0 / 255.0), 100);
+    }
+}
@@ -0,0 +1,416 @@
+//! One-file quickstart (`sdl2` feature)
+//!
+//! [`run`] creates the SDL2 window and the device, sets up the viewport and render states and
+//! runs the main loop. The closure draws a frame with the [`Context`]:
+//!
+//! ```no_run
+//! use fna3d::Color;
+//!
+//! fn main() -> Result<(), fna3d::easy::Error> {
+//!     fna3d::easy::run("Hello", (640, 360), |ctx| {
+//!         ctx.clear(Color::CORNFLOWER_BLUE);
+//!         ctx.rect([32.0, 64.0, 64.0, 64.0], Color::ORANGE);
+//!         ctx.image(include_bytes!("../examples/common/embedded/castle.png"), [128.0, 64.0]);
+//!         ctx.text([32.0, 16.0], Color::WHITE, "Hello, FNA3D!");
+//!     })
+//! }
+//! ```
+//!
+//! Sprites and text are drawn with a [`SpriteBatch`] in the push order, with premultiplied alpha
+//! blending and point sampling. Text uses a built-in 3x5 pixel font (ASCII only; lowercase letters
+//! are drawn as capitals). Use the device and the other modules directly once you outgrow it.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use sdl2::event::Event;
+
+use crate::{
+    fna3d::{
+        fna3d_device::{CreateDeviceError, Device},
+        fna3d_enums as enums, fna3d_functions as funcs,
+        fna3d_structs::*,
+    },
+    res,
+    spritebatch::SpriteBatch,
+    Color,
+};
+
+/// Errors of [`run`]
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to initialize SDL2 or create the window
+    Sdl(String),
+    /// Failed to create the FNA3D device
+    Device(CreateDeviceError),
+    /// Failed to create a resource or to load an image
+    Resource(res::ResourceError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Sdl(e) => write!(f, "SDL2 error: {}", e),
+            Error::Device(e) => write!(f, "{}", e),
+            Error::Resource(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<res::ResourceError> for Error {
+    fn from(e: res::ResourceError) -> Self {
+        Error::Resource(e)
+    }
+}
+
+/// Opens a window and calls `frame` every frame until the window is closed or
+/// [`Context::quit`] is called
+///
+/// The backbuffer is presented with vsync after each call.
+pub fn run(
+    title: &str,
+    size: (u32, u32),
+    mut frame: impl FnMut(&mut Context),
+) -> Result<(), Error> {
+    let sdl = sdl2::init().map_err(Error::Sdl)?;
+    let video = sdl.video().map_err(Error::Sdl)?;
+    let window = crate::win::create_window(&video, title, size, funcs::prepare_window_attributes())
+        .map_err(Error::Sdl)?;
+    let mut pump = sdl.event_pump().map_err(Error::Sdl)?;
+
+    let mut params = crate::utils::default_params_from_window_handle(window.raw() as *mut _);
+    let device = Device::try_from_params(&mut params, false).map_err(Error::Device)?;
+
+    let mut ctx = Context::new(&device, (params.backBufferWidth, params.backBufferHeight))?;
+    let mut last = Instant::now();
+
+    loop {
+        ctx.events.clear();
+        for ev in pump.poll_iter() {
+            if let Event::Quit { .. } = ev {
+                ctx.quit = true;
+            }
+            ctx.events.push(ev);
+        }
+        if ctx.quit {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        ctx.dt = now - last;
+        last = now;
+
+        ctx.begin();
+        frame(&mut ctx);
+        ctx.batch.flush();
+
+        device.swap_buffers(None, None, window.raw() as *mut _);
+    }
+}
+
+/// Per-frame drawing context of [`run`]
+pub struct Context {
+    device: Device,
+    batch: SpriteBatch,
+    size: (u32, u32),
+    white: res::Texture2d,
+    font: res::Texture2d,
+    /// Textures of [`Context::image`], keyed by the address and length of the bytes
+    images: HashMap<(usize, usize), res::Texture2d>,
+    text_scale: f32,
+    dt: Duration,
+    events: Vec<Event>,
+    quit: bool,
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("batch", &self.batch)
+            .field("size", &self.size)
+            .field("white", &self.white)
+            .field("font", &self.font)
+            .field("n_images", &self.images.len())
+            .field("text_scale", &self.text_scale)
+            .field("dt", &self.dt)
+            .field("n_events", &self.events.len())
+            .field("quit", &self.quit)
+            .finish()
+    }
+}
+
+impl Context {
+    fn new(device: &Device, size: (i32, i32)) -> Result<Self, Error> {
+        let size = (size.0 as u32, size.1 as u32);
+
+        device.set_viewport(&Viewport::new(0, 0, size.0 as i32, size.1 as i32));
+        device.apply_rasterizer_state(&RasterizerState::default());
+        device.set_blend_state(&BlendState::premultiplied());
+
+        let mut batch = SpriteBatch::new(device)?;
+        batch.set_sampler(SamplerState::point_clamp());
+        batch.set_screen_size(size.0, size.1);
+
+        let (w, h) = self::font_texture_size();
+        let mut font = res::Texture2d::new(device, enums::SurfaceFormat::Color, w, h, 1);
+        font.set_data(&self::font_pixels())?;
+
+        Ok(Self {
+            device: device.clone(),
+            batch,
+            size,
+            white: res::Texture2d::white_pixel(device),
+            font,
+            images: HashMap::new(),
+            text_scale: 2.0,
+            dt: Duration::default(),
+            events: Vec::new(),
+            quit: false,
+        })
+    }
+
+    /// Resets per-frame states changed by the user
+    fn begin(&mut self) {
+        self.device
+            .set_render_targets(None, 0, None, enums::DepthFormat::None, false);
+        self.device
+            .set_viewport(&Viewport::new(0, 0, self.size.0 as i32, self.size.1 as i32));
+    }
+
+    /// The device, for drawing beyond the `easy` API. Sprites pushed so far are drawn after
+    /// anything drawn with it unless [`Context::flush`] is called first
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Backbuffer size in pixels
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Time since the previous frame
+    pub fn dt(&self) -> Duration {
+        self.dt
+    }
+
+    /// SDL events polled this frame
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Stops the main loop after this frame
+    pub fn quit(&mut self) {
+        self.quit = true;
+    }
+
+    /// Draws the sprites pushed so far
+    pub fn flush(&mut self) {
+        self.batch.flush();
+    }
+
+    /// Clears the screen. Sprites pushed before are drawn first
+    pub fn clear(&mut self, color: Color) {
+        self.batch.flush();
+        self.device
+            .clear(enums::ClearOptions::TARGET, color.to_vec4(), 0.0, 0);
+    }
+
+    /// Draws a filled rectangle (`[x, y, w, h]` in pixels)
+    pub fn rect(&mut self, dst: [f32; 4], color: Color) {
+        self.batch
+            .push(self.white.raw(), dst, [0.0, 0.0, 1.0, 1.0], color);
+    }
+
+    /// Decodes a PNG/JPG/GIF image into a premultiplied alpha texture
+    pub fn load_texture(&self, bytes: &[u8]) -> Result<res::Texture2d, Error> {
+        Ok(res::Texture2d::from_encoded_bytes(&self.device, bytes)?)
+    }
+
+    /// Draws a texture at `pos` in its original size
+    pub fn sprite(&mut self, texture: &res::Texture2d, pos: [f32; 2]) {
+        let (w, h) = texture.size();
+        self.sprite_rect(texture, [pos[0], pos[1], w as f32, h as f32], Color::WHITE);
+    }
+
+    /// Draws a texture stretched to `dst` (`[x, y, w, h]`), multiplied by `color`
+    pub fn sprite_rect(&mut self, texture: &res::Texture2d, dst: [f32; 4], color: Color) {
+        self.batch
+            .push(texture.raw(), dst, [0.0, 0.0, 1.0, 1.0], color);
+    }
+
+    /// Draws an embedded image (e.g. `include_bytes!`) at `pos`
+    ///
+    /// The image is decoded on first use and cached for the lifetime of the loop. Images that
+    /// fail to decode are drawn as magenta rectangles.
+    pub fn image(&mut self, bytes: &'static [u8], pos: [f32; 2]) {
+        let key = (bytes.as_ptr() as usize, bytes.len());
+        if !self.images.contains_key(&key) {
+            match self.load_texture(bytes) {
+                Ok(texture) => {
+                    self.images.insert(key, texture);
+                }
+                Err(e) => {
+                    log::warn!("easy: {}", e);
+                    self.rect([pos[0], pos[1], 16.0, 16.0], Color::MAGENTA);
+                    return;
+                }
+            }
+        }
+
+        let texture = &self.images[&key];
+        let (w, h) = texture.size();
+        self.batch.push(
+            texture.raw(),
+            [pos[0], pos[1], w as f32, h as f32],
+            [0.0, 0.0, 1.0, 1.0],
+            Color::WHITE,
+        );
+    }
+
+    /// Pixel size of the font glyphs. Defaults to `2.0` (glyphs are 6x10 pixels)
+    pub fn set_text_scale(&mut self, scale: f32) {
+        self.text_scale = scale;
+    }
+
+    /// Draws text with the built-in font. `pos` is the top-left corner; `\n` starts a new line
+    pub fn text(&mut self, pos: [f32; 2], color: Color, text: &str) {
+        let s = self.text_scale;
+        let (tw, th) = self::font_texture_size();
+        let (tw, th) = (tw as f32, th as f32);
+
+        for (col, row, c) in self::layout(text) {
+            if c == ' ' {
+                continue;
+            }
+            let [gx, gy] = self::glyph_cell(c);
+            let dst = [
+                pos[0] + (col * CELL_W) as f32 * s,
+                pos[1] + (row * CELL_H) as f32 * s,
+                GLYPH_W as f32 * s,
+                GLYPH_H as f32 * s,
+            ];
+            let uv = [
+                gx as f32 / tw,
+                gy as f32 / th,
+                GLYPH_W as f32 / tw,
+                GLYPH_H as f32 / th,
+            ];
+            self.batch.push(self.font.raw(), dst, uv, color);
+        }
+    }
+
+    /// Size of `text` drawn with [`Context::text`] in pixels
+    pub fn text_size(&self, text: &str) -> [f32; 2] {
+        let (cols, rows) = self::layout(text).fold((0, 0), |(w, h), (col, row, _)| {
+            (w.max(col + 1), h.max(row + 1))
+        });
+        let rows = rows.max(text.lines().count() as u32);
+        [
+            (cols * CELL_W) as f32 * self.text_scale,
+            (rows * CELL_H) as f32 * self.text_scale,
+        ]
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Built-in font
+
+const GLYPH_W: u32 = 3;
+const GLYPH_H: u32 = 5;
+/// Glyph with 1 pixel spacing
+const CELL_W: u32 = GLYPH_W + 1;
+const CELL_H: u32 = GLYPH_H + 1;
+/// Glyphs per row in the font texture
+const ATLAS_COLS: u32 = 16;
+
+/// 3x5 glyphs of ASCII `0x20..0x80`. Bit 14 is the top-left pixel and rows go downwards
+#[rustfmt::skip]
+const GLYPHS: [u16; 96] = [
+    0x0000, 0x2482, 0x5A00, 0x5F7D, 0x3C9E, 0x52A5, 0x2AAB, 0x2400,
+    0x1491, 0x4494, 0x0AA8, 0x05D0, 0x0014, 0x01C0, 0x0002, 0x12A4,
+    0x7B6F, 0x2C97, 0x73E7, 0x72CF, 0x5BC9, 0x79CF, 0x79EF, 0x7292,
+    0x7BEF, 0x7BCF, 0x0410, 0x0414, 0x1511, 0x0E38, 0x4454, 0x7282,
+    0x2BE3, 0x2BED, 0x6BAE, 0x3923, 0x6B6E, 0x79A7, 0x79A4, 0x396B,
+    0x5BED, 0x7497, 0x126A, 0x5BAD, 0x4927, 0x5FED, 0x6B6D, 0x2B6A,
+    0x6BA4, 0x2B73, 0x6BAD, 0x388E, 0x7492, 0x5B6B, 0x5B52, 0x5BFD,
+    0x5AAD, 0x5A92, 0x72A7, 0x3493, 0x4889, 0x6496, 0x2A00, 0x0007,
+    0x4400, 0x2BED, 0x6BAE, 0x3923, 0x6B6E, 0x79A7, 0x79A4, 0x396B,
+    0x5BED, 0x7497, 0x126A, 0x5BAD, 0x4927, 0x5FED, 0x6B6D, 0x2B6A,
+    0x6BA4, 0x2B73, 0x6BAD, 0x388E, 0x7492, 0x5B6B, 0x5B52, 0x5BFD,
+    0x5AAD, 0x5A92, 0x72A7, 0x3513, 0x2492, 0x6456, 0x0780, 0x0000,
+];
+
+fn font_texture_size() -> (u32, u32) {
+    let rows = (GLYPHS.len() as u32).div_ceil(ATLAS_COLS);
+    (ATLAS_COLS * CELL_W, rows * CELL_H)
+}
+
+/// Top-left pixel of the glyph in the font texture. Non-ASCII characters are drawn as `?`
+fn glyph_cell(c: char) -> [u32; 2] {
+    let i = match c {
+        ' '..='\u{7f}' => c as u32 - 0x20,
+        _ => '?' as u32 - 0x20,
+    };
+    [(i % ATLAS_COLS) * CELL_W, (i / ATLAS_COLS) * CELL_H]
+}
+
+/// Opaque white glyphs on a transparent background (RGBA8, premultiplied)
+fn font_pixels() -> Vec<u8> {
+    let (w, h) = self::font_texture_size();
+    let mut pixels = vec![0; (w * h * 4) as usize];
+
+    for (i, bits) in GLYPHS.iter().enumerate() {
+        let [gx, gy] = self::glyph_cell(char::from(0x20 + i as u8));
+        for y in 0..GLYPH_H {
+            for x in 0..GLYPH_W {
+                let bit = GLYPH_W * GLYPH_H - 1 - (y * GLYPH_W + x);
+                if bits & (1 << bit) != 0 {
+                    let p = (((gy + y) * w + gx + x) * 4) as usize;
+                    pixels[p..p + 4].copy_from_slice(&[255; 4]);
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+/// `(column, row, char)` of each character except line breaks
+fn layout(text: &str) -> impl Iterator<Item = (u32, u32, char)> + '_ {
+    text.lines().enumerate().flat_map(|(row, line)| {
+        line.chars()
+            .enumerate()
+            .map(move |(col, c)| (col as u32, row as u32, c))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builtin_font() {
+        assert_eq!(font_texture_size(), (64, 36));
+        assert_eq!(glyph_cell('A'), [4, 12]);
+        assert_eq!(glyph_cell('あ'), glyph_cell('?'));
+
+        let pixels = font_pixels();
+        let (w, _) = font_texture_size();
+        let alpha = |x: u32, y: u32| pixels[((y * w + x) * 4 + 3) as usize];
+        // `1`: `.#.` / `##.` / .. / `###`
+        let [gx, gy] = glyph_cell('1');
+        assert_eq!([alpha(gx, gy), alpha(gx + 1, gy)], [0, 255]);
+        assert_eq!(alpha(gx, gy + 1), 255);
+        assert_eq!(alpha(gx + 2, gy + 4), 255);
+        // spacing column
+        assert_eq!(alpha(gx + 3, gy + 4), 0);
+
+        let layout = layout("ab\nc").collect::<Vec<_>>();
+        assert_eq!(layout, vec![(0, 0, 'a'), (1, 0, 'b'), (0, 1, 'c')]);
+    }
+}
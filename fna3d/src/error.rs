@@ -0,0 +1,158 @@
+//! One error type for applications
+//!
+//! Modules return their own errors ([`res::ResourceError`], [`mojo::ParamError`],
+//! [`img::dds::DdsError`], ..). They convert into [`Error`], as do the errors of `fna3d-imgui`
+//! and `fna3d-fontstash`, so that `?` works across the crates:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, bytes: &[u8]) -> fna3d::Result<()> {
+//! let texture = fna3d::img::dds::load(device, bytes)?;
+//! let mut batch = fna3d::spritebatch::SpriteBatch::new(device)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The module error is kept as the payload of the variant and returned from
+//! [`std::error::Error::source`]; the message of [`Error`] itself only tells what kind of thing
+//! failed. Downcast the source to get the module error back:
+//!
+//! ```no_run
+//! use std::error::Error as _;
+//!
+//! # fn f(e: &fna3d::Error) {
+//! if let Some(e) = e.source().and_then(|e| e.downcast_ref::<fna3d::res::ResourceError>()) {
+//!     // ..
+//! }
+//! # }
+//! ```
+
+use std::{error::Error as StdError, io};
+
+use crate::{fna3d::fna3d_device::CreateDeviceError, img, res};
+
+#[cfg(feature = "mojoshader")]
+use crate::mojo;
+
+/// Result<T, fna3d::Error>
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Payload of [`Error`]
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Error of `fna3d` and the crates built on it
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// FNA3D returned null on creating the resource
+    #[error("FNA3D failed to create {0}")]
+    NullResource(&'static str),
+    /// Failed to load or compile an effect
+    #[error("failed to compile shader")]
+    ShaderCompile(#[source] BoxError),
+    /// Invalid arguments or input data: resource sizes, effect parameters, images, ..
+    #[error("invalid data")]
+    InvalidData(#[source] BoxError),
+    /// FNA3D, the window or a library on top of FNA3D (ImGUI, fontstash) failed
+    #[error("backend error")]
+    Backend(#[source] BoxError),
+    #[error("IO error")]
+    Io(#[from] io::Error),
+}
+
+impl Error {
+    /// Returns [`Error::NullResource`] if the pointer is null
+    ///
+    /// `what` names the resource in the message, e.g. `"texture"`.
+    pub fn check_null<T>(ptr: *mut T, what: &'static str) -> Result<*mut T> {
+        if ptr.is_null() {
+            Err(Error::NullResource(what))
+        } else {
+            Ok(ptr)
+        }
+    }
+
+    pub fn invalid_data(e: impl StdError + Send + Sync + 'static) -> Self {
+        Error::InvalidData(Box::new(e))
+    }
+
+    /// Wraps an error of FNA3D, the window or a crate built on `fna3d`
+    pub fn backend(e: impl StdError + Send + Sync + 'static) -> Self {
+        Error::Backend(Box::new(e))
+    }
+}
+
+impl From<res::ResourceError> for Error {
+    fn from(e: res::ResourceError) -> Self {
+        match e {
+            res::ResourceError::InvalidEffect(_) => Error::ShaderCompile(Box::new(e)),
+            _ => Error::InvalidData(Box::new(e)),
+        }
+    }
+}
+
+#[cfg(feature = "mojoshader")]
+impl From<mojo::LoadShaderError> for Error {
+    fn from(e: mojo::LoadShaderError) -> Self {
+        Error::ShaderCompile(Box::new(e))
+    }
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $var:ident,)*) => {
+        $(
+            impl From<$ty> for Error {
+                fn from(e: $ty) -> Self {
+                    Error::$var(Box::new(e))
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    img::dds::DdsError => InvalidData,
+    img::hdr::HdrError => InvalidData,
+    img::qoi::QoiError => InvalidData,
+    CreateDeviceError => Backend,
+}
+
+#[cfg(feature = "mojoshader")]
+impl_from! {
+    mojo::ParamError => InvalidData,
+}
+
+#[cfg(all(feature = "sdl2", feature = "mojoshader"))]
+impl_from! {
+    crate::easy::Error => Backend,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_conversions() {
+        let e = Error::from(res::ResourceError::EmptyRenderTarget { w: 0, h: 1 });
+        assert!(matches!(e, Error::InvalidData(_)));
+        let source = e.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<res::ResourceError>(),
+            Some(&res::ResourceError::EmptyRenderTarget { w: 0, h: 1 })
+        );
+
+        let e = Error::from(res::ResourceError::InvalidEffect("bad".to_string()));
+        assert!(matches!(e, Error::ShaderCompile(_)));
+
+        let e = Error::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert_eq!(e.source().unwrap().to_string(), "missing");
+
+        let e = Error::backend(io::Error::other("imgui"));
+        assert_eq!(e.to_string(), "backend error");
+        assert_eq!(e.source().unwrap().to_string(), "imgui");
+
+        assert!(Error::check_null(8 as *mut u8, "texture").is_ok());
+        assert!(matches!(
+            Error::check_null(std::ptr::null_mut::<u8>(), "texture"),
+            Err(Error::NullResource("texture"))
+        ));
+    }
+}
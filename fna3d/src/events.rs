@@ -0,0 +1,73 @@
+//! Device event hooks
+//!
+//! Crates built on `fna3d` (state caches, render target trackers, statistics) often need to know
+//! when the backbuffer is reset, the render targets change or a resource is disposed. Implement
+//! [`DeviceObserver`] and register it with [`Device::add_observer`] instead of wrapping the
+//! device:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device) {
+//! use std::{cell::RefCell, rc::Rc};
+//! use fna3d::events::{DeviceObserver, TargetChange};
+//!
+//! #[derive(Default)]
+//! struct TargetSize(Option<(u32, u32)>);
+//!
+//! impl DeviceObserver for TargetSize {
+//!     fn on_target_change(&mut self, _device: &fna3d::Device, change: &TargetChange) {
+//!         self.0 = Some(change.size);
+//!     }
+//! }
+//!
+//! let size = Rc::new(RefCell::new(TargetSize::default()));
+//! device.add_observer(&size);
+//! // `size` is unregistered when it's dropped
+//! # }
+//! ```
+//!
+//! Observers are called on the device thread after the device call (`on_dispose` before it, so
+//! the resource is still valid). They can call `Device` methods, but an observer is skipped while
+//! it's already borrowed (e.g. when it disposes a resource from its own `on_dispose`).
+
+use crate::fna3d::{fna3d_device::Device, fna3d_enums as enums, fna3d_structs::*};
+
+/// Render targets set with [`Device::set_render_targets`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TargetChange {
+    /// Number of color targets. `0` means the backbuffer
+    pub n_targets: u32,
+    /// Size of the first color target, or the backbuffer
+    pub size: (u32, u32),
+    pub depth_format: enums::DepthFormat,
+}
+
+impl TargetChange {
+    pub fn is_backbuffer(&self) -> bool {
+        self.n_targets == 0
+    }
+}
+
+/// Resource given to one of the `Device::add_dispose_*` methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisposedResource {
+    Texture(*mut Texture),
+    VertexBuffer(*mut Buffer),
+    IndexBuffer(*mut Buffer),
+    Renderbuffer(*mut Renderbuffer),
+    Effect(*mut Effect),
+    Query(*mut Query),
+}
+
+/// Receives device events. Register with [`Device::add_observer`]
+///
+/// Every method does nothing by default.
+pub trait DeviceObserver {
+    /// The backbuffer was reset with the parameters ([`Device::reset_backbuffer`])
+    fn on_reset(&mut self, _device: &Device, _params: &PresentationParameters) {}
+
+    /// The render targets were set ([`Device::set_render_targets`])
+    fn on_target_change(&mut self, _device: &Device, _change: &TargetChange) {}
+
+    /// The resource was added to the dispose queue. It's still valid in this call
+    fn on_dispose(&mut self, _device: &Device, _resource: DisposedResource) {}
+}
@@ -0,0 +1,410 @@
+//! Named colors of XNA and the string form of [`Color`]
+//!
+//! Colors can be parsed from names (`"CornflowerBlue"`, case-insensitive) or hex codes
+//! (`"#RRGGBB"` or `"#RRGGBBAA"`). With the `serde` feature, [`Color`] is (de)serialized in this
+//! string form, which is handy for data-driven UI themes:
+//!
+//! ```no_run
+//! let color: fna3d::Color = "CornflowerBlue".parse().unwrap();
+//! assert_eq!(color, fna3d::Color::CORNFLOWER_BLUE);
+//! assert_eq!("#6495ed".parse::<fna3d::Color>().unwrap(), color);
+//! assert_eq!(color.to_string(), "#6495EDFF");
+//! ```
+//!
+//! Note that named colors are opaque except `TRANSPARENT`, so they're valid both as straight and
+//! premultiplied alpha.
+
+use std::{fmt, str::FromStr};
+
+use crate::fna3d::fna3d_structs::Color;
+
+/// XNA colors
+impl Color {
+    pub const ALICE_BLUE: Self = Self::rgb(240, 248, 255);
+    pub const ANTIQUE_WHITE: Self = Self::rgb(250, 235, 215);
+    pub const AQUA: Self = Self::rgb(0, 255, 255);
+    pub const AQUAMARINE: Self = Self::rgb(127, 255, 212);
+    pub const AZURE: Self = Self::rgb(240, 255, 255);
+    pub const BEIGE: Self = Self::rgb(245, 245, 220);
+    pub const BISQUE: Self = Self::rgb(255, 228, 196);
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const BLANCHED_ALMOND: Self = Self::rgb(255, 235, 205);
+    pub const BLUE: Self = Self::rgb(0, 0, 255);
+    pub const BLUE_VIOLET: Self = Self::rgb(138, 43, 226);
+    pub const BROWN: Self = Self::rgb(165, 42, 42);
+    pub const BURLY_WOOD: Self = Self::rgb(222, 184, 135);
+    pub const CADET_BLUE: Self = Self::rgb(95, 158, 160);
+    pub const CHARTREUSE: Self = Self::rgb(127, 255, 0);
+    pub const CHOCOLATE: Self = Self::rgb(210, 105, 30);
+    pub const CORAL: Self = Self::rgb(255, 127, 80);
+    pub const CORNFLOWER_BLUE: Self = Self::rgb(100, 149, 237);
+    pub const CORNSILK: Self = Self::rgb(255, 248, 220);
+    pub const CRIMSON: Self = Self::rgb(220, 20, 60);
+    pub const CYAN: Self = Self::rgb(0, 255, 255);
+    pub const DARK_BLUE: Self = Self::rgb(0, 0, 139);
+    pub const DARK_CYAN: Self = Self::rgb(0, 139, 139);
+    pub const DARK_GOLDENROD: Self = Self::rgb(184, 134, 11);
+    pub const DARK_GRAY: Self = Self::rgb(169, 169, 169);
+    pub const DARK_GREEN: Self = Self::rgb(0, 100, 0);
+    pub const DARK_KHAKI: Self = Self::rgb(189, 183, 107);
+    pub const DARK_MAGENTA: Self = Self::rgb(139, 0, 139);
+    pub const DARK_OLIVE_GREEN: Self = Self::rgb(85, 107, 47);
+    pub const DARK_ORANGE: Self = Self::rgb(255, 140, 0);
+    pub const DARK_ORCHID: Self = Self::rgb(153, 50, 204);
+    pub const DARK_RED: Self = Self::rgb(139, 0, 0);
+    pub const DARK_SALMON: Self = Self::rgb(233, 150, 122);
+    pub const DARK_SEA_GREEN: Self = Self::rgb(143, 188, 139);
+    pub const DARK_SLATE_BLUE: Self = Self::rgb(72, 61, 139);
+    pub const DARK_SLATE_GRAY: Self = Self::rgb(47, 79, 79);
+    pub const DARK_TURQUOISE: Self = Self::rgb(0, 206, 209);
+    pub const DARK_VIOLET: Self = Self::rgb(148, 0, 211);
+    pub const DEEP_PINK: Self = Self::rgb(255, 20, 147);
+    pub const DEEP_SKY_BLUE: Self = Self::rgb(0, 191, 255);
+    pub const DIM_GRAY: Self = Self::rgb(105, 105, 105);
+    pub const DODGER_BLUE: Self = Self::rgb(30, 144, 255);
+    pub const FIREBRICK: Self = Self::rgb(178, 34, 34);
+    pub const FLORAL_WHITE: Self = Self::rgb(255, 250, 240);
+    pub const FOREST_GREEN: Self = Self::rgb(34, 139, 34);
+    pub const FUCHSIA: Self = Self::rgb(255, 0, 255);
+    pub const GAINSBORO: Self = Self::rgb(220, 220, 220);
+    pub const GHOST_WHITE: Self = Self::rgb(248, 248, 255);
+    pub const GOLD: Self = Self::rgb(255, 215, 0);
+    pub const GOLDENROD: Self = Self::rgb(218, 165, 32);
+    pub const GRAY: Self = Self::rgb(128, 128, 128);
+    pub const GREEN: Self = Self::rgb(0, 128, 0);
+    pub const GREEN_YELLOW: Self = Self::rgb(173, 255, 47);
+    pub const HONEYDEW: Self = Self::rgb(240, 255, 240);
+    pub const HOT_PINK: Self = Self::rgb(255, 105, 180);
+    pub const INDIAN_RED: Self = Self::rgb(205, 92, 92);
+    pub const INDIGO: Self = Self::rgb(75, 0, 130);
+    pub const IVORY: Self = Self::rgb(255, 255, 240);
+    pub const KHAKI: Self = Self::rgb(240, 230, 140);
+    pub const LAVENDER: Self = Self::rgb(230, 230, 250);
+    pub const LAVENDER_BLUSH: Self = Self::rgb(255, 240, 245);
+    pub const LAWN_GREEN: Self = Self::rgb(124, 252, 0);
+    pub const LEMON_CHIFFON: Self = Self::rgb(255, 250, 205);
+    pub const LIGHT_BLUE: Self = Self::rgb(173, 216, 230);
+    pub const LIGHT_CORAL: Self = Self::rgb(240, 128, 128);
+    pub const LIGHT_CYAN: Self = Self::rgb(224, 255, 255);
+    pub const LIGHT_GOLDENROD_YELLOW: Self = Self::rgb(250, 250, 210);
+    pub const LIGHT_GRAY: Self = Self::rgb(211, 211, 211);
+    pub const LIGHT_GREEN: Self = Self::rgb(144, 238, 144);
+    pub const LIGHT_PINK: Self = Self::rgb(255, 182, 193);
+    pub const LIGHT_SALMON: Self = Self::rgb(255, 160, 122);
+    pub const LIGHT_SEA_GREEN: Self = Self::rgb(32, 178, 170);
+    pub const LIGHT_SKY_BLUE: Self = Self::rgb(135, 206, 250);
+    pub const LIGHT_SLATE_GRAY: Self = Self::rgb(119, 136, 153);
+    pub const LIGHT_STEEL_BLUE: Self = Self::rgb(176, 196, 222);
+    pub const LIGHT_YELLOW: Self = Self::rgb(255, 255, 224);
+    pub const LIME: Self = Self::rgb(0, 255, 0);
+    pub const LIME_GREEN: Self = Self::rgb(50, 205, 50);
+    pub const LINEN: Self = Self::rgb(250, 240, 230);
+    pub const MAGENTA: Self = Self::rgb(255, 0, 255);
+    pub const MAROON: Self = Self::rgb(128, 0, 0);
+    pub const MEDIUM_AQUAMARINE: Self = Self::rgb(102, 205, 170);
+    pub const MEDIUM_BLUE: Self = Self::rgb(0, 0, 205);
+    pub const MEDIUM_ORCHID: Self = Self::rgb(186, 85, 211);
+    pub const MEDIUM_PURPLE: Self = Self::rgb(147, 112, 219);
+    pub const MEDIUM_SEA_GREEN: Self = Self::rgb(60, 179, 113);
+    pub const MEDIUM_SLATE_BLUE: Self = Self::rgb(123, 104, 238);
+    pub const MEDIUM_SPRING_GREEN: Self = Self::rgb(0, 250, 154);
+    pub const MEDIUM_TURQUOISE: Self = Self::rgb(72, 209, 204);
+    pub const MEDIUM_VIOLET_RED: Self = Self::rgb(199, 21, 133);
+    pub const MIDNIGHT_BLUE: Self = Self::rgb(25, 25, 112);
+    pub const MINT_CREAM: Self = Self::rgb(245, 255, 250);
+    pub const MISTY_ROSE: Self = Self::rgb(255, 228, 225);
+    pub const MOCCASIN: Self = Self::rgb(255, 228, 181);
+    pub const NAVAJO_WHITE: Self = Self::rgb(255, 222, 173);
+    pub const NAVY: Self = Self::rgb(0, 0, 128);
+    pub const OLD_LACE: Self = Self::rgb(253, 245, 230);
+    pub const OLIVE: Self = Self::rgb(128, 128, 0);
+    pub const OLIVE_DRAB: Self = Self::rgb(107, 142, 35);
+    pub const ORANGE: Self = Self::rgb(255, 165, 0);
+    pub const ORANGE_RED: Self = Self::rgb(255, 69, 0);
+    pub const ORCHID: Self = Self::rgb(218, 112, 214);
+    pub const PALE_GOLDENROD: Self = Self::rgb(238, 232, 170);
+    pub const PALE_GREEN: Self = Self::rgb(152, 251, 152);
+    pub const PALE_TURQUOISE: Self = Self::rgb(175, 238, 238);
+    pub const PALE_VIOLET_RED: Self = Self::rgb(219, 112, 147);
+    pub const PAPAYA_WHIP: Self = Self::rgb(255, 239, 213);
+    pub const PEACH_PUFF: Self = Self::rgb(255, 218, 185);
+    pub const PERU: Self = Self::rgb(205, 133, 63);
+    pub const PINK: Self = Self::rgb(255, 192, 203);
+    pub const PLUM: Self = Self::rgb(221, 160, 221);
+    pub const POWDER_BLUE: Self = Self::rgb(176, 224, 230);
+    pub const PURPLE: Self = Self::rgb(128, 0, 128);
+    pub const RED: Self = Self::rgb(255, 0, 0);
+    pub const ROSY_BROWN: Self = Self::rgb(188, 143, 143);
+    pub const ROYAL_BLUE: Self = Self::rgb(65, 105, 225);
+    pub const SADDLE_BROWN: Self = Self::rgb(139, 69, 19);
+    pub const SALMON: Self = Self::rgb(250, 128, 114);
+    pub const SANDY_BROWN: Self = Self::rgb(244, 164, 96);
+    pub const SEA_GREEN: Self = Self::rgb(46, 139, 87);
+    pub const SEA_SHELL: Self = Self::rgb(255, 245, 238);
+    pub const SIENNA: Self = Self::rgb(160, 82, 45);
+    pub const SILVER: Self = Self::rgb(192, 192, 192);
+    pub const SKY_BLUE: Self = Self::rgb(135, 206, 235);
+    pub const SLATE_BLUE: Self = Self::rgb(106, 90, 205);
+    pub const SLATE_GRAY: Self = Self::rgb(112, 128, 144);
+    pub const SNOW: Self = Self::rgb(255, 250, 250);
+    pub const SPRING_GREEN: Self = Self::rgb(0, 255, 127);
+    pub const STEEL_BLUE: Self = Self::rgb(70, 130, 180);
+    pub const TAN: Self = Self::rgb(210, 180, 140);
+    pub const TEAL: Self = Self::rgb(0, 128, 128);
+    pub const THISTLE: Self = Self::rgb(216, 191, 216);
+    pub const TOMATO: Self = Self::rgb(255, 99, 71);
+    pub const TRANSPARENT: Self = Self::rgba(0, 0, 0, 0);
+    pub const TURQUOISE: Self = Self::rgb(64, 224, 208);
+    pub const VIOLET: Self = Self::rgb(238, 130, 238);
+    pub const WHEAT: Self = Self::rgb(245, 222, 179);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+    pub const WHITE_SMOKE: Self = Self::rgb(245, 245, 245);
+    pub const YELLOW: Self = Self::rgb(255, 255, 0);
+    pub const YELLOW_GREEN: Self = Self::rgb(154, 205, 50);
+}
+
+/// Named colors of XNA in alphabetical order. `Aqua`/`Cyan` and `Fuchsia`/`Magenta` are aliases
+pub const NAMED_COLORS: &[(&str, Color)] = &[
+    ("AliceBlue", Color::ALICE_BLUE),
+    ("AntiqueWhite", Color::ANTIQUE_WHITE),
+    ("Aqua", Color::AQUA),
+    ("Aquamarine", Color::AQUAMARINE),
+    ("Azure", Color::AZURE),
+    ("Beige", Color::BEIGE),
+    ("Bisque", Color::BISQUE),
+    ("Black", Color::BLACK),
+    ("BlanchedAlmond", Color::BLANCHED_ALMOND),
+    ("Blue", Color::BLUE),
+    ("BlueViolet", Color::BLUE_VIOLET),
+    ("Brown", Color::BROWN),
+    ("BurlyWood", Color::BURLY_WOOD),
+    ("CadetBlue", Color::CADET_BLUE),
+    ("Chartreuse", Color::CHARTREUSE),
+    ("Chocolate", Color::CHOCOLATE),
+    ("Coral", Color::CORAL),
+    ("CornflowerBlue", Color::CORNFLOWER_BLUE),
+    ("Cornsilk", Color::CORNSILK),
+    ("Crimson", Color::CRIMSON),
+    ("Cyan", Color::CYAN),
+    ("DarkBlue", Color::DARK_BLUE),
+    ("DarkCyan", Color::DARK_CYAN),
+    ("DarkGoldenrod", Color::DARK_GOLDENROD),
+    ("DarkGray", Color::DARK_GRAY),
+    ("DarkGreen", Color::DARK_GREEN),
+    ("DarkKhaki", Color::DARK_KHAKI),
+    ("DarkMagenta", Color::DARK_MAGENTA),
+    ("DarkOliveGreen", Color::DARK_OLIVE_GREEN),
+    ("DarkOrange", Color::DARK_ORANGE),
+    ("DarkOrchid", Color::DARK_ORCHID),
+    ("DarkRed", Color::DARK_RED),
+    ("DarkSalmon", Color::DARK_SALMON),
+    ("DarkSeaGreen", Color::DARK_SEA_GREEN),
+    ("DarkSlateBlue", Color::DARK_SLATE_BLUE),
+    ("DarkSlateGray", Color::DARK_SLATE_GRAY),
+    ("DarkTurquoise", Color::DARK_TURQUOISE),
+    ("DarkViolet", Color::DARK_VIOLET),
+    ("DeepPink", Color::DEEP_PINK),
+    ("DeepSkyBlue", Color::DEEP_SKY_BLUE),
+    ("DimGray", Color::DIM_GRAY),
+    ("DodgerBlue", Color::DODGER_BLUE),
+    ("Firebrick", Color::FIREBRICK),
+    ("FloralWhite", Color::FLORAL_WHITE),
+    ("ForestGreen", Color::FOREST_GREEN),
+    ("Fuchsia", Color::FUCHSIA),
+    ("Gainsboro", Color::GAINSBORO),
+    ("GhostWhite", Color::GHOST_WHITE),
+    ("Gold", Color::GOLD),
+    ("Goldenrod", Color::GOLDENROD),
+    ("Gray", Color::GRAY),
+    ("Green", Color::GREEN),
+    ("GreenYellow", Color::GREEN_YELLOW),
+    ("Honeydew", Color::HONEYDEW),
+    ("HotPink", Color::HOT_PINK),
+    ("IndianRed", Color::INDIAN_RED),
+    ("Indigo", Color::INDIGO),
+    ("Ivory", Color::IVORY),
+    ("Khaki", Color::KHAKI),
+    ("Lavender", Color::LAVENDER),
+    ("LavenderBlush", Color::LAVENDER_BLUSH),
+    ("LawnGreen", Color::LAWN_GREEN),
+    ("LemonChiffon", Color::LEMON_CHIFFON),
+    ("LightBlue", Color::LIGHT_BLUE),
+    ("LightCoral", Color::LIGHT_CORAL),
+    ("LightCyan", Color::LIGHT_CYAN),
+    ("LightGoldenrodYellow", Color::LIGHT_GOLDENROD_YELLOW),
+    ("LightGray", Color::LIGHT_GRAY),
+    ("LightGreen", Color::LIGHT_GREEN),
+    ("LightPink", Color::LIGHT_PINK),
+    ("LightSalmon", Color::LIGHT_SALMON),
+    ("LightSeaGreen", Color::LIGHT_SEA_GREEN),
+    ("LightSkyBlue", Color::LIGHT_SKY_BLUE),
+    ("LightSlateGray", Color::LIGHT_SLATE_GRAY),
+    ("LightSteelBlue", Color::LIGHT_STEEL_BLUE),
+    ("LightYellow", Color::LIGHT_YELLOW),
+    ("Lime", Color::LIME),
+    ("LimeGreen", Color::LIME_GREEN),
+    ("Linen", Color::LINEN),
+    ("Magenta", Color::MAGENTA),
+    ("Maroon", Color::MAROON),
+    ("MediumAquamarine", Color::MEDIUM_AQUAMARINE),
+    ("MediumBlue", Color::MEDIUM_BLUE),
+    ("MediumOrchid", Color::MEDIUM_ORCHID),
+    ("MediumPurple", Color::MEDIUM_PURPLE),
+    ("MediumSeaGreen", Color::MEDIUM_SEA_GREEN),
+    ("MediumSlateBlue", Color::MEDIUM_SLATE_BLUE),
+    ("MediumSpringGreen", Color::MEDIUM_SPRING_GREEN),
+    ("MediumTurquoise", Color::MEDIUM_TURQUOISE),
+    ("MediumVioletRed", Color::MEDIUM_VIOLET_RED),
+    ("MidnightBlue", Color::MIDNIGHT_BLUE),
+    ("MintCream", Color::MINT_CREAM),
+    ("MistyRose", Color::MISTY_ROSE),
+    ("Moccasin", Color::MOCCASIN),
+    ("NavajoWhite", Color::NAVAJO_WHITE),
+    ("Navy", Color::NAVY),
+    ("OldLace", Color::OLD_LACE),
+    ("Olive", Color::OLIVE),
+    ("OliveDrab", Color::OLIVE_DRAB),
+    ("Orange", Color::ORANGE),
+    ("OrangeRed", Color::ORANGE_RED),
+    ("Orchid", Color::ORCHID),
+    ("PaleGoldenrod", Color::PALE_GOLDENROD),
+    ("PaleGreen", Color::PALE_GREEN),
+    ("PaleTurquoise", Color::PALE_TURQUOISE),
+    ("PaleVioletRed", Color::PALE_VIOLET_RED),
+    ("PapayaWhip", Color::PAPAYA_WHIP),
+    ("PeachPuff", Color::PEACH_PUFF),
+    ("Peru", Color::PERU),
+    ("Pink", Color::PINK),
+    ("Plum", Color::PLUM),
+    ("PowderBlue", Color::POWDER_BLUE),
+    ("Purple", Color::PURPLE),
+    ("Red", Color::RED),
+    ("RosyBrown", Color::ROSY_BROWN),
+    ("RoyalBlue", Color::ROYAL_BLUE),
+    ("SaddleBrown", Color::SADDLE_BROWN),
+    ("Salmon", Color::SALMON),
+    ("SandyBrown", Color::SANDY_BROWN),
+    ("SeaGreen", Color::SEA_GREEN),
+    ("SeaShell", Color::SEA_SHELL),
+    ("Sienna", Color::SIENNA),
+    ("Silver", Color::SILVER),
+    ("SkyBlue", Color::SKY_BLUE),
+    ("SlateBlue", Color::SLATE_BLUE),
+    ("SlateGray", Color::SLATE_GRAY),
+    ("Snow", Color::SNOW),
+    ("SpringGreen", Color::SPRING_GREEN),
+    ("SteelBlue", Color::STEEL_BLUE),
+    ("Tan", Color::TAN),
+    ("Teal", Color::TEAL),
+    ("Thistle", Color::THISTLE),
+    ("Tomato", Color::TOMATO),
+    ("Transparent", Color::TRANSPARENT),
+    ("Turquoise", Color::TURQUOISE),
+    ("Violet", Color::VIOLET),
+    ("Wheat", Color::WHEAT),
+    ("White", Color::WHITE),
+    ("WhiteSmoke", Color::WHITE_SMOKE),
+    ("Yellow", Color::YELLOW),
+    ("YellowGreen", Color::YELLOW_GREEN),
+];
+
+impl Color {
+    /// Looks up a named color (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        NAMED_COLORS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, c)| *c)
+    }
+
+    /// Parses `#RRGGBB` or `#RRGGBBAA`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#')?;
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return None;
+        }
+
+        let byte = |i: usize| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok();
+        let a = if hex.len() == 8 { byte(3)? } else { 255 };
+        Some(Self::rgba(byte(0)?, byte(1)?, byte(2)?, a))
+    }
+}
+
+/// Error of parsing [`Color`] from a string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(pub String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid color `{}` (expected a color name, `#RRGGBB` or `#RRGGBBAA`)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let color = if s.starts_with('#') {
+            Self::from_hex(s)
+        } else {
+            Self::from_name(s)
+        };
+        color.ok_or_else(|| ParseColorError(s.to_string()))
+    }
+}
+
+/// `#RRGGBBAA`
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = self.raw();
+        write!(f, "#{:02X}{:02X}{:02X}{:02X}", c.r, c.g, c.b, c.a)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(
+            Color::from_name("cornflowerblue"),
+            Some(Color::cornflower_blue())
+        );
+        assert_eq!(
+            "#FF000080".parse::<Color>(),
+            Ok(Color::rgba(255, 0, 0, 128))
+        );
+        assert!("#FF00".parse::<Color>().is_err());
+        assert!("NotAColor".parse::<Color>().is_err());
+
+        // every named color survives the string round trip
+        for (name, color) in NAMED_COLORS {
+            assert_eq!(name.parse::<Color>().as_ref(), Ok(color));
+            assert_eq!(color.to_string().parse::<Color>().as_ref(), Ok(color));
+        }
+        assert_eq!(NAMED_COLORS.len(), 141);
+    }
+}
@@ -2,19 +2,33 @@
 
 use ::{
     fna3d_sys::*,
-    num_traits::FromPrimitive,
     std::{
+        cell::{Cell, RefCell},
+        ffi::CString,
+        fmt,
+        io,
         // this should be `std::ffi::c_void` but `bindgen` uses:
         os::raw::c_void,
-        rc::Rc,
+        path::Path,
+        rc::{Rc, Weak},
+        thread::{self, ThreadId},
     },
 };
 
 use crate::{
-    fna3d::{fna3d_enums as enums, fna3d_structs::*},
-    mojo,
+    defaults::{DefaultStore, Defaults},
+    events::{DeviceObserver, DisposedResource, TargetChange},
+    fna3d::{fna3d_enums as enums, fna3d_functions as funcs, fna3d_structs::*},
+    intern::{BlendId, BlendTable, SamplerId, SamplerTable},
+    logging::LogMessage,
+    res,
+    settings::GraphicsSettings,
+    stats::DrawStats,
 };
 
+#[cfg(feature = "mojoshader")]
+use {crate::mojo, std::cell::UnsafeCell};
+
 // --------------------------------------------------------------------------------
 // Helpers
 
@@ -28,6 +42,61 @@ macro_rules! as_ptr {
     };
 }
 
+/// Rasterizer state with the fill mode overridden in wireframe mode
+fn effective_rasterizer(rst: &RasterizerState, wireframe: bool) -> RasterizerState {
+    let mut rst = rst.clone();
+    if wireframe {
+        rst.set_fill_mode(enums::FillMode::WireFrame);
+    }
+    rst
+}
+
+/// Texture and interned sampler state of a sampler slot
+type SamplerSlot = (*mut Texture, SamplerId);
+
+/// Number of distinct states a device interns before dropping the ones not in use
+const MAX_INTERNED_STATES: usize = 1024;
+
+/// If the two binding arrays describe the same buffers and vertex declarations
+///
+/// Vertex elements are compared by pointer, not by content.
+fn same_bindings(a: &[VertexBufferBinding], b: &[VertexBufferBinding]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b.iter()).all(|(a, b)| {
+            let (da, db) = (&a.vertexDeclaration, &b.vertexDeclaration);
+            a.vertexBuffer == b.vertexBuffer
+                && a.vertexOffset == b.vertexOffset
+                && a.instanceFrequency == b.instanceFrequency
+                && da.vertexStride == db.vertexStride
+                && da.elementCount == db.elementCount
+                && da.elements == db.elements
+        })
+}
+
+/// Last bindings given to [`Device::apply_vertex_buffer_bindings`]
+///
+/// Cleared when a vertex buffer is disposed or the backbuffer is reset: a new buffer can be
+/// created at the address of a disposed one, and it has to be set up from scratch.
+#[derive(Debug, Default)]
+struct BindingCache {
+    last: Vec<VertexBufferBinding>,
+}
+
+impl BindingCache {
+    fn is_current(&self, bindings: &[VertexBufferBinding]) -> bool {
+        self::same_bindings(&self.last, bindings)
+    }
+
+    fn update(&mut self, bindings: &[VertexBufferBinding]) {
+        self.last.clear();
+        self.last.extend_from_slice(bindings);
+    }
+
+    fn clear(&mut self) {
+        self.last.clear();
+    }
+}
+
 // --------------------------------------------------------------------------------
 // Device
 
@@ -64,6 +133,14 @@ macro_rules! as_ptr {
 /// - [`Query`]
 /// - [`Texture`]
 ///
+/// or use the owned wrappers in [`crate::res`], which dispose them on drop:
+///
+/// - [`res::VertexBuffer`], [`res::IndexBuffer`]
+/// - [`res::RenderbufferHandle`], [`res::RenderTarget2d`]
+/// - [`res::EffectHandle`]
+/// - [`res::QueryHandle`]
+/// - [`res::Texture2d`]
+///
 /// # Initialization
 ///
 /// It's required to set viewport/rasterizer/blend state. **If this is skipped, we can't draw
@@ -101,28 +178,262 @@ impl Device {
     pub fn raw(&self) -> *mut FNA3D_Device {
         self.lifetime.raw
     }
+
+    /// Presentation parameters the device was created (or last reset) with
+    pub fn params(&self) -> PresentationParameters {
+        self.lifetime.params.get()
+    }
+
+    /// Thread the device was created on. Device functions must only be called on it
+    pub fn thread_id(&self) -> ThreadId {
+        self.lifetime.thread
+    }
+
+    /// If the calling thread is [`Device::thread_id`]
+    pub fn is_device_thread(&self) -> bool {
+        thread::current().id() == self.lifetime.thread
+    }
+
+    /// Shared default resources, created on first access and disposed with the device
+    pub fn defaults(&self) -> Defaults<'_> {
+        Defaults::new(self, &self.lifetime.defaults)
+    }
+
+    /// Draw calls made since the last [`Device::reset_draw_stats`]
+    pub fn draw_stats(&self) -> DrawStats {
+        self.lifetime.stats.get()
+    }
+
+    /// Call it at the beginning of a frame to get per-frame statistics
+    pub fn reset_draw_stats(&self) {
+        self.lifetime.stats.set(DrawStats::default());
+    }
+
+    fn count_draw_call(&self, n_primitives: u32, n_instances: u32) {
+        let mut stats = self.lifetime.stats.get();
+        stats.draw_calls += 1;
+        stats.primitives += n_primitives as u64 * n_instances as u64;
+        self.lifetime.stats.set(stats);
+    }
 }
 
+/// Called after the backbuffer is recreated by [`Device::handle_backbuffer_invalidation`]
+pub type BackbufferResetCallback = Box<dyn FnMut(&Device, &PresentationParameters)>;
+
+/// Ends the group of [`Device::debug_group`] on drop
+#[must_use = "the group ends when the guard is dropped"]
 #[derive(Debug)]
+pub struct DebugGroupGuard<'a> {
+    device: &'a Device,
+    /// Names of the nested groups joined with `/`
+    path: String,
+}
+
+impl DebugGroupGuard<'_> {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for DebugGroupGuard<'_> {
+    fn drop(&mut self) {
+        self.device.lifetime.debug_groups.borrow_mut().pop();
+        self.device.set_string_marker(&format!("end {}", self.path));
+    }
+}
+
+/// Marker text without interior nul characters
+fn marker_cstring(text: &str) -> CString {
+    match CString::new(text) {
+        Ok(text) => text,
+        Err(e) => {
+            let pos = e.nul_position();
+            CString::new(&text[..pos]).unwrap()
+        }
+    }
+}
+
+/// States returned from [`Device::snapshot_states`]
+#[cfg(feature = "mojoshader")]
+pub(crate) struct StateSnapshot {
+    blend: Option<BlendState>,
+    depth_stencil: Option<DepthStencilState>,
+    rasterizer: Option<RasterizerState>,
+    viewport: Option<Viewport>,
+    scissor: Option<Rect>,
+    sampler0: Option<(*mut Texture, SamplerState)>,
+}
+
 struct DeviceDrop {
     raw: *mut FNA3D_Device,
+    params: Cell<PresentationParameters>,
+    reset_callbacks: RefCell<Vec<BackbufferResetCallback>>,
+    /// Targets given to [`Device::register_render_target`]
+    render_targets: RefCell<Vec<Weak<RefCell<res::RenderTarget2d>>>>,
+    /// Observers given to [`Device::add_observer`]
+    observers: RefCell<Vec<Weak<RefCell<dyn DeviceObserver>>>>,
+    thread: ThreadId,
+    /// Number of [`Device::swap_buffers`] calls
+    frame: Cell<u64>,
+    stats: Cell<DrawStats>,
+    /// Last rasterizer state given to [`Device::apply_rasterizer_state`]
+    rasterizer: RefCell<Option<RasterizerState>>,
+    wireframe: Cell<bool>,
+    /// Last states given to the setters, for [`Device::dump_state`]
+    blend: Cell<Option<BlendId>>,
+    /// False after the blend factor or the multisample mask is set, so that the next
+    /// [`Device::set_blend_state`] is not skipped
+    blend_applied: Cell<bool>,
+    depth_stencil: RefCell<Option<DepthStencilState>>,
+    viewport: Cell<Option<Viewport>>,
+    scissor: Cell<Option<Rect>>,
+    /// Last textures and sampler states given to [`Device::verify_sampler`]
+    samplers: RefCell<Vec<Option<SamplerSlot>>>,
+    /// Last textures and sampler states given to [`Device::verify_vertex_sampler`]
+    vertex_samplers: RefCell<Vec<Option<SamplerSlot>>>,
+    /// States given to the setters. The shadow states above refer to them by id
+    blend_table: RefCell<BlendTable>,
+    sampler_table: RefCell<SamplerTable>,
+    bindings: RefCell<BindingCache>,
+    /// Resources returned from [`Device::defaults`]
+    defaults: DefaultStore,
+    /// Given to FNA3D by [`Device::apply_effect_simple`]. It lives as long as the device, so it's
+    /// valid while any effect is applied. FNA3D writes into it, hence the `UnsafeCell`
+    #[cfg(feature = "mojoshader")]
+    no_change_effect: UnsafeCell<mojo::EffectStateChanges>,
+    /// Names of the open [`Device::debug_group`]s
+    debug_groups: RefCell<Vec<String>>,
+    /// Display index and mode of the window at the last backbuffer reset, compared by
+    /// [`Device::check_backbuffer`]
+    #[cfg(feature = "sdl2")]
+    display: Cell<Option<(i32, DisplayModeInfo)>>,
+}
+
+impl fmt::Debug for DeviceDrop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("DeviceDrop");
+        s.field("raw", &self.raw)
+            .field("params", &self.params)
+            .field("reset_callbacks", &self.reset_callbacks.borrow().len())
+            .field("render_targets", &self.render_targets.borrow().len())
+            .field("observers", &self.observers.borrow().len())
+            .field("thread", &self.thread)
+            .field("frame", &self.frame)
+            .field("stats", &self.stats)
+            .field("rasterizer", &self.rasterizer)
+            .field("wireframe", &self.wireframe)
+            .field("blend", &self.blend)
+            .field("blend_applied", &self.blend_applied)
+            .field("depth_stencil", &self.depth_stencil)
+            .field("viewport", &self.viewport)
+            .field("scissor", &self.scissor)
+            .field("samplers", &self.samplers)
+            .field("vertex_samplers", &self.vertex_samplers)
+            .field("blend_table", &self.blend_table.borrow().len())
+            .field("sampler_table", &self.sampler_table.borrow().len())
+            .field("bindings", &self.bindings)
+            .field("defaults", &self.defaults);
+        #[cfg(feature = "mojoshader")]
+        s.field("no_change_effect", &self.no_change_effect);
+        s.field("debug_groups", &self.debug_groups);
+        #[cfg(feature = "sdl2")]
+        s.field("display", &self.display);
+        s.finish()
+    }
 }
 
 impl Drop for DeviceDrop {
     fn drop(&mut self) {
+        self.defaults.dispose(self.raw);
         unsafe {
             FNA3D_DestroyDevice(self.raw);
         };
     }
 }
 
+/// Returned from [`Device::try_from_params`] when `FNA3D_CreateDevice` fails
+///
+/// It's a snapshot of the environment to be shown to users or attached to crash reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateDeviceError {
+    /// `FNA3D_FORCE_DRIVER` (the requested backend). `None` if FNA3D selects one
+    pub requested_driver: Option<String>,
+    /// Flags of the window. `None` if the window handle is null or without the `sdl2` feature
+    pub window_flags: Option<funcs::SdlWindowFlags>,
+    /// Drawable size of the window in pixels. `None` if the window handle is null
+    pub drawable_size: Option<(u32, u32)>,
+    /// Requested backbuffer size
+    pub back_buffer_size: (i32, i32),
+    pub do_debug: bool,
+    /// [`funcs::linked_version`]
+    pub linked_version: u32,
+    /// Last FNA3D messages. Empty unless [`crate::logging::hook_log_functions`] is called
+    /// beforehand
+    pub recent_logs: Vec<LogMessage>,
+}
+
+impl CreateDeviceError {
+    fn collect(params: &PresentationParameters, do_debug: bool) -> Self {
+        let window = params.deviceWindowHandle;
+        let (window_flags, drawable_size) = if window.is_null() {
+            (None, None)
+        } else {
+            // SAFETY: the handle was given to FNA3D as `SDL_Window*`
+            #[cfg(feature = "sdl2")]
+            let flags = Some(unsafe { funcs::window_flags(window) });
+            #[cfg(not(feature = "sdl2"))]
+            let flags = None;
+            (flags, Some(funcs::get_drawable_size(window)))
+        };
+
+        Self {
+            requested_driver: std::env::var("FNA3D_FORCE_DRIVER").ok(),
+            window_flags,
+            drawable_size,
+            back_buffer_size: (params.backBufferWidth, params.backBufferHeight),
+            do_debug,
+            linked_version: funcs::linked_version(),
+            recent_logs: crate::logging::recent(),
+        }
+    }
+}
+
+impl fmt::Display for CreateDeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "unable to create FNA3D device")?;
+        writeln!(
+            f,
+            "  requested driver: {}",
+            self.requested_driver.as_deref().unwrap_or("(auto)")
+        )?;
+        match self.window_flags {
+            Some(flags) => writeln!(f, "  window flags: {:#x}", flags.0)?,
+            None => writeln!(f, "  window: null")?,
+        }
+        if let Some((w, h)) = self.drawable_size {
+            writeln!(f, "  drawable size: [{}, {}]", w, h)?;
+        }
+        let (w, h) = self.back_buffer_size;
+        writeln!(f, "  backbuffer size: [{}, {}]", w, h)?;
+        writeln!(f, "  debug: {}", self.do_debug)?;
+        write!(f, "  FNA3D version: {}", self.linked_version)?;
+        for msg in &self.recent_logs {
+            write!(f, "\n  {}", msg)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CreateDeviceError {}
+
 /// Init/Quit
 /// ---
 impl Device {
     /// Creates a rendering context for use on the calling thread.
     ///
     /// * `params`:
-    ///   The initial device/backbuffer settings.
+    ///   The initial device/backbuffer settings. FNA3D may adjust them (e.g. clamps MSAA), and the
+    ///   actual configuration is written back.
     /// * `do_debug`:
     ///   Enable debugging and backend validation features at the cost of reduced overall
     ///   performance.
@@ -131,12 +442,85 @@ impl Device {
     /// the thread that it was created on!
     ///
     /// See [initialization](./struct.Device.html#initialization)
-    pub fn from_params(mut params: PresentationParameters, do_debug: bool) -> Self {
-        Self {
+    ///
+    /// # Panics
+    ///
+    /// Panics with [`CreateDeviceError`] if FNA3D fails to create a device. Use
+    /// [`Device::try_from_params`] to handle it.
+    pub fn from_params(params: &mut PresentationParameters, do_debug: bool) -> Self {
+        Self::try_from_params(params, do_debug).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// [`Device::from_params`], but returns the environment on failure instead of panicking
+    ///
+    /// Call [`crate::logging::hook_log_functions`] beforehand so that the error includes the
+    /// messages of FNA3D.
+    pub fn try_from_params(
+        params: &mut PresentationParameters,
+        do_debug: bool,
+    ) -> Result<Self, CreateDeviceError> {
+        let raw = unsafe { FNA3D_CreateDevice(params, do_debug as u8) };
+        if raw.is_null() {
+            return Err(CreateDeviceError::collect(params, do_debug));
+        }
+
+        Ok(Self {
             lifetime: Rc::new(DeviceDrop {
-                raw: unsafe { FNA3D_CreateDevice(&mut params, do_debug as u8) },
+                raw,
+                params: Cell::new(*params),
+                reset_callbacks: RefCell::new(Vec::new()),
+                render_targets: RefCell::new(Vec::new()),
+                observers: RefCell::new(Vec::new()),
+                thread: thread::current().id(),
+                frame: Cell::new(0),
+                stats: Cell::new(DrawStats::default()),
+                rasterizer: RefCell::new(None),
+                wireframe: Cell::new(false),
+                blend: Cell::new(None),
+                blend_applied: Cell::new(false),
+                depth_stencil: RefCell::new(None),
+                viewport: Cell::new(None),
+                scissor: Cell::new(None),
+                samplers: RefCell::new(Vec::new()),
+                vertex_samplers: RefCell::new(Vec::new()),
+                blend_table: RefCell::new(BlendTable::new()),
+                sampler_table: RefCell::new(SamplerTable::new()),
+                bindings: RefCell::new(BindingCache::default()),
+                defaults: DefaultStore::new(),
+                #[cfg(feature = "mojoshader")]
+                no_change_effect: UnsafeCell::new(crate::utils::no_change_effect()),
+                debug_groups: RefCell::new(Vec::new()),
+                #[cfg(feature = "sdl2")]
+                display: Cell::new(None),
             }),
+        })
+    }
+
+    /// Creates a device from [`GraphicsSettings`]
+    ///
+    /// * `window`:
+    ///   The OS window handle (`SDL_Window*`). Create it with the flags returned from
+    ///   [`GraphicsSettings::prepare_window_attributes`] so that the backend setting is respected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if FNA3D fails to create a device. Use [`Device::try_from_settings`] to handle it.
+    pub fn from_settings(window: *mut c_void, settings: &GraphicsSettings) -> Self {
+        Self::try_from_settings(window, settings).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// [`Device::from_settings`], but returns the environment on failure instead of panicking
+    pub fn try_from_settings(
+        window: *mut c_void,
+        settings: &GraphicsSettings,
+    ) -> Result<Self, CreateDeviceError> {
+        let mut params = settings.to_params(window);
+        let device = Self::try_from_params(&mut params, settings.debug)?;
+        if settings.msaa > 0 {
+            // MSAA can only be clamped after creating the device
+            settings.apply(&device);
         }
+        Ok(device)
     }
 }
 
@@ -164,6 +548,15 @@ impl Device {
                 override_window_handle,
             );
         }
+        self.lifetime.frame.set(self.lifetime.frame.get() + 1);
+    }
+
+    /// Number of presented frames (calls of [`Device::swap_buffers`])
+    ///
+    /// Used as a fence: draw calls made in frame `n` are finished by the GPU when the count reaches
+    /// `n + frames_in_flight` (see [`crate::res::VertexBuffer::with_frames_in_flight`]).
+    pub fn frame_count(&self) -> u64 {
+        self.lifetime.frame.get()
     }
 }
 
@@ -182,7 +575,8 @@ impl Device {
     /// * `options`:
     ///   Bitflags to specify color/depth/stencil buffers for clearing.
     /// * `color`:
-    ///   The new value of the cleared color buffer.
+    ///   The new value of the cleared color buffer. A [`Vec4`], a [`Color`] or a [`ClearColor`]
+    ///   converted beforehand.
     /// * `depth`:
     ///   The new value of the cleared depth buffer.
     /// * `stencil`:
@@ -191,21 +585,28 @@ impl Device {
     /// # Example
     ///
     /// ```no_run
-    /// fn just_clear(device: &fna3d::Device) {
-    ///     device.clear(
-    ///         fna3d::ClearOptions::TARGET,
-    ///         fna3d::Color::cornflower_blue().to_vec4(),
-    ///         0.0,
-    ///         0,
-    ///     );
+    /// fn just_clear(device: &fna3d::Device, color: &fna3d::ClearColor) {
+    ///     device.clear(fna3d::ClearOptions::TARGET, *color, 0.0, 0);
     /// }
+    ///
+    /// # fn f(device: &fna3d::Device) {
+    /// let color = fna3d::ClearColor::new(fna3d::Color::cornflower_blue());
+    /// just_clear(device, &color);
+    /// # }
     /// ```
-    pub fn clear(&self, options: enums::ClearOptions, color: Vec4, depth: f32, stencil: i32) {
+    pub fn clear(
+        &self,
+        options: enums::ClearOptions,
+        color: impl Into<ClearColor>,
+        depth: f32,
+        stencil: i32,
+    ) {
+        let color = color.into();
         unsafe {
             FNA3D_Clear(
                 self.raw(),
                 options.bits(),
-                &color as *const _ as *mut _,
+                color.raw() as *const _ as *mut _,
                 depth,
                 stencil,
             );
@@ -227,6 +628,7 @@ impl Device {
         ibuf: *mut Buffer,
         index_elem_size: enums::IndexElementSize,
     ) {
+        self.count_draw_call(n_primitives, 1);
         unsafe {
             FNA3D_DrawIndexedPrimitives(
                 self.raw(),
@@ -260,6 +662,7 @@ impl Device {
         ibuf: *mut Buffer,
         index_elem_size: enums::IndexElementSize,
     ) {
+        self.count_draw_call(prim_count, instance_count);
         unsafe {
             FNA3D_DrawInstancedPrimitives(
                 self.raw(),
@@ -282,6 +685,7 @@ impl Device {
     pub fn draw_primitives(&self, type_: enums::PrimitiveType, base_vtx: u32, n_primitives: u32) {
         let vertex_start = base_vtx as i32;
         let prim_count = n_primitives as i32;
+        self.count_draw_call(n_primitives, 1);
         unsafe {
             FNA3D_DrawPrimitives(
                 self.raw(),
@@ -291,6 +695,124 @@ impl Device {
             );
         }
     }
+
+    /// Applies the effect pass and the vertex buffer binding, then draws with 16 bits indices
+    ///
+    /// It's the documented sequence ([`Device::apply_effect`],
+    /// [`Device::apply_vertex_buffer_bindings`] and [`Device::draw_indexed_primitives`]) in one
+    /// call. Samplers have to be verified beforehand.
+    ///
+    /// * `base_vtx`, `base_idx`: offsets returned from [`res::VertexBuffer::append`] and
+    ///   [`res::IndexBuffer::append`]
+    ///
+    /// ```no_run
+    /// # fn f(
+    /// #     device: &fna3d::Device,
+    /// #     effect: &fna3d::res::EffectHandle,
+    /// #     vbuf: &mut fna3d::res::VertexBuffer<[f32; 4]>,
+    /// #     ibuf: &mut fna3d::res::IndexBuffer<u16>,
+    /// #     decl: fna3d::VertexDeclaration,
+    /// # ) -> fna3d::res::Result<()> {
+    /// let base_vtx = vbuf.append(&[[0.0; 4]; 4])?;
+    /// let base_idx = ibuf.append(&[0, 1, 2, 3, 2, 1])?;
+    /// device.draw_indexed_primitives_u16(
+    ///     effect,
+    ///     0,
+    ///     (vbuf, decl),
+    ///     ibuf,
+    ///     fna3d::PrimitiveType::TriangleList,
+    ///     base_vtx,
+    ///     base_idx,
+    ///     2,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "mojoshader")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_indexed_primitives_u16<V>(
+        &self,
+        effect: &res::EffectHandle,
+        pass: u32,
+        vbuf: (&res::VertexBuffer<V>, VertexDeclaration),
+        ibuf: &res::IndexBuffer<u16>,
+        type_: enums::PrimitiveType,
+        base_vtx: u32,
+        base_idx: u32,
+        n_primitives: u32,
+    ) {
+        self.draw_indexed_with(
+            effect,
+            pass,
+            vbuf,
+            ibuf,
+            type_,
+            base_vtx,
+            base_idx,
+            n_primitives,
+        );
+    }
+
+    /// [`Device::draw_indexed_primitives_u16`] with 32 bits indices
+    #[cfg(feature = "mojoshader")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_indexed_primitives_u32<V>(
+        &self,
+        effect: &res::EffectHandle,
+        pass: u32,
+        vbuf: (&res::VertexBuffer<V>, VertexDeclaration),
+        ibuf: &res::IndexBuffer<u32>,
+        type_: enums::PrimitiveType,
+        base_vtx: u32,
+        base_idx: u32,
+        n_primitives: u32,
+    ) {
+        self.draw_indexed_with(
+            effect,
+            pass,
+            vbuf,
+            ibuf,
+            type_,
+            base_vtx,
+            base_idx,
+            n_primitives,
+        );
+    }
+
+    #[cfg(feature = "mojoshader")]
+    #[allow(clippy::too_many_arguments)]
+    fn draw_indexed_with<V, I: res::IndexElement>(
+        &self,
+        effect: &res::EffectHandle,
+        pass: u32,
+        (vbuf, decl): (&res::VertexBuffer<V>, VertexDeclaration),
+        ibuf: &res::IndexBuffer<I>,
+        type_: enums::PrimitiveType,
+        base_vtx: u32,
+        base_idx: u32,
+        n_primitives: u32,
+    ) {
+        self.apply_effect_no_change(effect.raw(), pass);
+
+        let binding = VertexBufferBinding {
+            vertexBuffer: vbuf.raw(),
+            vertexDeclaration: decl,
+            vertexOffset: 0,
+            instanceFrequency: 0,
+        };
+        self.apply_vertex_buffer_bindings_auto(&[binding], base_vtx);
+
+        self.draw_indexed_primitives(
+            type_,
+            base_vtx,
+            0,
+            vbuf.len().saturating_sub(base_vtx),
+            base_idx,
+            n_primitives,
+            ibuf.raw(),
+            I::SIZE,
+        );
+    }
 }
 
 /// Mutable render states
@@ -302,6 +824,7 @@ impl Device {
     /// to call this at least once after calling `set_render_targets`, as the renderer may need to
     /// adjust these dimensions to fit the backend's potentially goofy coordinate systems.
     pub fn set_viewport(&self, viewport: &Viewport) {
+        self.lifetime.viewport.set(Some(*viewport));
         unsafe {
             FNA3D_SetViewport(self.raw(), viewport as *const _ as *mut _);
         }
@@ -311,6 +834,7 @@ impl Device {
     /// call this at least once after calling `set_render_targets`, as the renderer may need to
     /// adjust these dimensions to fit the backend's potentially goofy coordinate systems.
     pub fn set_scissor_rect(&self, scissor: &Rect) {
+        self.lifetime.scissor.set(Some(*scissor));
         unsafe {
             FNA3D_SetScissorRect(self.raw(), scissor as *const _ as *mut _);
         }
@@ -330,6 +854,7 @@ impl Device {
     ///
     /// * `blend_factor`: The color to use as the device blend factor.
     pub fn set_blend_factor(&self, blend_factor: Color) {
+        self.lifetime.blend_applied.set(false);
         unsafe {
             FNA3D_SetBlendFactor(self.raw(), &mut blend_factor.raw() as *mut _);
         }
@@ -346,6 +871,7 @@ impl Device {
     ///
     /// * `ref`: The new stencil reference value.
     pub fn set_multi_sample_mask(&self, mask: i32) {
+        self.lifetime.blend_applied.set(false);
         unsafe {
             FNA3D_SetMultiSampleMask(self.raw(), mask);
         }
@@ -375,15 +901,41 @@ impl Device {
 impl Device {
     /// Applies a blending state to use for future draw calls. This only needs to be called when the
     /// state actually changes. Redundant calls may negatively affect performance!
+    ///
+    /// The state is interned, and calls with the current state are skipped by comparing the ids.
+    /// Setting the blend factor or the multisample mask makes the next call go through.
     pub fn set_blend_state(&self, blend_state: &BlendState) {
+        let id = self.intern_blend(blend_state);
+        let dev = &self.lifetime;
+        if dev.blend.replace(Some(id)) == Some(id) && dev.blend_applied.get() {
+            return;
+        }
+        dev.blend_applied.set(true);
         unsafe {
             FNA3D_SetBlendState(self.raw(), blend_state.raw() as *const _ as *mut _);
         }
     }
 
+    /// Last blend state given to [`Device::set_blend_state`]
+    pub fn blend_state(&self) -> Option<BlendState> {
+        let id = self.lifetime.blend.get()?;
+        Some(self.lifetime.blend_table.borrow().get(id).clone())
+    }
+
+    fn intern_blend(&self, state: &BlendState) -> BlendId {
+        let mut table = self.lifetime.blend_table.borrow_mut();
+        if table.find(state).is_none() && table.len() >= MAX_INTERNED_STATES {
+            let mut current = self.lifetime.blend.get();
+            table.compact(current.as_mut());
+            self.lifetime.blend.set(current);
+        }
+        table.intern(state)
+    }
+
     /// Applies depth/stencil states to use for future draw calls. This only needs to be called when
     /// the states actually change. Redundant calls may negatively affect performance!
     pub fn set_depth_stencil_state(&self, depth_stencil_state: &DepthStencilState) {
+        *self.lifetime.depth_stencil.borrow_mut() = Some(depth_stencil_state.clone());
         unsafe {
             FNA3D_SetDepthStencilState(self.raw(), depth_stencil_state.raw() as *const _ as *mut _);
         }
@@ -392,12 +944,51 @@ impl Device {
     /// Applies the rasterizing state to use for future draw calls. It's generally a good idea to
     /// call this for each draw call, but if you really wanted to you could try reducing it to when
     ///  the state changes and when the render target state changes.
+    ///
+    /// The state is cached. Its fill mode is overridden while [`Device::set_wireframe`] is on.
     pub fn apply_rasterizer_state(&self, rst: &RasterizerState) {
+        *self.lifetime.rasterizer.borrow_mut() = Some(rst.clone());
+        self.apply_rasterizer_state_raw(rst);
+    }
+
+    fn apply_rasterizer_state_raw(&self, rst: &RasterizerState) {
+        // copy the state only if the fill mode is overridden
+        let overridden;
+        let rst = if self.lifetime.wireframe.get() {
+            overridden = self::effective_rasterizer(rst, true);
+            &overridden
+        } else {
+            rst
+        };
         unsafe {
             FNA3D_ApplyRasterizerState(self.raw(), rst.raw() as *const _ as *mut _);
         }
     }
 
+    /// Last rasterizer state given to [`Device::apply_rasterizer_state`] (without the wireframe
+    /// override)
+    pub fn rasterizer_state(&self) -> Option<RasterizerState> {
+        self.lifetime.rasterizer.borrow().clone()
+    }
+
+    /// Draws everything as wireframe (`FillMode::WireFrame`) for debugging geometry
+    ///
+    /// The cached rasterizer state is re-applied with the fill mode swapped, and following
+    /// [`Device::apply_rasterizer_state`] calls keep the override until it's turned off.
+    pub fn set_wireframe(&self, wireframe: bool) {
+        if self.lifetime.wireframe.replace(wireframe) == wireframe {
+            return;
+        }
+
+        if let Some(rst) = self.rasterizer_state() {
+            self.apply_rasterizer_state_raw(&rst);
+        }
+    }
+
+    pub fn is_wireframe(&self) -> bool {
+        self.lifetime.wireframe.get()
+    }
+
     /// Updates a sampler slot with new texture/sampler data for future draw calls.
     /// This should only be called on slots that have modified texture/sampler state.
     /// Redundant calls may negatively affect performance!
@@ -405,6 +996,7 @@ impl Device {
     /// * `index`:
     ///   The sampler slot to update.
     pub fn verify_sampler(&self, index: u32, texture: *mut Texture, sampler: &SamplerState) {
+        self.record_sampler(&self.lifetime.samplers, index, texture, sampler);
         unsafe {
             FNA3D_VerifySampler(
                 self.raw(),
@@ -422,6 +1014,7 @@ impl Device {
     /// * `index`:
     ///   The vertex sampler slot to update.
     pub fn verify_vertex_sampler(&self, index: u32, texture: *mut Texture, sampler: &SamplerState) {
+        self.record_sampler(&self.lifetime.vertex_samplers, index, texture, sampler);
         unsafe {
             FNA3D_VerifyVertexSampler(
                 self.raw(),
@@ -432,6 +1025,56 @@ impl Device {
         }
     }
 
+    /// Last texture and sampler state given to [`Device::verify_sampler`] (or set by an effect
+    /// pass)
+    pub fn sampler(&self, index: u32) -> Option<(*mut Texture, SamplerState)> {
+        self.sampler_slot(&self.lifetime.samplers, index)
+    }
+
+    /// Last texture and sampler state given to [`Device::verify_vertex_sampler`] (or set by an
+    /// effect pass)
+    pub fn vertex_sampler(&self, index: u32) -> Option<(*mut Texture, SamplerState)> {
+        self.sampler_slot(&self.lifetime.vertex_samplers, index)
+    }
+
+    fn sampler_slot(
+        &self,
+        slots: &RefCell<Vec<Option<SamplerSlot>>>,
+        index: u32,
+    ) -> Option<(*mut Texture, SamplerState)> {
+        let (texture, id) = slots.borrow().get(index as usize).copied().flatten()?;
+        let state = self.lifetime.sampler_table.borrow().get(id).clone();
+        Some((texture, state))
+    }
+
+    fn record_sampler(
+        &self,
+        slots: &RefCell<Vec<Option<SamplerSlot>>>,
+        index: u32,
+        texture: *mut Texture,
+        sampler: &SamplerState,
+    ) {
+        let id = self.intern_sampler(sampler);
+        let mut slots = slots.borrow_mut();
+        let index = index as usize;
+        if slots.len() <= index {
+            slots.resize(index + 1, None);
+        }
+        slots[index] = Some((texture, id));
+    }
+
+    fn intern_sampler(&self, state: &SamplerState) -> SamplerId {
+        let dev = &self.lifetime;
+        let mut table = dev.sampler_table.borrow_mut();
+        if table.find(state).is_none() && table.len() >= MAX_INTERNED_STATES {
+            let mut samplers = dev.samplers.borrow_mut();
+            let mut vertex_samplers = dev.vertex_samplers.borrow_mut();
+            let live = samplers.iter_mut().chain(vertex_samplers.iter_mut());
+            table.compact(live.flatten().map(|(_, id)| id));
+        }
+        table.intern(state)
+    }
+
     /// Updates the vertex attribute state to read from a set of vertex buffers. This
     /// should be the very last thing you call before making a draw call, as this
     /// does all the final prep work for the shader program before it's ready to use.
@@ -454,6 +1097,9 @@ impl Device {
         is_bindings_updated: bool,
         base_vertex: u32,
     ) {
+        if is_bindings_updated {
+            self.lifetime.bindings.borrow_mut().update(bindings);
+        }
         unsafe {
             FNA3D_ApplyVertexBufferBindings(
                 self.raw(),
@@ -464,6 +1110,23 @@ impl Device {
             );
         }
     }
+
+    /// [`Device::apply_vertex_buffer_bindings`] with `is_bindings_updated` chosen automatically
+    ///
+    /// The bindings are compared with the last ones (buffers, offsets and vertex declarations),
+    /// so sprite batchers drawing from the same buffer skip the vertex attribute setup. The vertex
+    /// elements are compared by pointer; use [`Device::apply_vertex_buffer_bindings`] with `true`
+    /// if you modify them in place.
+    ///
+    /// The last bindings are forgotten when a vertex buffer is disposed or the backbuffer is reset.
+    pub fn apply_vertex_buffer_bindings_auto(
+        &self,
+        bindings: &[VertexBufferBinding],
+        base_vertex: u32,
+    ) {
+        let is_updated = !self.lifetime.bindings.borrow().is_current(bindings);
+        self.apply_vertex_buffer_bindings(bindings, is_updated, base_vertex);
+    }
 }
 
 /// Render targets
@@ -492,6 +1155,16 @@ impl Device {
         depth_format: enums::DepthFormat,
         preserve_target_contents: bool,
     ) {
+        let size = match &render_targets {
+            Some(r) if n_render_targets > 0 => r.size(),
+            _ => self.get_backbuffer_size(),
+        };
+        let n_targets = if render_targets.is_some() {
+            n_render_targets
+        } else {
+            0
+        };
+
         unsafe {
             FNA3D_SetRenderTargets(
                 self.raw(),
@@ -505,6 +1178,13 @@ impl Device {
                 preserve_target_contents as u8,
             );
         }
+
+        let change = TargetChange {
+            n_targets,
+            size,
+            depth_format,
+        };
+        self.notify(|obs| obs.on_target_change(self, &change));
     }
 
     /// After unsetting a render target, call this to resolve multisample targets or
@@ -525,6 +1205,11 @@ impl Device {
         unsafe {
             FNA3D_ResetBackbuffer(self.raw(), params as *const _ as *mut _);
         }
+        self.lifetime.bindings.borrow_mut().clear();
+        self.lifetime.params.set(*params);
+        #[cfg(feature = "sdl2")]
+        self.record_display(params.deviceWindowHandle);
+        self.notify(|obs| obs.on_reset(self, params));
     }
 
     /// Read the backbuffer's contents directly into client memory. This function is  basically one
@@ -544,6 +1229,79 @@ impl Device {
         }
     }
 
+    /// Copies the backbuffer into the texture of the render target, so that effects such as
+    /// refraction can sample what's drawn behind them
+    ///
+    /// FNA3D can't bind the backbuffer as a texture, so it's a readback and an upload: a CPU/GPU
+    /// sync point just like [`Device::read_backbuffer`]. Prefer drawing the scene into a render
+    /// target in the first place if you do it every frame.
+    ///
+    /// The top-left region of the overlapping size is copied. The target has to have the same
+    /// format as the backbuffer, and it has to be unbound.
+    pub fn blit_backbuffer_to_target(&self, target: &mut res::RenderTarget2d) -> res::Result<()> {
+        let src = self.get_backbuffer_surface_format();
+        if src != target.format() {
+            return Err(res::ResourceError::FormatMismatch {
+                src,
+                dest: target.format(),
+            });
+        }
+
+        let (bw, bh) = self.get_backbuffer_size();
+        let (tw, th) = target.size();
+        let (w, h) = (bw.min(tw), bh.min(th));
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+
+        let mut pixels = vec![0u8; w as usize * h as usize * src.size()];
+        self.read_backbuffer(0, 0, w, h, &mut pixels);
+        self.set_texture_data_2d(target.texture(), 0, 0, w, h, 0, &pixels);
+        Ok(())
+    }
+
+    /// Reads the whole backbuffer as RGBA8 pixels, width and height. For screenshots
+    ///
+    /// Alpha is set to opaque, since the backbuffer alpha is whatever blending left there. Same
+    /// cost as [`Device::read_backbuffer`].
+    ///
+    /// Only the `Color` backbuffer format (the default) is supported. FNA3D backends don't agree
+    /// on the layout of the data read back from other formats, so they return
+    /// [`io::ErrorKind::Unsupported`].
+    pub fn capture_backbuffer(&self) -> io::Result<(Vec<u8>, u32, u32)> {
+        let format = self.get_backbuffer_surface_format();
+        if format != enums::SurfaceFormat::Color {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("capturing {:?} backbuffer is not supported", format),
+            ));
+        }
+
+        let (w, h) = self.get_backbuffer_size();
+        let mut pixels = vec![0u8; w as usize * h as usize * format.size()];
+        self.read_backbuffer(0, 0, w, h, &mut pixels);
+        for px in pixels.chunks_exact_mut(4) {
+            px[3] = 255;
+        }
+
+        Ok((pixels, w, h))
+    }
+
+    /// Encodes the whole backbuffer into the bytes of a PNG file. See
+    /// [`Device::capture_backbuffer`]
+    pub fn capture_backbuffer_to_vec(&self) -> io::Result<Vec<u8>> {
+        let (pixels, w, h) = self.capture_backbuffer()?;
+        let mut png = Vec::new();
+        crate::img::save_png(&mut png, &pixels, w, h)?;
+        Ok(png)
+    }
+
+    /// Saves the whole backbuffer as a PNG file. See [`Device::capture_backbuffer`]
+    pub fn capture_backbuffer_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let (pixels, w, h) = self.capture_backbuffer()?;
+        crate::img::save_png_to(path, &pixels, w, h)
+    }
+
     pub fn get_backbuffer_size(&self) -> (u32, u32) {
         let (mut w, mut h) = (0, 0);
         unsafe {
@@ -555,18 +1313,48 @@ impl Device {
     pub fn get_backbuffer_surface_format(&self) -> enums::SurfaceFormat {
         let prim = unsafe { FNA3D_GetBackbufferSurfaceFormat(self.raw()) };
         // FIXME: is it ok to unwrap??
-        enums::SurfaceFormat::from_u32(prim).unwrap()
+        enums::SurfaceFormat::from_raw(prim).unwrap()
     }
 
     pub fn get_backbuffer_depth_format(&self) -> enums::DepthFormat {
         let prim = unsafe { FNA3D_GetBackbufferDepthFormat(self.raw()) };
         // FIXME: is it ok to unwrap??
-        enums::DepthFormat::from_u32(prim).unwrap()
+        enums::DepthFormat::from_raw(prim).unwrap()
     }
 
     pub fn get_backbuffer_multi_sample_count(&self) -> u32 {
         unsafe { FNA3D_GetBackbufferMultiSampleCount(self.raw()) as u32 }
     }
+
+    /// Size, formats and multi sample count of the backbuffer at once
+    ///
+    /// Unknown formats are logged and replaced with `SurfaceFormat::Color` and
+    /// `DepthFormat::None` rather than panicking.
+    pub fn backbuffer_info(&self) -> BackbufferInfo {
+        let (w, h) = self.get_backbuffer_size();
+
+        let (surface, depth, msaa) = unsafe {
+            (
+                FNA3D_GetBackbufferSurfaceFormat(self.raw()),
+                FNA3D_GetBackbufferDepthFormat(self.raw()),
+                FNA3D_GetBackbufferMultiSampleCount(self.raw()),
+            )
+        };
+
+        BackbufferInfo {
+            w,
+            h,
+            surface_format: enums::SurfaceFormat::from_raw(surface).unwrap_or_else(|| {
+                log::warn!("unknown backbuffer surface format: {}", surface);
+                enums::SurfaceFormat::Color
+            }),
+            depth_format: enums::DepthFormat::from_raw(depth).unwrap_or_else(|| {
+                log::warn!("unknown backbuffer depth format: {}", depth);
+                enums::DepthFormat::None
+            }),
+            multi_sample_count: msaa as u32,
+        }
+    }
 }
 
 /// Textures
@@ -674,6 +1462,7 @@ impl Device {
     ///
     /// * `texture`: The FNA3D_Texture to be destroyed.
     pub fn add_dispose_texture(&self, texture: *mut Texture) {
+        self.notify(|obs| obs.on_dispose(self, DisposedResource::Texture(texture)));
         unsafe {
             FNA3D_AddDisposeTexture(self.raw(), texture);
         }
@@ -681,8 +1470,8 @@ impl Device {
 
     /// Uploads image data to a 2D texture object.
     ///
-    /// * `target_level`:
-    ///   The mipmap level being updated (>= 0).
+    /// * `level`:
+    ///   The mipmap level being updated.
     pub fn set_texture_data_2d(
         &self,
         texture: *mut Texture,
@@ -690,7 +1479,7 @@ impl Device {
         y: u32,
         w: u32,
         h: u32,
-        target_level: u32,
+        level: u32,
         data: &[u8],
     ) {
         unsafe {
@@ -701,7 +1490,7 @@ impl Device {
                 y as i32,
                 w as i32,
                 h as i32,
-                target_level as i32,
+                level as i32,
                 data.as_ptr() as *mut _,
                 data.len() as i32,
             );
@@ -710,8 +1499,8 @@ impl Device {
 
     /// Uploads image data to a 3D texture object.
     ///
-    /// * `target_level`:
-    ///   The mipmap level being updated (>= 0).
+    /// * `level`:
+    ///   The mipmap level being updated.
     pub fn set_texture_data_3d(
         &self,
         texture: &mut Texture,
@@ -721,7 +1510,7 @@ impl Device {
         w: u32,
         h: u32,
         depth: u32,
-        target_level: u32,
+        level: u32,
         data: &mut [u8],
     ) {
         unsafe {
@@ -734,7 +1523,7 @@ impl Device {
                 w as i32,
                 h as i32,
                 depth as i32,
-                target_level as i32,
+                level as i32,
                 data.as_mut_ptr() as *mut _,
                 data.len() as i32,
             );
@@ -749,6 +1538,22 @@ impl Device {
     ///   The face of the cube being updated.
     /// * `level`:
     ///   The mipmap level being updated.
+    ///
+    /// Regions and mip levels are unsigned, like the other texture uploads and readbacks:
+    ///
+    /// ```no_run
+    /// # fn f(device: &fna3d::Device, texture: &mut fna3d::Texture, data: &mut [u8]) {
+    /// let face = fna3d::CubeMapFace::PositiveX;
+    /// device.set_texture_data_cube(texture, 0, 0, 4, 4, face, 1, data);
+    /// # }
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # fn f(device: &fna3d::Device, texture: &mut fna3d::Texture, data: &mut [u8]) {
+    /// let face = fna3d::CubeMapFace::PositiveX;
+    /// device.set_texture_data_cube(texture, 0, 0, 4, 4, face, -1, data);
+    /// # }
+    /// ```
     pub fn set_texture_data_cube(
         &self,
         texture: &mut Texture,
@@ -757,7 +1562,7 @@ impl Device {
         w: u32,
         h: u32,
         cube_map_face: enums::CubeMapFace,
-        level: i32,
+        level: u32,
         data: &mut [u8],
     ) {
         unsafe {
@@ -769,7 +1574,7 @@ impl Device {
                 w as i32,
                 h as i32,
                 cube_map_face as u32,
-                level,
+                level as i32,
                 data.as_mut_ptr() as *mut _,
                 data.len() as i32,
             );
@@ -779,7 +1584,11 @@ impl Device {
     /// Uploads YUV image data to three ALPHA8 texture objects.
     ///
     /// * `data`:
-    ///   A slice of the raw YUV image data.
+    ///   A slice of the raw YUV image data: the Y plane followed by the U and V planes.
+    ///
+    /// The planes have to be 4:2:0 subsampled (`uv_width = ceil(y_width / 2)`, same for the
+    /// height) and `data` has to be exactly `y_w * y_h + 2 * uv_w * uv_h` bytes. Otherwise an
+    /// error is returned and nothing is uploaded.
     pub fn set_texture_data_yuv(
         &self,
         y: &mut Texture,
@@ -790,7 +1599,9 @@ impl Device {
         uv_width: u32,
         uv_height: u32,
         data: &[u8],
-    ) {
+    ) -> res::Result<()> {
+        res::validate_yuv_planes([y_width, y_height], [uv_width, uv_height], data.len())?;
+
         unsafe {
             FNA3D_SetTextureDataYUV(
                 self.raw(),
@@ -805,6 +1616,8 @@ impl Device {
                 data.len() as i32,
             );
         }
+
+        Ok(())
     }
 
     /// Pulls image data from a 2D texture into client memory. Like any GetData,
@@ -971,6 +1784,8 @@ impl Device {
     ///
     /// * `renderbuffer`: The FNA3D_Renderbuffer to be destroyed.
     pub fn add_dispose_renderbuffer(&self, renderbuffer: &mut Renderbuffer) {
+        let raw = renderbuffer as *mut Renderbuffer;
+        self.notify(|obs| obs.on_dispose(self, DisposedResource::Renderbuffer(raw)));
         unsafe {
             FNA3D_AddDisposeRenderbuffer(self.raw(), renderbuffer);
         }
@@ -1013,6 +1828,8 @@ impl Device {
     ///
     /// * `buffer`: The FNA3D_Buffer to be destroyed.
     pub fn add_dispose_vertex_buffer(&self, buffer: *mut Buffer) {
+        self.notify(|obs| obs.on_dispose(self, DisposedResource::VertexBuffer(buffer)));
+        self.lifetime.bindings.borrow_mut().clear();
         unsafe {
             FNA3D_AddDisposeVertexBuffer(self.raw(), buffer);
         }
@@ -1045,27 +1862,29 @@ impl Device {
         }
     }
 
-    /// Pulls data from a region of the vertex buffer into a client pointer.
-    pub fn get_vertex_buffer_data(
+    /// Pulls data from a region of the vertex buffer into client memory.
+    ///
+    /// * `data`:
+    ///   Filled with `data.len()` elements, read tightly packed (the stride is the size of `T`).
+    ///
+    /// The buffer has to be created with `BufferUsage::None`.
+    pub fn get_vertex_buffer_data<T>(
         &self,
         buffer: *mut Buffer,
         buf_offset_in_bytes: u32,
-        data: *mut ::std::os::raw::c_void,
-        // element_count: i32,
-        elem_size_in_bytes: u32,
-        // vertex_stride: i32,
+        data: &mut [T],
     ) {
+        let elem_size = std::mem::size_of::<T>() as i32;
         unsafe {
             FNA3D_GetVertexBufferData(
                 self.raw(),
                 buffer,
                 buf_offset_in_bytes as i32,
-                data,
-                // element_count,
-                1,
-                elem_size_in_bytes as i32,
-                // vertex_stride,
-                1,
+                data.as_mut_ptr() as *mut _,
+                data.len() as i32,
+                elem_size,
+                // vertex stride
+                elem_size,
             );
         }
     }
@@ -1111,6 +1930,7 @@ impl Device {
     ///
     /// * `buffer`: The FNA3D_Buffer to be destroyed.
     pub fn add_dispose_index_buffer(&self, buf: *mut Buffer) {
+        self.notify(|obs| obs.on_dispose(self, DisposedResource::IndexBuffer(buf)));
         unsafe {
             FNA3D_AddDisposeIndexBuffer(self.raw(), buf);
         }
@@ -1191,6 +2011,7 @@ impl Device {
     ///   The D3D9 Effect binary blob.
     /// * `effect_code_length`:
     ///   The size (in bytes) of the blob.
+    #[cfg(feature = "mojoshader")]
     pub fn create_effect(
         &self,
         effect_code: *mut u8,
@@ -1223,6 +2044,7 @@ impl Device {
     ///   Filled with the new compiled FNA3D_Effect*.
     /// * `effect_data`:
     ///   Filled with the copied Effect Framework data.
+    #[cfg(feature = "mojoshader")]
     pub fn clone_effect(&self, clone_source: *mut Effect) -> (*mut Effect, *mut mojo::Effect) {
         let mut effect = std::ptr::null_mut();
         let mut data = std::ptr::null_mut();
@@ -1239,6 +2061,7 @@ impl Device {
     ///
     /// * `effect`: The FNA3D_Effect to be destroyed.
     pub fn add_dispose_effect(&self, effect: *mut Effect) {
+        self.notify(|obs| obs.on_dispose(self, DisposedResource::Effect(effect)));
         unsafe {
             FNA3D_AddDisposeEffect(self.raw(), effect);
         }
@@ -1248,6 +2071,10 @@ impl Device {
     ///
     /// * `effect`:	The Effect to be modified.
     /// * `technique`:	The technique to be used by future `apply_effect` calls.
+    ///
+    /// The technique is not checked; a pointer of another effect is undefined behavior. Prefer
+    /// [`crate::res::EffectHandle::set_technique`].
+    #[cfg(feature = "mojoshader")]
     pub fn set_effect_technique(&self, effect: *mut Effect, technique: *mut mojo::EffectTechnique) {
         unsafe {
             FNA3D_SetEffectTechnique(self.raw(), effect, technique as *mut _);
@@ -1265,19 +2092,97 @@ impl Device {
     ///   Structure to be filled with any render state changes
     ///	  made by the Effect. This must be valid for the entire
     ///   duration that this Effect is being applied.
+    ///
+    /// Sampler states declared in the pass (`sampler_state { .. }` blocks) are applied on top of
+    /// the states given to [`Device::verify_sampler`]. See [`mojo::effect_sampler_state`].
+    #[cfg(feature = "mojoshader")]
     pub fn apply_effect(
         &self,
         effect: *mut Effect,
         pass: u32,
         state_changes: &mojo::EffectStateChanges,
+    ) {
+        self.apply_effect_raw(effect, pass, state_changes as *const _ as *mut _);
+    }
+
+    /// [`Device::apply_effect`] with the first pass of the current technique, ignoring render
+    /// state changes made by the effect
+    ///
+    /// The state changes structure is owned by the device, so there's no need to keep one alive
+    /// while the effect is applied.
+    #[cfg(feature = "mojoshader")]
+    pub fn apply_effect_simple(&self, effect: &res::EffectHandle) {
+        self.apply_effect_no_change(effect.raw(), 0);
+    }
+
+    /// [`Device::apply_effect`] with the state changes structure of
+    /// [`Device::apply_effect_simple`], for internal draws
+    #[cfg(feature = "mojoshader")]
+    pub(crate) fn apply_effect_no_change(&self, effect: *mut Effect, pass: u32) {
+        self.apply_effect_raw(effect, pass, self.lifetime.no_change_effect.get());
+    }
+
+    #[cfg(feature = "mojoshader")]
+    fn apply_effect_raw(
+        &self,
+        effect: *mut Effect,
+        pass: u32,
+        state_changes: *mut mojo::EffectStateChanges,
     ) {
         unsafe {
-            FNA3D_ApplyEffect(
-                self.raw(),
-                effect,
-                pass,
-                state_changes as *const _ as *mut _,
-            );
+            FNA3D_ApplyEffect(self.raw(), effect, pass, state_changes as *mut _);
+            // FNA3D has written the changes; no reference to the struct outlives this call
+            self.apply_effect_sampler_states(&*state_changes);
+        }
+    }
+
+    /// Applies `sampler_state { .. }` blocks of the current pass on top of the sampler states of
+    /// the slots
+    ///
+    /// Slots without textures only remember the state; it's used when a texture is given with
+    /// [`Device::verify_sampler`] and the same sampler state.
+    #[cfg(feature = "mojoshader")]
+    fn apply_effect_sampler_states(&self, state_changes: &mojo::EffectStateChanges) {
+        let regs = |ptr: *const mojo::SamplerStateRegister, len: u32| {
+            if ptr.is_null() {
+                &[][..]
+            } else {
+                unsafe { std::slice::from_raw_parts(ptr, len as usize) }
+            }
+        };
+
+        let fragment = regs(
+            state_changes.sampler_state_changes,
+            state_changes.sampler_state_change_count,
+        );
+        for reg in fragment {
+            let index = reg.sampler_register;
+            let (texture, base) = self
+                .sampler(index)
+                .unwrap_or((std::ptr::null_mut(), SamplerState::default()));
+            let state = unsafe { mojo::effect_sampler_state(&base, reg) };
+            if texture.is_null() {
+                self.record_sampler(&self.lifetime.samplers, index, texture, &state);
+            } else {
+                self.verify_sampler(index, texture, &state);
+            }
+        }
+
+        let vertex = regs(
+            state_changes.vertex_sampler_state_changes,
+            state_changes.vertex_sampler_state_change_count,
+        );
+        for reg in vertex {
+            let index = reg.sampler_register;
+            let (texture, base) = self
+                .vertex_sampler(index)
+                .unwrap_or((std::ptr::null_mut(), SamplerState::default()));
+            let state = unsafe { mojo::effect_sampler_state(&base, reg) };
+            if texture.is_null() {
+                self.record_sampler(&self.lifetime.vertex_samplers, index, texture, &state);
+            } else {
+                self.verify_vertex_sampler(index, texture, &state);
+            }
         }
     }
 
@@ -1290,6 +2195,9 @@ impl Device {
     /// * `state_changes`:	Structure to be filled with any render state changes
     ///			made by the Effect. This must be valid for the entire
     /// 			duration that this Effect is being applied.
+    ///
+    /// See also [`crate::res::EffectHandle::pass_restore_scope`], which ends the pass on drop.
+    #[cfg(feature = "mojoshader")]
     pub fn begin_pass_restore(
         &self,
         effect: *mut Effect,
@@ -1328,6 +2236,7 @@ impl Device {
     ///
     /// * `query`: The FNA3D_Query to be destroyed.
     pub fn add_dispose_query(&self, query: *mut Query) {
+        self.notify(|obs| obs.on_dispose(self, DisposedResource::Query(query)));
         unsafe {
             FNA3D_AddDisposeQuery(self.raw(), query);
         }
@@ -1370,6 +2279,220 @@ impl Device {
     pub fn query_pixel_count(&self, query: *mut Query) -> i32 {
         unsafe { FNA3D_QueryPixelCount(self.raw(), query) }
     }
+
+    /// Calls `draw` unless the `query` is known to have counted no pixels. Returns if `draw` was
+    /// called
+    ///
+    /// Conditional rendering: wrap a cheap proxy (e.g. the bounding box with color and depth
+    /// writes off) with [`Device::query_begin`] and [`Device::query_end`], and draw the real
+    /// object in later frames only if the proxy was visible. While the result is not available,
+    /// `draw` is called, so objects never disappear because of GPU latency.
+    ///
+    /// `query` has to be null (drawn unconditionally) or ended at least once.
+    ///
+    /// ```no_run
+    /// # fn f(device: &fna3d::Device, query: *mut fna3d::Query) {
+    /// device.draw_if_visible(query, || {
+    ///     // draw calls of the object
+    /// });
+    ///
+    /// device.query_begin(query);
+    /// // draw the bounding box of the object
+    /// device.query_end(query);
+    /// # }
+    /// ```
+    pub fn draw_if_visible(&self, query: *mut Query, draw: impl FnOnce()) -> bool {
+        let visible =
+            query.is_null() || !self.query_complete(query) || self.query_pixel_count(query) > 0;
+        if visible {
+            draw();
+        }
+        visible
+    }
+}
+
+/// Backbuffer invalidation
+/// ---
+///
+/// FNA3D doesn't notify when the backbuffer stops matching the window: e.g. the window is moved
+/// to a monitor with another DPI scale, or the display mode changes. Poll it once per frame (or on
+/// window events) with [`Device::handle_backbuffer_invalidation`].
+impl Device {
+    /// Compares the drawable size of the window with the backbuffer size. Doesn't reset anything
+    ///
+    /// With the `sdl2` feature, the display of the window and its display mode are also compared
+    /// with the ones at the last [`Device::reset_backbuffer`] (or the first call).
+    pub fn check_backbuffer(&self) -> Option<BackbufferEvent> {
+        let params = self.params();
+        if params.deviceWindowHandle.is_null() {
+            return None;
+        }
+
+        let old = self.get_backbuffer_size();
+        let new = crate::get_drawable_size(params.deviceWindowHandle);
+        if new.0 == 0 || new.1 == 0 {
+            // a minimized window has zero size; keep the backbuffer until it's restored
+            return None;
+        }
+
+        if old != new {
+            return Some(BackbufferEvent::SizeChanged { old, new });
+        }
+
+        #[cfg(feature = "sdl2")]
+        if let Some(event) = self.check_display(params.deviceWindowHandle, new) {
+            return Some(event);
+        }
+
+        None
+    }
+
+    #[cfg(feature = "sdl2")]
+    fn check_display(&self, window: *mut c_void, size: (u32, u32)) -> Option<BackbufferEvent> {
+        // SAFETY: the handle is given to FNA3D as `SDL_Window*` anyway
+        let (index, mode) = unsafe { crate::fna3d::fna3d_functions::window_display(window)? };
+        let (old_index, old_mode) = match self.lifetime.display.get() {
+            Some(display) => display,
+            None => {
+                // nothing to compare with yet
+                self.lifetime.display.set(Some((index, mode)));
+                return None;
+            }
+        };
+
+        if index != old_index {
+            Some(BackbufferEvent::DisplayChanged {
+                old: old_index,
+                new: index,
+                size,
+            })
+        } else if mode != old_mode {
+            Some(BackbufferEvent::DisplayModeChanged {
+                old: old_mode,
+                new: mode,
+                size,
+            })
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "sdl2")]
+    fn record_display(&self, window: *mut c_void) {
+        if window.is_null() {
+            return;
+        }
+        // SAFETY: the handle is given to FNA3D as `SDL_Window*` anyway
+        let display = unsafe { crate::fna3d::fna3d_functions::window_display(window) };
+        self.lifetime.display.set(display);
+    }
+
+    /// Resets the backbuffer if it's invalidated and runs the callbacks
+    ///
+    /// Returns the event if the backbuffer was reset.
+    pub fn handle_backbuffer_invalidation(&self) -> Option<BackbufferEvent> {
+        let event = self.check_backbuffer()?;
+        let (w, h) = event.size();
+
+        let mut params = self.params();
+        params.backBufferWidth = w as i32;
+        params.backBufferHeight = h as i32;
+        self.reset_backbuffer(&params);
+        self.resize_render_targets((w, h));
+
+        // take the callbacks so that they can call `Device` methods (even adding callbacks)
+        let mut callbacks = self.lifetime.reset_callbacks.replace(Vec::new());
+        for f in callbacks.iter_mut() {
+            f(self, &params);
+        }
+        let mut added = self.lifetime.reset_callbacks.replace(Vec::new());
+        callbacks.append(&mut added);
+        self.lifetime.reset_callbacks.replace(callbacks);
+
+        Some(event)
+    }
+
+    /// Registers a callback run after [`Device::handle_backbuffer_invalidation`] resets the
+    /// backbuffer
+    ///
+    /// Recreate window-sized render targets (and ones with `PlatformContents` usage, whose
+    /// contents are undefined after a reset) and update viewports and projections here.
+    pub fn add_backbuffer_reset_callback(
+        &self,
+        f: impl FnMut(&Device, &PresentationParameters) + 'static,
+    ) {
+        self.lifetime.reset_callbacks.borrow_mut().push(Box::new(f));
+    }
+
+    /// Keeps a weak reference to the target so that it's resized with the backbuffer. Prefer
+    /// [`res::RenderTarget2d::register`]
+    pub fn register_render_target(&self, target: &res::SharedRenderTarget) {
+        self.lifetime
+            .render_targets
+            .borrow_mut()
+            .push(Rc::downgrade(target));
+    }
+
+    /// Resizes the registered render targets following their [`res::ResizePolicy`]
+    ///
+    /// Called by [`Device::handle_backbuffer_invalidation`]. Call it yourself after resetting the
+    /// backbuffer manually. Dropped targets are unregistered here.
+    pub fn resize_render_targets(&self, backbuffer: (u32, u32)) {
+        // upgrade first so that the targets can use the device while resizing
+        let targets = {
+            let mut weaks = self.lifetime.render_targets.borrow_mut();
+            weaks.retain(|t| t.strong_count() > 0);
+            weaks.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+        };
+
+        for target in targets {
+            let mut target = match target.try_borrow_mut() {
+                Ok(t) => t,
+                Err(_) => {
+                    log::warn!("skipping a borrowed render target on backbuffer resize");
+                    continue;
+                }
+            };
+            if let Err(err) = target.fit_backbuffer(backbuffer) {
+                log::warn!("failed to resize a render target: {}", err);
+            }
+        }
+    }
+}
+
+/// Event hooks
+/// ---
+///
+/// See [`crate::events`].
+impl Device {
+    /// Keeps a weak reference to the observer. It's unregistered when it's dropped
+    pub fn add_observer<T: DeviceObserver + 'static>(&self, observer: &Rc<RefCell<T>>) {
+        let observer: Rc<RefCell<dyn DeviceObserver>> = observer.clone();
+        self.lifetime
+            .observers
+            .borrow_mut()
+            .push(Rc::downgrade(&observer));
+    }
+
+    /// Calls `f` on each alive observer. Dropped observers are unregistered here
+    fn notify(&self, mut f: impl FnMut(&mut dyn DeviceObserver)) {
+        // upgrade first so that the observers can use the device (even adding observers)
+        let observers = {
+            let mut weaks = self.lifetime.observers.borrow_mut();
+            if weaks.is_empty() {
+                return;
+            }
+            weaks.retain(|o| o.strong_count() > 0);
+            weaks.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+        };
+
+        for observer in observers {
+            // skip re-entrant calls
+            if let Ok(mut observer) = observer.try_borrow_mut() {
+                f(&mut *observer);
+            }
+        }
+    }
 }
 
 /// Feature queries
@@ -1425,19 +2548,258 @@ impl Device {
     ) -> i32 {
         unsafe { FNA3D_GetMaxMultiSampleCount(self.raw(), fmt as u32, multi_sample_count as i32) }
     }
+
+    /// Returns the clip space convention seen by effects and projection matrices
+    ///
+    /// It's always [`mojo::ClipSpaceInfo::XNA`]; FNA3D and MojoShader hide the differences of the
+    /// backends. See [`mojo::ClipSpaceInfo`] for converting matrices of other conventions.
+    #[cfg(feature = "mojoshader")]
+    pub fn clip_space_info(&self) -> mojo::ClipSpaceInfo {
+        mojo::ClipSpaceInfo::XNA
+    }
 }
 
 /// Debug
 /// ---
 impl Device {
+    /// Last states given to the setters, for restoring them after internal draws
+    #[cfg(feature = "mojoshader")]
+    pub(crate) fn snapshot_states(&self) -> StateSnapshot {
+        let dev = &self.lifetime;
+        StateSnapshot {
+            blend: self.blend_state(),
+            depth_stencil: dev.depth_stencil.borrow().clone(),
+            rasterizer: dev.rasterizer.borrow().clone(),
+            viewport: dev.viewport.get(),
+            scissor: dev.scissor.get(),
+            sampler0: self.sampler(0),
+        }
+    }
+
+    /// Sets the states of the snapshot again. States that were not set are left as they are
+    #[cfg(feature = "mojoshader")]
+    pub(crate) fn restore_states(&self, snapshot: &StateSnapshot) {
+        if let Some(blend) = &snapshot.blend {
+            self.set_blend_state(blend);
+        }
+        if let Some(dss) = &snapshot.depth_stencil {
+            self.set_depth_stencil_state(dss);
+        }
+        if let Some(rst) = &snapshot.rasterizer {
+            self.apply_rasterizer_state(rst);
+        }
+        if let Some(viewport) = &snapshot.viewport {
+            self.set_viewport(viewport);
+        }
+        if let Some(scissor) = &snapshot.scissor {
+            self.set_scissor_rect(scissor);
+        }
+        if let Some((texture, sampler)) = &snapshot.sampler0 {
+            self.verify_sampler(0, *texture, sampler);
+        }
+    }
+
     /// Sets an arbitrary string constant to be stored in a rendering API trace,
     /// useful for labeling call streams for debugging purposes.
     ///
-    /// * `text`: The string constant to mark in the API call stream.
-    // FIXME: C string wrapper?? I have to read Rust nomicon
-    pub fn set_string_marker(&self, text: *const ::std::os::raw::c_char) {
+    /// * `text`: The string to mark in the API call stream. It's cut at the first nul character.
+    pub fn set_string_marker(&self, text: &str) {
+        let text = self::marker_cstring(text);
         unsafe {
-            FNA3D_SetStringMarker(self.raw(), text);
+            FNA3D_SetStringMarker(self.raw(), text.as_ptr());
         }
     }
+
+    /// Marks the draw calls until the guard is dropped as a named group
+    ///
+    /// FNA3D only has string markers, so a group shows up in API traces (RenderDoc, apitrace) as
+    /// `begin <path>` and `end <path>` markers. `<path>` joins the names of the nested groups
+    /// with `/`:
+    ///
+    /// ```no_run
+    /// # fn f(device: &fna3d::Device) {
+    /// let _scene = device.debug_group("scene");
+    /// {
+    ///     let _shadows = device.debug_group("shadows");
+    ///     // draw calls between `begin scene/shadows` and `end scene/shadows`
+    /// }
+    /// # }
+    /// ```
+    pub fn debug_group(&self, name: &str) -> DebugGroupGuard<'_> {
+        let path = {
+            let mut groups = self.lifetime.debug_groups.borrow_mut();
+            groups.push(name.to_string());
+            groups.join("/")
+        };
+        self.set_string_marker(&format!("begin {}", path));
+        DebugGroupGuard { device: self, path }
+    }
+
+    /// Readable report of the last states given to this device: blend, depth/stencil,
+    /// rasterizer, viewport, scissor and samplers
+    ///
+    /// Useful when nothing is drawn. FNA3D doesn't let us read back the states, so states that
+    /// are not set through this [`Device`] are shown as `(not set)`.
+    pub fn dump_state(&self) -> String {
+        use std::fmt::Write;
+
+        fn line(out: &mut String, name: &str, value: Option<impl fmt::Debug>) {
+            match value {
+                Some(v) => writeln!(out, "{}: {:?}", name, v).unwrap(),
+                None => writeln!(out, "{}: (not set)", name).unwrap(),
+            }
+        }
+
+        let dev = &self.lifetime;
+        let mut out = String::new();
+
+        line(&mut out, "blend", self.blend_state());
+        line(
+            &mut out,
+            "depth_stencil",
+            dev.depth_stencil.borrow().as_ref(),
+        );
+        line(&mut out, "rasterizer", dev.rasterizer.borrow().as_ref());
+        writeln!(out, "wireframe: {}", dev.wireframe.get()).unwrap();
+        line(&mut out, "viewport", dev.viewport.get());
+        line(&mut out, "scissor", dev.scissor.get());
+
+        for (name, slots) in [
+            ("sampler", &dev.samplers),
+            ("vertex_sampler", &dev.vertex_samplers),
+        ]
+        .iter()
+        {
+            let table = dev.sampler_table.borrow();
+            for (i, slot) in slots.borrow().iter().enumerate() {
+                if let Some((texture, id)) = slot {
+                    let state = table.get(*id);
+                    writeln!(out, "{}[{}]: {:?} {:?}", name, i, texture, state).unwrap();
+                }
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_marker_cstring() {
+        assert_eq!(marker_cstring("shadows").as_bytes(), b"shadows");
+        assert_eq!(marker_cstring("a\0b").as_bytes(), b"a");
+    }
+
+    #[test]
+    fn test_effective_rasterizer() {
+        let rst = RasterizerState::default();
+        assert_eq!(
+            effective_rasterizer(&rst, true).fill_mode(),
+            enums::FillMode::WireFrame
+        );
+        assert_eq!(
+            effective_rasterizer(&rst, false).fill_mode(),
+            enums::FillMode::Solid
+        );
+    }
+
+    #[test]
+    fn test_same_bindings() {
+        let mut elems = [VertexElement::default(); 2];
+        let decl = VertexDeclaration {
+            vertexStride: 20,
+            elementCount: 2,
+            elements: elems.as_mut_ptr(),
+        };
+        let a = VertexBufferBinding {
+            vertexBuffer: 0x10 as *mut Buffer,
+            vertexDeclaration: decl,
+            vertexOffset: 0,
+            instanceFrequency: 0,
+        };
+        let mut b = a;
+        assert!(same_bindings(&[a], &[b]));
+        assert!(!same_bindings(&[a], &[a, b]));
+        b.vertexOffset = 4;
+        assert!(!same_bindings(&[a], &[b]));
+    }
+
+    #[test]
+    fn test_binding_cache() {
+        let binding = VertexBufferBinding {
+            vertexBuffer: 0x10 as *mut Buffer,
+            vertexDeclaration: VertexDeclaration {
+                vertexStride: 0,
+                elementCount: 0,
+                elements: std::ptr::null_mut(),
+            },
+            vertexOffset: 0,
+            instanceFrequency: 0,
+        };
+
+        let mut cache = BindingCache::default();
+        cache.update(&[binding]);
+        assert!(cache.is_current(&[binding]));
+
+        // dispose, then a new buffer at the same address
+        cache.clear();
+        assert!(!cache.is_current(&[binding]));
+        cache.update(&[binding]);
+        assert!(cache.is_current(&[binding]));
+    }
+
+    #[test]
+    fn test_create_device_error_display() {
+        let err = CreateDeviceError {
+            requested_driver: Some("Vulkan".to_string()),
+            window_flags: None,
+            drawable_size: None,
+            back_buffer_size: (1280, 720),
+            do_debug: false,
+            linked_version: 2200,
+            recent_logs: vec![LogMessage {
+                level: log::Level::Error,
+                text: "Vulkan: no suitable device".to_string(),
+            }],
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("requested driver: Vulkan"));
+        assert!(msg.contains("window: null"));
+        assert!(msg.ends_with("[ERROR] Vulkan: no suitable device"));
+    }
+
+    #[test]
+    #[ignore = "requires a display"]
+    fn test_vertex_buffer_round_trip() {
+        let sdl = sdl2::init().unwrap();
+        let vid = sdl.video().unwrap();
+        let flags = crate::prepare_window_attributes() | crate::SdlWindowFlags::HIDDEN;
+        let win = vid
+            .window("round trip", 64, 64)
+            .set_window_flags(flags.bits())
+            .build()
+            .unwrap();
+
+        let mut params = crate::utils::default_params_from_window_handle(win.raw() as *mut _);
+        let device = Device::from_params(&mut params, false);
+
+        let src: Vec<[f32; 3]> = (0..16).map(|i| [i as f32, 0.5, -(i as f32)]).collect();
+        let size = (src.len() * std::mem::size_of::<[f32; 3]>()) as u32;
+        let buf = device.gen_vertex_buffer(false, enums::BufferUsage::None, size);
+        device.set_vertex_buffer_data(buf, 0, &src, enums::SetDataOptions::None);
+
+        let mut dst = vec![[0.0f32; 3]; src.len()];
+        device.get_vertex_buffer_data(buf, 0, &mut dst);
+        assert_eq!(src, dst);
+
+        // partial read with an offset
+        let mut tail = vec![[0.0f32; 3]; 4];
+        device.get_vertex_buffer_data(buf, 12 * 12, &mut tail);
+        assert_eq!(&src[12..], &tail[..]);
+
+        device.add_dispose_vertex_buffer(buf);
+    }
 }
@@ -152,6 +152,21 @@ impl SurfaceFormat {
             SurfaceFormat::HdrBlendable => panic!("SurfaceFormat::HdrBlendable is only used for RenderTarget and should not get size (?)"),
         }
     }
+
+    /// If the format can be used for render targets and the backbuffer
+    ///
+    /// Block-compressed formats, signed normalized formats and `Alpha8` can only be sampled.
+    pub fn is_renderable(&self) -> bool {
+        !matches!(
+            self,
+            SurfaceFormat::Dxt1
+                | SurfaceFormat::Dxt3
+                | SurfaceFormat::Dxt5
+                | SurfaceFormat::NormalizedByte2
+                | SurfaceFormat::NormalizedByte4
+                | SurfaceFormat::Alpha8
+        )
+    }
 }
 
 /// [`PresentationParameters`] component, [`Renderbuffer`] attribute
@@ -187,6 +202,8 @@ pub enum BufferUsage {
 }
 
 /// How vertex or index buffer data will be flushed during a SetData operation.
+///
+/// [`crate::res::VertexBuffer`] picks `Discard` or `NoOverwrite` for you.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
 #[repr(u32)]
 pub enum SetDataOptions {
@@ -202,6 +219,7 @@ pub enum SetDataOptions {
 
 /// [`BlendState`] component, which specifies blend mode
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum Blend {
     /// Each component of the color is multiplied by {1, 1, 1, 1}.
@@ -243,6 +261,7 @@ pub enum Blend {
 
 /// [`BlendState`] component, which specifies color blending function (expression)
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum BlendFunction {
     /// `(src_color * src_blend) + (dest_color * dest_blend)`
@@ -258,6 +277,7 @@ pub enum BlendFunction {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 /// [`BlendState`] component, which specifies color channels for render target blending operations
 pub enum ColorWriteChannels {
@@ -271,6 +291,7 @@ pub enum ColorWriteChannels {
 
 /// [`DepthStencilState`] component
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum StencilOperation {
     Keep = sys::FNA3D_StencilOperation_FNA3D_STENCILOPERATION_KEEP,
@@ -285,6 +306,7 @@ pub enum StencilOperation {
 
 /// [`DepthStencilState`] component, which specifies comparison operator for depth testing
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum CompareFunction {
     Always = sys::FNA3D_CompareFunction_FNA3D_COMPAREFUNCTION_ALWAYS,
@@ -299,6 +321,7 @@ pub enum CompareFunction {
 
 /// [`RasterizerState `] component
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum CullMode {
     None = sys::FNA3D_CullMode_FNA3D_CULLMODE_NONE,
@@ -308,6 +331,7 @@ pub enum CullMode {
 
 /// [`RasterizerState`] component
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum FillMode {
     Solid = sys::FNA3D_FillMode_FNA3D_FILLMODE_SOLID,
@@ -318,6 +342,7 @@ pub enum FillMode {
 ///
 /// Applied for texture coordinates that are outside of range [0.0, 1.0]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum TextureAddressMode {
     /// Texels outside range will form the tile at every integer junction.
@@ -331,6 +356,7 @@ pub enum TextureAddressMode {
 
 /// [`SamplerState`] component, which specifies filtering types
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Primitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum TextureFilter {
     Linear = sys::FNA3D_TextureFilter_FNA3D_TEXTUREFILTER_LINEAR,
@@ -403,3 +429,302 @@ pub enum VertexElementUsage {
     Sample = sys::FNA3D_VertexElementUsage_FNA3D_VERTEXELEMENTUSAGE_SAMPLE,
     TesselateFactor = sys::FNA3D_VertexElementUsage_FNA3D_VERTEXELEMENTUSAGE_TESSELATEFACTOR,
 }
+
+/// `from_raw` of enums read back from raw structs in accessors
+///
+/// Discriminants of FNA3D enums are mostly `0..n`, so the value is looked up by index first
+/// instead of being compared with every variant.
+macro_rules! impl_from_raw {
+    ($($ty:ident: [$($var:ident),* $(,)?],)*) => {
+        $(
+            impl $ty {
+                /// Raw value -> variant. Returns `None` for unknown values
+                pub fn from_raw(raw: u32) -> Option<Self> {
+                    const ALL: &[$ty] = &[$($ty::$var),*];
+                    match ALL.get(raw as usize) {
+                        Some(&e) if e as u32 == raw => Some(e),
+                        _ => ALL.iter().copied().find(|&e| e as u32 == raw),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_raw! {
+    SurfaceFormat: [
+        Color,
+        Bgr565,
+        Bgra5551,
+        Bgra4444,
+        Dxt1,
+        Dxt3,
+        Dxt5,
+        NormalizedByte2,
+        NormalizedByte4,
+        Rgba1010102,
+        Rg32,
+        Rgba64,
+        Alpha8,
+        Single,
+        Vector2,
+        Vector4,
+        HalfSingle,
+        HalfVector2,
+        HalfVector4,
+        HdrBlendable,
+        ColorBgraExt,
+    ],
+    DepthFormat: [None, D16, D24, D24S8],
+    Blend: [
+        One,
+        Zero,
+        SourceColor,
+        InverseSourceColor,
+        SourceAlpha,
+        InverseSourceAlpha,
+        DestinationColor,
+        InveseDestinationColor,
+        DestinaitonAlpha,
+        InverseDetinationAlpha,
+        BlendFactor,
+        InverseBlendFactor,
+        SourceAlphaSaturation,
+    ],
+    BlendFunction: [Add, Substract, ReverseSubstract, Max, Min],
+    ColorWriteChannels: [None, Red, Green, Blue, Alpha, All],
+    StencilOperation: [
+        Keep,
+        Zero,
+        Replace,
+        Increment,
+        Decrement,
+        IncrementSaturation,
+        DecrementSaturation,
+        Invert,
+    ],
+    CompareFunction: [Always, Never, Less, LessEqual, Equal, GreaterEqual, Greater, NonEqual],
+    CullMode: [None, CullClockWiseFace, CullCounterClockwiseFace],
+    FillMode: [Solid, WireFrame],
+    TextureAddressMode: [Wrap, Clamp, Mirror],
+    TextureFilter: [
+        Linear,
+        Point,
+        Anisotropic,
+        LinearMipPoint,
+        PointMipLinear,
+        MinLinearMagPointMipLinear,
+        MinLinearMagPointMipPoint,
+        MinPointMagLinearMipLinear,
+        MinPointMagLinearMipPoint,
+    ],
+}
+
+/// Conversions between the wrapper enums and the `fna3d_sys::rustified` enums
+///
+/// Each pair of variants is listed, so the matches are exhaustive in both directions: a variant
+/// missing on either side is a compile error. They compile down to no-ops.
+#[cfg(feature = "rustified-enums")]
+macro_rules! impl_rustified {
+    ($($ty:ident => $raw:ident { $($var:ident => $raw_var:ident,)* },)*) => {
+        $(
+            impl From<$ty> for sys::rustified::$raw {
+                fn from(e: $ty) -> Self {
+                    match e {
+                        $($ty::$var => sys::rustified::$raw::$raw_var,)*
+                    }
+                }
+            }
+
+            impl From<sys::rustified::$raw> for $ty {
+                fn from(e: sys::rustified::$raw) -> Self {
+                    match e {
+                        $(sys::rustified::$raw::$raw_var => $ty::$var,)*
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "rustified-enums")]
+impl_rustified! {
+    PresentInterval => FNA3D_PresentInterval {
+        Default => FNA3D_PRESENTINTERVAL_DEFAULT,
+        One => FNA3D_PRESENTINTERVAL_ONE,
+        Two => FNA3D_PRESENTINTERVAL_TWO,
+        Immediate => FNA3D_PRESENTINTERVAL_IMMEDIATE,
+    },
+    DisplayOrientation => FNA3D_DisplayOrientation {
+        Defaut => FNA3D_DISPLAYORIENTATION_DEFAULT,
+        LandscapeLeft => FNA3D_DISPLAYORIENTATION_LANDSCAPELEFT,
+        LandscapeRight => FNA3D_DISPLAYORIENTATION_LANDSCAPERIGHT,
+        Portrait => FNA3D_DISPLAYORIENTATION_PORTRAIT,
+    },
+    RenderTargetUsage => FNA3D_RenderTargetUsage {
+        DiscardContents => FNA3D_RENDERTARGETUSAGE_DISCARDCONTENTS,
+        PreserveContents => FNA3D_RENDERTARGETUSAGE_PRESERVECONTENTS,
+        PlatformContents => FNA3D_RENDERTARGETUSAGE_PLATFORMCONTENTS,
+    },
+    PrimitiveType => FNA3D_PrimitiveType {
+        TriangleList => FNA3D_PRIMITIVETYPE_TRIANGLELIST,
+        TriangleStrip => FNA3D_PRIMITIVETYPE_TRIANGLESTRIP,
+        LineList => FNA3D_PRIMITIVETYPE_LINELIST,
+        LineStrip => FNA3D_PRIMITIVETYPE_LINESTRIP,
+        PointListExt => FNA3D_PRIMITIVETYPE_POINTLIST_EXT,
+    },
+    IndexElementSize => FNA3D_IndexElementSize {
+        Bits16 => FNA3D_INDEXELEMENTSIZE_16BIT,
+        Bits32 => FNA3D_INDEXELEMENTSIZE_32BIT,
+    },
+    SurfaceFormat => FNA3D_SurfaceFormat {
+        Color => FNA3D_SURFACEFORMAT_COLOR,
+        Bgr565 => FNA3D_SURFACEFORMAT_BGR565,
+        Bgra5551 => FNA3D_SURFACEFORMAT_BGRA5551,
+        Bgra4444 => FNA3D_SURFACEFORMAT_BGRA4444,
+        Dxt1 => FNA3D_SURFACEFORMAT_DXT1,
+        Dxt3 => FNA3D_SURFACEFORMAT_DXT3,
+        Dxt5 => FNA3D_SURFACEFORMAT_DXT5,
+        NormalizedByte2 => FNA3D_SURFACEFORMAT_NORMALIZEDBYTE2,
+        NormalizedByte4 => FNA3D_SURFACEFORMAT_NORMALIZEDBYTE4,
+        Rgba1010102 => FNA3D_SURFACEFORMAT_RGBA1010102,
+        Rg32 => FNA3D_SURFACEFORMAT_RG32,
+        Rgba64 => FNA3D_SURFACEFORMAT_RGBA64,
+        Alpha8 => FNA3D_SURFACEFORMAT_ALPHA8,
+        Single => FNA3D_SURFACEFORMAT_SINGLE,
+        Vector2 => FNA3D_SURFACEFORMAT_VECTOR2,
+        Vector4 => FNA3D_SURFACEFORMAT_VECTOR4,
+        HalfSingle => FNA3D_SURFACEFORMAT_HALFSINGLE,
+        HalfVector2 => FNA3D_SURFACEFORMAT_HALFVECTOR2,
+        HalfVector4 => FNA3D_SURFACEFORMAT_HALFVECTOR4,
+        HdrBlendable => FNA3D_SURFACEFORMAT_HDRBLENDABLE,
+        ColorBgraExt => FNA3D_SURFACEFORMAT_COLORBGRA_EXT,
+    },
+    DepthFormat => FNA3D_DepthFormat {
+        None => FNA3D_DEPTHFORMAT_NONE,
+        D16 => FNA3D_DEPTHFORMAT_D16,
+        D24 => FNA3D_DEPTHFORMAT_D24,
+        D24S8 => FNA3D_DEPTHFORMAT_D24S8,
+    },
+    CubeMapFace => FNA3D_CubeMapFace {
+        PositiveX => FNA3D_CUBEMAPFACE_POSITIVEX,
+        NegativeX => FNA3D_CUBEMAPFACE_NEGATIVEX,
+        PositiveY => FNA3D_CUBEMAPFACE_POSITIVEY,
+        NegativeY => FNA3D_CUBEMAPFACE_NEGATIVEY,
+        PositiveZ => FNA3D_CUBEMAPFACE_POSITIVEZ,
+        NegativeZ => FNA3D_CUBEMAPFACE_NEGATIVEZ,
+    },
+    BufferUsage => FNA3D_BufferUsage {
+        None => FNA3D_BUFFERUSAGE_NONE,
+        WriteOnly => FNA3D_BUFFERUSAGE_WRITEONLY,
+    },
+    SetDataOptions => FNA3D_SetDataOptions {
+        None => FNA3D_SETDATAOPTIONS_NONE,
+        Discard => FNA3D_SETDATAOPTIONS_DISCARD,
+        NoOverwrite => FNA3D_SETDATAOPTIONS_NOOVERWRITE,
+    },
+    Blend => FNA3D_Blend {
+        One => FNA3D_BLEND_ONE,
+        Zero => FNA3D_BLEND_ZERO,
+        SourceColor => FNA3D_BLEND_SOURCECOLOR,
+        InverseSourceColor => FNA3D_BLEND_INVERSESOURCECOLOR,
+        SourceAlpha => FNA3D_BLEND_SOURCEALPHA,
+        InverseSourceAlpha => FNA3D_BLEND_INVERSESOURCEALPHA,
+        DestinationColor => FNA3D_BLEND_DESTINATIONCOLOR,
+        InveseDestinationColor => FNA3D_BLEND_INVERSEDESTINATIONCOLOR,
+        DestinaitonAlpha => FNA3D_BLEND_DESTINATIONALPHA,
+        InverseDetinationAlpha => FNA3D_BLEND_INVERSEDESTINATIONALPHA,
+        BlendFactor => FNA3D_BLEND_BLENDFACTOR,
+        InverseBlendFactor => FNA3D_BLEND_INVERSEBLENDFACTOR,
+        SourceAlphaSaturation => FNA3D_BLEND_SOURCEALPHASATURATION,
+    },
+    BlendFunction => FNA3D_BlendFunction {
+        Add => FNA3D_BLENDFUNCTION_ADD,
+        Substract => FNA3D_BLENDFUNCTION_SUBTRACT,
+        ReverseSubstract => FNA3D_BLENDFUNCTION_REVERSESUBTRACT,
+        Max => FNA3D_BLENDFUNCTION_MAX,
+        Min => FNA3D_BLENDFUNCTION_MIN,
+    },
+    ColorWriteChannels => FNA3D_ColorWriteChannels {
+        None => FNA3D_COLORWRITECHANNELS_NONE,
+        Red => FNA3D_COLORWRITECHANNELS_RED,
+        Green => FNA3D_COLORWRITECHANNELS_GREEN,
+        Blue => FNA3D_COLORWRITECHANNELS_BLUE,
+        Alpha => FNA3D_COLORWRITECHANNELS_ALPHA,
+        All => FNA3D_COLORWRITECHANNELS_ALL,
+    },
+    StencilOperation => FNA3D_StencilOperation {
+        Keep => FNA3D_STENCILOPERATION_KEEP,
+        Zero => FNA3D_STENCILOPERATION_ZERO,
+        Replace => FNA3D_STENCILOPERATION_REPLACE,
+        Increment => FNA3D_STENCILOPERATION_INCREMENT,
+        Decrement => FNA3D_STENCILOPERATION_DECREMENT,
+        IncrementSaturation => FNA3D_STENCILOPERATION_INCREMENTSATURATION,
+        DecrementSaturation => FNA3D_STENCILOPERATION_DECREMENTSATURATION,
+        Invert => FNA3D_STENCILOPERATION_INVERT,
+    },
+    CompareFunction => FNA3D_CompareFunction {
+        Always => FNA3D_COMPAREFUNCTION_ALWAYS,
+        Never => FNA3D_COMPAREFUNCTION_NEVER,
+        Less => FNA3D_COMPAREFUNCTION_LESS,
+        LessEqual => FNA3D_COMPAREFUNCTION_LESSEQUAL,
+        Equal => FNA3D_COMPAREFUNCTION_EQUAL,
+        GreaterEqual => FNA3D_COMPAREFUNCTION_GREATEREQUAL,
+        Greater => FNA3D_COMPAREFUNCTION_GREATER,
+        NonEqual => FNA3D_COMPAREFUNCTION_NOTEQUAL,
+    },
+    CullMode => FNA3D_CullMode {
+        None => FNA3D_CULLMODE_NONE,
+        CullClockWiseFace => FNA3D_CULLMODE_CULLCLOCKWISEFACE,
+        CullCounterClockwiseFace => FNA3D_CULLMODE_CULLCOUNTERCLOCKWISEFACE,
+    },
+    FillMode => FNA3D_FillMode {
+        Solid => FNA3D_FILLMODE_SOLID,
+        WireFrame => FNA3D_FILLMODE_WIREFRAME,
+    },
+    TextureAddressMode => FNA3D_TextureAddressMode {
+        Wrap => FNA3D_TEXTUREADDRESSMODE_WRAP,
+        Clamp => FNA3D_TEXTUREADDRESSMODE_CLAMP,
+        Mirror => FNA3D_TEXTUREADDRESSMODE_MIRROR,
+    },
+    TextureFilter => FNA3D_TextureFilter {
+        Linear => FNA3D_TEXTUREFILTER_LINEAR,
+        Point => FNA3D_TEXTUREFILTER_POINT,
+        Anisotropic => FNA3D_TEXTUREFILTER_ANISOTROPIC,
+        LinearMipPoint => FNA3D_TEXTUREFILTER_LINEAR_MIPPOINT,
+        PointMipLinear => FNA3D_TEXTUREFILTER_POINT_MIPLINEAR,
+        MinLinearMagPointMipLinear => FNA3D_TEXTUREFILTER_MINLINEAR_MAGPOINT_MIPLINEAR,
+        MinLinearMagPointMipPoint => FNA3D_TEXTUREFILTER_MINLINEAR_MAGPOINT_MIPPOINT,
+        MinPointMagLinearMipLinear => FNA3D_TEXTUREFILTER_MINPOINT_MAGLINEAR_MIPLINEAR,
+        MinPointMagLinearMipPoint => FNA3D_TEXTUREFILTER_MINPOINT_MAGLINEAR_MIPPOINT,
+    },
+    VertexElementFormat => FNA3D_VertexElementFormat {
+        Single => FNA3D_VERTEXELEMENTFORMAT_SINGLE,
+        Vector2 => FNA3D_VERTEXELEMENTFORMAT_VECTOR2,
+        Vector3 => FNA3D_VERTEXELEMENTFORMAT_VECTOR3,
+        Vector4 => FNA3D_VERTEXELEMENTFORMAT_VECTOR4,
+        Color => FNA3D_VERTEXELEMENTFORMAT_COLOR,
+        Byte4 => FNA3D_VERTEXELEMENTFORMAT_BYTE4,
+        Short2 => FNA3D_VERTEXELEMENTFORMAT_SHORT2,
+        Short4 => FNA3D_VERTEXELEMENTFORMAT_SHORT4,
+        NormalizedShort2 => FNA3D_VERTEXELEMENTFORMAT_NORMALIZEDSHORT2,
+        NormalizedShort4 => FNA3D_VERTEXELEMENTFORMAT_NORMALIZEDSHORT4,
+        HalfVector2 => FNA3D_VERTEXELEMENTFORMAT_HALFVECTOR2,
+        HalfVector4 => FNA3D_VERTEXELEMENTFORMAT_HALFVECTOR4,
+    },
+    VertexElementUsage => FNA3D_VertexElementUsage {
+        Position => FNA3D_VERTEXELEMENTUSAGE_POSITION,
+        Color => FNA3D_VERTEXELEMENTUSAGE_COLOR,
+        TextureCoordinate => FNA3D_VERTEXELEMENTUSAGE_TEXTURECOORDINATE,
+        Nornal => FNA3D_VERTEXELEMENTUSAGE_NORMAL,
+        BinNormal => FNA3D_VERTEXELEMENTUSAGE_BINORMAL,
+        Tangent => FNA3D_VERTEXELEMENTUSAGE_TANGENT,
+        BlendIndices => FNA3D_VERTEXELEMENTUSAGE_BLENDINDICES,
+        BendWeight => FNA3D_VERTEXELEMENTUSAGE_BLENDWEIGHT,
+        Depth => FNA3D_VERTEXELEMENTUSAGE_DEPTH,
+        Fog => FNA3D_VERTEXELEMENTUSAGE_FOG,
+        PointSize => FNA3D_VERTEXELEMENTUSAGE_POINTSIZE,
+        Sample => FNA3D_VERTEXELEMENTUSAGE_SAMPLE,
+        TesselateFactor => FNA3D_VERTEXELEMENTUSAGE_TESSELATEFACTOR,
+    },
+}
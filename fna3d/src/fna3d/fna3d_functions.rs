@@ -1,5 +1,8 @@
 use ::{fna3d_sys as sys, std::ffi::c_void};
 
+#[cfg(feature = "sdl2")]
+use crate::fna3d::fna3d_structs::DisplayModeInfo;
+
 /// Version of FNA3D in use
 pub fn linked_version() -> u32 {
     unsafe { sys::FNA3D_LinkedVersion() }
@@ -7,11 +10,107 @@ pub fn linked_version() -> u32 {
 
 /// [Newtype] of untyped [SDL_WindowFlags], which is used for [SDL_CreateWindow]
 ///
+/// OR the flags from [`prepare_window_attributes`] with your own ones, then
+/// [`SdlWindowFlags::validate`] them:
+///
+/// ```no_run
+/// use fna3d::SdlWindowFlags;
+///
+/// let flags = fna3d::prepare_window_attributes()
+///     | SdlWindowFlags::RESIZABLE
+///     | SdlWindowFlags::ALLOW_HIGHDPI;
+/// flags.validate().unwrap();
+/// ```
+///
 /// [Newtype]: https://doc.rust-lang.org/stable/rust-by-example/generics/new_types.html
 /// [SDL_WindowFlags]: https://wiki.libsdl.org/SDL_WindowFlags
 /// [SDL_CreateWindow]: https://wiki.libsdl.org/SDL_CreateWindow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct SdlWindowFlags(pub u32);
 
+/// Flags (values of `SDL_WindowFlags`)
+impl SdlWindowFlags {
+    pub const FULLSCREEN: Self = Self(0x0000_0001);
+    pub const OPENGL: Self = Self(0x0000_0002);
+    pub const SHOWN: Self = Self(0x0000_0004);
+    pub const HIDDEN: Self = Self(0x0000_0008);
+    pub const BORDERLESS: Self = Self(0x0000_0010);
+    pub const RESIZABLE: Self = Self(0x0000_0020);
+    pub const MINIMIZED: Self = Self(0x0000_0040);
+    pub const MAXIMIZED: Self = Self(0x0000_0080);
+    /// Fullscreen at the desktop resolution (includes [`SdlWindowFlags::FULLSCREEN`])
+    pub const FULLSCREEN_DESKTOP: Self = Self(0x0000_1001);
+    pub const ALLOW_HIGHDPI: Self = Self(0x0000_2000);
+    pub const VULKAN: Self = Self(0x1000_0000);
+    pub const METAL: Self = Self(0x2000_0000);
+
+    /// Flags that select the graphics API. Only [`prepare_window_attributes`] should set them
+    const BACKENDS: [Self; 3] = [Self::OPENGL, Self::VULKAN, Self::METAL];
+}
+
+impl std::ops::BitOr for SdlWindowFlags {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for SdlWindowFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl SdlWindowFlags {
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// True if all the bits of `flags` are set
+    pub fn contains(&self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    /// Checks that the flags don't conflict with each other
+    pub fn validate(&self) -> Result<(), WindowFlagsError> {
+        let pairs = [
+            (Self::SHOWN, Self::HIDDEN),
+            (Self::MINIMIZED, Self::MAXIMIZED),
+        ];
+        for &(a, b) in pairs.iter() {
+            if self.contains(a) && self.contains(b) {
+                return Err(WindowFlagsError::Conflicting(a, b));
+            }
+        }
+
+        let mut backends = Self::BACKENDS.iter().filter(|f| self.contains(**f));
+        if let (Some(&a), Some(&b)) = (backends.next(), backends.next()) {
+            return Err(WindowFlagsError::Conflicting(a, b));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returned from [`SdlWindowFlags::validate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFlagsError {
+    /// The two flags can't be set at the same time
+    Conflicting(SdlWindowFlags, SdlWindowFlags),
+}
+
+impl std::fmt::Display for WindowFlagsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowFlagsError::Conflicting(a, b) => {
+                write!(f, "window flags {:#x} and {:#x} conflict", a.0, b.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowFlagsError {}
+
 // Init/Quit
 
 /// Selects the most suitable graphics rendering backend for the system, then provides the
@@ -30,3 +129,128 @@ pub fn get_drawable_size(window: *mut c_void) -> (u32, u32) {
     }
     (w as u32, h as u32)
 }
+
+/// `SDL_WINDOW_ALLOW_HIGHDPI`
+#[cfg(feature = "sdl2")]
+const SDL_WINDOW_ALLOW_HIGHDPI: u32 = 0x0000_2000;
+
+/// Size of a window in pixels and in logical units (points), returned from [`drawable_metrics`]
+#[cfg(feature = "sdl2")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawableMetrics {
+    /// Drawable size in pixels. Use it for the backbuffer and viewports
+    pub pixel_size: (u32, u32),
+    /// Window size in logical units. Mouse positions from SDL are in this unit
+    pub logical_size: (u32, u32),
+    /// `pixel_size / logical_size`, e.g. `2.0` on retina displays
+    pub scale_factor: f32,
+}
+
+#[cfg(feature = "sdl2")]
+impl DrawableMetrics {
+    /// Logical position (e.g. mouse position) to pixel position
+    pub fn to_pixels(&self, pos: [f32; 2]) -> [f32; 2] {
+        [pos[0] * self.scale_factor, pos[1] * self.scale_factor]
+    }
+}
+
+/// Pixel/logical size of the window (`SDL_Window*`)
+///
+/// The window has to be created with `SDL_WINDOW_ALLOW_HIGHDPI` to get the full resolution on
+/// high-DPI displays; otherwise the pixel size equals the logical size and the OS upscales it.
+///
+/// # Safety
+///
+/// `window` must be a valid `SDL_Window*`.
+#[cfg(feature = "sdl2")]
+pub unsafe fn drawable_metrics(window: *mut c_void) -> DrawableMetrics {
+    let pixel_size = self::get_drawable_size(window);
+
+    let (mut w, mut h) = (0, 0);
+    sdl2::sys::SDL_GetWindowSize(window as *mut _, &mut w, &mut h);
+    let logical_size = (w.max(0) as u32, h.max(0) as u32);
+
+    let scale_factor = if logical_size.0 > 0 {
+        pixel_size.0 as f32 / logical_size.0 as f32
+    } else {
+        1.0
+    };
+
+    DrawableMetrics {
+        pixel_size,
+        logical_size,
+        scale_factor,
+    }
+}
+
+/// If the window (`SDL_Window*`) is created with `SDL_WINDOW_ALLOW_HIGHDPI`
+///
+/// # Safety
+///
+/// `window` must be a valid `SDL_Window*`.
+#[cfg(feature = "sdl2")]
+pub unsafe fn is_high_dpi_allowed(window: *mut c_void) -> bool {
+    self::window_flags(window).0 & SDL_WINDOW_ALLOW_HIGHDPI != 0
+}
+
+/// Index of the display the window (`SDL_Window*`) is on and the current mode of the display
+///
+/// # Safety
+///
+/// `window` must be a valid `SDL_Window*`.
+#[cfg(feature = "sdl2")]
+pub(crate) unsafe fn window_display(window: *mut c_void) -> Option<(i32, DisplayModeInfo)> {
+    let index = sdl2::sys::SDL_GetWindowDisplayIndex(window as *mut _);
+    if index < 0 {
+        return None;
+    }
+
+    let mut mode = std::mem::MaybeUninit::uninit();
+    if sdl2::sys::SDL_GetCurrentDisplayMode(index, mode.as_mut_ptr()) != 0 {
+        return None;
+    }
+    let mode = mode.assume_init();
+
+    Some((
+        index,
+        DisplayModeInfo {
+            w: mode.w,
+            h: mode.h,
+            refresh_rate: mode.refresh_rate,
+            format: mode.format,
+        },
+    ))
+}
+
+/// Current flags of the window (`SDL_Window*`)
+///
+/// # Safety
+///
+/// `window` must be a valid `SDL_Window*`.
+#[cfg(feature = "sdl2")]
+pub unsafe fn window_flags(window: *mut c_void) -> SdlWindowFlags {
+    SdlWindowFlags(sdl2::sys::SDL_GetWindowFlags(window as *mut _))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_window_flags_validate() {
+        let flags = SdlWindowFlags::OPENGL | SdlWindowFlags::RESIZABLE;
+        assert!(flags.validate().is_ok());
+        assert!(SdlWindowFlags::FULLSCREEN_DESKTOP.contains(SdlWindowFlags::FULLSCREEN));
+
+        assert_eq!(
+            (flags | SdlWindowFlags::METAL).validate(),
+            Err(WindowFlagsError::Conflicting(
+                SdlWindowFlags::OPENGL,
+                SdlWindowFlags::METAL
+            ))
+        );
+        assert!((SdlWindowFlags::SHOWN | SdlWindowFlags::HIDDEN)
+            .validate()
+            .is_err());
+    }
+}
@@ -8,7 +8,7 @@
 //!
 //! [paste]: https://github.com/dtolnay/paste
 
-use ::{fna3d_sys as sys, num_traits::FromPrimitive};
+use ::fna3d_sys as sys;
 
 use crate::fna3d::fna3d_enums as enums;
 
@@ -83,6 +83,19 @@ impl RenderTargetBinding {
         &mut self.raw
     }
 
+    /// `(w, h)` of the 2D target or `(size, size)` of the cube target
+    pub fn size(&self) -> (u32, u32) {
+        unsafe {
+            if self.raw.type_ == RenderTargetType::Cube as u8 {
+                let size = self.raw.__bindgen_anon_1.cube.size as u32;
+                (size, size)
+            } else {
+                let twod = self.raw.__bindgen_anon_1.twod;
+                (twod.width as u32, twod.height as u32)
+            }
+        }
+    }
+
     // two constructors handling the union
 
     pub fn new_2d(
@@ -166,8 +179,61 @@ impl RenderTargetBinding {
 // TODO: maybe wrap those types
 
 /// The view bounds for render-target surface
+///
+/// Create it with [`ViewportExt::new`], which sets the depth range to `0..1`.
 pub type Viewport = sys::FNA3D_Viewport;
 
+/// Constructors of [`Viewport`] (it's a `bindgen` type, so they're provided as a trait)
+///
+/// `minDepth` and `maxDepth` map the depth of the clip space (`0..1`) to the range of the depth
+/// buffer. `0..1` uses the whole depth buffer. A zero range such as the `0..0` of
+/// `Viewport::default()` writes the same depth for every fragment, which breaks depth testing.
+///
+/// ```no_run
+/// use fna3d::{Viewport, ViewportExt};
+///
+/// let vp = Viewport::new(0, 0, 1280, 720);
+/// // e.g. for a skybox drawn behind everything
+/// let far = Viewport::new(0, 0, 1280, 720).with_depth_range(1.0, 1.0);
+/// ```
+pub trait ViewportExt {
+    /// Viewport with the depth range `0..1`
+    fn new(x: i32, y: i32, w: i32, h: i32) -> Self;
+    /// Viewport covering the rectangle with the depth range `0..1`
+    fn from_rect(rect: Rect) -> Self;
+    /// Sets the depth range. Both values have to be in `0..=1` and `min <= max`
+    fn with_depth_range(self, min: f32, max: f32) -> Self;
+}
+
+impl ViewportExt for Viewport {
+    fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Self {
+            x,
+            y,
+            w,
+            h,
+            minDepth: 0.0,
+            maxDepth: 1.0,
+        }
+    }
+
+    fn from_rect(rect: Rect) -> Self {
+        Self::new(rect.x, rect.y, rect.w, rect.h)
+    }
+
+    fn with_depth_range(mut self, min: f32, max: f32) -> Self {
+        debug_assert!(
+            0.0 <= min && min <= max && max <= 1.0,
+            "invalid viewport depth range: {}..{}",
+            min,
+            max
+        );
+        self.minDepth = min;
+        self.maxDepth = max;
+        self
+    }
+}
+
 /// 24 bits RGBA color
 ///
 /// [`Color::to_vec4`] is available.
@@ -239,28 +305,36 @@ impl Color {
         }
     }
 
-    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
         Self::rgba(r, g, b, 255)
     }
 
-    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
         Self {
-            raw: sys::FNA3D_Color {
-                r: r,
-                g: g,
-                b: b,
-                a: a,
-            },
+            raw: sys::FNA3D_Color { r, g, b, a },
         }
     }
 
     /// Returns premultiplied alpha
     pub fn from_non_premultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
-        Self::rgba((r * a) / 255, (g * a) / 255, (b * a) / 255, a)
+        use crate::img::premultiply_channel as p;
+        Self::rgba(p(r, a), p(g, a), p(b, a), a)
+    }
+}
+
+impl From<Vec4> for Color {
+    fn from(v: Vec4) -> Self {
+        Self::from_vec4(v)
+    }
+}
+
+impl From<Color> for Vec4 {
+    fn from(c: Color) -> Self {
+        c.to_vec4()
     }
 }
 
-/// Predefined colors
+/// Predefined colors. See also the XNA colors such as [`Color::CORNFLOWER_BLUE`]
 impl Color {
     pub fn transparent() -> Self {
         Self::rgba(0, 0, 0, 0)
@@ -276,11 +350,132 @@ impl Color {
 }
 
 /// Scissor rect or region of [`Renderbuffer`]
+///
+/// Create it with [`RectExt::new`] or convert from `(x, y, w, h)` or `[x, y, w, h]`:
+///
+/// ```no_run
+/// use fna3d::{Rect, RectExt};
+///
+/// let a = Rect::new(0, 0, 320, 240);
+/// let b = Rect::from([0, 0, 320, 240]);
+/// let c: Rect = (0, 0, 320, 240).into();
+/// ```
 pub type Rect = sys::FNA3D_Rect;
+
+/// Constructor of [`Rect`] (it's a `bindgen` type, so it's provided as a trait)
+pub trait RectExt {
+    fn new(x: i32, y: i32, w: i32, h: i32) -> Self;
+}
+
+impl RectExt for Rect {
+    fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Self { x, y, w, h }
+    }
+}
+
 /// Used to represent color
+///
+/// It converts from `[x, y, z, w]` and `(x, y, z, w)` and supports element-wise arithmetic:
+///
+/// ```no_run
+/// use fna3d::{Color, Vec4};
+///
+/// let tint = Vec4::from([1.0, 0.5, 0.5, 1.0]);
+/// let faded: Color = (Color::white().to_vec4() * tint * 0.5).into();
+/// ```
 pub type Vec4 = sys::FNA3D_Vec4;
 pub type PresentationParameters = sys::FNA3D_PresentationParameters;
 
+/// Color given to [`Device::clear`], kept as the normalized [`Vec4`] FNA3D takes
+///
+/// Converting a [`Color`] costs four divisions per clear. Create a `ClearColor` once to clear
+/// with the same color every frame; a [`Vec4`] is used as it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearColor {
+    raw: Vec4,
+}
+
+impl ClearColor {
+    pub fn new(color: Color) -> Self {
+        Self {
+            raw: color.to_vec4(),
+        }
+    }
+
+    pub fn raw(&self) -> &Vec4 {
+        &self.raw
+    }
+
+    pub fn color(&self) -> Color {
+        Color::from_vec4(self.raw)
+    }
+}
+
+impl From<Color> for ClearColor {
+    fn from(color: Color) -> Self {
+        Self::new(color)
+    }
+}
+
+impl From<Vec4> for ClearColor {
+    fn from(raw: Vec4) -> Self {
+        Self { raw }
+    }
+}
+
+/// Reason the backbuffer has to be recreated, returned from [`Device::check_backbuffer`]
+///
+/// The display events are only detected with the `sdl2` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackbufferEvent {
+    /// The drawable size of the window changed (resize, DPI change or display mode change)
+    SizeChanged { old: (u32, u32), new: (u32, u32) },
+    /// The window moved to another display. `size` is the drawable size
+    DisplayChanged {
+        old: i32,
+        new: i32,
+        size: (u32, u32),
+    },
+    /// The display mode of the window's display changed without resizing the drawable, e.g. the
+    /// refresh rate. `size` is the drawable size
+    DisplayModeChanged {
+        old: DisplayModeInfo,
+        new: DisplayModeInfo,
+        size: (u32, u32),
+    },
+}
+
+impl BackbufferEvent {
+    /// Drawable size of the window, i.e. the new backbuffer size
+    pub fn size(&self) -> (u32, u32) {
+        match *self {
+            BackbufferEvent::SizeChanged { new, .. } => new,
+            BackbufferEvent::DisplayChanged { size, .. } => size,
+            BackbufferEvent::DisplayModeChanged { size, .. } => size,
+        }
+    }
+}
+
+/// Current display mode of a display, compared by [`Device::check_backbuffer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisplayModeInfo {
+    pub w: i32,
+    pub h: i32,
+    pub refresh_rate: i32,
+    /// `SDL_PixelFormatEnum`
+    pub format: u32,
+}
+
+/// Description of the backbuffer returned from [`Device::backbuffer_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BackbufferInfo {
+    pub w: u32,
+    pub h: u32,
+    pub surface_format: enums::SurfaceFormat,
+    pub depth_format: enums::DepthFormat,
+    pub multi_sample_count: u32,
+}
+
 // MOJOSHADER_effect?
 
 // --------------------------------------------------------------------------------
@@ -352,7 +547,7 @@ impl RasterizerState {
 /// Accessors
 impl RasterizerState {
     pub fn fill_mode(&self) -> enums::FillMode {
-        enums::FillMode::from_u32(self.raw.fillMode).unwrap()
+        enums::FillMode::from_raw(self.raw.fillMode).unwrap()
     }
 
     pub fn set_fill_mode(&mut self, fill_mode: enums::FillMode) {
@@ -360,7 +555,7 @@ impl RasterizerState {
     }
 
     pub fn cull_mode(&self) -> enums::CullMode {
-        enums::CullMode::from_u32(self.raw.cullMode).unwrap()
+        enums::CullMode::from_raw(self.raw.cullMode).unwrap()
     }
 
     pub fn set_cull_mode(&mut self, value: enums::CullMode) {
@@ -383,20 +578,30 @@ impl RasterizerState {
         self.raw.slopeScaleDepthBias = value;
     }
 
-    pub fn scissor_test_enable(&self) -> u8 {
-        self.raw.scissorTestEnable
+    pub fn is_scissor_test_enabled(&self) -> bool {
+        debug_assert!(
+            self.raw.scissorTestEnable <= 1,
+            "non-boolean scissorTestEnable: {}",
+            self.raw.scissorTestEnable
+        );
+        self.raw.scissorTestEnable != 0
     }
 
-    pub fn set_scissor_test_enable(&mut self, value: u8) {
-        self.raw.scissorTestEnable = value;
+    pub fn set_is_scissor_test_enabled(&mut self, b: bool) {
+        self.raw.scissorTestEnable = b as u8;
     }
 
-    pub fn multi_sample_anti_alias(&self) -> u8 {
-        self.raw.multiSampleAntiAlias
+    pub fn is_multi_sample_anti_alias_enabled(&self) -> bool {
+        debug_assert!(
+            self.raw.multiSampleAntiAlias <= 1,
+            "non-boolean multiSampleAntiAlias: {}",
+            self.raw.multiSampleAntiAlias
+        );
+        self.raw.multiSampleAntiAlias != 0
     }
 
-    pub fn set_multi_sample_anti_alias(&mut self, value: u8) {
-        self.raw.multiSampleAntiAlias = value;
+    pub fn set_is_multi_sample_anti_alias_enabled(&mut self, b: bool) {
+        self.raw.multiSampleAntiAlias = b as u8;
     }
 }
 
@@ -406,7 +611,11 @@ impl RasterizerState {
 /// Specifies texture sampling method
 ///
 /// Wrap, mirror, etc.
+///
+/// `repr(transparent)` because [`crate::Device::verify_sampler`] passes it to FNA3D as
+/// `FNA3D_SamplerState*`.
 #[derive(Debug, Clone)]
+#[repr(transparent)]
 pub struct SamplerState {
     raw: sys::FNA3D_SamplerState,
 }
@@ -429,12 +638,16 @@ impl Default for SamplerState {
 }
 
 impl SamplerState {
+    pub fn raw(&self) -> &sys::FNA3D_SamplerState {
+        &self.raw
+    }
+
     pub fn raw_mut(&mut self) -> &mut sys::FNA3D_SamplerState {
         &mut self.raw
     }
 
     pub fn filter(&self) -> enums::TextureFilter {
-        enums::TextureFilter::from_u32(self.raw.filter).unwrap()
+        enums::TextureFilter::from_raw(self.raw.filter).unwrap()
     }
 
     pub fn set_filter(&mut self, filter: enums::TextureFilter) {
@@ -442,7 +655,7 @@ impl SamplerState {
     }
 
     pub fn address_u(&self) -> enums::TextureAddressMode {
-        enums::TextureAddressMode::from_u32(self.raw.addressU).unwrap()
+        enums::TextureAddressMode::from_raw(self.raw.addressU).unwrap()
     }
 
     pub fn set_address_u(&mut self, address: enums::TextureAddressMode) {
@@ -450,7 +663,7 @@ impl SamplerState {
     }
 
     pub fn address_v(&self) -> enums::TextureAddressMode {
-        enums::TextureAddressMode::from_u32(self.raw.addressV).unwrap()
+        enums::TextureAddressMode::from_raw(self.raw.addressV).unwrap()
     }
 
     pub fn set_address_v(&mut self, address: enums::TextureAddressMode) {
@@ -458,7 +671,7 @@ impl SamplerState {
     }
 
     pub fn address_w(&self) -> enums::TextureAddressMode {
-        enums::TextureAddressMode::from_u32(self.raw.addressW).unwrap()
+        enums::TextureAddressMode::from_raw(self.raw.addressW).unwrap()
     }
 
     pub fn set_address_w(&mut self, address: enums::TextureAddressMode) {
@@ -469,6 +682,10 @@ impl SamplerState {
         self.raw.mipMapLevelOfDetailBias
     }
 
+    /// Offset added to the mip level the GPU selects
+    ///
+    /// Negative values pick more detailed (larger) mips, positive values pick blurrier (smaller)
+    /// ones. The result is still clamped by [`SamplerState::max_mip_level`].
     pub fn set_mip_map_level_of_detail_bias(&mut self, value: f32) {
         self.raw.mipMapLevelOfDetailBias = value;
     }
@@ -477,14 +694,36 @@ impl SamplerState {
         self.raw.maxAnisotropy
     }
 
+    /// Sets the maximum anisotropy, clamped to `1..=`[`SamplerState::MAX_ANISOTROPY`]
+    ///
+    /// Only used when the filter is `Anisotropic`. Out-of-range values are either silently clamped
+    /// by the driver or rejected by the backend validation layer, so they're clamped here (with a
+    /// warning).
     pub fn set_max_anisotropy(&mut self, value: i32) {
-        self.raw.maxAnisotropy = value;
+        let clamped = Self::clamp_anisotropy(value);
+        if clamped != value {
+            log::warn!(
+                "max anisotropy {} is out of range; clamped to {}",
+                value,
+                clamped
+            );
+        }
+        self.raw.maxAnisotropy = clamped;
+    }
+
+    fn clamp_anisotropy(value: i32) -> i32 {
+        value.clamp(1, Self::MAX_ANISOTROPY)
     }
 
     pub fn max_mip_level(&self) -> i32 {
         self.raw.maxMipLevel
     }
 
+    /// Index of the most detailed mip level that can be sampled (XNA's `MaxMipLevel`)
+    ///
+    /// `0` (default) allows the full resolution texture. Larger values skip the top levels, e.g.
+    /// while they're still being streamed in. Despite the name, it's the lower bound of the level
+    /// index (it's `GL_TEXTURE_BASE_LEVEL` / `MinLOD`).
     pub fn set_max_mip_level(&mut self, value: i32) {
         self.raw.maxMipLevel = value;
     }
@@ -492,6 +731,13 @@ impl SamplerState {
 
 /// Preset values
 impl SamplerState {
+    /// Maximum anisotropy level FNA3D backends accept
+    ///
+    /// FNA3D doesn't expose the device limit, but D3D11 guarantees 16 and the OpenGL, Vulkan and
+    /// Metal drivers clamp to their limits, which are 16 on any GPU that supports anisotropic
+    /// filtering.
+    pub const MAX_ANISOTROPY: i32 = 16;
+
     fn new_(
         filter: enums::TextureFilter,
         address_u: enums::TextureAddressMode,
@@ -506,6 +752,34 @@ impl SamplerState {
         me
     }
 
+    /// Anisotropic filtering with wrapping addresses
+    ///
+    /// `level` is clamped to `1..=`[`SamplerState::MAX_ANISOTROPY`] with a warning, like
+    /// [`SamplerState::set_max_anisotropy`]. Typical values are 2, 4, 8 and 16.
+    pub fn anisotropic(level: u32) -> Self {
+        let mut me = Self::anisotropic_wrap();
+        // saturate instead of wrapping to a negative value
+        me.set_max_anisotropy(level.min(i32::MAX as u32) as i32);
+        me
+    }
+
+    /// Sets [`SamplerState::set_mip_map_level_of_detail_bias`]
+    ///
+    /// Positive bias reduces texture bandwidth (and sharpness), e.g. for low quality settings.
+    pub fn with_lod_bias(mut self, bias: f32) -> Self {
+        self.set_mip_map_level_of_detail_bias(bias);
+        self
+    }
+
+    /// Samples mip level `n` and smaller ones only. See [`SamplerState::set_max_mip_level`]
+    ///
+    /// Useful for streaming: upload small mips first, sample with `clamp_to_mip(n)`, then lower
+    /// `n` as larger levels arrive. `n` must be less than the level count of the texture.
+    pub fn clamp_to_mip(mut self, n: u32) -> Self {
+        self.set_max_mip_level(n as i32);
+        self
+    }
+
     pub fn anisotropic_clamp() -> Self {
         Self::new_(
             enums::TextureFilter::Anisotropic,
@@ -629,6 +903,7 @@ impl BlendState {
         )
     }
 
+    /// Same as [`BlendState::premultiplied`]
     pub fn alpha_blend() -> Self {
         Self::with_blend(
             enums::Blend::One,
@@ -638,7 +913,17 @@ impl BlendState {
         )
     }
 
-    /// ImGUI font texture uses this blending function
+    /// Blending for premultiplied alpha, the convention of Rust-FNA3D (and XNA 4)
+    ///
+    /// Textures have to be premultiplied (see [`crate::img::premultiply`]) and so do vertex colors
+    /// (see [`Color::from_non_premultiplied`]).
+    pub fn premultiplied() -> Self {
+        Self::alpha_blend()
+    }
+
+    /// Blending for straight (non-premultiplied) alpha
+    ///
+    /// ImGUI font texture and vertex colors use this blending function.
     pub fn non_premultiplied() -> Self {
         Self::with_blend(
             enums::Blend::SourceAlpha,
@@ -656,6 +941,13 @@ impl BlendState {
             enums::Blend::One,
         )
     }
+
+    /// Writes no color, e.g. for depth pre-passes
+    pub fn depth_only() -> Self {
+        let mut me = Self::opaque();
+        me.set_color_write_enable(enums::ColorWriteChannels::None);
+        me
+    }
 }
 
 /// Accessors
@@ -664,7 +956,7 @@ impl BlendState {
     // Color blending
 
     pub fn color_src_blend(&self) -> enums::Blend {
-        enums::Blend::from_u32(self.raw.colorSourceBlend).unwrap()
+        enums::Blend::from_raw(self.raw.colorSourceBlend).unwrap()
     }
 
     pub fn set_color_src_blend(&mut self, blend: enums::Blend) {
@@ -672,7 +964,7 @@ impl BlendState {
     }
 
     pub fn color_dest_blend(&self) -> enums::Blend {
-        enums::Blend::from_u32(self.raw.colorDestinationBlend).unwrap()
+        enums::Blend::from_raw(self.raw.colorDestinationBlend).unwrap()
     }
 
     pub fn set_color_dest_blend(&mut self, blend: enums::Blend) {
@@ -680,7 +972,7 @@ impl BlendState {
     }
 
     pub fn color_blend_fn(&self) -> enums::BlendFunction {
-        enums::BlendFunction::from_u32(self.raw.colorBlendFunction).unwrap()
+        enums::BlendFunction::from_raw(self.raw.colorBlendFunction).unwrap()
     }
 
     pub fn set_color_blend_fn(&mut self, value: enums::BlendFunction) {
@@ -691,7 +983,7 @@ impl BlendState {
     // Alpha blending
 
     pub fn alpha_src_blend(&self) -> enums::Blend {
-        enums::Blend::from_u32(self.raw.alphaSourceBlend).unwrap()
+        enums::Blend::from_raw(self.raw.alphaSourceBlend).unwrap()
     }
 
     pub fn set_alpha_src_blend(&mut self, blend: enums::Blend) {
@@ -699,7 +991,7 @@ impl BlendState {
     }
 
     pub fn alpha_dest_blend(&self) -> enums::Blend {
-        enums::Blend::from_u32(self.raw.alphaDestinationBlend).unwrap()
+        enums::Blend::from_raw(self.raw.alphaDestinationBlend).unwrap()
     }
 
     pub fn set_alpha_dest_blend(&mut self, blend: enums::Blend) {
@@ -707,7 +999,7 @@ impl BlendState {
     }
 
     pub fn alpha_blend_fn(&self) -> enums::BlendFunction {
-        enums::BlendFunction::from_u32(self.raw.alphaBlendFunction).unwrap()
+        enums::BlendFunction::from_raw(self.raw.alphaBlendFunction).unwrap()
     }
 
     pub fn set_alpha_blend_fn(&mut self, blend_fn: enums::BlendFunction) {
@@ -718,7 +1010,7 @@ impl BlendState {
     // Color write
 
     pub fn color_write_enable(&self) -> enums::ColorWriteChannels {
-        enums::ColorWriteChannels::from_u32(self.raw.colorWriteEnable).unwrap()
+        enums::ColorWriteChannels::from_raw(self.raw.colorWriteEnable).unwrap()
     }
 
     pub fn set_color_write_enable(&mut self, channel: enums::ColorWriteChannels) {
@@ -726,7 +1018,7 @@ impl BlendState {
     }
 
     pub fn color_write_enable1(&self) -> enums::ColorWriteChannels {
-        enums::ColorWriteChannels::from_u32(self.raw.colorWriteEnable1).unwrap()
+        enums::ColorWriteChannels::from_raw(self.raw.colorWriteEnable1).unwrap()
     }
 
     pub fn set_color_write_enable1(&mut self, channel: enums::ColorWriteChannels) {
@@ -734,7 +1026,7 @@ impl BlendState {
     }
 
     pub fn color_write_enable2(&self) -> enums::ColorWriteChannels {
-        enums::ColorWriteChannels::from_u32(self.raw.colorWriteEnable2).unwrap()
+        enums::ColorWriteChannels::from_raw(self.raw.colorWriteEnable2).unwrap()
     }
 
     pub fn set_color_write_enable2(&mut self, channel: enums::ColorWriteChannels) {
@@ -742,7 +1034,7 @@ impl BlendState {
     }
 
     pub fn color_write_enable3(&self) -> enums::ColorWriteChannels {
-        enums::ColorWriteChannels::from_u32(self.raw.colorWriteEnable3).unwrap()
+        enums::ColorWriteChannels::from_raw(self.raw.colorWriteEnable3).unwrap()
     }
 
     pub fn set_color_write_enable3(&mut self, channel: enums::ColorWriteChannels) {
@@ -801,6 +1093,21 @@ impl DepthStencilState {
         me
     }
 
+    /// Depth test without writing (`DepthRead` in XNA)
+    pub fn depth_read() -> Self {
+        let mut me = Self::default();
+        me.raw.depthBufferWriteEnable = false as u8;
+        me
+    }
+
+    /// Passes only where the depth equals the depth buffer, without writing. Used after a depth
+    /// pre-pass so that each pixel is shaded once
+    pub fn depth_equal() -> Self {
+        let mut me = Self::depth_read();
+        me.raw.depthBufferFunction = enums::CompareFunction::Equal as u32;
+        me
+    }
+
     // ----------------------------------------
     // depth buffer
 
@@ -821,7 +1128,7 @@ impl DepthStencilState {
     }
 
     pub fn depth_buffer_function(&self) -> enums::CompareFunction {
-        enums::CompareFunction::from_u32(self.raw.depthBufferFunction).unwrap()
+        enums::CompareFunction::from_raw(self.raw.depthBufferFunction).unwrap()
     }
 
     pub fn set_depth_buffer_function(&mut self, f: enums::CompareFunction) {
@@ -864,7 +1171,7 @@ impl DepthStencilState {
     }
 
     pub fn stencil_fail(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.stencilFail).unwrap()
+        enums::StencilOperation::from_raw(self.raw.stencilFail).unwrap()
     }
 
     pub fn set_stencil_fail(&mut self, stencil: enums::StencilOperation) {
@@ -872,7 +1179,7 @@ impl DepthStencilState {
     }
 
     pub fn stencil_depth_buffer_fail(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.stencilDepthBufferFail).unwrap()
+        enums::StencilOperation::from_raw(self.raw.stencilDepthBufferFail).unwrap()
     }
 
     pub fn set_stencil_depth_buffer_fail(&mut self, stencil: enums::StencilOperation) {
@@ -880,23 +1187,26 @@ impl DepthStencilState {
     }
 
     pub fn stencil_pass(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.stencilPass).unwrap()
+        enums::StencilOperation::from_raw(self.raw.stencilPass).unwrap()
     }
 
     pub fn set_stencil_pass(&mut self, stencil: enums::StencilOperation) {
         self.raw.stencilPass = stencil as u32;
     }
 
-    //     pub stencil_function: enums::CompareFunction,
     pub fn stencil_function(&self) -> enums::CompareFunction {
-        enums::CompareFunction::from_u32(self.raw.depthBufferFunction).unwrap()
+        enums::CompareFunction::from_raw(self.raw.stencilFunction).unwrap()
+    }
+
+    pub fn set_stencil_function(&mut self, f: enums::CompareFunction) {
+        self.raw.stencilFunction = f as u32;
     }
 
     // ----------------------------------------
     // ccw
 
     pub fn ccw_stencil_fail(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.ccwStencilFail).unwrap()
+        enums::StencilOperation::from_raw(self.raw.ccwStencilFail).unwrap()
     }
 
     pub fn set_ccw_stencil_fail(&mut self, stencil: enums::StencilOperation) {
@@ -904,7 +1214,7 @@ impl DepthStencilState {
     }
 
     pub fn ccw_stencil_depth_buffer_fail(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.ccwStencilDepthBufferFail).unwrap()
+        enums::StencilOperation::from_raw(self.raw.ccwStencilDepthBufferFail).unwrap()
     }
 
     pub fn set_ccw_stencil_depth_buffer_fail(&mut self, stencil: enums::StencilOperation) {
@@ -912,7 +1222,7 @@ impl DepthStencilState {
     }
 
     pub fn ccw_stencil_pass(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.ccwStencilPass).unwrap()
+        enums::StencilOperation::from_raw(self.raw.ccwStencilPass).unwrap()
     }
 
     pub fn set_ccw_stencil_pass(&mut self, stencil: enums::StencilOperation) {
@@ -920,7 +1230,7 @@ impl DepthStencilState {
     }
 
     pub fn ccw_stencil_function(&self) -> enums::StencilOperation {
-        enums::StencilOperation::from_u32(self.raw.ccwStencilFunction).unwrap()
+        enums::StencilOperation::from_raw(self.raw.ccwStencilFunction).unwrap()
     }
 
     pub fn set_ccw_stencil_function(&mut self, stencil: enums::StencilOperation) {
@@ -935,3 +1245,115 @@ impl DepthStencilState {
         self.raw.referenceStencil = stencil
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use num_traits::FromPrimitive;
+
+    /// Reads the sampler the same way as `Device::verify_sampler` hands it to FNA3D
+    fn as_ffi(sampler: &SamplerState) -> sys::FNA3D_SamplerState {
+        unsafe { *(sampler as *const _ as *const sys::FNA3D_SamplerState) }
+    }
+
+    #[test]
+    fn test_sampler_mip_presets() {
+        let sampler = SamplerState::linear_wrap()
+            .with_lod_bias(-0.5)
+            .clamp_to_mip(2);
+        let raw = as_ffi(&sampler);
+        assert_eq!(raw.mipMapLevelOfDetailBias, -0.5);
+        assert_eq!(raw.maxMipLevel, 2);
+        assert_eq!(raw.filter, enums::TextureFilter::Linear as u32);
+        assert_eq!(raw.addressU, enums::TextureAddressMode::Wrap as u32);
+    }
+
+    #[test]
+    fn test_sampler_anisotropy_clamp() {
+        assert_eq!(SamplerState::anisotropic(0).max_anisotropy(), 1);
+        assert_eq!(as_ffi(&SamplerState::anisotropic(8)).maxAnisotropy, 8);
+        assert_eq!(
+            SamplerState::anisotropic(64).max_anisotropy(),
+            SamplerState::MAX_ANISOTROPY
+        );
+        assert_eq!(
+            SamplerState::anisotropic(u32::MAX).max_anisotropy(),
+            SamplerState::MAX_ANISOTROPY
+        );
+    }
+
+    #[test]
+    fn test_depth_prepass_presets() {
+        let eq = DepthStencilState::depth_equal();
+        assert!(eq.is_depth_buffer_enabled());
+        assert!(!eq.is_depth_buffer_write_enabled());
+        assert_eq!(eq.depth_buffer_function(), enums::CompareFunction::Equal);
+
+        assert_eq!(
+            BlendState::depth_only().color_write_enable(),
+            enums::ColorWriteChannels::None
+        );
+    }
+
+    #[test]
+    fn test_viewport_new() {
+        let vp = Viewport::new(0, 0, 640, 480);
+        assert_eq!((vp.w, vp.h, vp.minDepth, vp.maxDepth), (640, 480, 0.0, 1.0));
+        let vp = vp.with_depth_range(0.5, 1.0);
+        assert_eq!((vp.minDepth, vp.maxDepth), (0.5, 1.0));
+
+        let rect = Rect::new(1, 2, 3, 4);
+        let vp = Viewport::from_rect(rect);
+        assert_eq!((vp.x, vp.y, vp.w, vp.h, vp.maxDepth), (1, 2, 3, 4, 1.0));
+
+        let from_tuple = Rect::from((1, 2, 3, 4));
+        let from_array = Rect::from([1, 2, 3, 4]);
+        for r in &[from_tuple, from_array] {
+            assert_eq!((r.x, r.y, r.w, r.h), (rect.x, rect.y, rect.w, rect.h));
+        }
+    }
+
+    #[test]
+    fn test_vec4_ops() {
+        let a = Vec4::from([1.0, 2.0, 3.0, 4.0]);
+        let b = Vec4::from((0.5, 0.5, 0.5, 0.5));
+        assert_eq!(<[f32; 4]>::from(a + b), [1.5, 2.5, 3.5, 4.5]);
+        assert_eq!(<[f32; 4]>::from(a * b - b), [0.0, 0.5, 1.0, 1.5]);
+        assert_eq!(-(a / 2.0), Vec4::from([-0.5, -1.0, -1.5, -2.0]));
+
+        let mut c = a;
+        c *= 2.0;
+        c -= a;
+        assert_eq!(c, a);
+
+        let tint = Vec4::from([1.0, 0.5, 0.5, 1.0]);
+        let faded: Color = (Color::white().to_vec4() * tint * 0.5).into();
+        assert_eq!(faded, Color::rgba(127, 63, 63, 127));
+        assert_eq!(Vec4::from(faded), faded.to_vec4());
+    }
+
+    #[test]
+    fn test_clear_color() {
+        let color = Color::rgba(255, 0, 51, 255);
+        let clear = ClearColor::from(color);
+        assert_eq!(*clear.raw(), color.to_vec4());
+        assert_eq!(clear.color(), color);
+        assert_eq!(ClearColor::from(color.to_vec4()), clear);
+    }
+
+    #[test]
+    fn test_enum_from_raw() {
+        // agrees with the constant matching, including non-contiguous discriminants
+        for raw in 0..32 {
+            assert_eq!(
+                enums::ColorWriteChannels::from_raw(raw),
+                enums::ColorWriteChannels::from_u32(raw)
+            );
+            assert_eq!(
+                enums::SurfaceFormat::from_raw(raw),
+                enums::SurfaceFormat::from_u32(raw)
+            );
+            assert_eq!(enums::Blend::from_raw(raw), enums::Blend::from_u32(raw));
+        }
+    }
+}
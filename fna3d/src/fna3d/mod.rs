@@ -1,5 +1,6 @@
 //! FNA3D.h
 
+pub mod fna3d_colors;
 pub mod fna3d_device;
 pub mod fna3d_enums;
 pub mod fna3d_functions;
@@ -9,42 +9,40 @@
 //! [`stb_image`]: https://github.com/nothings/stb/blob/master/stb_image.h
 //! [`SDL_RWops`]: https://wiki.libsdl.org/SDL_RWops
 //!
-//! # Example
+//! DDS and QOI files are handled by [`dds`] and [`qoi`] without `stb_image`, and HDR images
+//! (Radiance and 16-bit PNG) by [`hdr`].
 //!
-//! ```no_run
-//! pub struct MyTexture2d {
-//!     raw: *mut fna3d::Texture,
-//!     w: u32,
-//!     h: u32,
-//! }
+//! # Alpha convention
+//!
+//! Rust-FNA3D renders with premultiplied alpha ([`crate::BlendState::premultiplied`]) like XNA 4.
+//! Decoded images have straight alpha, so [`premultiply`] them before uploading. Mixing the
+//! conventions results in dark or bright fringes around transparent edges.
 //!
-//! impl MyTexture2d {
-//!     pub fn from_path(
-//!         device: &fna3d::Device,
-//!         path: impl AsRef<std::path::Path>,
-//!     ) -> Option<Self> {
-//!         let (pixels_ptr, len, [w, h]) = fna3d::img::from_path(path, None);
+//! # Example
 //!
-//!         if pixels_ptr == std::ptr::null_mut() {
-//!             return None;
-//!         }
+//! [`load_from_memory`], [`load_from_path`] and [`load_from_reader`] return owned pixels (the
+//! memory of `FNA3D_Image` is freed for you):
 //!
-//!         let pixels: &[u8] = unsafe { std::slice::from_raw_parts(pixels_ptr, len as usize) };
-//!         let raw = device.create_texture_2d(
-//!             fna3d::SurfaceFormat::Color,
-//!             w,
-//!             h,
-//!             1, // mipmap level (do not set it to zero)
-//!             false,
-//!         );
-//!         let texture = MyTexture2d { raw, w, h };
+//! ```no_run
+//! # fn f(device: &fna3d::Device) -> std::io::Result<()> {
+//! let (mut pixels, w, h) = fna3d::img::load_from_path("player.png")?;
+//! fna3d::img::premultiply(&mut pixels);
 //!
-//!         fna3d::img::free(pixels_ptr as *mut _);
+//! let texture = device.create_texture_2d(fna3d::SurfaceFormat::Color, w, h, 1, false);
+//! device.set_texture_data_2d(texture, 0, 0, w, h, 0, &pixels);
 //!
-//!         Some(texture)
-//!     }
-//! }
+//! // e.g. screenshots
+//! fna3d::img::save_png_to("out.png", &pixels, w, h)?;
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! The raw functions ([`from_encoded_bytes`], [`from_path`] and [`from_reader`]) return the
+//! `FNA3D_Image` memory as it is. [`free`] it after use.
+
+pub mod dds;
+pub mod hdr;
+pub mod qoi;
 
 use {
     fna3d_sys as sys,
@@ -56,8 +54,6 @@ use {
     },
 };
 
-use crate::Texture;
-
 /// Callback used to pull data from the stream
 type ReadFunc = sys::FNA3D_Image_ReadFunc;
 
@@ -70,7 +66,62 @@ type EofFunc = sys::FNA3D_Image_EOFFunc;
 // /// Callback used to check that we're reached to the end of a stream
 // type WriteFunc = sys::FNA3D_Image_WriteFunc;
 
-/// Frees pixels loaded with a helper method in this module
+/// Premultiplies RGBA8 pixels by their alpha in place
+pub fn premultiply(pixels: &mut [u8]) {
+    for px in pixels.chunks_exact_mut(4) {
+        let a = px[3];
+        for c in px[0..3].iter_mut() {
+            *c = self::premultiply_channel(*c, a);
+        }
+    }
+}
+
+/// `c * a / 255` rounded to the nearest
+pub(crate) fn premultiply_channel(c: u8, a: u8) -> u8 {
+    ((c as u16 * a as u16 + 127) / 255) as u8
+}
+
+// --------------------------------------------------------------------------------
+// Loading
+
+/// Decodes PNG/JPG/GIF data into RGBA8 pixels (straight alpha), width and height
+///
+/// Mainly for `include_bytes!`.
+pub fn load_from_memory(bytes: &[u8]) -> io::Result<(Vec<u8>, u32, u32)> {
+    self::load_from_reader(io::Cursor::new(bytes))
+}
+
+/// Decodes a PNG/JPG/GIF file into RGBA8 pixels (straight alpha), width and height
+pub fn load_from_path(path: impl AsRef<Path>) -> io::Result<(Vec<u8>, u32, u32)> {
+    let file = File::open(path)?;
+    self::load_from_reader(BufReader::new(file))
+}
+
+/// Decodes PNG/JPG/GIF data into RGBA8 pixels (straight alpha), width and height
+///
+/// IO errors of the reader are returned as they are. Data that can't be decoded results in
+/// [`io::ErrorKind::InvalidData`].
+pub fn load_from_reader<R: Read + Seek>(reader: R) -> io::Result<(Vec<u8>, u32, u32)> {
+    let mut cx = LoadContext::new(reader);
+    let (ptr, len, [w, h]) = unsafe { cx.load(None) };
+
+    if let Some(err) = cx.error {
+        self::free(ptr);
+        return Err(err);
+    }
+    if ptr.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "FNA3D_Image failed to decode the image",
+        ));
+    }
+
+    let pixels = unsafe { std::slice::from_raw_parts(ptr, len as usize) }.to_vec();
+    self::free(ptr);
+    Ok((pixels, w, h))
+}
+
+/// Frees pixels loaded with a raw helper method in this module. Null is ignored
 pub fn free(mem: *const u8) {
     unsafe {
         sys::FNA3D_Image_Free(mem as *mut _);
@@ -115,87 +166,115 @@ pub fn from_reader<R: Read + Seek>(
     reader: R,
     force_size: Option<[u32; 2]>,
 ) -> (*const u8, u32, [u32; 2]) {
-    let context = LoadContext {
-        reader,
-        is_end: false,
-    };
+    let mut cx = LoadContext::new(reader);
+    unsafe { cx.load(force_size) }
+}
 
-    unsafe {
-        self::load_impl(
-            Some(LoadCallbacks::<R>::read),
-            Some(LoadCallbacks::<R>::skip),
-            Some(LoadCallbacks::<R>::eof),
-            std::mem::transmute(&context),
-            force_size,
-        )
-    }
+// --------------------------------------------------------------------------------
+// Saving
+
+/// Encodes `w` x `h` RGBA8 pixels into PNG data with a writer
+pub fn save_png<W: Write>(writer: W, pixels: &[u8], w: u32, h: u32) -> io::Result<()> {
+    self::save_impl(writer, pixels, w, h, None)
 }
 
-/// Encodes RGBA8 image data into PNG data with a writer
-pub fn save_png<T: Write>(
-    writer: T,
-    data: *mut Texture,
-    src_w: u32,
-    src_h: u32,
-    dst_w: u32,
-    dst_h: u32,
-) {
-    let mut cx = SaveContext { writer };
+/// Encodes `w` x `h` RGBA8 pixels into a PNG file
+pub fn save_png_to(path: impl AsRef<Path>, pixels: &[u8], w: u32, h: u32) -> io::Result<()> {
+    let file = File::create(path)?;
+    self::save_png(io::BufWriter::new(file), pixels, w, h)
+}
 
-    unsafe {
-        fna3d_sys::FNA3D_Image_SavePNG(
-            Some(SaveContext::<T>::write),
-            &mut cx as *mut _ as _,
-            src_w as i32,
-            src_h as i32,
-            dst_w as i32,
-            dst_h as i32,
-            data as *mut u8,
-        );
-    }
+/// Encodes `w` x `h` RGBA8 pixels into JPEG data with a writer. `quality` is in `1..=100`
+///
+/// JPEG has no alpha channel; it's dropped.
+pub fn save_jpg<W: Write>(writer: W, pixels: &[u8], w: u32, h: u32, quality: u8) -> io::Result<()> {
+    self::save_impl(writer, pixels, w, h, Some(quality.clamp(1, 100)))
 }
 
-/// Encodes RGBA8 image data into PNG data to some path
-pub fn save_png_to(
+/// Encodes `w` x `h` RGBA8 pixels into a JPEG file. `quality` is in `1..=100`
+pub fn save_jpg_to(
     path: impl AsRef<Path>,
-    data: *mut u8,
-    src_w: u32,
-    src_h: u32,
-    dst_w: u32,
-    dst_h: u32,
+    pixels: &[u8],
+    w: u32,
+    h: u32,
+    quality: u8,
 ) -> io::Result<()> {
     let file = File::create(path)?;
-    let mut cx = SaveContext { writer: file };
+    self::save_jpg(io::BufWriter::new(file), pixels, w, h, quality)
+}
+
+/// Saves PNG if `jpg_quality` is `None`
+fn save_impl<W: Write>(
+    writer: W,
+    pixels: &[u8],
+    w: u32,
+    h: u32,
+    jpg_quality: Option<u8>,
+) -> io::Result<()> {
+    let expected = w as usize * h as usize * 4;
+    if pixels.len() != expected {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "expected {} bytes of RGBA8 pixels for {}x{}, got {}",
+                expected,
+                w,
+                h,
+                pixels.len()
+            ),
+        ));
+    }
+
+    let mut cx = SaveContext {
+        writer,
+        error: None,
+    };
+    let context = &mut cx as *mut SaveContext<W> as *mut c_void;
+    // FNA3D doesn't write to the pixels
+    let data = pixels.as_ptr() as *mut u8;
+    let (w, h) = (w as i32, h as i32);
 
     unsafe {
-        fna3d_sys::FNA3D_Image_SavePNG(
-            Some(SaveContext::<File>::write),
-            &mut cx as *mut _ as _,
-            src_w as i32,
-            src_h as i32,
-            dst_w as i32,
-            dst_h as i32,
-            data,
-        );
+        match jpg_quality {
+            None => {
+                sys::FNA3D_Image_SavePNG(Some(SaveContext::<W>::write), context, w, h, w, h, data)
+            }
+            Some(quality) => sys::FNA3D_Image_SaveJPG(
+                Some(SaveContext::<W>::write),
+                context,
+                w,
+                h,
+                w,
+                h,
+                data,
+                quality as i32,
+            ),
+        }
     }
 
-    Ok(())
+    match cx.error {
+        Some(err) => Err(err),
+        None => cx.writer.flush(),
+    }
 }
 
-struct SaveContext<T: Write> {
-    writer: T,
+struct SaveContext<W: Write> {
+    writer: W,
+    /// First error of the writer. Following writes are skipped
+    error: Option<io::Error>,
 }
 
-impl<T: Write> SaveContext<T> {
-    unsafe extern "C" fn write(
-        context: *mut ::std::os::raw::c_void,
-        data: *mut ::std::os::raw::c_void,
-        size: i32,
-    ) {
+impl<W: Write> SaveContext<W> {
+    unsafe extern "C" fn write(context: *mut c_void, data: *mut c_void, size: i32) {
         let cx = &mut *(context as *mut Self);
-        let buf = std::slice::from_raw_parts(data as *mut u8, size as usize);
-        // TODO: don't unwrap
-        cx.writer.write(buf).unwrap();
+        if cx.error.is_some() {
+            return;
+        }
+
+        let buf = std::slice::from_raw_parts(data as *const u8, size as usize);
+        if let Err(err) = cx.writer.write_all(buf) {
+            cx.error = Some(err);
+        }
     }
 }
 
@@ -205,7 +284,31 @@ impl<T: Write> SaveContext<T> {
 /// Context passed around callback functions
 struct LoadContext<R: Read + Seek> {
     reader: R,
-    is_end: bool, // FIXME: is this right?
+    /// Set when a read returns less than requested
+    is_end: bool,
+    /// First IO error of the reader. The stream is treated as ended after it
+    error: Option<io::Error>,
+}
+
+impl<R: Read + Seek> LoadContext<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            is_end: false,
+            error: None,
+        }
+    }
+
+    /// Returns the raw output of `FNA3D_Image_Load`
+    unsafe fn load(&mut self, force_size: Option<[u32; 2]>) -> (*const u8, u32, [u32; 2]) {
+        self::load_impl(
+            Some(LoadCallbacks::<R>::read),
+            Some(LoadCallbacks::<R>::skip),
+            Some(LoadCallbacks::<R>::eof),
+            self as *mut Self as *mut c_void,
+            force_size,
+        )
+    }
 }
 
 /// Callback functions for `FNA3D_Image.h`, i.e. `stb_image.h`
@@ -244,26 +347,40 @@ impl<R: Read + Seek> LoadCallbacks<R> {
     /// Reads up to `size` bytes
     unsafe extern "C" fn read(context: *mut c_void, out_ptr: *mut c_char, size: i32) -> i32 {
         let cx = &mut *(context as *mut LoadContext<R>);
+        if cx.is_end {
+            return 0;
+        }
 
         let out = std::slice::from_raw_parts_mut(out_ptr as *mut u8, size as usize);
-        let len_read = self::read_as_much(&mut cx.reader, out).unwrap();
-
-        len_read as i32
+        match self::read_as_much(&mut cx.reader, out) {
+            Ok(len_read) => {
+                cx.is_end = len_read < out.len();
+                len_read as i32
+            }
+            Err(err) => {
+                cx.error = Some(err);
+                cx.is_end = true;
+                0
+            }
+        }
     }
 
     /// Skips `n` bytes
     unsafe extern "C" fn skip(context: *mut c_void, n: i32) {
-        // log::warn!("FNA3D_Image stbi skipFunc called: {}", n);
         let cx = &mut *(context as *mut LoadContext<R>);
-        cx.reader
-            .seek(SeekFrom::Current(n as i64))
-            .unwrap_or_else(|err| panic!("error in anf skip func {}", err));
+        if cx.is_end {
+            return;
+        }
+
+        if let Err(err) = cx.reader.seek(SeekFrom::Current(n as i64)) {
+            cx.error = Some(err);
+            cx.is_end = true;
+        }
     }
 
-    /// FIXME: is this OK? I've never seen it's called and I'm really not confident
+    /// Non-zero if the stream is ended (`stbi` asks before reading more)
     unsafe extern "C" fn eof(context: *mut c_void) -> i32 {
         let cx = &mut *(context as *mut LoadContext<R>);
-        log::warn!("FNA3D_Image stbi eofFunc called: is_end={}", cx.is_end);
         cx.is_end as i32
     }
 }
@@ -315,3 +432,46 @@ unsafe fn load_impl(
 
     (pixels, len as u32, [w as u32, h as u32])
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_premultiply() {
+        let mut pixels = [255, 128, 0, 128, 10, 20, 30, 255, 200, 200, 200, 0];
+        premultiply(&mut pixels);
+        assert_eq!(pixels, [128, 64, 0, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+        assert_eq!(
+            crate::Color::from_non_premultiplied(255, 255, 255, 128),
+            crate::Color::rgba(128, 128, 128, 128)
+        );
+    }
+
+    #[test]
+    fn test_load_callbacks() {
+        let mut cx = LoadContext::new(io::Cursor::new(vec![1u8, 2, 3, 4, 5]));
+        let ptr = &mut cx as *mut LoadContext<_> as *mut c_void;
+        let mut buf = [0u8; 3];
+
+        unsafe {
+            let read = LoadCallbacks::<io::Cursor<Vec<u8>>>::read;
+            let skip = LoadCallbacks::<io::Cursor<Vec<u8>>>::skip;
+            let eof = LoadCallbacks::<io::Cursor<Vec<u8>>>::eof;
+
+            assert_eq!(read(ptr, buf.as_mut_ptr() as *mut c_char, 2), 2);
+            assert_eq!(eof(ptr), 0);
+            skip(ptr, 1);
+            assert_eq!(read(ptr, buf.as_mut_ptr() as *mut c_char, 3), 2);
+            assert_eq!(&buf[..2], &[4, 5]);
+            assert_eq!(eof(ptr), 1);
+        }
+        assert!(cx.error.is_none());
+    }
+
+    #[test]
+    fn test_save_checks_length() {
+        let err = save_png(Vec::new(), &[0; 12], 2, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
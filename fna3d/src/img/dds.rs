@@ -0,0 +1,415 @@
+//! DDS loader for DXT1/DXT3/DXT5 and 32-bit RGBA textures
+//!
+//! Some GL backends don't support S3TC. With the `s3tc-decoder` feature, [`load`] decompresses
+//! such textures to `SurfaceFormat::Color` on the CPU, so that the same assets load everywhere
+//! (at 4-8 times the memory). Without it, [`DdsError::NotSupported`] is returned.
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, bytes: &[u8]) -> Result<(), fna3d::img::dds::DdsError> {
+//! // `bytes` is the content of a `.dds` file
+//! let texture = fna3d::img::dds::load(device, bytes)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::{
+    fna3d::{fna3d_device::Device, fna3d_enums::SurfaceFormat},
+    res,
+};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+/// Magic and header
+const DATA_OFFSET: usize = 4 + 124;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+
+/// Error on loading a DDS file
+#[derive(Debug, Clone, PartialEq)]
+pub enum DdsError {
+    /// Not a DDS file or the header is broken
+    InvalidHeader,
+    /// Pixel format other than DXT1/DXT3/DXT5 and 32-bit RGBA/BGRA
+    UnsupportedFormat {
+        four_cc: [u8; 4],
+        bit_count: u32,
+    },
+    /// The file is shorter than the header says
+    UnexpectedEof {
+        expected: usize,
+        actual: usize,
+    },
+    /// The device can't sample the format and the `s3tc-decoder` feature is disabled
+    NotSupported(SurfaceFormat),
+    Resource(res::ResourceError),
+}
+
+impl fmt::Display for DdsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DdsError::InvalidHeader => write!(f, "invalid DDS header"),
+            DdsError::UnsupportedFormat { four_cc, bit_count } => write!(
+                f,
+                "unsupported DDS pixel format (FourCC: {:?}, bit count: {})",
+                String::from_utf8_lossy(four_cc),
+                bit_count
+            ),
+            DdsError::UnexpectedEof { expected, actual } => write!(
+                f,
+                "DDS data is truncated: expected {} bytes but got {} bytes",
+                expected, actual
+            ),
+            DdsError::NotSupported(format) => write!(
+                f,
+                "the device doesn't support {:?} textures (enable the `s3tc-decoder` feature)",
+                format
+            ),
+            DdsError::Resource(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DdsError {}
+
+impl From<res::ResourceError> for DdsError {
+    fn from(err: res::ResourceError) -> Self {
+        DdsError::Resource(err)
+    }
+}
+
+/// Parsed DDS file
+#[derive(Debug, Clone, PartialEq)]
+pub struct DdsImage {
+    pub format: SurfaceFormat,
+    pub w: u32,
+    pub h: u32,
+    /// Tightly packed data of each mip level
+    pub levels: Vec<Vec<u8>>,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut b = [0; 4];
+    b.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(b)
+}
+
+impl DdsImage {
+    /// Parses a DDS file. 2D textures only (no cube maps, volumes or DX10 headers)
+    pub fn parse(bytes: &[u8]) -> Result<Self, DdsError> {
+        if bytes.len() < DATA_OFFSET || &bytes[0..4] != MAGIC || read_u32(bytes, 4) != 124 {
+            return Err(DdsError::InvalidHeader);
+        }
+
+        let flags = read_u32(bytes, 8);
+        let h = read_u32(bytes, 12);
+        let w = read_u32(bytes, 16);
+        let n_levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+            read_u32(bytes, 32).max(1)
+        } else {
+            1
+        };
+        if w == 0 || h == 0 || n_levels > 32 {
+            return Err(DdsError::InvalidHeader);
+        }
+
+        // DDS_PIXELFORMAT
+        let pf_flags = read_u32(bytes, 80);
+        let mut four_cc = [0; 4];
+        four_cc.copy_from_slice(&bytes[84..88]);
+        let bit_count = read_u32(bytes, 88);
+        let r_mask = read_u32(bytes, 92);
+
+        let (format, bgra) = match (pf_flags & DDPF_FOURCC != 0, &four_cc) {
+            (true, b"DXT1") => (SurfaceFormat::Dxt1, false),
+            (true, b"DXT3") => (SurfaceFormat::Dxt3, false),
+            (true, b"DXT5") => (SurfaceFormat::Dxt5, false),
+            (false, _) if pf_flags & DDPF_RGB != 0 && bit_count == 32 => {
+                (SurfaceFormat::Color, r_mask == 0x00ff_0000)
+            }
+            _ => return Err(DdsError::UnsupportedFormat { four_cc, bit_count }),
+        };
+
+        let mut levels = Vec::with_capacity(n_levels as usize);
+        let mut offset = DATA_OFFSET;
+        for level in 0..n_levels {
+            let (lw, lh) = res::level_size(w, h, level);
+            let (row_len, n_rows) = res::region_layout(format, lw, lh);
+            let end = offset + row_len * n_rows;
+            if bytes.len() < end {
+                return Err(DdsError::UnexpectedEof {
+                    expected: end,
+                    actual: bytes.len(),
+                });
+            }
+
+            let mut data = bytes[offset..end].to_vec();
+            if bgra {
+                for px in data.chunks_exact_mut(4) {
+                    px.swap(0, 2);
+                }
+            }
+            levels.push(data);
+            offset = end;
+        }
+
+        Ok(Self {
+            format,
+            w,
+            h,
+            levels,
+        })
+    }
+
+    /// If the device can sample the format
+    pub fn is_supported_by(&self, device: &Device) -> bool {
+        match self.format {
+            SurfaceFormat::Dxt1 => device.supports_dxt1(),
+            SurfaceFormat::Dxt3 | SurfaceFormat::Dxt5 => device.supports_s3_tc(),
+            _ => true,
+        }
+    }
+
+    /// Decompresses DXT data into `SurfaceFormat::Color`. Uncompressed images are returned as-is
+    #[cfg(feature = "s3tc-decoder")]
+    pub fn decompress(self) -> Self {
+        if !res::is_compressed(self.format) {
+            return self;
+        }
+
+        let levels = (0..self.levels.len() as u32)
+            .zip(self.levels.iter())
+            .map(|(level, data)| {
+                let (lw, lh) = res::level_size(self.w, self.h, level);
+                self::decode_s3tc(self.format, lw, lh, data)
+            })
+            .collect();
+
+        Self {
+            format: SurfaceFormat::Color,
+            levels,
+            ..self
+        }
+    }
+
+    /// Decompresses the image if the device doesn't support the format and the `s3tc-decoder`
+    /// feature is enabled
+    pub fn for_device(self, device: &Device) -> Result<Self, DdsError> {
+        if self.is_supported_by(device) {
+            return Ok(self);
+        }
+
+        #[cfg(feature = "s3tc-decoder")]
+        {
+            log::info!(
+                "decompressing {:?} texture ({}x{}) on the CPU",
+                self.format,
+                self.w,
+                self.h
+            );
+            Ok(self.decompress())
+        }
+
+        #[cfg(not(feature = "s3tc-decoder"))]
+        Err(DdsError::NotSupported(self.format))
+    }
+
+    /// Creates a texture with all the mip levels
+    pub fn to_texture(&self, device: &Device) -> Result<res::Texture2d, DdsError> {
+        let n_levels = self.levels.len() as u32;
+        let mut texture = res::Texture2d::new(device, self.format, self.w, self.h, n_levels);
+        for (level, data) in self.levels.iter().enumerate() {
+            texture.set_level_data(level as u32, data)?;
+        }
+        Ok(texture)
+    }
+}
+
+/// Parses a DDS file and creates a texture, decompressing it if the device doesn't support the
+/// format (see the module documentation)
+pub fn load(device: &Device, bytes: &[u8]) -> Result<res::Texture2d, DdsError> {
+    DdsImage::parse(bytes)?
+        .for_device(device)?
+        .to_texture(device)
+}
+
+// --------------------------------------------------------------------------------
+// S3TC decoder
+
+/// Decodes DXT1/DXT3/DXT5 data of a `w` x `h` image into RGBA8 pixels
+#[cfg(feature = "s3tc-decoder")]
+pub fn decode_s3tc(format: SurfaceFormat, w: u32, h: u32, data: &[u8]) -> Vec<u8> {
+    let (w, h) = (w as usize, h as usize);
+    let block_size = format.size();
+    let blocks_per_row = w.div_ceil(4);
+
+    let mut pixels = vec![0; w * h * 4];
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        let (bx, by) = (i % blocks_per_row * 4, i / blocks_per_row * 4);
+        let texels = match format {
+            SurfaceFormat::Dxt1 => self::decode_color_block(block, true),
+            SurfaceFormat::Dxt3 => {
+                let mut texels = self::decode_color_block(&block[8..], false);
+                for (j, t) in texels.iter_mut().enumerate() {
+                    let a = (block[j / 2] >> (j % 2 * 4)) & 0xf;
+                    t[3] = a * 17;
+                }
+                texels
+            }
+            SurfaceFormat::Dxt5 => {
+                let mut texels = self::decode_color_block(&block[8..], false);
+                let alphas = self::decode_alpha_block(&block[0..8]);
+                for (t, a) in texels.iter_mut().zip(alphas.iter()) {
+                    t[3] = *a;
+                }
+                texels
+            }
+            _ => panic!("not a block-compressed format: {:?}", format),
+        };
+
+        // partial blocks at the right/bottom edges
+        for (j, texel) in texels.iter().enumerate() {
+            let (x, y) = (bx + j % 4, by + j / 4);
+            if x < w && y < h {
+                let p = (y * w + x) * 4;
+                pixels[p..p + 4].copy_from_slice(texel);
+            }
+        }
+    }
+
+    pixels
+}
+
+#[cfg(feature = "s3tc-decoder")]
+fn rgb565(c: u16) -> [u8; 4] {
+    let (r, g, b) = ((c >> 11) & 0x1f, (c >> 5) & 0x3f, c & 0x1f);
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+        255,
+    ]
+}
+
+/// 16 texels of the 8-byte color block, in row-major order
+///
+/// * `dxt1`: If the 3-color mode with transparent black is allowed
+#[cfg(feature = "s3tc-decoder")]
+fn decode_color_block(block: &[u8], dxt1: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (p0, p1) = (self::rgb565(c0), self::rgb565(c1));
+
+    let mix = |a: u8, b: u8, wa: u16, wb: u16| ((a as u16 * wa + b as u16 * wb) / (wa + wb)) as u8;
+    let mut palette = [p0, p1, [0; 4], [0; 4]];
+    for i in 0..3 {
+        if c0 > c1 || !dxt1 {
+            palette[2][i] = mix(p0[i], p1[i], 2, 1);
+            palette[3][i] = mix(p0[i], p1[i], 1, 2);
+        } else {
+            palette[2][i] = mix(p0[i], p1[i], 1, 1);
+        }
+    }
+    palette[2][3] = 255;
+    palette[3][3] = if c0 > c1 || !dxt1 { 255 } else { 0 };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut texels = [[0; 4]; 16];
+    for (i, t) in texels.iter_mut().enumerate() {
+        *t = palette[(indices >> (i * 2) & 0b11) as usize];
+    }
+    texels
+}
+
+/// 16 alpha values of the 8-byte DXT5 alpha block, in row-major order
+#[cfg(feature = "s3tc-decoder")]
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u16, block[1] as u16);
+    let mut palette = [a0 as u8, a1 as u8, 0, 0, 0, 0, 0, 255];
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i) as u16 * a0 + i as u16 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i) as u16 * a0 + i as u16 * a1) / 5) as u8;
+        }
+    }
+
+    let mut bits = [0; 8];
+    bits[0..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+
+    let mut alphas = [0; 16];
+    for (i, a) in alphas.iter_mut().enumerate() {
+        *a = palette[(indices >> (i * 3) & 0b111) as usize];
+    }
+    alphas
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// DDS header of a `w` x `h` image with the pixel format fields
+    fn header(w: u32, h: u32, pf_flags: u32, four_cc: &[u8; 4], bit_count: u32) -> Vec<u8> {
+        let mut bytes = vec![0; DATA_OFFSET];
+        bytes[0..4].copy_from_slice(MAGIC);
+        let mut put = |offset: usize, v: u32| {
+            bytes[offset..offset + 4].copy_from_slice(&v.to_le_bytes());
+        };
+        put(4, 124);
+        put(12, h);
+        put(16, w);
+        put(80, pf_flags);
+        put(88, bit_count);
+        put(92, 0x00ff_0000);
+        bytes[84..88].copy_from_slice(four_cc);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_dds() {
+        let mut bytes = header(1, 1, DDPF_RGB, &[0; 4], 32);
+        // B, G, R, A
+        bytes.extend_from_slice(&[1, 2, 3, 4]);
+        let img = DdsImage::parse(&bytes).unwrap();
+        assert_eq!(img.format, SurfaceFormat::Color);
+        assert_eq!(img.levels, vec![vec![3, 2, 1, 4]]);
+
+        let mut bytes = header(6, 6, DDPF_FOURCC, b"DXT1", 0);
+        bytes.extend_from_slice(&[0; 8 * 3]);
+        assert_eq!(
+            DdsImage::parse(&bytes),
+            Err(DdsError::UnexpectedEof {
+                expected: DATA_OFFSET + 8 * 4,
+                actual: DATA_OFFSET + 8 * 3
+            })
+        );
+
+        assert_eq!(DdsImage::parse(b"PNG"), Err(DdsError::InvalidHeader));
+    }
+
+    #[test]
+    #[cfg(feature = "s3tc-decoder")]
+    fn test_decode_s3tc() {
+        // red and blue endpoints, texel 0 = c0, texel 1 = c1, texel 2 = 2/3 c0 + 1/3 c1
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0b10_01_00, 0, 0, 0];
+        let px = decode_s3tc(SurfaceFormat::Dxt1, 3, 1, &block);
+        assert_eq!(px, [255, 0, 0, 255, 0, 0, 255, 255, 170, 0, 85, 255]);
+
+        // DXT1 3-color mode: index 3 is transparent black
+        let block = [0x1f, 0x00, 0x00, 0xf8, 0b11, 0, 0, 0];
+        assert_eq!(decode_s3tc(SurfaceFormat::Dxt1, 1, 1, &block), [0, 0, 0, 0]);
+
+        // DXT5 alpha: a0 = 255, a1 = 0, texel 0 uses index 1
+        let mut block = [0; 16];
+        block[0..3].copy_from_slice(&[255, 0, 0b001]);
+        assert_eq!(
+            decode_s3tc(SurfaceFormat::Dxt5, 2, 1, &block)[3..8],
+            [0, 0, 0, 0, 255]
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! High dynamic range images for HDR skyboxes and lightmaps
+//!
+//! `stb_image` (through `FNA3D_Image`) only gives us 8 bits per channel, so this module decodes
+//! Radiance `.hdr` files in pure Rust, and 16-bit PNG files with the `png16` feature. Decoded
+//! images are RGBA `f32` and can be converted to the data of the float surface formats:
+//!
+//! | Surface format | Conversion                  | Bytes per pixel |
+//! |----------------|-----------------------------|-----------------|
+//! | `Rgba64`       | [`HdrImage::to_rgba64`]     | 8 (clamped to `[0, 1]`) |
+//! | `HalfVector4`  | [`HdrImage::to_half_vector4`] | 8             |
+//! | `Vector4`      | [`HdrImage::to_vector4`]    | 16              |
+//!
+//! OpenEXR is not supported; convert EXR files to `.hdr` in the asset pipeline.
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+//! use fna3d::{img::hdr, SurfaceFormat};
+//!
+//! let img = hdr::decode_radiance(bytes)?;
+//! let sky = img.to_texture(device, SurfaceFormat::HalfVector4)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::{
+    fna3d::{fna3d_device::Device, fna3d_enums::SurfaceFormat},
+    res,
+};
+
+/// Error on decoding HDR images
+#[derive(Debug, Clone, PartialEq)]
+pub enum HdrError {
+    /// Not a Radiance file, or the header is broken
+    InvalidHeader,
+    /// The data ended before all the pixels are decoded
+    UnexpectedEof,
+    /// Broken run-length encoding
+    InvalidScanline { y: u32 },
+    /// Error from the PNG decoder
+    Png(String),
+}
+
+impl fmt::Display for HdrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HdrError::InvalidHeader => write!(f, "invalid Radiance HDR header"),
+            HdrError::UnexpectedEof => write!(f, "HDR data is truncated"),
+            HdrError::InvalidScanline { y } => write!(f, "broken HDR scanline at y = {}", y),
+            HdrError::Png(err) => write!(f, "failed to decode PNG: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HdrError {}
+
+/// Decoded HDR image
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdrImage {
+    pub w: u32,
+    pub h: u32,
+    /// Linear RGBA `f32` pixels (4 floats per pixel)
+    pub pixels: Vec<f32>,
+}
+
+impl HdrImage {
+    /// `SurfaceFormat::Vector4` data
+    pub fn to_vector4(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|x| x.to_le_bytes()).collect()
+    }
+
+    /// `SurfaceFormat::HalfVector4` data
+    pub fn to_half_vector4(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|x| self::f32_to_f16(*x).to_le_bytes())
+            .collect()
+    }
+
+    /// `SurfaceFormat::Rgba64` data. Values are clamped to `[0, 1]`
+    pub fn to_rgba64(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|x| ((x.clamp(0.0, 1.0) * 65535.0).round() as u16).to_le_bytes())
+            .collect()
+    }
+
+    /// Creates a texture of `Rgba64`, `HalfVector4` or `Vector4` format
+    ///
+    /// # Panics
+    ///
+    /// Panics if the format is not one of them.
+    pub fn to_texture(
+        &self,
+        device: &Device,
+        format: SurfaceFormat,
+    ) -> res::Result<res::Texture2d> {
+        let data = match format {
+            SurfaceFormat::Rgba64 => self.to_rgba64(),
+            SurfaceFormat::HalfVector4 => self.to_half_vector4(),
+            SurfaceFormat::Vector4 => self.to_vector4(),
+            _ => panic!("{:?} is not an RGBA float format", format),
+        };
+        let mut texture = res::Texture2d::new(device, format, self.w, self.h, 1);
+        texture.set_data(&data)?;
+        Ok(texture)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Half floats
+
+/// Converts `f32` to IEEE 754 half float bits, rounding to the nearest even
+///
+/// Values too large for half floats become infinity.
+pub fn f32_to_f16(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let man = bits & 0x7f_ffff;
+
+    if exp == 0xff {
+        // infinity or NaN (keep it NaN)
+        return sign | 0x7c00 | if man != 0 { 0x200 } else { 0 };
+    }
+
+    let e = exp - 127 + 15;
+    if e >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    let (half, shift) = if e <= 0 {
+        // subnormal half (or zero)
+        if e < -10 {
+            return sign;
+        }
+        let shift = (14 - e) as u32;
+        ((man | 0x80_0000) >> shift, shift)
+    } else {
+        (((e as u32) << 10) | (man >> 13), 13)
+    };
+
+    let rem = (man | 0x80_0000) & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round_up = rem > halfway || (rem == halfway && half & 1 == 1);
+    // a carry into the exponent is the correct result
+    sign | (half + round_up as u32) as u16
+}
+
+/// Converts IEEE 754 half float bits to `f32`
+pub fn f16_to_f32(h: u16) -> f32 {
+    let sign = ((h & 0x8000) as u32) << 16;
+    let exp = ((h >> 10) & 0x1f) as u32;
+    let man = (h & 0x3ff) as u32;
+
+    match (exp, man) {
+        (0, 0) => f32::from_bits(sign),
+        (0, _) => {
+            let x = man as f32 / (1 << 24) as f32;
+            if sign != 0 {
+                -x
+            } else {
+                x
+            }
+        }
+        (0x1f, _) => f32::from_bits(sign | 0x7f80_0000 | (man << 13)),
+        _ => f32::from_bits(sign | ((exp + 112) << 23) | (man << 13)),
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Radiance
+
+/// Decodes a Radiance `.hdr` (RGBE) file
+pub fn decode_radiance(bytes: &[u8]) -> Result<HdrImage, HdrError> {
+    if !bytes.starts_with(b"#?") {
+        return Err(HdrError::InvalidHeader);
+    }
+
+    // header lines until an empty line, then the resolution line
+    let mut pos = 0;
+    let mut line = || -> Result<&[u8], HdrError> {
+        let len = bytes[pos..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .ok_or(HdrError::InvalidHeader)?;
+        let line = &bytes[pos..pos + len];
+        pos += len + 1;
+        Ok(line)
+    };
+
+    loop {
+        let l = line()?;
+        if l.is_empty() {
+            break;
+        }
+        if l.starts_with(b"FORMAT=") && l != b"FORMAT=32-bit_rle_rgbe" {
+            return Err(HdrError::InvalidHeader);
+        }
+    }
+
+    // only the standard orientation
+    let res = String::from_utf8_lossy(line()?).into_owned();
+    let (w, h) = match res.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["-Y", h, "+X", w] => (
+            w.parse::<u32>().map_err(|_| HdrError::InvalidHeader)?,
+            h.parse::<u32>().map_err(|_| HdrError::InvalidHeader)?,
+        ),
+        _ => return Err(HdrError::InvalidHeader),
+    };
+    if w == 0 || h == 0 {
+        return Err(HdrError::InvalidHeader);
+    }
+
+    let mut data = &bytes[pos..];
+    let mut scanline = vec![[0u8; 4]; w as usize];
+    let mut pixels = Vec::with_capacity(w as usize * h as usize * 4);
+    for y in 0..h {
+        data = self::read_scanline(data, &mut scanline, y)?;
+        for rgbe in scanline.iter() {
+            let [r, g, b] = self::rgbe_to_f32(*rgbe);
+            pixels.extend_from_slice(&[r, g, b, 1.0]);
+        }
+    }
+
+    Ok(HdrImage { w, h, pixels })
+}
+
+fn rgbe_to_f32([r, g, b, e]: [u8; 4]) -> [f32; 3] {
+    if e == 0 {
+        return [0.0; 3];
+    }
+    let f = 2.0f32.powi(e as i32 - (128 + 8));
+    [r as f32 * f, g as f32 * f, b as f32 * f]
+}
+
+/// Reads a flat or a new-style run-length encoded scanline. Returns the rest of the data
+fn read_scanline<'a>(data: &'a [u8], out: &mut [[u8; 4]], y: u32) -> Result<&'a [u8], HdrError> {
+    let w = out.len();
+    let is_rle = (8..0x8000).contains(&w)
+        && data.len() >= 4
+        && data[0] == 2
+        && data[1] == 2
+        && data[2] & 0x80 == 0;
+
+    if !is_rle {
+        let len = w * 4;
+        if data.len() < len {
+            return Err(HdrError::UnexpectedEof);
+        }
+        for (px, rgbe) in out.iter_mut().zip(data.chunks_exact(4)) {
+            px.copy_from_slice(rgbe);
+        }
+        return Ok(&data[len..]);
+    }
+
+    if ((data[2] as usize) << 8 | data[3] as usize) != w {
+        return Err(HdrError::InvalidScanline { y });
+    }
+
+    // each channel is encoded separately
+    let mut pos = 4;
+    let mut byte = || -> Result<u8, HdrError> {
+        let b = *data.get(pos).ok_or(HdrError::UnexpectedEof)?;
+        pos += 1;
+        Ok(b)
+    };
+    for c in 0..4 {
+        let mut x = 0;
+        while x < w {
+            let count = byte()? as usize;
+            let (n, run) = if count > 128 {
+                (count - 128, true)
+            } else {
+                (count, false)
+            };
+            if n == 0 || x + n > w {
+                return Err(HdrError::InvalidScanline { y });
+            }
+            if run {
+                let v = byte()?;
+                for px in out[x..x + n].iter_mut() {
+                    px[c] = v;
+                }
+            } else {
+                for px in out[x..x + n].iter_mut() {
+                    px[c] = byte()?;
+                }
+            }
+            x += n;
+        }
+    }
+
+    Ok(&data[pos..])
+}
+
+// --------------------------------------------------------------------------------
+// 16-bit PNG
+
+/// Decodes an 8-bit or 16-bit PNG file into linear RGBA `f32` pixels (`value / max`)
+///
+/// Gray and RGB images are expanded to RGBA. No gamma conversion is done.
+#[cfg(feature = "png16")]
+pub fn decode_png16(bytes: &[u8]) -> Result<HdrImage, HdrError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| HdrError::Png(e.to_string()))?;
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| HdrError::Png(e.to_string()))?;
+    let buf = &buf[..info.buffer_size()];
+
+    let samples: Vec<f32> = match info.bit_depth {
+        png::BitDepth::Sixteen => buf
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as f32 / 65535.0)
+            .collect(),
+        _ => buf.iter().map(|b| *b as f32 / 255.0).collect(),
+    };
+
+    let (w, h) = (info.width, info.height);
+    let n_pixels = w as usize * h as usize;
+    let n_channels = samples.len() / n_pixels;
+
+    let mut pixels = Vec::with_capacity(n_pixels * 4);
+    for px in samples.chunks_exact(n_channels) {
+        let rgba = match *px {
+            [l] => [l, l, l, 1.0],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, 1.0],
+            [r, g, b, a] => [r, g, b, a],
+            _ => return Err(HdrError::Png("unexpected number of channels".to_string())),
+        };
+        pixels.extend_from_slice(&rgba);
+    }
+
+    Ok(HdrImage { w, h, pixels })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_half_float() {
+        for x in [0.0, 1.0, -2.5, 0.333_333_34, 65504.0, 6.1e-5, 1e-7].iter() {
+            let h = f32_to_f16(*x);
+            assert!(
+                (f16_to_f32(h) - x).abs() <= x.abs() / 1024.0 + 6e-8,
+                "{}",
+                x
+            );
+        }
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        // 1 + 2^-11 is halfway between 1 and the next half float; ties to even
+        assert_eq!(f32_to_f16(1.0 + 1.0 / 2048.0), 0x3c00);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+    }
+
+    #[test]
+    fn test_decode_radiance() {
+        let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 8\n".to_vec();
+        // RLE scanline: R = run of 8 x 128, G = 8 literals, B = 0, E = 129
+        bytes.extend_from_slice(&[2, 2, 0, 8, 128 + 8, 128, 8]);
+        bytes.extend_from_slice(&[0, 16, 32, 48, 64, 80, 96, 112]);
+        bytes.extend_from_slice(&[128 + 8, 0, 128 + 8, 129]);
+        // flat scanline
+        for _ in 0..8 {
+            bytes.extend_from_slice(&[64, 0, 0, 128]);
+        }
+
+        let img = decode_radiance(&bytes).unwrap();
+        assert_eq!((img.w, img.h), (8, 2));
+        // 128 * 2^(129 - 136) = 1.0
+        assert_eq!(&img.pixels[4..8], &[1.0, 0.125, 0.0, 1.0]);
+        assert_eq!(img.pixels[8 * 4], 0.25);
+
+        assert_eq!(
+            decode_radiance(&bytes[..bytes.len() - 1]),
+            Err(HdrError::UnexpectedEof)
+        );
+        assert_eq!(&img.to_half_vector4()[0..2], &0x3c00u16.to_le_bytes());
+        assert_eq!(&img.to_rgba64()[0..2], &[255, 255]);
+    }
+
+    #[test]
+    #[cfg(feature = "png16")]
+    fn test_decode_png16() {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Sixteen);
+            let mut writer = encoder.write_header().unwrap();
+            // big endian samples
+            let data = [0xff, 0xff, 0x80, 0x00, 0, 0, 0, 0, 0, 0, 0xff, 0xff];
+            writer.write_image_data(&data).unwrap();
+        }
+
+        let img = decode_png16(&bytes).unwrap();
+        assert_eq!((img.w, img.h), (2, 1));
+        assert_eq!(img.pixels[0], 1.0);
+        assert_eq!(img.pixels[1], 32768.0 / 65535.0);
+        assert_eq!(&img.pixels[4..8], &[0.0, 0.0, 1.0, 1.0]);
+    }
+}
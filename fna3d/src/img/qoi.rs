@@ -0,0 +1,277 @@
+//! [QOI] ("Quite OK Image") decoder and encoder
+//!
+//! QOI is lossless and much faster to encode and decode than PNG, which makes it a good
+//! intermediate format for asset pipelines. Unlike the other formats, it doesn't go through
+//! `stb_image`, and the decoded pixels are a `Vec` (no [`super::free`] needed).
+//!
+//! [QOI]: https://qoiformat.org
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, bytes: &[u8]) -> Result<(), fna3d::img::qoi::QoiError> {
+//! let mut img = fna3d::img::qoi::decode(bytes)?;
+//! fna3d::img::premultiply(&mut img.pixels);
+//!
+//! let mut texture = fna3d::res::Texture2d::new(
+//!     device,
+//!     fna3d::SurfaceFormat::Color,
+//!     img.w,
+//!     img.h,
+//!     1,
+//! );
+//! texture.set_data(&img.pixels).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"qoif";
+const HEADER_LEN: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+/// Limit of the reference implementation, which keeps the decoded size within 2 GB
+const MAX_PIXELS: u64 = 400_000_000;
+
+const OP_INDEX: u8 = 0b0000_0000;
+const OP_DIFF: u8 = 0b0100_0000;
+const OP_LUMA: u8 = 0b1000_0000;
+const OP_RUN: u8 = 0b1100_0000;
+const OP_RGB: u8 = 0xfe;
+const OP_RGBA: u8 = 0xff;
+const MASK_2: u8 = 0b1100_0000;
+
+/// Error on decoding or encoding QOI images
+#[derive(Debug, Clone, PartialEq)]
+pub enum QoiError {
+    /// Not a QOI file, or the size or the number of channels is invalid
+    InvalidHeader,
+    /// The data ended before all the pixels are decoded
+    UnexpectedEof,
+    /// The byte length of the pixels doesn't match the size
+    DataLength { expected: usize, actual: usize },
+}
+
+impl fmt::Display for QoiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QoiError::InvalidHeader => write!(f, "invalid QOI header"),
+            QoiError::UnexpectedEof => write!(f, "QOI data is truncated"),
+            QoiError::DataLength { expected, actual } => write!(
+                f,
+                "expected {} bytes of pixels but got {} bytes",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QoiError {}
+
+/// Decoded QOI image
+#[derive(Debug, Clone, PartialEq)]
+pub struct QoiImage {
+    pub w: u32,
+    pub h: u32,
+    /// Number of channels in the file (`3` or `4`). The pixels are always RGBA8
+    pub channels: u8,
+    /// `0`: sRGB with linear alpha, `1`: all channels linear. Informative only
+    pub colorspace: u8,
+    /// RGBA8 pixels with straight alpha
+    pub pixels: Vec<u8>,
+}
+
+fn hash([r, g, b, a]: [u8; 4]) -> usize {
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+/// Decodes QOI data into RGBA8 pixels
+pub fn decode(bytes: &[u8]) -> Result<QoiImage, QoiError> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+        return Err(QoiError::InvalidHeader);
+    }
+
+    let be_u32 =
+        |i: usize| u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+    let (w, h) = (be_u32(4), be_u32(8));
+    let (channels, colorspace) = (bytes[12], bytes[13]);
+    if w == 0 || h == 0 || w as u64 * h as u64 > MAX_PIXELS || !(3..=4).contains(&channels) {
+        return Err(QoiError::InvalidHeader);
+    }
+
+    let n_pixels = w as usize * h as usize;
+    let mut pixels = Vec::with_capacity(n_pixels * 4);
+    let mut index = [[0u8; 4]; 64];
+    let mut px = [0, 0, 0, 255];
+    let mut run = 0;
+
+    let mut data = bytes[HEADER_LEN..].iter().copied();
+    let mut next = || data.next().ok_or(QoiError::UnexpectedEof);
+
+    for _ in 0..n_pixels {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let b = next()?;
+            match b {
+                OP_RGB => {
+                    px[0] = next()?;
+                    px[1] = next()?;
+                    px[2] = next()?;
+                }
+                OP_RGBA => {
+                    px = [next()?, next()?, next()?, next()?];
+                }
+                _ => match b & MASK_2 {
+                    OP_INDEX => px = index[b as usize],
+                    OP_DIFF => {
+                        px[0] = px[0].wrapping_add((b >> 4) & 3).wrapping_sub(2);
+                        px[1] = px[1].wrapping_add((b >> 2) & 3).wrapping_sub(2);
+                        px[2] = px[2].wrapping_add(b & 3).wrapping_sub(2);
+                    }
+                    OP_LUMA => {
+                        let b2 = next()?;
+                        let dg = (b & 0x3f).wrapping_sub(32);
+                        px[0] = px[0].wrapping_add(dg).wrapping_add(b2 >> 4).wrapping_sub(8);
+                        px[1] = px[1].wrapping_add(dg);
+                        px[2] = px[2]
+                            .wrapping_add(dg)
+                            .wrapping_add(b2 & 0xf)
+                            .wrapping_sub(8);
+                    }
+                    // OP_RUN
+                    _ => run = b & 0x3f,
+                },
+            }
+            index[self::hash(px)] = px;
+        }
+        pixels.extend_from_slice(&px);
+    }
+
+    Ok(QoiImage {
+        w,
+        h,
+        channels,
+        colorspace,
+        pixels,
+    })
+}
+
+/// Encodes RGBA8 pixels into QOI data (4 channels, sRGB with linear alpha)
+pub fn encode(pixels: &[u8], w: u32, h: u32) -> Result<Vec<u8>, QoiError> {
+    if w == 0 || h == 0 || w as u64 * h as u64 > MAX_PIXELS {
+        return Err(QoiError::InvalidHeader);
+    }
+    let expected = w as usize * h as usize * 4;
+    if pixels.len() != expected {
+        return Err(QoiError::DataLength {
+            expected,
+            actual: pixels.len(),
+        });
+    }
+
+    let mut out = Vec::with_capacity(HEADER_LEN + expected / 2 + END_MARKER.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&w.to_be_bytes());
+    out.extend_from_slice(&h.to_be_bytes());
+    out.extend_from_slice(&[4, 0]);
+
+    let mut index = [[0u8; 4]; 64];
+    let mut prev = [0, 0, 0, 255];
+    let mut run = 0u8;
+
+    let n_pixels = pixels.len() / 4;
+    for (i, px) in pixels.chunks_exact(4).enumerate() {
+        let px = [px[0], px[1], px[2], px[3]];
+
+        if px == prev {
+            run += 1;
+            if run == 62 || i + 1 == n_pixels {
+                out.push(OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+
+        if run > 0 {
+            out.push(OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let i_hash = self::hash(px);
+        if index[i_hash] == px {
+            out.push(OP_INDEX | i_hash as u8);
+        } else {
+            index[i_hash] = px;
+
+            if px[3] == prev[3] {
+                let dr = px[0].wrapping_sub(prev[0]) as i8;
+                let dg = px[1].wrapping_sub(prev[1]) as i8;
+                let db = px[2].wrapping_sub(prev[2]) as i8;
+                let (dr_dg, db_dg) = (dr.wrapping_sub(dg), db.wrapping_sub(dg));
+
+                let small = |d: i8| (-2..=1).contains(&d);
+                let luma = |d: i8| (-8..=7).contains(&d);
+                if small(dr) && small(dg) && small(db) {
+                    out.push(
+                        OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8,
+                    );
+                } else if (-32..=31).contains(&dg) && luma(dr_dg) && luma(db_dg) {
+                    out.push(OP_LUMA | (dg + 32) as u8);
+                    out.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    out.extend_from_slice(&[OP_RGB, px[0], px[1], px[2]]);
+                }
+            } else {
+                out.extend_from_slice(&[OP_RGBA, px[0], px[1], px[2], px[3]]);
+            }
+        }
+
+        prev = px;
+    }
+
+    out.extend_from_slice(&END_MARKER);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_qoi_round_trip() {
+        let (w, h) = (5, 3);
+        let mut pixels = Vec::new();
+        for i in 0..w * h {
+            // runs, small diffs, luma diffs, full colors and alpha changes
+            let px = match i {
+                0..=2 => [10, 20, 30, 255],
+                3 => [11, 19, 30, 255],
+                4 => [31, 40, 50, 255],
+                5 => [200, 0, 100, 255],
+                6 => [200, 0, 100, 128],
+                7 => [10, 20, 30, 255],
+                _ => [i as u8 * 17, 255 - i as u8, 3, 255],
+            };
+            pixels.extend_from_slice(&px);
+        }
+
+        let bytes = encode(&pixels, w, h).unwrap();
+        assert_eq!(&bytes[0..4], b"qoif");
+        assert!(bytes.ends_with(&END_MARKER));
+
+        let img = decode(&bytes).unwrap();
+        assert_eq!((img.w, img.h, img.channels), (w, h, 4));
+        assert_eq!(img.pixels, pixels);
+
+        assert_eq!(
+            decode(&bytes[0..bytes.len() - 12]),
+            Err(QoiError::UnexpectedEof)
+        );
+        assert_eq!(
+            encode(&pixels[4..], w, h),
+            Err(QoiError::DataLength {
+                expected: 60,
+                actual: 56
+            })
+        );
+    }
+}
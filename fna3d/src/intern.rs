@@ -0,0 +1,267 @@
+//! Interning of blend and sampler states
+//!
+//! [`StateTable`] deduplicates state objects by value and hands out small ids. Store
+//! [`BlendId`]s and [`SamplerId`]s in your materials, and compare them instead of comparing the
+//! states field by field. [`BlendCache`] and [`SamplerCache`] skip redundant device calls that
+//! way:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, texture: *mut fna3d::Texture) {
+//! use fna3d::{intern::*, BlendState, SamplerState};
+//!
+//! let mut blends = BlendTable::new();
+//! let mut samplers = SamplerTable::new();
+//! let alpha = blends.intern(&BlendState::alpha_blend());
+//! let point = samplers.intern(&SamplerState::point_clamp());
+//!
+//! let mut blend_cache = BlendCache::default();
+//! let mut sampler_cache = SamplerCache::default();
+//!
+//! // for each draw call
+//! blend_cache.set(device, &blends, alpha);
+//! sampler_cache.verify(device, &samplers, 0, texture, point);
+//! # }
+//! ```
+//!
+//! The caches only know about the calls made through them. Call `invalidate` after setting the
+//! states directly on the device.
+//!
+//! [`Device`] interns the states given to it as well, and skips [`Device::set_blend_state`] calls
+//! with the current state by id.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::fna3d::{
+    fna3d_device::Device,
+    fna3d_structs::{BlendState, SamplerState, Texture},
+};
+
+/// State that can be interned into a [`StateTable`]
+pub trait InternKey {
+    /// Value identifying the state (floats are compared bitwise)
+    type Key: Eq + Hash;
+    fn intern_key(&self) -> Self::Key;
+}
+
+impl InternKey for BlendState {
+    type Key = [u32; 12];
+    fn intern_key(&self) -> Self::Key {
+        let raw = self.raw();
+        let c = raw.blendFactor;
+        [
+            raw.colorSourceBlend,
+            raw.colorDestinationBlend,
+            raw.colorBlendFunction,
+            raw.alphaSourceBlend,
+            raw.alphaDestinationBlend,
+            raw.alphaBlendFunction,
+            raw.colorWriteEnable,
+            raw.colorWriteEnable1,
+            raw.colorWriteEnable2,
+            raw.colorWriteEnable3,
+            u32::from_le_bytes([c.r, c.g, c.b, c.a]),
+            raw.multiSampleMask as u32,
+        ]
+    }
+}
+
+impl InternKey for SamplerState {
+    type Key = [u32; 7];
+    fn intern_key(&self) -> Self::Key {
+        let raw = self.raw();
+        [
+            raw.filter,
+            raw.addressU,
+            raw.addressV,
+            raw.addressW,
+            raw.mipMapLevelOfDetailBias.to_bits(),
+            raw.maxAnisotropy as u32,
+            raw.maxMipLevel as u32,
+        ]
+    }
+}
+
+/// Id handed out by a [`StateTable`]
+pub trait StateId: Copy {
+    fn from_index(index: usize) -> Self;
+    fn index(self) -> usize;
+}
+
+/// Id of a [`BlendState`] in a [`BlendTable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlendId(u32);
+
+/// Id of a [`SamplerState`] in a [`SamplerTable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SamplerId(u32);
+
+impl StateId for BlendId {
+    fn from_index(index: usize) -> Self {
+        Self(index as u32)
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl StateId for SamplerId {
+    fn from_index(index: usize) -> Self {
+        Self(index as u32)
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Deduplicated states. Ids are only meaningful for the table that created them
+#[derive(Debug)]
+pub struct StateTable<T: InternKey, Id> {
+    states: Vec<T>,
+    ids: HashMap<T::Key, Id>,
+}
+
+pub type BlendTable = StateTable<BlendState, BlendId>;
+pub type SamplerTable = StateTable<SamplerState, SamplerId>;
+
+impl<T: InternKey, Id> Default for StateTable<T, Id> {
+    fn default() -> Self {
+        Self {
+            states: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+}
+
+impl<T: InternKey + Clone, Id: StateId> StateTable<T, Id> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of the state, adding it to the table if it's new
+    pub fn intern(&mut self, state: &T) -> Id {
+        let states = &mut self.states;
+        *self.ids.entry(state.intern_key()).or_insert_with(|| {
+            states.push(state.clone());
+            Id::from_index(states.len() - 1)
+        })
+    }
+
+    /// Id of the state if it's interned
+    pub fn find(&self, state: &T) -> Option<Id> {
+        self.ids.get(&state.intern_key()).copied()
+    }
+
+    pub fn get(&self, id: Id) -> &T {
+        &self.states[id.index()]
+    }
+
+    /// Drops the states not referred to by `live` and updates the ids in `live`
+    ///
+    /// Other ids from this table become invalid.
+    pub fn compact<'a>(&mut self, live: impl IntoIterator<Item = &'a mut Id>)
+    where
+        Id: 'a,
+    {
+        let old = std::mem::take(self);
+        for id in live {
+            *id = self.intern(old.get(*id));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+/// Skips [`Device::set_blend_state`] calls with the current state
+#[derive(Debug, Default)]
+pub struct BlendCache {
+    current: Option<BlendId>,
+}
+
+impl BlendCache {
+    /// Sets the blend state unless it's the current one. Returns true if it's set
+    pub fn set(&mut self, device: &Device, table: &BlendTable, id: BlendId) -> bool {
+        if self.current == Some(id) {
+            return false;
+        }
+        device.set_blend_state(table.get(id));
+        self.current = Some(id);
+        true
+    }
+
+    pub fn invalidate(&mut self) {
+        self.current = None;
+    }
+}
+
+/// Skips [`Device::verify_sampler`] calls with the current texture and sampler state
+#[derive(Debug, Default)]
+pub struct SamplerCache {
+    slots: Vec<Option<(*mut Texture, SamplerId)>>,
+}
+
+impl SamplerCache {
+    /// Verifies the sampler slot unless nothing changed. Returns true if it's verified
+    pub fn verify(
+        &mut self,
+        device: &Device,
+        table: &SamplerTable,
+        index: u32,
+        texture: *mut Texture,
+        id: SamplerId,
+    ) -> bool {
+        let i = index as usize;
+        if self.slots.len() <= i {
+            self.slots.resize(i + 1, None);
+        }
+        if self.slots[i] == Some((texture, id)) {
+            return false;
+        }
+        device.verify_sampler(index, texture, table.get(id));
+        self.slots[i] = Some((texture, id));
+        true
+    }
+
+    pub fn invalidate(&mut self) {
+        self.slots.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_intern_states() {
+        let mut blends = BlendTable::new();
+        let a = blends.intern(&BlendState::alpha_blend());
+        let b = blends.intern(&BlendState::additive());
+        assert_ne!(a, b);
+        assert_eq!(blends.intern(&BlendState::alpha_blend()), a);
+        assert_eq!(blends.find(&BlendState::premultiplied()), Some(a));
+        assert_eq!(blends.len(), 2);
+
+        let mut samplers = SamplerTable::new();
+        let s = samplers.intern(&SamplerState::linear_wrap());
+        assert_eq!(
+            samplers.find(&SamplerState::linear_wrap().with_lod_bias(-0.5)),
+            None
+        );
+        assert_eq!(
+            samplers.get(s).filter(),
+            SamplerState::linear_wrap().filter()
+        );
+
+        let mut live = b;
+        blends.compact(std::iter::once(&mut live));
+        assert_eq!(blends.len(), 1);
+        assert_eq!(blends.find(&BlendState::additive()), Some(live));
+    }
+}
@@ -3,6 +3,7 @@
 //! See [fna3h](https://docs.rs/fna3h/latest/fna3h/) as an alternative.
 //!
 //! See [examples](https://github.com/toyboot4e/rust-fna3d/tree/master/fna3d/examples) to get started.
+//! `fna3d::easy` (`sdl2` feature) is a one-file quickstart.
 //!
 //! ## What is `fna3d`?
 //!
@@ -29,11 +30,41 @@
 //! [bindgen]: https://github.com/rust-lang/rust-bindgen
 //! [file]: https://github.com/toyboot4e/rust-fna3d/blob/master/docs/wrapping_c.md
 
+pub mod context;
+pub mod defaults;
+#[cfg(feature = "mojoshader")]
+pub mod depth_readback;
+#[cfg(all(feature = "sdl2", feature = "mojoshader"))]
+pub mod easy;
+pub mod error;
+pub mod events;
 mod fna3d;
 pub mod img;
+pub mod intern;
+pub mod logging;
+#[cfg(feature = "mojoshader")]
+pub mod mip_view;
+#[cfg(feature = "mojoshader")]
 pub mod mojo;
+pub mod queue;
+pub mod res;
+#[cfg(feature = "send")]
+pub mod send;
+pub mod settings;
+#[cfg(feature = "mojoshader")]
+pub mod spritebatch;
+pub mod staging;
+pub mod stats;
+pub mod vertex;
+#[cfg(feature = "sdl2")]
+pub mod win;
+#[cfg(feature = "winit")]
+pub mod winit_support;
 
-pub use crate::fna3d::{fna3d_device::*, fna3d_enums::*, fna3d_functions::*, fna3d_structs::*};
+pub use crate::error::{Error, Result};
+pub use crate::fna3d::{
+    fna3d_colors::*, fna3d_device::*, fna3d_enums::*, fna3d_functions::*, fna3d_structs::*,
+};
 pub use {bitflags, fna3d_sys as sys};
 
 pub mod utils {
@@ -48,28 +79,40 @@ pub mod utils {
 
     use crate::fna3d::fna3d_enums as enums;
 
-    /// Hooks default log functions to FNA3D, which outputs to `stdout`
+    /// Routes FNA3D messages to `log::info!`, `log::warn!` and `log::error!` (target `FNA3D`)
     ///
-    /// FIXME: is it really working?
+    /// Install a logger to see them (e.g. `env_logger` or
+    /// `fna3d_toolkit::console::ConsoleLogger`). The last messages are also kept by
+    /// [`crate::logging`].
+    pub fn hook_log_functions_log_crate() {
+        crate::logging::hook_log_functions();
+    }
+
+    /// Old name of [`hook_log_functions_log_crate`]
+    #[deprecated(note = "use `hook_log_functions_log_crate`")]
     pub fn hook_log_functions_default() {
-        unsafe {
-            // info, warn and error, respectively
-            sys::FNA3D_HookLogFunctions(Some(log), Some(log), Some(log));
-        }
+        self::hook_log_functions_log_crate();
+    }
 
-        unsafe extern "C" fn log(msg: *const ::std::os::raw::c_char) {
-            let slice = ::std::ffi::CStr::from_ptr(msg);
-            let string = slice.to_string_lossy().into_owned();
-            println!("{}", string);
-            // log::warn!("{}", string);
-        }
+    /// Routes FNA3D info, warning and error messages to the callbacks
+    ///
+    /// See [`crate::logging::hook_log_functions_with`].
+    pub fn hook_log_functions_with(
+        info: impl Fn(&str) + Send + Sync + 'static,
+        warn: impl Fn(&str) + Send + Sync + 'static,
+        error: impl Fn(&str) + Send + Sync + 'static,
+    ) {
+        crate::logging::hook_log_functions_with(info, warn, error);
     }
 
     /// The argument `handle: *mut c_void` is often `*SDL_Window`
+    ///
+    /// The backbuffer is sized in pixels (see [`crate::get_drawable_size`]). With the `sdl2`
+    /// feature, the size is taken from `crate::drawable_metrics` and the scale factor is logged.
     pub fn default_params_from_window_handle(
         window_handle: *mut c_void,
     ) -> sys::FNA3D_PresentationParameters {
-        let (w, h) = crate::get_drawable_size(window_handle);
+        let (w, h) = self::backbuffer_size(window_handle);
 
         sys::FNA3D_PresentationParameters {
             backBufferWidth: w as i32,
@@ -87,7 +130,44 @@ pub mod utils {
         }
     }
 
+    #[cfg(not(feature = "sdl2"))]
+    fn backbuffer_size(window_handle: *mut c_void) -> (u32, u32) {
+        crate::get_drawable_size(window_handle)
+    }
+
+    #[cfg(feature = "sdl2")]
+    fn backbuffer_size(window_handle: *mut c_void) -> (u32, u32) {
+        if window_handle.is_null() {
+            return crate::get_drawable_size(window_handle);
+        }
+        // SAFETY: the handle is given to FNA3D as `SDL_Window*` anyway
+        let (metrics, allowed) = unsafe {
+            (
+                crate::drawable_metrics(window_handle),
+                crate::is_high_dpi_allowed(window_handle),
+            )
+        };
+
+        log::debug!(
+            "backbuffer: {:?} pixels for {:?} points (scale factor {})",
+            metrics.pixel_size,
+            metrics.logical_size,
+            metrics.scale_factor
+        );
+        if metrics.scale_factor == 1.0 && !allowed {
+            log::debug!(
+                "window is not created with `SDL_WINDOW_ALLOW_HIGHDPI`; high-DPI displays upscale the backbuffer"
+            );
+        }
+
+        metrics.pixel_size
+    }
+
     /// Creates [`crate::mojo::EffectStateChanges`]
+    ///
+    /// To apply the first pass of an [`crate::res::EffectHandle`], use
+    /// [`crate::Device::apply_effect_simple`] instead.
+    #[cfg(feature = "mojoshader")]
     pub fn no_change_effect() -> crate::mojo::EffectStateChanges {
         crate::mojo::EffectStateChanges {
             render_state_change_count: 0,
@@ -0,0 +1,232 @@
+//! FNA3D log messages
+//!
+//! [`hook_log_functions`] routes FNA3D messages to the `log` crate (target `FNA3D`) and also keeps
+//! the last [`capacity`] messages in a ring buffer. [`recent`] returns them even if no logger is
+//! installed, so error dialogs and crash reports can include backend diagnostics:
+//!
+//! ```no_run
+//! fna3d::logging::hook_log_functions();
+//!
+//! // later, on failure
+//! for msg in fna3d::logging::recent() {
+//!     eprintln!("{}", msg);
+//! }
+//! ```
+//!
+//! [`hook_log_functions_with`] sends the messages to your callbacks instead of `log`.
+
+use {
+    fna3d_sys as sys,
+    log::Level,
+    std::{
+        collections::VecDeque,
+        fmt,
+        os::raw::c_char,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex, MutexGuard, RwLock, RwLockWriteGuard,
+        },
+    },
+};
+
+/// Default number of messages kept by the ring buffer
+pub const DEFAULT_CAPACITY: usize = 64;
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+static RECENT: Mutex<VecDeque<LogMessage>> = Mutex::new(VecDeque::new());
+static CALLBACKS: RwLock<Option<Arc<Callbacks>>> = RwLock::new(None);
+
+type Callback = Box<dyn Fn(&str) + Send + Sync>;
+
+/// User callbacks of [`hook_log_functions_with`]. FNA3D can log from any thread
+struct Callbacks {
+    info: Callback,
+    warn: Callback,
+    error: Callback,
+}
+
+/// Message logged by FNA3D
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMessage {
+    /// `Info`, `Warn` or `Error`
+    pub level: Level,
+    pub text: String,
+}
+
+impl fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.level, self.text)
+    }
+}
+
+/// Hooks log functions to FNA3D. Call it before creating a device to see device creation logs
+///
+/// Replaces the callbacks set by [`hook_log_functions_with`].
+pub fn hook_log_functions() {
+    *self::callbacks_mut() = None;
+    self::hook_raw();
+}
+
+/// Hooks log functions to FNA3D that call `info`, `warn` and `error` instead of the `log` crate
+///
+/// The callbacks are kept until the next call of this or [`hook_log_functions`]. They can be
+/// called from any thread and must not panic (a panic can't unwind into FNA3D and aborts). The
+/// messages are kept by the ring buffer as well.
+pub fn hook_log_functions_with(
+    info: impl Fn(&str) + Send + Sync + 'static,
+    warn: impl Fn(&str) + Send + Sync + 'static,
+    error: impl Fn(&str) + Send + Sync + 'static,
+) {
+    *self::callbacks_mut() = Some(Arc::new(Callbacks {
+        info: Box::new(info),
+        warn: Box::new(warn),
+        error: Box::new(error),
+    }));
+    self::hook_raw();
+}
+
+fn callbacks_mut() -> RwLockWriteGuard<'static, Option<Arc<Callbacks>>> {
+    CALLBACKS.write().unwrap_or_else(|e| e.into_inner())
+}
+
+fn hook_raw() {
+    unsafe {
+        // info, warn and error, respectively
+        sys::FNA3D_HookLogFunctions(Some(info), Some(warn), Some(error));
+    }
+
+    unsafe extern "C" fn info(msg: *const c_char) {
+        self::on_message(Level::Info, msg);
+    }
+
+    unsafe extern "C" fn warn(msg: *const c_char) {
+        self::on_message(Level::Warn, msg);
+    }
+
+    unsafe extern "C" fn error(msg: *const c_char) {
+        self::on_message(Level::Error, msg);
+    }
+}
+
+unsafe fn on_message(level: Level, msg: *const c_char) {
+    let text = ::std::ffi::CStr::from_ptr(msg)
+        .to_string_lossy()
+        .into_owned();
+    if !self::call_user(level, &text) {
+        log::log!(target: "FNA3D", level, "{}", text);
+    }
+    self::push(LogMessage { level, text });
+}
+
+/// Calls the user callback for the level. Returns false if there are no user callbacks
+///
+/// The lock is released before the call, so callbacks can hook log functions again.
+fn call_user(level: Level, text: &str) -> bool {
+    let callbacks = CALLBACKS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let callbacks = match callbacks {
+        Some(callbacks) => callbacks,
+        None => return false,
+    };
+
+    match level {
+        Level::Info => (callbacks.info)(text),
+        Level::Warn => (callbacks.warn)(text),
+        _ => (callbacks.error)(text),
+    }
+    true
+}
+
+/// The ring buffer. A panic while holding the lock doesn't lose the messages
+fn lock() -> MutexGuard<'static, VecDeque<LogMessage>> {
+    RECENT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn push(msg: LogMessage) {
+    let capacity = self::capacity();
+    if capacity == 0 {
+        return;
+    }
+    let mut recent = self::lock();
+    while recent.len() >= capacity {
+        recent.pop_front();
+    }
+    recent.push_back(msg);
+}
+
+/// Copy of the kept messages, oldest first
+pub fn recent() -> Vec<LogMessage> {
+    self::lock().iter().cloned().collect()
+}
+
+/// Removes the kept messages
+pub fn clear() {
+    self::lock().clear();
+}
+
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Sets the number of kept messages, dropping the oldest ones if there are more. `0` disables
+/// the ring buffer (messages are still sent to `log`)
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    let mut recent = self::lock();
+    while recent.len() > capacity {
+        recent.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_recent_messages() {
+        set_capacity(2);
+        clear();
+
+        for (i, level) in [Level::Info, Level::Warn, Level::Error].iter().enumerate() {
+            push(LogMessage {
+                level: *level,
+                text: format!("msg {}", i),
+            });
+        }
+
+        let msgs = recent();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].to_string(), "[WARN] msg 1");
+        assert_eq!(msgs[1].level, Level::Error);
+
+        set_capacity(1);
+        assert_eq!(recent()[0].text, "msg 2");
+        set_capacity(DEFAULT_CAPACITY);
+    }
+
+    #[test]
+    fn test_user_callbacks() {
+        let got = Arc::new(Mutex::new(Vec::new()));
+        let sink = |tag: &'static str| {
+            let got = Arc::clone(&got);
+            move |text: &str| got.lock().unwrap().push(format!("{}: {}", tag, text))
+        };
+
+        *callbacks_mut() = Some(Arc::new(Callbacks {
+            info: Box::new(sink("info")),
+            warn: Box::new(sink("warn")),
+            error: Box::new(sink("error")),
+        }));
+        assert!(call_user(Level::Warn, "a"));
+        assert!(call_user(Level::Error, "b"));
+        assert_eq!(*got.lock().unwrap(), vec!["warn: a", "error: b"]);
+
+        // a callback can replace the callbacks without deadlocking
+        *callbacks_mut() = Some(Arc::new(Callbacks {
+            info: Box::new(|_| *callbacks_mut() = None),
+            warn: Box::new(|_| {}),
+            error: Box::new(|_| {}),
+        }));
+        assert!(call_user(Level::Info, "c"));
+        assert!(!call_user(Level::Info, "d"));
+    }
+}
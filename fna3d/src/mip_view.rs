@@ -0,0 +1,134 @@
+//! Debug view of the mip chain of a texture
+//!
+//! [`draw_mip_levels`] draws every mip level of a texture side by side, at the same tile size.
+//! Use it to check generated mips or the chains of DDS files:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, batch: &mut fna3d::spritebatch::SpriteBatch, bytes: &[u8]) {
+//! use fna3d::mip_view::{self, MipView};
+//!
+//! let texture = fna3d::img::dds::load(device, bytes).unwrap();
+//! let view = MipView {
+//!     pos: [8.0, 8.0],
+//!     ..Default::default()
+//! };
+//! let tiles = mip_view::draw_mip_levels(batch, &texture, &view);
+//! batch.flush();
+//! // `tiles[level]` is where the level was drawn (e.g. for labels)
+//! # }
+//! ```
+//!
+//! # How it works
+//!
+//! Each level is drawn with its own sampler: [`SamplerState::clamp_to_mip`] makes the level the
+//! largest one sampled, and a negative LOD bias makes the GPU pick the largest one available. So
+//! the tile shows exactly that level, magnified with point filtering, whatever the tile size is.
+//!
+//! Some drivers clamp the LOD bias (the minimum guaranteed range is `±2`). There, tiles smaller
+//! than a quarter of the level `0` can show smaller levels than intended.
+
+use crate::{fna3d::fna3d_structs::SamplerState, res, spritebatch::SpriteBatch, Color};
+
+/// Biases the LOD toward the largest allowed level
+const LOD_BIAS: f32 = -16.0;
+
+/// Layout of [`draw_mip_levels`] in pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct MipView {
+    /// Top-left position of the first tile
+    pub pos: [f32; 2],
+    /// Size of the longer side of each tile. The aspect ratio of the texture is kept
+    pub tile: f32,
+    /// Gap between tiles
+    pub spacing: f32,
+    pub color: Color,
+}
+
+impl Default for MipView {
+    fn default() -> Self {
+        Self {
+            pos: [0.0, 0.0],
+            tile: 128.0,
+            spacing: 4.0,
+            color: Color::white(),
+        }
+    }
+}
+
+impl MipView {
+    /// `[x, y, w, h]` of each tile from left to right
+    pub fn tile_rects(&self, size: (u32, u32), level_count: u32) -> Vec<[f32; 4]> {
+        let (w, h) = (size.0.max(1) as f32, size.1.max(1) as f32);
+        let scale = self.tile / w.max(h);
+        let (tw, th) = (w * scale, h * scale);
+
+        (0..level_count)
+            .map(|level| {
+                let x = self.pos[0] + level as f32 * (tw + self.spacing);
+                [x, self.pos[1], tw, th]
+            })
+            .collect()
+    }
+}
+
+/// Sampler showing only the mip `level`
+pub fn level_sampler(level: u32) -> SamplerState {
+    let mut sampler = SamplerState::point_clamp().clamp_to_mip(level);
+    sampler.set_mip_map_level_of_detail_bias(LOD_BIAS);
+    sampler
+}
+
+/// Draws each mip level of the texture into a tile. See the module document
+///
+/// The batch is flushed per level and its sampler is restored afterwards. Returns the tiles
+/// ([`MipView::tile_rects`]).
+pub fn draw_mip_levels(
+    batch: &mut SpriteBatch,
+    texture: &res::Texture2d,
+    view: &MipView,
+) -> Vec<[f32; 4]> {
+    let tiles = view.tile_rects(texture.size(), texture.level_count());
+    let sampler = batch.sampler().clone();
+
+    for (level, &tile) in tiles.iter().enumerate() {
+        batch.set_sampler(self::level_sampler(level as u32));
+        batch.push(texture.raw(), tile, [0.0, 0.0, 1.0, 1.0], view.color);
+    }
+
+    batch.set_sampler(sampler);
+    tiles
+}
+
+/// Number of levels in a full mip chain down to `1x1`
+///
+/// Compare it with [`res::Texture2d::level_count`] to find truncated chains.
+pub fn full_level_count(size: (u32, u32)) -> u32 {
+    32 - size.0.max(size.1).max(1).leading_zeros()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mip_view() {
+        let view = MipView {
+            pos: [10.0, 20.0],
+            tile: 64.0,
+            spacing: 2.0,
+            ..Default::default()
+        };
+        let tiles = view.tile_rects((256, 128), 3);
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(tiles[0], [10.0, 20.0, 64.0, 32.0]);
+        assert_eq!(tiles[2], [142.0, 20.0, 64.0, 32.0]);
+
+        assert_eq!(full_level_count((256, 128)), 9);
+        assert_eq!(full_level_count((1, 1)), 1);
+        assert_eq!(full_level_count((0, 0)), 1);
+
+        let sampler = level_sampler(3);
+        assert_eq!(sampler.max_mip_level(), 3);
+        assert_eq!(sampler.mip_map_level_of_detail_bias(), LOD_BIAS);
+    }
+}
@@ -43,6 +43,8 @@
 //!
 //! [`SpriteEffect.fxb`] could be used for the `shader_path`.
 //!
+//! [`EffectData`] sets parameters by name without `unsafe`.
+//!
 //! [Orthographic projection]: https://en.wikipedia.org/wiki/Orthographic_projection
 //! [`SpriteEffect.fxb`]: https://github.com/FNA-XNA/FNA/blob/d3d5840d9f42d109413b9c489af12e5642b336b9/src/Graphics/Effect/StockEffects/FXB/SpriteEffect.fxb
 //!
@@ -59,6 +61,7 @@ pub type Effect = sys::mojo::MOJOSHADER_effect;
 pub type EffectTechnique = sys::mojo::MOJOSHADER_effectTechnique;
 pub type EffectStateChanges = sys::mojo::MOJOSHADER_effectStateChanges;
 pub type EffectParam = sys::mojo::MOJOSHADER_effectParam;
+pub type SamplerStateRegister = sys::mojo::MOJOSHADER_samplerStateRegister;
 
 // --------------------------------------------------------------------------------
 // Helpers
@@ -73,6 +76,8 @@ use ::{
     },
 };
 
+use crate::{SamplerState, TextureAddressMode, TextureFilter};
+
 pub type Result<T> = std::result::Result<T, LoadShaderError>;
 
 #[derive(Debug)]
@@ -90,6 +95,8 @@ impl fmt::Display for LoadShaderError {
     }
 }
 
+impl std::error::Error for LoadShaderError {}
+
 /// Helper for loading shader. Be sure to set projection matrix after loading!
 pub fn from_file(
     device: &crate::Device,
@@ -170,6 +177,120 @@ pub fn orthographic_off_center(
     ]
 }
 
+/// Clip space convention of the device, returned from
+/// [`Device::clip_space_info`](crate::Device::clip_space_info)
+///
+/// FNA3D and MojoShader emulate Direct3D 9 (XNA) conventions on every backend: MojoShader patches
+/// the generated GLSL/SPIR-V/MSL (depth remapping and Y flip with the `vpFlip` uniform) and FNA3D
+/// flips viewport, scissor rect and render targets as needed. So a projection matrix written for
+/// D3D conventions renders identically on OpenGL, Vulkan, Metal and D3D11.
+///
+/// Use the helpers when your math library produces matrices for other conventions (e.g. OpenGL's
+/// `[-1, 1]` depth range).
+///
+/// Matrices are in the same layout as [`orthographic_off_center`]: element `4 * i + j` is the
+/// coefficient of input component `j` for output component `i`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipSpaceInfo {
+    /// If the Y axis of normalized device coordinates goes up
+    pub ndc_y_up: bool,
+    /// Depth range of normalized device coordinates: `[0, 1]` for D3D, `[-1, 1]` for OpenGL
+    pub depth_range: [f32; 2],
+    /// If pixel centers are at integer coordinates (Direct3D 9), i.e. projections have to be
+    /// shifted by [`half_pixel_offset`] for pixel-perfect rendering
+    pub half_pixel_offset: bool,
+}
+
+impl ClipSpaceInfo {
+    /// D3D9 (XNA) convention, which FNA3D exposes on every backend
+    ///
+    /// Rasterization follows the backends (D3D11, OpenGL, Vulkan, Metal), where pixel centers are
+    /// at `+0.5`; unlike D3D9, no half-pixel offset is required.
+    pub const XNA: Self = Self {
+        ndc_y_up: true,
+        depth_range: [0.0, 1.0],
+        half_pixel_offset: false,
+    };
+
+    /// Converts a projection matrix made for `src` convention into this convention
+    pub fn adjust_projection(&self, src: &ClipSpaceInfo, mat: [f32; 16]) -> [f32; 16] {
+        let mut mat = mat;
+
+        if src.ndc_y_up != self.ndc_y_up {
+            mat = self::flip_y(mat);
+        }
+
+        if src.depth_range != self.depth_range {
+            // z' = a * z + b * w maps `src.depth_range` to `self.depth_range`
+            let [s0, s1] = src.depth_range;
+            let [d0, d1] = self.depth_range;
+            let a = (d1 - d0) / (s1 - s0);
+            let b = d0 - s0 * a;
+            for j in 0..4 {
+                mat[8 + j] = a * mat[8 + j] + b * mat[12 + j];
+            }
+        }
+
+        mat
+    }
+
+    /// Converts a projection matrix made for OpenGL conventions (Y up, depth in `[-1, 1]`)
+    pub fn from_gl_projection(&self, mat: [f32; 16]) -> [f32; 16] {
+        let gl = ClipSpaceInfo {
+            ndc_y_up: true,
+            depth_range: [-1.0, 1.0],
+            half_pixel_offset: false,
+        };
+        self.adjust_projection(&gl, mat)
+    }
+}
+
+/// Flips the Y axis of the output of a matrix
+pub fn flip_y(mut mat: [f32; 16]) -> [f32; 16] {
+    for j in 0..4 {
+        mat[4 + j] = -mat[4 + j];
+    }
+    mat
+}
+
+/// Translates the output of a matrix in normalized device coordinates
+pub fn translate_ndc(mut mat: [f32; 16], offset: [f32; 2]) -> [f32; 16] {
+    for j in 0..4 {
+        mat[j] += offset[0] * mat[12 + j];
+        mat[4 + j] += offset[1] * mat[12 + j];
+    }
+    mat
+}
+
+/// Half-pixel offset in normalized device coordinates for a `w` x `h` viewport
+///
+/// Apply it with [`translate_ndc`] on backends that put pixel centers at integer coordinates
+/// (see [`ClipSpaceInfo::half_pixel_offset`]), so that pixel `(x, y)` maps to its center.
+pub fn half_pixel_offset(w: u32, h: u32) -> [f32; 2] {
+    // half a pixel is `1 / size` in the [-1, 1] range; shift to the left and up
+    [-1.0 / w as f32, 1.0 / h as f32]
+}
+
+/// UV offset to the center of a texel of a `w` x `h` texture
+///
+/// Add it to UVs of texel corners (e.g. `x / w`) to sample exactly one texel, avoiding bleeding
+/// between adjacent sprites in an atlas under linear filtering.
+pub fn half_texel_offset(w: u32, h: u32) -> [f32; 2] {
+    [0.5 / w as f32, 0.5 / h as f32]
+}
+
+/// Orthographic projection of a `w` x `h` screen in pixels (origin at top-left) for the clip space
+///
+/// Applies [`half_pixel_offset`] if required.
+pub fn pixel_projection(w: u32, h: u32, clip: &ClipSpaceInfo) -> [f32; 16] {
+    let mat = self::orthographic_off_center(0.0, w as f32, h as f32, 0.0, 1.0, 0.0);
+    if clip.half_pixel_offset {
+        self::translate_ndc(mat, self::half_pixel_offset(w, h))
+    } else {
+        mat
+    }
+}
+
 /// Tries to find a shader parameter with name
 pub fn find_param(data: *mut Effect, name: &CStr) -> Option<*mut c_void> {
     unsafe {
@@ -202,3 +323,861 @@ pub unsafe fn set_param<T>(data: *mut Effect, name: &CStr, value: &T) -> bool {
 
     true
 }
+
+// --------------------------------------------------------------------------------
+// Typed effect access
+
+/// Error of [`EffectData`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError {
+    /// The effect doesn't have a parameter with the name
+    UnknownParameter(String),
+    /// The effect doesn't have a technique with the name
+    UnknownTechnique(String),
+    /// The value is larger than the parameter (in number of 32-bit values)
+    SizeMismatch {
+        name: String,
+        capacity: u32,
+        len: u32,
+    },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::UnknownParameter(name) => write!(f, "no effect parameter `{}`", name),
+            ParamError::UnknownTechnique(name) => write!(f, "no effect technique `{}`", name),
+            ParamError::SizeMismatch {
+                name,
+                capacity,
+                len,
+            } => write!(
+                f,
+                "effect parameter `{}` has {} values but {} were given",
+                name, capacity, len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// Finds a parameter by name (`&str` version of [`find_param`])
+///
+/// # Safety
+///
+/// `data` has to point to valid MojoShader data.
+unsafe fn param_by_name<'a>(data: *mut Effect, name: &str) -> Option<&'a mut EffectParam> {
+    if (*data).params.is_null() {
+        return None;
+    }
+    let params =
+        std::slice::from_raw_parts_mut((*data).params, (*data).param_count.max(0) as usize);
+    params.iter_mut().find(|p| {
+        !p.value.name.is_null() && CStr::from_ptr(p.value.name).to_bytes() == name.as_bytes()
+    })
+}
+
+/// Writes float values to a parameter, checking the size of the parameter
+///
+/// # Safety
+///
+/// `data` has to point to valid MojoShader data.
+unsafe fn write_floats(
+    data: *mut Effect,
+    name: &str,
+    values: &[f32],
+) -> std::result::Result<(), ParamError> {
+    let param = self::param_by_name(data, name)
+        .ok_or_else(|| ParamError::UnknownParameter(name.to_string()))?;
+
+    let value = &mut param.value;
+    if values.len() > value.value_count as usize || value.__bindgen_anon_1.valuesF.is_null() {
+        return Err(ParamError::SizeMismatch {
+            name: name.to_string(),
+            capacity: value.value_count,
+            len: values.len() as u32,
+        });
+    }
+
+    let dest = std::slice::from_raw_parts_mut(value.__bindgen_anon_1.valuesF, values.len());
+    dest.copy_from_slice(values);
+    Ok(())
+}
+
+/// [`crate::res::EffectHandle`] with parameter setters by name
+///
+/// No `unsafe` or `CString` is needed to set uniforms:
+///
+/// ```no_run
+/// # fn f(device: &fna3d::Device, texture: *mut fna3d::Texture) -> Result<(), Box<dyn std::error::Error>> {
+/// use fna3d::mojo::{self, EffectData};
+///
+/// let mut effect = EffectData::from_bytes(device, fna3d::defaults::SPRITE_EFFECT)?;
+/// effect.set_matrix("MatrixTransform", &mojo::pixel_projection(1280, 720, &device.clip_space_info()))?;
+/// effect.set_texture("TextureSampler", texture);
+///
+/// effect.apply_pass(device, 0);
+/// // draw calls
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Textures
+///
+/// Textures are bound to the register of the sampler with the name, as reported by the pass
+/// (sampler states of [`EffectStateChanges`]). Samplers without a `sampler_state { .. }` block
+/// are not reported; bind textures to them with [`crate::Device::verify_sampler`].
+pub struct EffectData {
+    handle: crate::res::EffectHandle,
+    /// `(sampler name, texture)`
+    textures: Vec<(String, *mut crate::Texture)>,
+    /// Boxed because FNA3D refers to it while the effect is applied
+    state_changes: Box<EffectStateChanges>,
+}
+
+impl fmt::Debug for EffectData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EffectData")
+            .field("handle", &self.handle)
+            .field("textures", &self.textures)
+            .finish()
+    }
+}
+
+impl From<crate::res::EffectHandle> for EffectData {
+    fn from(handle: crate::res::EffectHandle) -> Self {
+        Self {
+            handle,
+            textures: Vec::new(),
+            state_changes: Box::new(crate::utils::no_change_effect()),
+        }
+    }
+}
+
+impl EffectData {
+    /// Creates an effect from a `fx_2_0` binary and selects the first technique
+    pub fn from_bytes(device: &crate::Device, bytes: &[u8]) -> crate::res::Result<Self> {
+        crate::res::EffectHandle::from_bytes(device, bytes).map(Self::from)
+    }
+
+    pub fn handle(&self) -> &crate::res::EffectHandle {
+        &self.handle
+    }
+
+    pub fn into_handle(self) -> crate::res::EffectHandle {
+        self.handle
+    }
+
+    /// Techniques, passes and parameters of the effect
+    pub fn describe(&self) -> EffectDescription {
+        // the effect was created without errors
+        unsafe { self::describe(&*self.handle.data()).unwrap_or_default() }
+    }
+
+    pub fn has_param(&self, name: &str) -> bool {
+        unsafe { self::param_by_name(self.handle.data(), name).is_some() }
+    }
+
+    /// Selects the technique used by [`EffectData::apply_pass`]
+    pub fn set_technique(&self, name: &str) -> std::result::Result<(), ParamError> {
+        let tech = self
+            .handle
+            .technique_by_name(name)
+            .ok_or_else(|| ParamError::UnknownTechnique(name.to_string()))?;
+        // the technique was taken from the same effect
+        self.handle.set_technique(tech).unwrap();
+        Ok(())
+    }
+
+    /// Sets float values. The parameter can be larger than `values` (e.g. `float4` for a
+    /// `float3`)
+    pub fn set_floats(
+        &mut self,
+        name: &str,
+        values: &[f32],
+    ) -> std::result::Result<(), ParamError> {
+        unsafe { self::write_floats(self.handle.data(), name, values) }
+    }
+
+    pub fn set_f32(&mut self, name: &str, value: f32) -> std::result::Result<(), ParamError> {
+        self.set_floats(name, &[value])
+    }
+
+    pub fn set_vec2(&mut self, name: &str, value: [f32; 2]) -> std::result::Result<(), ParamError> {
+        self.set_floats(name, &value)
+    }
+
+    pub fn set_vec4(&mut self, name: &str, value: [f32; 4]) -> std::result::Result<(), ParamError> {
+        self.set_floats(name, &value)
+    }
+
+    /// Sets a column-major 4x4 matrix
+    pub fn set_matrix(
+        &mut self,
+        name: &str,
+        value: &[f32; 16],
+    ) -> std::result::Result<(), ParamError> {
+        self.set_floats(name, value)
+    }
+
+    /// Sets the texture of the sampler with the `name`. Pass null to unset it
+    pub fn set_texture(&mut self, name: &str, texture: *mut crate::Texture) {
+        self.textures.retain(|(n, _)| n != name);
+        if !texture.is_null() {
+            self.textures.push((name.to_string(), texture));
+        }
+    }
+
+    /// Applies the pass of the current technique, uploading the parameters, and binds the
+    /// textures
+    pub fn apply_pass(&mut self, device: &crate::Device, pass: u32) {
+        device.apply_effect(self.handle.raw(), pass, &self.state_changes);
+
+        let regs = |ptr: *const SamplerStateRegister, len: u32| {
+            if ptr.is_null() {
+                &[][..]
+            } else {
+                unsafe { std::slice::from_raw_parts(ptr, len as usize) }
+            }
+        };
+
+        let sc = &*self.state_changes;
+        let fragment = regs(sc.sampler_state_changes, sc.sampler_state_change_count);
+        for reg in fragment {
+            if let Some(texture) = self.texture_of(reg) {
+                let state = device
+                    .sampler(reg.sampler_register)
+                    .map(|(_, s)| s)
+                    .unwrap_or_default();
+                device.verify_sampler(reg.sampler_register, texture, &state);
+            }
+        }
+
+        let vertex = regs(
+            sc.vertex_sampler_state_changes,
+            sc.vertex_sampler_state_change_count,
+        );
+        for reg in vertex {
+            if let Some(texture) = self.texture_of(reg) {
+                let state = device
+                    .vertex_sampler(reg.sampler_register)
+                    .map(|(_, s)| s)
+                    .unwrap_or_default();
+                device.verify_vertex_sampler(reg.sampler_register, texture, &state);
+            }
+        }
+    }
+
+    fn texture_of(&self, reg: &SamplerStateRegister) -> Option<*mut crate::Texture> {
+        if reg.sampler_name.is_null() {
+            return None;
+        }
+        let name = unsafe { CStr::from_ptr(reg.sampler_name) }.to_bytes();
+        self.textures
+            .iter()
+            .find(|(n, _)| n.as_bytes() == name)
+            .map(|(_, t)| *t)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Sampler states
+
+/// Filter of a sampling operation (minification, magnification or mipmapping)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Point,
+    Linear,
+    Anisotropic,
+}
+
+/// `(min, mag, mip)` filters of a [`TextureFilter`]
+fn split_filter(filter: TextureFilter) -> [Filter; 3] {
+    use Filter::*;
+    match filter {
+        TextureFilter::Linear => [Linear, Linear, Linear],
+        TextureFilter::Point => [Point, Point, Point],
+        TextureFilter::Anisotropic => [Anisotropic, Anisotropic, Linear],
+        TextureFilter::LinearMipPoint => [Linear, Linear, Point],
+        TextureFilter::PointMipLinear => [Point, Point, Linear],
+        TextureFilter::MinLinearMagPointMipLinear => [Linear, Point, Linear],
+        TextureFilter::MinLinearMagPointMipPoint => [Linear, Point, Point],
+        TextureFilter::MinPointMagLinearMipLinear => [Point, Linear, Linear],
+        TextureFilter::MinPointMagLinearMipPoint => [Point, Linear, Point],
+    }
+}
+
+/// [`TextureFilter`] of `(min, mag, mip)` filters, the same way as FNA does
+fn join_filter([min, mag, mip]: [Filter; 3]) -> TextureFilter {
+    use Filter::*;
+    match (min, mag, mip) {
+        (Anisotropic, _, _) | (_, Anisotropic, _) => TextureFilter::Anisotropic,
+        (Point, Point, Point) => TextureFilter::Point,
+        (Point, Point, _) => TextureFilter::PointMipLinear,
+        (Linear, Linear, Point) => TextureFilter::LinearMipPoint,
+        (Linear, Linear, _) => TextureFilter::Linear,
+        (Linear, Point, Point) => TextureFilter::MinLinearMagPointMipPoint,
+        (Linear, Point, _) => TextureFilter::MinLinearMagPointMipLinear,
+        (Point, Linear, Point) => TextureFilter::MinPointMagLinearMipPoint,
+        (Point, Linear, _) => TextureFilter::MinPointMagLinearMipLinear,
+    }
+}
+
+fn filter_from_mojo(value: u32) -> Filter {
+    match value {
+        // `NONE` only makes sense for mipmapping
+        sys::mojo::MOJOSHADER_textureFilterType_MOJOSHADER_TEXTUREFILTER_NONE
+        | sys::mojo::MOJOSHADER_textureFilterType_MOJOSHADER_TEXTUREFILTER_POINT => Filter::Point,
+        sys::mojo::MOJOSHADER_textureFilterType_MOJOSHADER_TEXTUREFILTER_ANISOTROPIC => {
+            Filter::Anisotropic
+        }
+        _ => Filter::Linear,
+    }
+}
+
+fn address_from_mojo(value: u32) -> TextureAddressMode {
+    match value {
+        sys::mojo::MOJOSHADER_textureAddress_MOJOSHADER_TADDRESS_MIRROR
+        | sys::mojo::MOJOSHADER_textureAddress_MOJOSHADER_TADDRESS_MIRRORONCE => {
+            TextureAddressMode::Mirror
+        }
+        sys::mojo::MOJOSHADER_textureAddress_MOJOSHADER_TADDRESS_CLAMP
+        | sys::mojo::MOJOSHADER_textureAddress_MOJOSHADER_TADDRESS_BORDER => {
+            TextureAddressMode::Clamp
+        }
+        _ => TextureAddressMode::Wrap,
+    }
+}
+
+/// Applies a `sampler_state { .. }` block of an effect pass on top of `base`
+///
+/// Registers are found in [`EffectStateChanges`] filled by
+/// [`Device::apply_effect`](crate::Device::apply_effect), which calls this by itself.
+/// FNA3D has no border color, so `BORDER` is mapped to `Clamp` and `MIRRORONCE` to `Mirror`.
+///
+/// # Safety
+///
+/// `reg` has to point to valid MojoShader data, i.e., the effect is alive.
+pub unsafe fn effect_sampler_state(
+    base: &SamplerState,
+    reg: &SamplerStateRegister,
+) -> SamplerState {
+    let mut state = base.clone();
+    let mut filter = self::split_filter(base.filter());
+
+    let states = if reg.sampler_states.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(reg.sampler_states, reg.sampler_state_count as usize)
+    };
+
+    for s in states {
+        let value = &s.value;
+        if value.value_count == 0 || value.__bindgen_anon_1.values.is_null() {
+            continue;
+        }
+        let i = *value.__bindgen_anon_1.valuesI;
+
+        match s.type_ {
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_ADDRESSU => {
+                state.set_address_u(self::address_from_mojo(i as u32))
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_ADDRESSV => {
+                state.set_address_v(self::address_from_mojo(i as u32))
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_ADDRESSW => {
+                state.set_address_w(self::address_from_mojo(i as u32))
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_MINFILTER => {
+                filter[0] = self::filter_from_mojo(i as u32)
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_MAGFILTER => {
+                filter[1] = self::filter_from_mojo(i as u32)
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_MIPFILTER => {
+                filter[2] = self::filter_from_mojo(i as u32)
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_MIPMAPLODBIAS => {
+                state.set_mip_map_level_of_detail_bias(*value.__bindgen_anon_1.valuesF)
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_MAXMIPLEVEL => {
+                state.set_max_mip_level(i)
+            }
+            sys::mojo::MOJOSHADER_samplerStateType_MOJOSHADER_SAMP_MAXANISOTROPY => {
+                state.set_max_anisotropy(i)
+            }
+            // textures are bound by FNA3D
+            _ => {}
+        }
+    }
+
+    state.set_filter(self::join_filter(filter));
+    state
+}
+
+// --------------------------------------------------------------------------------
+// Compilation
+
+/// Environment variable to override the effect compiler of [`compile_source`]
+#[cfg(feature = "fxc")]
+pub const FXC_ENV: &str = "FNA3D_FXC";
+
+/// Compiles HLSL effect source into an `fx_2_0` binary (`fxc` feature)
+///
+/// Runs an external effect compiler: `fxc` in `PATH` or the command in the [`FXC_ENV`]
+/// environment variable, e.g. `FNA3D_FXC="wine /path/to/fxc.exe"`. It's called as
+/// `<command> /nologo /T fx_2_0 /Fo <output> <input>` with temporary files.
+///
+/// Failures are reported as [`LoadShaderError::Io`] if the compiler can't be run and
+/// [`LoadShaderError::EffectError`] with the compiler's output if the compilation fails.
+///
+/// This is for prototyping. Ship precompiled effects.
+#[cfg(feature = "fxc")]
+pub fn compile_source(src: &str) -> Result<Vec<u8>> {
+    let command = std::env::var(FXC_ENV).unwrap_or_else(|_| "fxc".to_string());
+    let mut words = command.split_whitespace();
+    let program = words.next().unwrap_or("fxc");
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(words);
+    self::compile_source_with(cmd, src)
+}
+
+/// [`compile_source`] with a custom compiler command. The compiler arguments are appended to it
+#[cfg(feature = "fxc")]
+pub fn compile_source_with(mut cmd: std::process::Command, src: &str) -> Result<Vec<u8>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let stem = format!(
+        "fna3d-fxc-{}-{}",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    let dir = std::env::temp_dir();
+    let input = dir.join(format!("{}.fx", stem));
+    let output = dir.join(format!("{}.fxb", stem));
+
+    let result = (|| {
+        fs::write(&input, src).map_err(LoadShaderError::Io)?;
+
+        let out = cmd
+            .args(["/nologo", "/T", "fx_2_0", "/Fo"])
+            .arg(&output)
+            .arg(&input)
+            .output()
+            .map_err(LoadShaderError::Io)?;
+
+        if !out.status.success() {
+            let mut msg = String::from_utf8_lossy(&out.stderr).into_owned();
+            msg.push_str(&String::from_utf8_lossy(&out.stdout));
+            return Err(LoadShaderError::EffectError(msg.trim().to_string()));
+        }
+
+        fs::read(&output).map_err(LoadShaderError::Io)
+    })();
+
+    fs::remove_file(&input).ok();
+    fs::remove_file(&output).ok();
+    result
+}
+
+// --------------------------------------------------------------------------------
+// Inspection
+
+/// Contents of an effect, returned from [`inspect`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EffectDescription {
+    pub techniques: Vec<TechniqueDescription>,
+    /// All the parameters, including samplers and textures
+    pub parameters: Vec<ParamDescription>,
+}
+
+impl EffectDescription {
+    /// Sampler parameters
+    pub fn samplers(&self) -> impl Iterator<Item = &ParamDescription> {
+        self.parameters.iter().filter(|p| p.is_sampler())
+    }
+
+    pub fn technique(&self, name: &str) -> Option<&TechniqueDescription> {
+        self.techniques.iter().find(|t| t.name == name)
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&ParamDescription> {
+        self.parameters.iter().find(|p| p.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TechniqueDescription {
+    pub name: String,
+    /// Pass names
+    pub passes: Vec<String>,
+}
+
+/// Effect parameter. Class and type are MojoShader's `MOJOSHADER_symbolClass` and
+/// `MOJOSHADER_symbolType`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamDescription {
+    pub name: String,
+    pub semantic: Option<String>,
+    pub class: sys::mojo::MOJOSHADER_symbolClass,
+    pub type_: sys::mojo::MOJOSHADER_symbolType,
+    pub rows: u32,
+    pub columns: u32,
+    /// Array length (`0` if it's not an array)
+    pub elements: u32,
+}
+
+impl ParamDescription {
+    pub fn is_sampler(&self) -> bool {
+        (sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_SAMPLER
+            ..=sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_SAMPLERCUBE)
+            .contains(&self.type_)
+    }
+
+    pub fn is_texture(&self) -> bool {
+        (sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_TEXTURE
+            ..=sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_TEXTURECUBE)
+            .contains(&self.type_)
+    }
+}
+
+/// Parses an effect (`fx_2_0` binary) without a [`crate::Device`]
+///
+/// Useful for validating assets in build scripts and tools. Shaders are parsed (into GLSL) but
+/// never uploaded to GPU.
+///
+/// ```no_run
+/// let bytes = std::fs::read("SpriteEffect.fxb").unwrap();
+/// let desc = fna3d::mojo::inspect(&bytes).unwrap();
+/// assert!(desc.parameter("MatrixTransform").is_some());
+/// ```
+pub fn inspect(bytes: &[u8]) -> Result<EffectDescription> {
+    let ctx = sys::mojo::MOJOSHADER_effectShaderContext {
+        compileShader: Some(self::inspect_ctx::compile_shader),
+        shaderAddRef: Some(self::inspect_ctx::add_ref),
+        deleteShader: Some(self::inspect_ctx::delete_shader),
+        getParseData: Some(self::inspect_ctx::get_parse_data),
+        bindShaders: Some(self::inspect_ctx::bind_shaders),
+        getBoundShaders: Some(self::inspect_ctx::get_bound_shaders),
+        mapUniformBufferMemory: Some(self::inspect_ctx::map_uniform_buffer_memory),
+        unmapUniformBufferMemory: Some(self::inspect_ctx::unmap_uniform_buffer_memory),
+        getError: Some(self::inspect_ctx::get_error),
+        m: None,
+        f: None,
+        malloc_data: std::ptr::null_mut(),
+    };
+
+    let effect = unsafe {
+        sys::mojo::MOJOSHADER_compileEffect(
+            bytes.as_ptr(),
+            bytes.len() as u32,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            0,
+            &ctx,
+        )
+    };
+
+    if effect.is_null() {
+        return Err(LoadShaderError::EffectError(
+            "out of memory while parsing effect".to_string(),
+        ));
+    }
+
+    let result = unsafe { self::describe(&*effect) };
+    unsafe { sys::mojo::MOJOSHADER_deleteEffect(effect) };
+    result
+}
+
+unsafe fn describe(effect: &Effect) -> Result<EffectDescription> {
+    unsafe fn string(ptr: *const std::os::raw::c_char) -> String {
+        if ptr.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        }
+    }
+
+    unsafe fn slice<'a, T>(ptr: *const T, len: impl Into<i64>) -> &'a [T] {
+        let len = len.into();
+        if ptr.is_null() || len <= 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(ptr, len as usize)
+        }
+    }
+
+    if effect.error_count > 0 {
+        let errs = slice(effect.errors, effect.error_count)
+            .iter()
+            .map(|e| string(e.error))
+            .collect::<Vec<_>>();
+        return Err(LoadShaderError::EffectError(errs.join("; ")));
+    }
+
+    let techniques = slice(effect.techniques, effect.technique_count)
+        .iter()
+        .map(|t| TechniqueDescription {
+            name: string(t.name),
+            passes: slice(t.passes, t.pass_count)
+                .iter()
+                .map(|p| string(p.name))
+                .collect(),
+        })
+        .collect();
+
+    let parameters = slice(effect.params, effect.param_count)
+        .iter()
+        .map(|p| {
+            let v = &p.value;
+            ParamDescription {
+                name: string(v.name),
+                semantic: if v.semantic.is_null() {
+                    None
+                } else {
+                    Some(string(v.semantic))
+                },
+                class: v.type_.parameter_class,
+                type_: v.type_.parameter_type,
+                rows: v.type_.rows,
+                columns: v.type_.columns,
+                elements: v.type_.elements,
+            }
+        })
+        .collect();
+
+    Ok(EffectDescription {
+        techniques,
+        parameters,
+    })
+}
+
+/// Shader context of [`inspect`]: shaders are MojoShader parse data and never bound
+mod inspect_ctx {
+    use std::{
+        cell::RefCell,
+        ffi::{CStr, CString},
+        os::raw::{c_char, c_int, c_uchar, c_uint, c_void},
+    };
+
+    use fna3d_sys::mojo as m;
+
+    thread_local! {
+        static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+    }
+
+    pub unsafe extern "C" fn compile_shader(
+        mainfn: *const c_char,
+        tokenbuf: *const c_uchar,
+        bufsize: c_uint,
+        swiz: *const m::MOJOSHADER_swizzle,
+        swizcount: c_uint,
+        smap: *const m::MOJOSHADER_samplerMap,
+        smapcount: c_uint,
+    ) -> *mut c_void {
+        // FNA3D builds MojoShader without the bytecode profile
+        let pd = m::MOJOSHADER_parse(
+            m::MOJOSHADER_PROFILE_GLSL.as_ptr() as *const c_char,
+            mainfn,
+            tokenbuf,
+            bufsize,
+            swiz,
+            swizcount,
+            smap,
+            smapcount,
+            None,
+            None,
+            std::ptr::null_mut(),
+        );
+
+        if pd.is_null() {
+            self::set_error("out of memory while parsing shader");
+            return std::ptr::null_mut();
+        }
+
+        if (*pd).error_count > 0 {
+            let msg = CStr::from_ptr((*(*pd).errors).error).to_string_lossy();
+            self::set_error(&msg);
+            m::MOJOSHADER_freeParseData(pd);
+            return std::ptr::null_mut();
+        }
+
+        pd as *mut c_void
+    }
+
+    fn set_error(msg: &str) {
+        let msg = CString::new(msg.replace('\0', "")).unwrap_or_default();
+        LAST_ERROR.with(|e| *e.borrow_mut() = msg);
+    }
+
+    // effects are never cloned
+    pub unsafe extern "C" fn add_ref(_shader: *mut c_void) {}
+
+    pub unsafe extern "C" fn delete_shader(shader: *mut c_void) {
+        m::MOJOSHADER_freeParseData(shader as *const m::MOJOSHADER_parseData);
+    }
+
+    pub unsafe extern "C" fn get_parse_data(shader: *mut c_void) -> *mut m::MOJOSHADER_parseData {
+        shader as *mut m::MOJOSHADER_parseData
+    }
+
+    pub unsafe extern "C" fn bind_shaders(_vshader: *mut c_void, _pshader: *mut c_void) {}
+
+    pub unsafe extern "C" fn get_bound_shaders(
+        vshader: *mut *mut c_void,
+        pshader: *mut *mut c_void,
+    ) {
+        *vshader = std::ptr::null_mut();
+        *pshader = std::ptr::null_mut();
+    }
+
+    pub unsafe extern "C" fn map_uniform_buffer_memory(
+        _vsf: *mut *mut f32,
+        _vsi: *mut *mut c_int,
+        _vsb: *mut *mut c_uchar,
+        _psf: *mut *mut f32,
+        _psi: *mut *mut c_int,
+        _psb: *mut *mut c_uchar,
+    ) {
+    }
+
+    pub unsafe extern "C" fn unmap_uniform_buffer_memory() {}
+
+    pub unsafe extern "C" fn get_error() -> *const c_char {
+        LAST_ERROR.with(|e| e.borrow().as_ptr())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Applies the matrix to a position vector
+    fn apply(mat: &[f32; 16], v: [f32; 4]) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                out[i] += mat[4 * i + j] * v[j];
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_gl_depth_range_to_xna() {
+        // identity: z in [-1, 1] is already in clip space
+        let mut mat = [0.0; 16];
+        for i in 0..4 {
+            mat[4 * i + i] = 1.0;
+        }
+
+        let mat = ClipSpaceInfo::XNA.from_gl_projection(mat);
+        assert_eq!(apply(&mat, [0.0, 0.0, -1.0, 1.0])[2], 0.0);
+        assert_eq!(apply(&mat, [0.0, 0.0, 1.0, 1.0])[2], 1.0);
+        assert_eq!(apply(&mat, [0.5, 0.5, 0.0, 1.0])[..2], [0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_half_pixel_offset() {
+        let clip = ClipSpaceInfo {
+            half_pixel_offset: true,
+            ..ClipSpaceInfo::XNA
+        };
+        let mat = pixel_projection(4, 2, &clip);
+
+        // the top-left pixel center (0.5, 0.5) goes to the top-left corner of the NDC
+        let out = apply(&mat, [0.5, 0.5, 0.0, 1.0]);
+        assert!((out[0] - -1.0).abs() < 1e-6);
+        assert!((out[1] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_param_description_kind() {
+        let param = |type_| ParamDescription {
+            name: "p".to_string(),
+            semantic: None,
+            class: sys::mojo::MOJOSHADER_symbolClass_MOJOSHADER_SYMCLASS_OBJECT,
+            type_,
+            rows: 1,
+            columns: 1,
+            elements: 0,
+        };
+
+        let sampler = param(sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_SAMPLER2D);
+        assert!(sampler.is_sampler() && !sampler.is_texture());
+        let texture = param(sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_TEXTURE2D);
+        assert!(texture.is_texture() && !texture.is_sampler());
+        let float = param(sys::mojo::MOJOSHADER_symbolType_MOJOSHADER_SYMTYPE_FLOAT);
+        assert!(!float.is_sampler() && !float.is_texture());
+
+        let desc = EffectDescription {
+            techniques: vec![],
+            parameters: vec![float, sampler],
+        };
+        assert_eq!(desc.samplers().count(), 1);
+    }
+
+    #[test]
+    fn test_write_floats() {
+        let mut storage = [0.0f32; 4];
+        let name = std::ffi::CString::new("Tint").unwrap();
+
+        let mut param: EffectParam = unsafe { std::mem::zeroed() };
+        param.value.name = name.as_ptr();
+        param.value.value_count = 4;
+        param.value.__bindgen_anon_1.valuesF = storage.as_mut_ptr();
+
+        let mut effect: Effect = unsafe { std::mem::zeroed() };
+        effect.param_count = 1;
+        effect.params = &mut param;
+
+        unsafe {
+            assert_eq!(write_floats(&mut effect, "Tint", &[1.0, 0.5]), Ok(()));
+            assert_eq!(
+                write_floats(&mut effect, "Tint", &[0.0; 16]),
+                Err(ParamError::SizeMismatch {
+                    name: "Tint".to_string(),
+                    capacity: 4,
+                    len: 16
+                })
+            );
+            assert_eq!(
+                write_floats(&mut effect, "Time", &[0.0]),
+                Err(ParamError::UnknownParameter("Time".to_string()))
+            );
+        }
+        assert_eq!(storage, [1.0, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_filter_round_trip() {
+        let filters = [
+            TextureFilter::Linear,
+            TextureFilter::Point,
+            TextureFilter::Anisotropic,
+            TextureFilter::LinearMipPoint,
+            TextureFilter::PointMipLinear,
+            TextureFilter::MinLinearMagPointMipLinear,
+            TextureFilter::MinLinearMagPointMipPoint,
+            TextureFilter::MinPointMagLinearMipLinear,
+            TextureFilter::MinPointMagLinearMipPoint,
+        ];
+        for f in filters.iter().copied() {
+            assert_eq!(join_filter(split_filter(f)), f);
+        }
+
+        // `Filter = POINT;` on a linear sampler
+        let mut parts = split_filter(TextureFilter::Linear);
+        parts[1] = filter_from_mojo(
+            sys::mojo::MOJOSHADER_textureFilterType_MOJOSHADER_TEXTUREFILTER_POINT,
+        );
+        assert_eq!(
+            join_filter(parts),
+            TextureFilter::MinLinearMagPointMipLinear
+        );
+    }
+}
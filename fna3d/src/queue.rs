@@ -0,0 +1,453 @@
+//! Deferred GPU resource creation for loading screens
+//!
+//! Creating hundreds of textures in one frame stalls the game for a second or more. Push the
+//! creation and uploads to a [`ResourceQueue`] instead and [`ResourceQueue::pump`] it with a time
+//! budget each frame, drawing the loading screen in between:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, images: Vec<(u32, u32, Vec<u8>)>) {
+//! use fna3d::{queue::ResourceQueue, SurfaceFormat};
+//!
+//! let mut queue = ResourceQueue::new(device);
+//! let textures = images
+//!     .into_iter()
+//!     .map(|(w, h, pixels)| queue.push_texture(SurfaceFormat::Color, w, h, pixels))
+//!     .collect::<Vec<_>>();
+//!
+//! while !queue.is_empty() {
+//!     queue.pump(4);
+//!     // draw the loading screen with `queue.progress()` and present
+//! }
+//!
+//! let textures = textures
+//!     .into_iter()
+//!     .map(|t| t.take().unwrap())
+//!     .collect::<Result<Vec<_>, _>>();
+//! # }
+//! ```
+//!
+//! Large textures can also be streamed: [`ResourceQueue::push_streamed_texture`] uploads the small
+//! mips right away and the larger ones through the queue. See [`StreamedTexture`].
+//!
+//! Jobs run on the device thread (the queue holds a [`Device`], which is not `Send`), so decode
+//! files on other threads and only push the GPU work here.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt,
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    fna3d::{
+        fna3d_device::Device,
+        fna3d_enums as enums,
+        fna3d_structs::{SamplerState, Texture},
+    },
+    res,
+};
+
+/// Default maximum number of bytes uploaded by one texture upload job
+pub const DEFAULT_UPLOAD_CHUNK: usize = 1024 * 1024;
+
+/// Result of a job pushed to a [`ResourceQueue`], filled when the job is run
+#[derive(Debug)]
+pub struct Pending<T> {
+    slot: Rc<RefCell<Option<T>>>,
+}
+
+impl<T> Clone for Pending<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: Rc::clone(&self.slot),
+        }
+    }
+}
+
+impl<T> Pending<T> {
+    fn new() -> Self {
+        Self {
+            slot: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    fn fill(&self, value: T) {
+        *self.slot.borrow_mut() = Some(value);
+    }
+
+    /// If the result is available (and not taken yet)
+    pub fn is_ready(&self) -> bool {
+        self.slot.borrow().is_some()
+    }
+
+    /// Takes the result. `None` if the job hasn't run yet or the result is already taken
+    pub fn take(&self) -> Option<T> {
+        self.slot.borrow_mut().take()
+    }
+}
+
+/// Texture whose larger mips are uploaded later by a [`ResourceQueue`]
+///
+/// Sample it with [`StreamedTexture::sampler`] so that only the uploaded mips are read. Dropping
+/// it cancels the remaining uploads.
+///
+/// ```no_run
+/// # fn f(queue: &mut fna3d::queue::ResourceQueue, mips: Vec<Vec<u8>>) -> fna3d::res::Result<()> {
+/// use fna3d::{SamplerState, SurfaceFormat};
+///
+/// // `mips[0]` is 4096x4096; upload the 4 smallest levels now
+/// let tex = queue.push_streamed_texture(SurfaceFormat::Color, 4096, 4096, mips, 4)?;
+///
+/// // each frame
+/// let sampler = tex.sampler(&SamplerState::linear_wrap());
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamedTexture {
+    texture: Rc<RefCell<res::Texture2d>>,
+    /// Most detailed uploaded level
+    resident: Rc<Cell<u32>>,
+}
+
+impl fmt::Debug for StreamedTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamedTexture")
+            .field("raw", &self.raw())
+            .field("level_count", &self.level_count())
+            .field("resident", &self.resident)
+            .finish()
+    }
+}
+
+impl StreamedTexture {
+    pub fn raw(&self) -> *mut Texture {
+        self.texture.borrow().raw()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.texture.borrow().size()
+    }
+
+    pub fn level_count(&self) -> u32 {
+        self.texture.borrow().level_count()
+    }
+
+    /// Index of the most detailed mip level uploaded so far
+    pub fn resident_level(&self) -> u32 {
+        self.resident.get()
+    }
+
+    /// If every mip level is uploaded
+    pub fn is_complete(&self) -> bool {
+        self.resident.get() == 0
+    }
+
+    /// `base` clamped to the uploaded mips (see [`SamplerState::clamp_to_mip`])
+    pub fn sampler(&self, base: &SamplerState) -> SamplerState {
+        base.clone().clamp_to_mip(self.resident.get())
+    }
+}
+
+type Job = Box<dyn FnOnce(&Device)>;
+
+/// Queue of GPU jobs run within a time budget per frame
+pub struct ResourceQueue {
+    device: Device,
+    jobs: VecDeque<Job>,
+    /// Number of jobs run so far (for progress bars)
+    n_done: usize,
+    upload_chunk: usize,
+}
+
+impl fmt::Debug for ResourceQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceQueue")
+            .field("jobs", &self.jobs.len())
+            .field("n_done", &self.n_done)
+            .field("upload_chunk", &self.upload_chunk)
+            .finish()
+    }
+}
+
+impl ResourceQueue {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            device: device.clone(),
+            jobs: VecDeque::new(),
+            n_done: 0,
+            upload_chunk: DEFAULT_UPLOAD_CHUNK,
+        }
+    }
+
+    /// Sets the maximum number of bytes uploaded by one job of [`ResourceQueue::push_texture`].
+    /// Large textures are uploaded in bands of rows over multiple jobs
+    pub fn with_upload_chunk(mut self, bytes: usize) -> Self {
+        self.upload_chunk = bytes.max(1);
+        self
+    }
+
+    /// Number of jobs waiting
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Ratio of the jobs run since the queue was last empty, in `[0.0, 1.0]`
+    pub fn progress(&self) -> f32 {
+        let total = self.n_done + self.jobs.len();
+        if total == 0 {
+            1.0
+        } else {
+            self.n_done as f32 / total as f32
+        }
+    }
+
+    /// Pushes an arbitrary job
+    pub fn push<T: 'static>(&mut self, f: impl FnOnce(&Device) -> T + 'static) -> Pending<T> {
+        let pending = Pending::new();
+        let result = pending.clone();
+        self.jobs
+            .push_back(Box::new(move |device| result.fill(f(device))));
+        pending
+    }
+
+    /// Pushes creation of a texture and the upload of `data` (the whole level `0`)
+    ///
+    /// The upload is split into bands of rows of at most [`ResourceQueue::with_upload_chunk`]
+    /// bytes. The texture is ready after the last band is uploaded. Zero-sized textures are
+    /// rejected with [`res::ResourceError::EmptyTexture`].
+    pub fn push_texture(
+        &mut self,
+        format: enums::SurfaceFormat,
+        w: u32,
+        h: u32,
+        data: Vec<u8>,
+    ) -> Pending<res::Result<res::Texture2d>> {
+        let pending = Pending::new();
+
+        // no band would be pushed to fill `pending`
+        if w == 0 || h == 0 {
+            pending.fill(Err(res::ResourceError::EmptyTexture { w, h }));
+            return pending;
+        }
+
+        let (row_len, n_rows) = res::region_layout(format, w, h);
+        if data.len() != row_len * n_rows {
+            pending.fill(Err(res::ResourceError::DataLength {
+                expected: row_len * n_rows,
+                actual: data.len(),
+            }));
+            return pending;
+        }
+
+        // shared between the jobs until the last one moves it to `pending`
+        let texture = Rc::new(RefCell::new(None));
+        let data = Rc::new(data);
+
+        let texture_ = Rc::clone(&texture);
+        self.jobs.push_back(Box::new(move |device| {
+            *texture_.borrow_mut() = Some(res::Texture2d::new(device, format, w, h, 1));
+        }));
+
+        let bands = self::bands(format, w, h, self.upload_chunk);
+        let n_bands = bands.len();
+        for (i, (y, band_h, range)) in bands.into_iter().enumerate() {
+            let (texture, data, pending) = (Rc::clone(&texture), Rc::clone(&data), pending.clone());
+            self.jobs.push_back(Box::new(move |_device| {
+                let mut slot = texture.borrow_mut();
+                let result = match slot.as_mut() {
+                    Some(t) => t.update_region(0, y, w, band_h, &data[range]),
+                    // an earlier band failed
+                    None => return,
+                };
+                match result {
+                    Err(err) => {
+                        slot.take();
+                        pending.fill(Err(err));
+                    }
+                    Ok(()) if i + 1 == n_bands => pending.fill(Ok(slot.take().unwrap())),
+                    Ok(()) => {}
+                }
+            }));
+        }
+
+        pending
+    }
+
+    /// Creates a texture with the full mip chain and uploads the smallest `n_immediate` levels
+    /// now. The other levels are pushed as jobs, from smaller to larger, one level per job
+    ///
+    /// `levels[i]` is the tightly packed data of mip level `i`. Only the GPU memory of the
+    /// texture is allocated up front; the CPU data of a level is freed after it's uploaded.
+    pub fn push_streamed_texture(
+        &mut self,
+        format: enums::SurfaceFormat,
+        w: u32,
+        h: u32,
+        levels: Vec<Vec<u8>>,
+        n_immediate: u32,
+    ) -> res::Result<StreamedTexture> {
+        let level_count = levels.len() as u32;
+        if level_count == 0 {
+            return Err(res::ResourceError::LevelOutOfBounds { level: 0, count: 0 });
+        }
+
+        // validate every level before uploading anything
+        for (i, data) in levels.iter().enumerate() {
+            let (lw, lh) = res::level_size(w, h, i as u32);
+            let (row_len, n_rows) = res::region_layout(format, lw, lh);
+            if data.len() != row_len * n_rows {
+                return Err(res::ResourceError::DataLength {
+                    expected: row_len * n_rows,
+                    actual: data.len(),
+                });
+            }
+        }
+
+        let mut texture = res::Texture2d::new(&self.device, format, w, h, level_count);
+        let n_immediate = n_immediate.clamp(1, level_count);
+        let resident = level_count - n_immediate;
+
+        let mut levels = levels.into_iter().enumerate().collect::<Vec<_>>();
+        for (level, data) in levels.drain(resident as usize..).rev() {
+            texture.set_level_data(level as u32, &data)?;
+        }
+
+        let texture = Rc::new(RefCell::new(texture));
+        let resident = Rc::new(Cell::new(resident));
+
+        for (level, data) in levels.into_iter().rev() {
+            // weak so that dropping the texture cancels the upload
+            let (texture, resident) = (Rc::downgrade(&texture), Rc::downgrade(&resident));
+            self.jobs.push_back(Box::new(move |_device| {
+                if let (Some(texture), Some(resident)) =
+                    (Weak::upgrade(&texture), Weak::upgrade(&resident))
+                {
+                    // validated on push
+                    texture
+                        .borrow_mut()
+                        .set_level_data(level as u32, &data)
+                        .unwrap();
+                    resident.set(level as u32);
+                }
+            }));
+        }
+
+        Ok(StreamedTexture { texture, resident })
+    }
+
+    /// Pushes creation of a vertex buffer filled with `data`
+    pub fn push_vertex_buffer<V: 'static>(
+        &mut self,
+        data: Vec<V>,
+    ) -> Pending<res::Result<res::VertexBuffer<V>>> {
+        self.push(move |device| {
+            let mut buf = res::VertexBuffer::new(device, data.len())?;
+            // the capacity is the data length
+            buf.overwrite_all(&data)?;
+            Ok(buf)
+        })
+    }
+
+    /// Pushes creation of an index buffer filled with `data`
+    pub fn push_index_buffer<I: res::IndexElement + 'static>(
+        &mut self,
+        data: Vec<I>,
+    ) -> Pending<res::Result<res::IndexBuffer<I>>> {
+        self.push(move |device| {
+            let mut buf = res::IndexBuffer::new(device, data.len())?;
+            buf.overwrite_all(&data)?;
+            Ok(buf)
+        })
+    }
+
+    /// Runs jobs until `max_millis` milliseconds pass. Returns the number of jobs run
+    ///
+    /// At least one job is run so that the queue always makes progress. A single job can still
+    /// exceed the budget, so keep the upload chunk small enough for the target frame time.
+    pub fn pump(&mut self, max_millis: u32) -> usize {
+        let budget = Duration::from_millis(max_millis as u64);
+        let start = Instant::now();
+
+        let mut n = 0;
+        while let Some(job) = self.jobs.pop_front() {
+            job(&self.device);
+            n += 1;
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+
+        self.n_done += n;
+        if self.jobs.is_empty() {
+            self.n_done = 0;
+        }
+        n
+    }
+
+    /// Runs all the jobs
+    pub fn flush(&mut self) -> usize {
+        let mut n = 0;
+        while let Some(job) = self.jobs.pop_front() {
+            job(&self.device);
+            n += 1;
+        }
+        self.n_done = 0;
+        n
+    }
+}
+
+/// Splits level `0` of a `w` x `h` texture into `(y, h, byte range)` bands of at most `chunk`
+/// bytes (but at least one row). Bands of compressed formats are aligned to the 4x4 blocks
+fn bands(
+    format: enums::SurfaceFormat,
+    w: u32,
+    h: u32,
+    chunk: usize,
+) -> Vec<(u32, u32, std::ops::Range<usize>)> {
+    let (row_len, n_rows) = res::region_layout(format, w, h);
+    // pixel rows per (block) row
+    let unit = if res::is_compressed(format) { 4 } else { 1 };
+    let rows_per_band = (chunk / row_len.max(1)).max(1);
+
+    let mut bands = Vec::new();
+    let (mut row, mut offset) = (0, 0);
+    while row < n_rows {
+        let n = rows_per_band.min(n_rows - row);
+        let y = row as u32 * unit;
+        let band_h = (n as u32 * unit).min(h - y);
+        bands.push((y, band_h, offset..offset + n * row_len));
+        row += n;
+        offset += n * row_len;
+    }
+    bands
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bands() {
+        let color = enums::SurfaceFormat::Color;
+        // 16 bytes per row, 3 rows per band
+        assert_eq!(
+            self::bands(color, 4, 7, 50),
+            vec![(0, 3, 0..48), (3, 3, 48..96), (6, 1, 96..112)]
+        );
+
+        // 10x10 DXT1: 3x3 blocks of 8 bytes, 2 block rows per band
+        let dxt = enums::SurfaceFormat::Dxt1;
+        assert_eq!(
+            self::bands(dxt, 10, 10, 48),
+            vec![(0, 8, 0..48), (8, 2, 48..72)]
+        );
+
+        assert_eq!(self::bands(color, 4, 2, 1).len(), 2);
+    }
+}
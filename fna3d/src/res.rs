@@ -0,0 +1,2022 @@
+//! Resource types that own FNA3D objects and dispose them on drop
+//!
+//! Types in the crate root are thin wrappers of FNA3D; resources are raw pointers that have to be
+//! disposed with `Device::add_dispose_*` methods. Types in this module hold a cloned [`Device`]
+//! and do it automatically.
+
+use std::{
+    cell::RefCell, collections::VecDeque, convert::TryFrom, fmt, marker::PhantomData, mem,
+    path::Path, rc::Rc,
+};
+
+use crate::{
+    fna3d::{fna3d_device::Device, fna3d_enums as enums, fna3d_structs::*},
+    img,
+    staging::StagingBelt,
+};
+
+#[cfg(feature = "mojoshader")]
+use {
+    crate::mojo,
+    std::{
+        ffi::CStr,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+};
+
+pub type Result<T> = std::result::Result<T, ResourceError>;
+
+/// Invalid arguments to the resource types, detected before calling FNA3D
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceError {
+    /// `[x, y, w, h]` doesn't fit in the `[w, h]` texture
+    OutOfBounds { rect: [u32; 4], size: [u32; 2] },
+    /// Block-compressed textures are updated in 4x4 blocks
+    UnalignedRegion { rect: [u32; 4] },
+    /// The byte length of the data doesn't match the region
+    DataLength { expected: usize, actual: usize },
+    /// The U/V planes are not 4:2:0 subsampled from the Y plane
+    YuvSubsampling { y_size: [u32; 2], uv_size: [u32; 2] },
+    /// Zero-sized render target
+    EmptyRenderTarget { w: u32, h: u32 },
+    /// Zero-sized texture
+    EmptyTexture { w: u32, h: u32 },
+    /// The color format can't be rendered to (see [`enums::SurfaceFormat::is_renderable`])
+    NotRenderable(enums::SurfaceFormat),
+    /// The device supports up to `max` samples for the format
+    UnsupportedMultiSampleCount {
+        format: enums::SurfaceFormat,
+        requested: u32,
+        max: u32,
+    },
+    /// The depth/stencil buffer can't be created with the MSAA count
+    UnsupportedDepthFormat {
+        depth_format: enums::DepthFormat,
+        multi_sample_count: u32,
+    },
+    /// Ping-pong targets have different sizes
+    TargetSizeMismatch { a: [u32; 2], b: [u32; 2] },
+    /// More elements than the buffer can hold
+    BufferOverflow { capacity: u32, requested: usize },
+    /// The byte size of the buffer doesn't fit in `u32`
+    BufferTooLarge { capacity: usize, stride: usize },
+    /// MojoShader failed to parse the effect binary
+    InvalidEffect(String),
+    /// Failed to read or decode an image
+    ImageLoad(String),
+    /// The technique was taken from another [`EffectHandle`]
+    ForeignTechnique,
+    /// The technique index is not less than the number of techniques of the effect
+    TechniqueOutOfBounds { index: u32, count: u32 },
+    /// The mip level is not less than the number of levels of the texture
+    LevelOutOfBounds { level: u32, count: u32 },
+    /// The surface formats of the source and the destination of a copy differ
+    FormatMismatch {
+        src: enums::SurfaceFormat,
+        dest: enums::SurfaceFormat,
+    },
+    /// The render target has no depth buffer (or no stencil bits) to read
+    NoDepthStencil(enums::DepthFormat),
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceError::OutOfBounds { rect, size } => write!(
+                f,
+                "region {:?} is out of the texture bounds {:?}",
+                rect, size
+            ),
+            ResourceError::UnalignedRegion { rect } => write!(
+                f,
+                "region {:?} of a block-compressed texture is not aligned to 4x4 blocks",
+                rect
+            ),
+            ResourceError::DataLength { expected, actual } => write!(
+                f,
+                "expected {} bytes of data but got {} bytes",
+                expected, actual
+            ),
+            ResourceError::YuvSubsampling { y_size, uv_size } => write!(
+                f,
+                "UV planes {:?} are not 4:2:0 subsampled from the Y plane {:?}",
+                uv_size, y_size
+            ),
+            ResourceError::EmptyRenderTarget { w, h } => {
+                write!(f, "render target size {}x{} is empty", w, h)
+            }
+            ResourceError::EmptyTexture { w, h } => {
+                write!(f, "texture size {}x{} is empty", w, h)
+            }
+            ResourceError::NotRenderable(format) => {
+                write!(f, "{:?} can't be used as a render target format", format)
+            }
+            ResourceError::UnsupportedMultiSampleCount {
+                format,
+                requested,
+                max,
+            } => write!(
+                f,
+                "{}x MSAA is not supported for {:?} render targets (max: {})",
+                requested, format, max
+            ),
+            ResourceError::UnsupportedDepthFormat {
+                depth_format,
+                multi_sample_count,
+            } => write!(
+                f,
+                "{:?} depth buffers can't be created with {}x MSAA",
+                depth_format, multi_sample_count
+            ),
+            ResourceError::TargetSizeMismatch { a, b } => {
+                write!(f, "render targets of different sizes: {:?} and {:?}", a, b)
+            }
+            ResourceError::BufferOverflow {
+                capacity,
+                requested,
+            } => write!(
+                f,
+                "{} elements don't fit in the buffer of capacity {}",
+                requested, capacity
+            ),
+            ResourceError::BufferTooLarge { capacity, stride } => write!(
+                f,
+                "buffer of {} elements of {} bytes exceeds {} bytes",
+                capacity,
+                stride,
+                u32::MAX
+            ),
+            ResourceError::InvalidEffect(err) => write!(f, "failed to create effect: {}", err),
+            ResourceError::ForeignTechnique => {
+                write!(f, "the technique belongs to another effect")
+            }
+            ResourceError::TechniqueOutOfBounds { index, count } => write!(
+                f,
+                "technique index {} is out of bounds (the effect has {})",
+                index, count
+            ),
+            ResourceError::LevelOutOfBounds { level, count } => write!(
+                f,
+                "mip level {} is out of bounds (the texture has {})",
+                level, count
+            ),
+            ResourceError::FormatMismatch { src, dest } => {
+                write!(f, "can't copy {:?} pixels into a {:?} surface", src, dest)
+            }
+            ResourceError::ImageLoad(e) => write!(f, "failed to load image: {}", e),
+            ResourceError::NoDepthStencil(format) => {
+                write!(
+                    f,
+                    "no depth/stencil data to read (depth format {:?})",
+                    format
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+// --------------------------------------------------------------------------------
+// Texture2d
+
+/// Block-compressed (4x4 texels per block) or not
+pub(crate) fn is_compressed(format: enums::SurfaceFormat) -> bool {
+    matches!(
+        format,
+        enums::SurfaceFormat::Dxt1 | enums::SurfaceFormat::Dxt3 | enums::SurfaceFormat::Dxt5
+    )
+}
+
+/// (Bytes per row, number of rows) of a `w` x `h` region. Rows are block rows if compressed
+pub(crate) fn region_layout(format: enums::SurfaceFormat, w: u32, h: u32) -> (usize, usize) {
+    if self::is_compressed(format) {
+        let (bw, bh) = ((w as usize).div_ceil(4), (h as usize).div_ceil(4));
+        (bw * format.size(), bh)
+    } else {
+        (w as usize * format.size(), h as usize)
+    }
+}
+
+/// Size of the mip level of a `w` x `h` texture
+pub(crate) fn level_size(w: u32, h: u32, level: u32) -> (u32, u32) {
+    let shift = |x: u32| x.checked_shr(level).unwrap_or(0).max(1);
+    (shift(w), shift(h))
+}
+
+/// Checks if the `[x, y, w, h]` region can be updated
+pub(crate) fn validate_region(
+    format: enums::SurfaceFormat,
+    size: [u32; 2],
+    rect: [u32; 4],
+) -> Result<()> {
+    let [x, y, w, h] = rect;
+    if x as u64 + w as u64 > size[0] as u64 || y as u64 + h as u64 > size[1] as u64 {
+        return Err(ResourceError::OutOfBounds { rect, size });
+    }
+
+    if self::is_compressed(format) {
+        // partial blocks are only allowed at the right/bottom edges
+        let aligned = |v: u32, end: u32, size: u32| v & 3 == 0 && (end & 3 == 0 || end == size);
+        if !aligned(x, x + w, size[0]) || !aligned(y, y + h, size[1]) {
+            return Err(ResourceError::UnalignedRegion { rect });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the plane sizes and the data length of [`Device::set_texture_data_yuv`]
+pub(crate) fn validate_yuv_planes(y_size: [u32; 2], uv_size: [u32; 2], len: usize) -> Result<()> {
+    if uv_size[0] != y_size[0].div_ceil(2) || uv_size[1] != y_size[1].div_ceil(2) {
+        return Err(ResourceError::YuvSubsampling { y_size, uv_size });
+    }
+
+    let expected =
+        y_size[0] as usize * y_size[1] as usize + 2 * uv_size[0] as usize * uv_size[1] as usize;
+    if len != expected {
+        return Err(ResourceError::DataLength {
+            expected,
+            actual: len,
+        });
+    }
+
+    Ok(())
+}
+
+/// 2D texture with a CPU staging buffer for partial updates
+///
+/// Made for frequently updated textures such as minimaps and fog of war: keep the full image on
+/// the CPU and upload only the dirty rectangle with [`Texture2d::update_region_strided`].
+///
+/// Images are loaded with [`Texture2d::from_encoded_bytes`] and [`Texture2d::from_path`].
+pub struct Texture2d {
+    device: Device,
+    raw: *mut Texture,
+    format: enums::SurfaceFormat,
+    w: u32,
+    h: u32,
+    level_count: u32,
+    /// Reused for re-packing rows
+    staging: StagingBelt,
+}
+
+impl fmt::Debug for Texture2d {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Texture2d")
+            .field("raw", &self.raw)
+            .field("format", &self.format)
+            .field("w", &self.w)
+            .field("h", &self.h)
+            .field("level_count", &self.level_count)
+            .finish()
+    }
+}
+
+impl Drop for Texture2d {
+    fn drop(&mut self) {
+        self.device.add_dispose_texture(self.raw);
+    }
+}
+
+impl Texture2d {
+    pub fn new(
+        device: &Device,
+        format: enums::SurfaceFormat,
+        w: u32,
+        h: u32,
+        level_count: u32,
+    ) -> Self {
+        let raw = device.create_texture_2d(format, w, h, level_count, false);
+
+        Self {
+            device: device.clone(),
+            raw,
+            format,
+            w,
+            h,
+            level_count,
+            staging: StagingBelt::new(),
+        }
+    }
+
+    pub fn raw(&self) -> *mut Texture {
+        self.raw
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.w, self.h)
+    }
+
+    pub fn format(&self) -> enums::SurfaceFormat {
+        self.format
+    }
+
+    pub fn level_count(&self) -> u32 {
+        self.level_count
+    }
+
+    /// Size of the mip level (halved per level, at least 1x1)
+    pub fn level_size(&self, level: u32) -> (u32, u32) {
+        self::level_size(self.w, self.h, level)
+    }
+
+    /// Uploads the whole mip level
+    pub fn set_level_data(&mut self, level: u32, data: &[u8]) -> Result<()> {
+        if level >= self.level_count {
+            return Err(ResourceError::LevelOutOfBounds {
+                level,
+                count: self.level_count,
+            });
+        }
+
+        let (w, h) = self.level_size(level);
+        let (row_len, n_rows) = self::region_layout(self.format, w, h);
+        if data.len() != row_len * n_rows {
+            return Err(ResourceError::DataLength {
+                expected: row_len * n_rows,
+                actual: data.len(),
+            });
+        }
+
+        self.device
+            .set_texture_data_2d(self.raw, 0, 0, w, h, level, data);
+        Ok(())
+    }
+
+    /// Uploads the whole level `0`
+    pub fn set_data(&mut self, data: &[u8]) -> Result<()> {
+        let (w, h) = (self.w, self.h);
+        self.update_region(0, 0, w, h, data)
+    }
+
+    /// Uploads tightly packed data of the `[x, y, w, h]` region of level `0`
+    pub fn update_region(&mut self, x: u32, y: u32, w: u32, h: u32, data: &[u8]) -> Result<()> {
+        self::validate_region(self.format, [self.w, self.h], [x, y, w, h])?;
+
+        let (row_len, n_rows) = self::region_layout(self.format, w, h);
+        if data.len() != row_len * n_rows {
+            return Err(ResourceError::DataLength {
+                expected: row_len * n_rows,
+                actual: data.len(),
+            });
+        }
+
+        self.device
+            .set_texture_data_2d(self.raw, x, y, w, h, 0, data);
+        Ok(())
+    }
+
+    /// Uploads the `[x, y, w, h]` region from an image whose rows are `row_pitch` bytes apart
+    ///
+    /// `data` starts at the top-left corner of the region. With a CPU copy of the whole texture,
+    /// pass the slice starting at `(x, y)` and `row_pitch = texture width * bytes per pixel`. Rows
+    /// are re-packed into the internal staging buffer, which is reused between calls.
+    pub fn update_region_strided(
+        &mut self,
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+        data: &[u8],
+        row_pitch: usize,
+    ) -> Result<()> {
+        self::validate_region(self.format, [self.w, self.h], [x, y, w, h])?;
+
+        let (row_len, n_rows) = self::region_layout(self.format, w, h);
+        if n_rows == 0 {
+            return Ok(());
+        }
+
+        let expected = row_pitch.max(row_len) * (n_rows - 1) + row_len;
+        if row_pitch < row_len || data.len() < expected {
+            return Err(ResourceError::DataLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        // already tightly packed
+        if row_pitch == row_len {
+            let len = row_len * n_rows;
+            self.device
+                .set_texture_data_2d(self.raw, x, y, w, h, 0, &data[0..len]);
+            return Ok(());
+        }
+
+        self.staging.clear();
+        for row in data.chunks(row_pitch).take(n_rows) {
+            self.staging.extend_bytes(&row[0..row_len]);
+        }
+        self.device
+            .set_texture_data_2d(self.raw, x, y, w, h, 0, self.staging.as_bytes());
+
+        Ok(())
+    }
+}
+
+/// Loading
+impl Texture2d {
+    /// Decodes PNG/JPG/GIF data (e.g. `include_bytes!`) into a `Color` texture
+    ///
+    /// The pixels are premultiplied by alpha on load (see [`crate::img`]).
+    pub fn from_encoded_bytes(device: &Device, bytes: &[u8]) -> Result<Self> {
+        let (pixels, w, h) =
+            img::load_from_memory(bytes).map_err(|e| ResourceError::ImageLoad(e.to_string()))?;
+        Self::from_straight_pixels(device, pixels, w, h)
+    }
+
+    /// Decodes a PNG/JPG/GIF file into a `Color` texture
+    ///
+    /// The pixels are premultiplied by alpha on load (see [`crate::img`]).
+    pub fn from_path(device: &Device, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let (pixels, w, h) = img::load_from_path(path)
+            .map_err(|e| ResourceError::ImageLoad(format!("{}: {}", path.display(), e)))?;
+        Self::from_straight_pixels(device, pixels, w, h)
+    }
+
+    /// Premultiplies and uploads decoded RGBA8 pixels
+    fn from_straight_pixels(device: &Device, mut pixels: Vec<u8>, w: u32, h: u32) -> Result<Self> {
+        img::premultiply(&mut pixels);
+        let mut texture = Self::new(device, enums::SurfaceFormat::Color, w, h, 1);
+        texture.set_data(&pixels)?;
+        Ok(texture)
+    }
+}
+
+/// Generators
+impl Texture2d {
+    /// 1x1 opaque white texture
+    ///
+    /// Sampling it returns the vertex color, so batchers use it for untextured quads.
+    pub fn white_pixel(device: &Device) -> Self {
+        let mut texture = Self::new(device, enums::SurfaceFormat::Color, 1, 1, 1);
+        texture.set_data(&[255; 4]).unwrap();
+        texture
+    }
+
+    /// `size` x `size` checkerboard of 8x8 cells, for placeholders of missing textures and UV
+    /// debugging
+    pub fn debug_checkerboard(device: &Device, size: u32, colors: [Color; 2]) -> Self {
+        let mut texture = Self::new(device, enums::SurfaceFormat::Color, size, size, 1);
+        texture
+            .set_data(&self::checkerboard_pixels(size, colors))
+            .unwrap();
+        texture
+    }
+}
+
+/// RGBA8 pixels of [`Texture2d::debug_checkerboard`]
+fn checkerboard_pixels(size: u32, colors: [Color; 2]) -> Vec<u8> {
+    let cell = (size / 8).max(1);
+    let colors = colors.map(|c| {
+        let c = c.raw();
+        [c.r, c.g, c.b, c.a]
+    });
+
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .flat_map(|(x, y)| colors[((x / cell + y / cell) % 2) as usize])
+        .collect()
+}
+
+// --------------------------------------------------------------------------------
+// ReadbackPool
+
+/// Counters of [`ReadbackPool`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadbackStats {
+    /// Number of `get_texture_data_2d` calls
+    pub n_readbacks: u64,
+    /// Bytes read back from the GPU
+    pub n_bytes: u64,
+    /// Buffers allocated because no pooled buffer was large enough
+    pub n_allocations: u64,
+}
+
+/// Reuses byte buffers for repeated small texture readbacks (GPU picking, histograms, ..)
+///
+/// Every readback is a sync point: the CPU waits until the GPU finishes the commands that write
+/// to the texture. The pool logs it once and counts readbacks in [`ReadbackStats`], so that they
+/// can be watched in one place.
+///
+/// ```no_run
+/// # fn run(device: &fna3d::Device, texture: &fna3d::res::Texture2d) -> fna3d::res::Result<()> {
+/// use fna3d::res::ReadbackPool;
+///
+/// let mut pool = ReadbackPool::new(device);
+/// let alpha = pool.read_with(texture, [10, 20, 1, 1], |px| px[3])?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReadbackPool {
+    device: Device,
+    free: Vec<Vec<u8>>,
+    stats: ReadbackStats,
+}
+
+impl ReadbackPool {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            device: device.clone(),
+            free: Vec::new(),
+            stats: ReadbackStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> ReadbackStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = ReadbackStats::default();
+    }
+
+    /// Reads `[x, y, w, h]` of level `0` into a pooled buffer
+    ///
+    /// Returns an error if the region is out of the texture (or not block-aligned). Give the
+    /// buffer back with [`ReadbackPool::recycle`] to reuse it.
+    pub fn read(&mut self, texture: &Texture2d, rect: [u32; 4]) -> Result<Vec<u8>> {
+        let format = texture.format();
+        self::validate_region(format, [texture.w, texture.h], rect)?;
+
+        let [x, y, w, h] = rect;
+        let (row_len, n_rows) = self::region_layout(format, w, h);
+        let len = row_len * n_rows;
+
+        let mut buf = self.take(len);
+        if self.stats.n_readbacks == 0 {
+            log::warn!("texture readback stalls the GPU pipeline (further readbacks are counted in `ReadbackStats`)");
+        }
+        self.stats.n_readbacks += 1;
+        self.stats.n_bytes += len as u64;
+
+        self.device
+            .get_texture_data_2d(texture.raw(), x, y, w, h, 0, &mut buf);
+        Ok(buf)
+    }
+
+    /// Reads `[x, y, w, h]` of level `0` and passes the bytes to `f`, then recycles the buffer
+    pub fn read_with<T>(
+        &mut self,
+        texture: &Texture2d,
+        rect: [u32; 4],
+        f: impl FnOnce(&[u8]) -> T,
+    ) -> Result<T> {
+        let buf = self.read(texture, rect)?;
+        let ret = f(&buf);
+        self.recycle(buf);
+        Ok(ret)
+    }
+
+    /// Returns a buffer to the pool
+    pub fn recycle(&mut self, buf: Vec<u8>) {
+        self.free.push(buf);
+    }
+
+    /// Smallest pooled buffer that can hold `len` bytes (or a new one), resized to `len`
+    fn take(&mut self, len: usize) -> Vec<u8> {
+        let best = self
+            .free
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.capacity() >= len)
+            .min_by_key(|(_, b)| b.capacity())
+            .map(|(i, _)| i);
+
+        let mut buf = match best {
+            Some(i) => self.free.swap_remove(i),
+            None => {
+                self.stats.n_allocations += 1;
+                Vec::with_capacity(len)
+            }
+        };
+
+        buf.clear();
+        buf.resize(len, 0);
+        buf
+    }
+}
+
+// --------------------------------------------------------------------------------
+// RenderTarget2d
+
+/// How a [`RenderTarget2d`] follows the backbuffer size
+///
+/// Targets with a policy other than `Fixed` are recreated by
+/// [`Device::handle_backbuffer_invalidation`] once they're [`RenderTarget2d::register`]ed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ResizePolicy {
+    /// Keeps the size given on creation
+    #[default]
+    Fixed,
+    /// Same size as the backbuffer
+    MatchBackbuffer,
+    /// Backbuffer size multiplied by the factor (e.g. `0.5` for a half-resolution bloom target)
+    ScaleOfBackbuffer(f32),
+}
+
+impl ResizePolicy {
+    /// Size of the target for the backbuffer size, or `None` if it's `Fixed`. Never zero
+    pub fn size_for(self, backbuffer: (u32, u32)) -> Option<(u32, u32)> {
+        let scale = |x: u32, s: f32| ((x as f32 * s).round() as u32).max(1);
+        match self {
+            ResizePolicy::Fixed => None,
+            ResizePolicy::MatchBackbuffer => Some((backbuffer.0.max(1), backbuffer.1.max(1))),
+            ResizePolicy::ScaleOfBackbuffer(s) => {
+                Some((scale(backbuffer.0, s), scale(backbuffer.1, s)))
+            }
+        }
+    }
+}
+
+/// [`RenderTarget2d`] registered to the device
+pub type SharedRenderTarget = Rc<RefCell<RenderTarget2d>>;
+
+/// Texture, MSAA color buffer and depth buffer owned as one offscreen target
+///
+/// Same as [`RenderTarget2d`]: `new(device, w, h, format, depth_format, multi_sample_count)`
+/// creates all of them, [`RenderTarget2d::bind`], [`RenderTarget2d::unbind`] and
+/// [`RenderTarget2d::resolve`] use them and they're disposed on drop.
+pub type OffscreenTarget = RenderTarget2d;
+
+/// Offscreen 2D render target: texture, optional MSAA color buffer and depth/stencil buffer
+///
+/// Bind it with [`RenderTarget2d::bind`], draw, then [`RenderTarget2d::unbind`] to resolve it.
+/// After that, [`RenderTarget2d::texture`] can be sampled.
+pub struct RenderTarget2d {
+    device: Device,
+    texture: *mut Texture,
+    /// Non-null if multi-sampled
+    color: *mut Renderbuffer,
+    /// Non-null if `depth_format` is not `None`
+    depth: *mut Renderbuffer,
+    binding: RenderTargetBinding,
+    w: u32,
+    h: u32,
+    format: enums::SurfaceFormat,
+    depth_format: enums::DepthFormat,
+    multi_sample_count: u32,
+    usage: enums::RenderTargetUsage,
+    /// Set the viewport and the scissor rectangle on bind/unbind
+    auto_viewport: bool,
+    resize_policy: ResizePolicy,
+}
+
+impl Drop for RenderTarget2d {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.color.is_null() {
+                self.device.add_dispose_renderbuffer(&mut *self.color);
+            }
+            if !self.depth.is_null() {
+                self.device.add_dispose_renderbuffer(&mut *self.depth);
+            }
+        }
+        self.device.add_dispose_texture(self.texture);
+    }
+}
+
+impl RenderTarget2d {
+    /// Creates a render target with `DiscardContents` usage
+    ///
+    /// * `multi_sample_count`: `0` to disable MSAA
+    ///
+    /// The combination of the formats and the MSAA count is validated first, so that an
+    /// unsupported one returns an error instead of failing in the driver.
+    pub fn new(
+        device: &Device,
+        w: u32,
+        h: u32,
+        format: enums::SurfaceFormat,
+        depth_format: enums::DepthFormat,
+        multi_sample_count: u32,
+    ) -> Result<Self> {
+        Self::validate(device, w, h, format, depth_format, multi_sample_count)?;
+
+        let texture = device.create_texture_2d(format, w, h, 1, true);
+
+        let color = if multi_sample_count > 0 {
+            device.gen_color_renderbuffer(w, h, format, multi_sample_count, texture)
+        } else {
+            std::ptr::null_mut()
+        };
+
+        let depth = if depth_format != enums::DepthFormat::None {
+            device.gen_depth_stencil_renderbuffer(w, h, depth_format, multi_sample_count as i32)
+        } else {
+            std::ptr::null_mut()
+        };
+
+        let binding = RenderTargetBinding::new_2d(
+            RenderTargetType::TwoD,
+            1,
+            multi_sample_count,
+            texture,
+            w,
+            h,
+            color,
+        );
+
+        Ok(Self {
+            device: device.clone(),
+            texture,
+            color,
+            depth,
+            binding,
+            w,
+            h,
+            format,
+            depth_format,
+            multi_sample_count,
+            usage: enums::RenderTargetUsage::DiscardContents,
+            auto_viewport: true,
+            resize_policy: ResizePolicy::Fixed,
+        })
+    }
+
+    fn validate(
+        device: &Device,
+        w: u32,
+        h: u32,
+        format: enums::SurfaceFormat,
+        depth_format: enums::DepthFormat,
+        multi_sample_count: u32,
+    ) -> Result<()> {
+        if w == 0 || h == 0 {
+            return Err(ResourceError::EmptyRenderTarget { w, h });
+        }
+
+        if !format.is_renderable() {
+            return Err(ResourceError::NotRenderable(format));
+        }
+
+        if multi_sample_count > 0 {
+            // FNA3D can't be queried for depth formats; they're checked by `validate_depth`
+            let max = device
+                .get_max_multi_sample_count(format, multi_sample_count)
+                .max(0) as u32;
+            if max < multi_sample_count {
+                return Err(ResourceError::UnsupportedMultiSampleCount {
+                    format,
+                    requested: multi_sample_count,
+                    max,
+                });
+            }
+        }
+
+        self::validate_depth(depth_format, multi_sample_count)
+    }
+
+    /// Keeps the contents between binds, for accumulation-style rendering
+    ///
+    /// Sets the usage to `PreserveContents`, so that trails, decals and other things drawn over
+    /// previous frames are not lost when the target is bound again.
+    ///
+    /// # Cost
+    ///
+    /// The driver has to load the previous contents when the target is bound (and with MSAA, the
+    /// resolved texture has to be copied back to the multi-sampled buffer). That's GPU bandwidth
+    /// proportional to the target size per bind, which is free with `DiscardContents`. Tiled
+    /// (mobile) GPUs pay the most. Only use it for targets that really accumulate.
+    pub fn with_preserved_contents(mut self) -> Self {
+        self.usage = enums::RenderTargetUsage::PreserveContents;
+        self
+    }
+
+    pub fn usage(&self) -> enums::RenderTargetUsage {
+        self.usage
+    }
+
+    /// See [`RenderTarget2d::with_preserved_contents`] for the cost of `PreserveContents`
+    pub fn set_usage(&mut self, usage: enums::RenderTargetUsage) {
+        self.usage = usage;
+    }
+
+    /// If the contents are preserved on bind (`preserve_target_contents` of
+    /// [`Device::set_render_targets`])
+    pub fn preserves_contents(&self) -> bool {
+        self.usage != enums::RenderTargetUsage::DiscardContents
+    }
+
+    /// Opts out of the automatic viewport, e.g. to render into a sub-viewport of the target
+    ///
+    /// By default, [`RenderTarget2d::bind`] sets the viewport and the scissor rectangle to the
+    /// whole target and [`RenderTarget2d::unbind`] sets them to the whole backbuffer. FNA3D keeps
+    /// the previous viewport when the render targets change, so without it, drawing to a target
+    /// of another size is stretched or cropped.
+    pub fn with_auto_viewport(mut self, auto_viewport: bool) -> Self {
+        self.auto_viewport = auto_viewport;
+        self
+    }
+
+    pub fn auto_viewport(&self) -> bool {
+        self.auto_viewport
+    }
+
+    pub fn set_auto_viewport(&mut self, auto_viewport: bool) {
+        self.auto_viewport = auto_viewport;
+    }
+}
+
+/// Resizing
+impl RenderTarget2d {
+    /// Creates a render target sized by the policy from the current backbuffer size
+    ///
+    /// Call [`RenderTarget2d::register`] on it so that it's kept in sync with the backbuffer.
+    pub fn with_backbuffer_size(
+        device: &Device,
+        policy: ResizePolicy,
+        format: enums::SurfaceFormat,
+        depth_format: enums::DepthFormat,
+        multi_sample_count: u32,
+    ) -> Result<Self> {
+        let backbuffer = device.get_backbuffer_size();
+        let (w, h) = policy.size_for(backbuffer).unwrap_or(backbuffer);
+        let target = Self::new(device, w, h, format, depth_format, multi_sample_count)?;
+        Ok(target.with_resize_policy(policy))
+    }
+
+    pub fn with_resize_policy(mut self, policy: ResizePolicy) -> Self {
+        self.resize_policy = policy;
+        self
+    }
+
+    pub fn resize_policy(&self) -> ResizePolicy {
+        self.resize_policy
+    }
+
+    pub fn set_resize_policy(&mut self, policy: ResizePolicy) {
+        self.resize_policy = policy;
+    }
+
+    /// Recreates the texture and the renderbuffers with the new size, keeping the formats and
+    /// the settings. The contents are lost. Does nothing if the size doesn't change
+    ///
+    /// The old [`RenderTarget2d::texture`] is disposed, so fetch it again after this.
+    pub fn resize(&mut self, w: u32, h: u32) -> Result<()> {
+        if (w, h) == self.size() {
+            return Ok(());
+        }
+
+        let mut new = Self::new(
+            &self.device,
+            w,
+            h,
+            self.format,
+            self.depth_format,
+            self.multi_sample_count,
+        )?;
+        new.usage = self.usage;
+        new.auto_viewport = self.auto_viewport;
+        new.resize_policy = self.resize_policy;
+
+        // the old resources are disposed on drop
+        mem::swap(self, &mut new);
+        Ok(())
+    }
+
+    /// Resizes the target following the policy. Returns true if it's resized
+    pub fn fit_backbuffer(&mut self, backbuffer: (u32, u32)) -> Result<bool> {
+        match self.resize_policy.size_for(backbuffer) {
+            Some((w, h)) if (w, h) != self.size() => self.resize(w, h).map(|()| true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Shares the target with the device, which resizes it in
+    /// [`Device::handle_backbuffer_invalidation`] (before the reset callbacks run)
+    ///
+    /// The device only keeps a weak reference; dropping the returned handle disposes the target.
+    pub fn register(self) -> SharedRenderTarget {
+        let device = self.device.clone();
+        let target = Rc::new(RefCell::new(self));
+        device.register_render_target(&target);
+        target
+    }
+}
+
+/// Accessors
+impl RenderTarget2d {
+    /// Texture to sample after [`RenderTarget2d::unbind`]
+    pub fn texture(&self) -> *mut Texture {
+        self.texture
+    }
+
+    pub fn binding_mut(&mut self) -> &mut RenderTargetBinding {
+        &mut self.binding
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.w, self.h)
+    }
+
+    pub fn format(&self) -> enums::SurfaceFormat {
+        self.format
+    }
+
+    pub fn depth_format(&self) -> enums::DepthFormat {
+        self.depth_format
+    }
+
+    pub fn multi_sample_count(&self) -> u32 {
+        self.multi_sample_count
+    }
+
+    /// Null if the depth format is `None`
+    #[cfg(feature = "mojoshader")]
+    pub(crate) fn depth_buffer(&self) -> *mut Renderbuffer {
+        self.depth
+    }
+}
+
+/// Binding
+impl RenderTarget2d {
+    /// Sets this target as the only render target
+    ///
+    /// The viewport and the scissor rectangle are set to the whole target unless
+    /// [`RenderTarget2d::with_auto_viewport`] is disabled. Set them after this otherwise.
+    pub fn bind(&mut self) {
+        let preserve = self.preserves_contents();
+        let depth = if self.depth.is_null() {
+            None
+        } else {
+            Some(unsafe { &mut *self.depth })
+        };
+
+        self.device.set_render_targets(
+            Some(&mut self.binding),
+            1,
+            depth,
+            self.depth_format,
+            preserve,
+        );
+
+        if self.auto_viewport {
+            self::set_full_viewport(&self.device, self.w, self.h);
+        }
+    }
+
+    /// Sets the backbuffer as the render target and resolves this target
+    ///
+    /// The viewport and the scissor rectangle are set to the whole backbuffer unless
+    /// [`RenderTarget2d::with_auto_viewport`] is disabled.
+    pub fn unbind(&mut self) {
+        self.device
+            .set_render_targets(None, 0, None, enums::DepthFormat::None, false);
+        self.resolve();
+
+        if self.auto_viewport {
+            let (w, h) = self.device.get_backbuffer_size();
+            self::set_full_viewport(&self.device, w, h);
+        }
+    }
+
+    /// Resolves the multi-sampled color buffer into the texture (no-op without MSAA)
+    ///
+    /// [`RenderTarget2d::unbind`] does it for you. Call this to sample the texture while the
+    /// target is still bound, e.g. between passes that keep drawing into it.
+    pub fn resolve(&mut self) {
+        self.device.resolve_target(&mut self.binding);
+    }
+}
+
+/// Checks the depth/stencil buffer of a render target, which shares the MSAA count of the color
+/// buffer
+///
+/// Multi-sampled depth buffers are created with power-of-two sample counts only (FNA rounds the
+/// count with `MathHelper.ClosestMSAAPower`), and FNA3D takes the count as `i32`.
+fn validate_depth(depth_format: enums::DepthFormat, multi_sample_count: u32) -> Result<()> {
+    if depth_format == enums::DepthFormat::None || multi_sample_count == 0 {
+        return Ok(());
+    }
+
+    if !multi_sample_count.is_power_of_two() || multi_sample_count > i32::MAX as u32 {
+        return Err(ResourceError::UnsupportedDepthFormat {
+            depth_format,
+            multi_sample_count,
+        });
+    }
+
+    Ok(())
+}
+
+/// Sets the viewport and the scissor rectangle to cover the `w` x `h` render target
+fn set_full_viewport(device: &Device, w: u32, h: u32) {
+    let rect = Rect::new(0, 0, w as i32, h as i32);
+    device.set_viewport(&Viewport::from_rect(rect));
+    device.set_scissor_rect(&rect);
+}
+
+// --------------------------------------------------------------------------------
+// PingPong
+
+/// Two same-size render targets for iterative effects (blur chains, simulations in fragment
+/// shaders)
+///
+/// Each step samples [`PingPong::source`], renders into [`PingPong::dest_mut`] and then
+/// [`PingPong::swap`]s, so the result of a step is the source of the next one.
+///
+/// ```no_run
+/// # fn f(device: &fna3d::Device) -> fna3d::res::Result<()> {
+/// use fna3d::{res::PingPong, DepthFormat, SurfaceFormat};
+///
+/// let mut pp = PingPong::new(device, 256, 256, SurfaceFormat::Color, DepthFormat::None)?;
+/// for _ in 0..4 {
+///     let src = pp.source().texture();
+///     pp.dest_mut().bind();
+///     // draw a full-screen quad sampling `src`
+///     pp.dest_mut().unbind();
+///     pp.swap();
+/// }
+/// // `pp.source()` has the final result
+/// # Ok(())
+/// # }
+/// ```
+pub struct PingPong {
+    targets: [RenderTarget2d; 2],
+    /// Index of the source target
+    src: usize,
+}
+
+impl PingPong {
+    /// Creates two targets without MSAA
+    pub fn new(
+        device: &Device,
+        w: u32,
+        h: u32,
+        format: enums::SurfaceFormat,
+        depth_format: enums::DepthFormat,
+    ) -> Result<Self> {
+        let a = RenderTarget2d::new(device, w, h, format, depth_format, 0)?;
+        let b = RenderTarget2d::new(device, w, h, format, depth_format, 0)?;
+        Self::from_targets(a, b)
+    }
+
+    /// Uses existing targets. `a` is the first source
+    pub fn from_targets(a: RenderTarget2d, b: RenderTarget2d) -> Result<Self> {
+        if a.size() != b.size() {
+            let (a, b) = (a.size(), b.size());
+            return Err(ResourceError::TargetSizeMismatch {
+                a: [a.0, a.1],
+                b: [b.0, b.1],
+            });
+        }
+
+        Ok(Self {
+            targets: [a, b],
+            src: 0,
+        })
+    }
+
+    /// Target to sample in the current step (the result of the previous one)
+    pub fn source(&self) -> &RenderTarget2d {
+        &self.targets[self.src]
+    }
+
+    /// Target to render into in the current step
+    pub fn dest(&self) -> &RenderTarget2d {
+        &self.targets[1 - self.src]
+    }
+
+    pub fn dest_mut(&mut self) -> &mut RenderTarget2d {
+        &mut self.targets[1 - self.src]
+    }
+
+    /// Makes the destination the source of the next step
+    pub fn swap(&mut self) {
+        self.src = 1 - self.src;
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.targets[0].size()
+    }
+
+    pub fn into_targets(self) -> [RenderTarget2d; 2] {
+        self.targets
+    }
+}
+
+// --------------------------------------------------------------------------------
+// VertexBuffer
+
+/// Default of [`VertexBuffer::with_frames_in_flight`] and [`IndexBuffer::with_frames_in_flight`]
+pub const DEFAULT_FRAMES_IN_FLIGHT: u32 = 3;
+
+/// Frames in which regions of a buffer were written since the last orphaning
+///
+/// A region written in frame `n` may be read by the GPU until frame `n + frames_in_flight`, so
+/// writing to it with `NoOverwrite` before that can corrupt draw calls in flight.
+#[derive(Debug, Clone, Default)]
+struct WriteFence {
+    /// `(start, end, frame)` in elements
+    spans: VecDeque<(u32, u32, u64)>,
+}
+
+impl WriteFence {
+    /// If `[start, end)` is not read by draw calls in flight
+    fn is_free(&self, start: u32, end: u32, frame: u64, frames_in_flight: u32) -> bool {
+        self.spans
+            .iter()
+            .all(|&(s, e, f)| e <= start || end <= s || f + frames_in_flight as u64 <= frame)
+    }
+
+    /// Where to write `len` elements: `(offset, options)` in elements
+    ///
+    /// Appends after the `cursor`, or wraps around to the beginning if the region is free.
+    /// Otherwise the buffer is orphaned with `Discard`.
+    fn plan(
+        &self,
+        cursor: u32,
+        capacity: u32,
+        len: u32,
+        frame: u64,
+        frames_in_flight: u32,
+    ) -> (u32, enums::SetDataOptions) {
+        let fits = |offset: u32| offset as u64 + len as u64 <= capacity as u64;
+
+        if fits(cursor) && self.is_free(cursor, cursor + len, frame, frames_in_flight) {
+            (cursor, enums::SetDataOptions::NoOverwrite)
+        } else if cursor != 0 && fits(0) && self.is_free(0, len, frame, frames_in_flight) {
+            (0, enums::SetDataOptions::NoOverwrite)
+        } else {
+            (0, enums::SetDataOptions::Discard)
+        }
+    }
+
+    /// Records a write. Forgets the history on `Discard` (the driver gives us new memory)
+    fn record(
+        &mut self,
+        start: u32,
+        end: u32,
+        opts: enums::SetDataOptions,
+        frame: u64,
+        frames_in_flight: u32,
+    ) {
+        if opts == enums::SetDataOptions::Discard {
+            self.spans.clear();
+        } else {
+            // spans out of flight or hidden by the new one are no longer needed
+            self.spans.retain(|&(s, e, f)| {
+                f + frames_in_flight as u64 > frame && !(start <= s && e <= end)
+            });
+        }
+        self.spans.push_back((start, end, frame));
+    }
+}
+
+/// Returns `(capacity, byte size)` of a buffer of `capacity` elements of `T`
+fn buffer_layout<T>(capacity: usize) -> Result<(u32, u32)> {
+    let stride = mem::size_of::<T>();
+    let err = ResourceError::BufferTooLarge { capacity, stride };
+    let bytes = capacity.checked_mul(stride).ok_or_else(|| err.clone())?;
+    match (u32::try_from(capacity), u32::try_from(bytes)) {
+        (Ok(capacity), Ok(bytes)) => Ok((capacity, bytes)),
+        _ => Err(err),
+    }
+}
+
+fn check_capacity(capacity: u32, len: usize) -> Result<()> {
+    if len > capacity as usize {
+        Err(ResourceError::BufferOverflow {
+            capacity,
+            requested: len,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Dynamic vertex buffer of `T` that picks the right [`enums::SetDataOptions`]
+///
+/// * [`VertexBuffer::overwrite_all`] replaces the contents with `Discard`. The driver gives us new
+///   memory (orphaning) and doesn't wait for draw calls using the old contents.
+/// * [`VertexBuffer::append`] writes after the previous data with `NoOverwrite`, which is safe
+///   because in-flight draw calls never read the region. When the buffer is full, it's orphaned
+///   with `Discard` and writing restarts from the beginning.
+///
+/// Once the writes at the beginning of the buffer are out of flight (see
+/// [`VertexBuffer::with_frames_in_flight`]), a full buffer is reused with `NoOverwrite` as a ring
+/// instead of being orphaned. Regions that the GPU may still read are never written with
+/// `NoOverwrite`.
+///
+/// `SetDataOptions::None` (which can stall) is never used.
+pub struct VertexBuffer<T> {
+    device: Device,
+    raw: *mut Buffer,
+    /// Capacity in elements
+    capacity: u32,
+    /// End of the written elements
+    cursor: u32,
+    fence: WriteFence,
+    frames_in_flight: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T> fmt::Debug for VertexBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VertexBuffer")
+            .field("raw", &self.raw)
+            .field("capacity", &self.capacity)
+            .field("cursor", &self.cursor)
+            .field("fence", &self.fence)
+            .field("frames_in_flight", &self.frames_in_flight)
+            .finish()
+    }
+}
+
+impl<T> Drop for VertexBuffer<T> {
+    fn drop(&mut self) {
+        self.device.add_dispose_vertex_buffer(self.raw);
+    }
+}
+
+impl<T> VertexBuffer<T> {
+    /// Creates a dynamic, write-only buffer of `capacity` elements
+    ///
+    /// Returns an error if the byte size of the buffer doesn't fit in `u32`.
+    pub fn new(device: &Device, capacity: usize) -> Result<Self> {
+        let (capacity, bytes) = self::buffer_layout::<T>(capacity)?;
+        let raw = device.gen_vertex_buffer(true, enums::BufferUsage::WriteOnly, bytes);
+
+        Ok(Self {
+            device: device.clone(),
+            raw,
+            capacity,
+            cursor: 0,
+            fence: WriteFence::default(),
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Sets the number of frames the GPU may lag behind the CPU ([`DEFAULT_FRAMES_IN_FLIGHT`] by
+    /// default)
+    ///
+    /// Frames are counted with [`Device::frame_count`]. Without `swap_buffers`, the buffer is
+    /// always orphaned when it's full.
+    pub fn with_frames_in_flight(mut self, frames_in_flight: u32) -> Self {
+        self.frames_in_flight = frames_in_flight;
+        self
+    }
+
+    pub fn frames_in_flight(&self) -> u32 {
+        self.frames_in_flight
+    }
+
+    pub fn raw(&self) -> *mut Buffer {
+        self.raw
+    }
+
+    /// Capacity in elements
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// End of the last written elements
+    pub fn len(&self) -> u32 {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cursor == 0
+    }
+
+    /// Replaces the contents with `data` (`SetDataOptions::Discard`)
+    pub fn overwrite_all(&mut self, data: &[T]) -> Result<()> {
+        self::check_capacity(self.capacity, data.len())?;
+
+        let opts = enums::SetDataOptions::Discard;
+        self.device.set_vertex_buffer_data(self.raw, 0, data, opts);
+        self.cursor = data.len() as u32;
+        self.fence.record(
+            0,
+            self.cursor,
+            opts,
+            self.device.frame_count(),
+            self.frames_in_flight,
+        );
+        Ok(())
+    }
+
+    /// Writes `data` after the previously written elements (`SetDataOptions::NoOverwrite`)
+    ///
+    /// Returns the offset of the first written element, i.e. the base vertex for the draw call.
+    /// If the data doesn't fit after the previous elements, writing restarts from the beginning:
+    /// with `NoOverwrite` if the region is out of flight, otherwise orphaning the buffer. The
+    /// offset is `0` then.
+    pub fn append(&mut self, data: &[T]) -> Result<u32> {
+        self::check_capacity(self.capacity, data.len())?;
+
+        let len = data.len() as u32;
+        let frame = self.device.frame_count();
+        let (offset, opts) = self.fence.plan(
+            self.cursor,
+            self.capacity,
+            len,
+            frame,
+            self.frames_in_flight,
+        );
+        let offset_in_bytes = offset * mem::size_of::<T>() as u32;
+
+        self.device
+            .set_vertex_buffer_data(self.raw, offset_in_bytes, data, opts);
+        self.cursor = offset + len;
+        self.fence
+            .record(offset, self.cursor, opts, frame, self.frames_in_flight);
+        Ok(offset)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// IndexBuffer
+
+/// `u16` or `u32`, the index types of FNA3D
+pub trait IndexElement: Copy + private::Sealed {
+    const SIZE: enums::IndexElementSize;
+}
+
+impl IndexElement for u16 {
+    const SIZE: enums::IndexElementSize = enums::IndexElementSize::Bits16;
+}
+
+impl IndexElement for u32 {
+    const SIZE: enums::IndexElementSize = enums::IndexElementSize::Bits32;
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+/// Dynamic index buffer of `u16` or `u32`. Same as [`VertexBuffer`] but for indices
+pub struct IndexBuffer<I: IndexElement> {
+    device: Device,
+    raw: *mut Buffer,
+    /// Capacity in elements
+    capacity: u32,
+    /// End of the written elements
+    cursor: u32,
+    fence: WriteFence,
+    frames_in_flight: u32,
+    _marker: PhantomData<I>,
+}
+
+impl<I: IndexElement> fmt::Debug for IndexBuffer<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexBuffer")
+            .field("raw", &self.raw)
+            .field("capacity", &self.capacity)
+            .field("cursor", &self.cursor)
+            .field("fence", &self.fence)
+            .field("frames_in_flight", &self.frames_in_flight)
+            .finish()
+    }
+}
+
+impl<I: IndexElement> Drop for IndexBuffer<I> {
+    fn drop(&mut self) {
+        self.device.add_dispose_index_buffer(self.raw);
+    }
+}
+
+impl<I: IndexElement> IndexBuffer<I> {
+    /// Creates a dynamic, write-only buffer of `capacity` indices
+    ///
+    /// Returns an error if the byte size of the buffer doesn't fit in `u32`.
+    pub fn new(device: &Device, capacity: usize) -> Result<Self> {
+        let (capacity, bytes) = self::buffer_layout::<I>(capacity)?;
+        let raw = device.gen_index_buffer(true, enums::BufferUsage::WriteOnly, bytes);
+
+        Ok(Self {
+            device: device.clone(),
+            raw,
+            capacity,
+            cursor: 0,
+            fence: WriteFence::default(),
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Sets the number of frames the GPU may lag behind the CPU. See
+    /// [`VertexBuffer::with_frames_in_flight`]
+    pub fn with_frames_in_flight(mut self, frames_in_flight: u32) -> Self {
+        self.frames_in_flight = frames_in_flight;
+        self
+    }
+
+    pub fn frames_in_flight(&self) -> u32 {
+        self.frames_in_flight
+    }
+
+    pub fn raw(&self) -> *mut Buffer {
+        self.raw
+    }
+
+    pub fn element_size(&self) -> enums::IndexElementSize {
+        I::SIZE
+    }
+
+    /// Capacity in elements
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// End of the last written elements
+    pub fn len(&self) -> u32 {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cursor == 0
+    }
+
+    /// Replaces the contents with `data` (`SetDataOptions::Discard`)
+    pub fn overwrite_all(&mut self, data: &[I]) -> Result<()> {
+        self::check_capacity(self.capacity, data.len())?;
+
+        let opts = enums::SetDataOptions::Discard;
+        self.device.set_index_buffer_data(self.raw, 0, data, opts);
+        self.cursor = data.len() as u32;
+        self.fence.record(
+            0,
+            self.cursor,
+            opts,
+            self.device.frame_count(),
+            self.frames_in_flight,
+        );
+        Ok(())
+    }
+
+    /// Writes `data` after the previously written elements (`SetDataOptions::NoOverwrite`)
+    ///
+    /// Returns the offset of the first written element, i.e. the first index for the draw call.
+    /// If the data doesn't fit after the previous elements, writing restarts from the beginning
+    /// like [`VertexBuffer::append`]. The offset is `0` then.
+    pub fn append(&mut self, data: &[I]) -> Result<u32> {
+        self::check_capacity(self.capacity, data.len())?;
+
+        let len = data.len() as u32;
+        let frame = self.device.frame_count();
+        let (offset, opts) = self.fence.plan(
+            self.cursor,
+            self.capacity,
+            len,
+            frame,
+            self.frames_in_flight,
+        );
+        let offset_in_bytes = offset * mem::size_of::<I>() as u32;
+
+        self.device
+            .set_index_buffer_data(self.raw, offset_in_bytes, data, opts);
+        self.cursor = offset + len;
+        self.fence
+            .record(offset, self.cursor, opts, frame, self.frames_in_flight);
+        Ok(offset)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// RenderbufferHandle
+
+/// Color or depth/stencil renderbuffer
+///
+/// [`RenderTarget2d`] creates them for you; use this type to share a depth buffer between
+/// targets or to build custom targets with [`RenderTargetBinding`].
+pub struct RenderbufferHandle {
+    device: Device,
+    raw: *mut Renderbuffer,
+    w: u32,
+    h: u32,
+}
+
+impl fmt::Debug for RenderbufferHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderbufferHandle")
+            .field("raw", &self.raw)
+            .field("w", &self.w)
+            .field("h", &self.h)
+            .finish()
+    }
+}
+
+impl Drop for RenderbufferHandle {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.add_dispose_renderbuffer(&mut *self.raw);
+        }
+    }
+}
+
+impl RenderbufferHandle {
+    /// Multi-sampled color buffer resolved into the `texture`
+    pub fn color(
+        device: &Device,
+        w: u32,
+        h: u32,
+        format: enums::SurfaceFormat,
+        multi_sample_count: u32,
+        texture: *mut Texture,
+    ) -> Self {
+        let raw = device.gen_color_renderbuffer(w, h, format, multi_sample_count, texture);
+        Self::from_raw(device, raw, w, h)
+    }
+
+    pub fn depth_stencil(
+        device: &Device,
+        w: u32,
+        h: u32,
+        format: enums::DepthFormat,
+        multi_sample_count: u32,
+    ) -> Self {
+        let raw = device.gen_depth_stencil_renderbuffer(w, h, format, multi_sample_count as i32);
+        Self::from_raw(device, raw, w, h)
+    }
+
+    fn from_raw(device: &Device, raw: *mut Renderbuffer, w: u32, h: u32) -> Self {
+        Self {
+            device: device.clone(),
+            raw,
+            w,
+            h,
+        }
+    }
+
+    pub fn raw(&self) -> *mut Renderbuffer {
+        self.raw
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.w, self.h)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// QueryHandle
+
+/// Occlusion query
+///
+/// ```no_run
+/// # fn f(device: &fna3d::Device) {
+/// let query = fna3d::res::QueryHandle::new(device);
+/// query.begin();
+/// // draw calls
+/// query.end();
+///
+/// // later (e.g. the next frame)
+/// if let Some(n) = query.pixel_count() {
+///     println!("{} pixels passed", n);
+/// }
+/// # }
+/// ```
+pub struct QueryHandle {
+    device: Device,
+    raw: *mut Query,
+}
+
+impl fmt::Debug for QueryHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryHandle")
+            .field("raw", &self.raw)
+            .finish()
+    }
+}
+
+impl Drop for QueryHandle {
+    fn drop(&mut self) {
+        self.device.add_dispose_query(self.raw);
+    }
+}
+
+impl QueryHandle {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            device: device.clone(),
+            raw: device.create_query(),
+        }
+    }
+
+    pub fn raw(&self) -> *mut Query {
+        self.raw
+    }
+
+    /// Starts counting the pixels written
+    pub fn begin(&self) {
+        self.device.query_begin(self.raw);
+    }
+
+    pub fn end(&self) {
+        self.device.query_end(self.raw);
+    }
+
+    /// If the result is available. Doesn't block
+    pub fn is_complete(&self) -> bool {
+        self.device.query_complete(self.raw)
+    }
+
+    /// Pixels written between [`QueryHandle::begin`] and [`QueryHandle::end`], or `None` if the
+    /// query is not complete yet
+    pub fn pixel_count(&self) -> Option<u32> {
+        if self.is_complete() {
+            Some(self.device.query_pixel_count(self.raw).max(0) as u32)
+        } else {
+            None
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+// EffectHandle
+
+/// Source of [`EffectHandle::id`]
+#[cfg(feature = "mojoshader")]
+static NEXT_EFFECT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Technique of an [`EffectHandle`]
+///
+/// Only valid for the effect it was taken from, which is checked by
+/// [`EffectHandle::set_technique`].
+#[cfg(feature = "mojoshader")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Technique {
+    effect_id: u64,
+    index: u32,
+}
+
+#[cfg(feature = "mojoshader")]
+impl Technique {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+/// Checks if the `technique` can be set to the effect with the `effect_id`
+#[cfg(feature = "mojoshader")]
+fn validate_technique(effect_id: u64, count: u32, technique: Technique) -> Result<()> {
+    if technique.effect_id != effect_id {
+        return Err(ResourceError::ForeignTechnique);
+    }
+
+    if technique.index >= count {
+        return Err(ResourceError::TechniqueOutOfBounds {
+            index: technique.index,
+            count,
+        });
+    }
+
+    Ok(())
+}
+
+/// Effect and its MojoShader data
+///
+/// Techniques are selected with [`Technique`] handles instead of raw pointers, so a technique
+/// pointer of another (or disposed) effect can't be set.
+#[cfg(feature = "mojoshader")]
+pub struct EffectHandle {
+    device: Device,
+    raw: *mut Effect,
+    data: *mut mojo::Effect,
+    /// Unique among effect handles (never reused)
+    id: u64,
+}
+
+#[cfg(feature = "mojoshader")]
+impl fmt::Debug for EffectHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EffectHandle")
+            .field("raw", &self.raw)
+            .field("data", &self.data)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+#[cfg(feature = "mojoshader")]
+impl Drop for EffectHandle {
+    fn drop(&mut self) {
+        // the MojoShader data is disposed with the effect
+        self.device.add_dispose_effect(self.raw);
+    }
+}
+
+#[cfg(feature = "mojoshader")]
+impl EffectHandle {
+    /// Creates an effect from a `fx_2_0` binary and selects the first technique
+    pub fn from_bytes(device: &Device, bytes: &[u8]) -> Result<Self> {
+        let (raw, data) = device.create_effect(bytes as *const _ as *mut _, bytes.len() as u32);
+        if raw.is_null() || data.is_null() {
+            return Err(ResourceError::InvalidEffect(
+                "FNA3D returned null".to_string(),
+            ));
+        }
+
+        let effect = Self::from_raw(device, raw, data);
+
+        let error_count = unsafe { (*data).error_count };
+        if error_count > 0 {
+            // the effect is disposed on drop
+            return Err(ResourceError::InvalidEffect(format!(
+                "{} errors in the effect binary",
+                error_count
+            )));
+        }
+
+        if effect.technique_count() > 0 {
+            effect.set_technique(Technique {
+                effect_id: effect.id,
+                index: 0,
+            })?;
+        }
+
+        Ok(effect)
+    }
+
+    fn from_raw(device: &Device, raw: *mut Effect, data: *mut mojo::Effect) -> Self {
+        Self {
+            device: device.clone(),
+            raw,
+            data,
+            id: NEXT_EFFECT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Creates an independent copy of the effect, e.g. an instance per material
+    ///
+    /// The current technique and parameter values are copied. The clone is a different effect, so
+    /// techniques of `self` have to be re-taken with [`EffectHandle::technique`] (using
+    /// [`Technique::index`]).
+    pub fn clone_instance(&self, device: &Device) -> Result<EffectHandle> {
+        let (raw, data) = device.clone_effect(self.raw);
+        if raw.is_null() || data.is_null() {
+            return Err(ResourceError::InvalidEffect(
+                "FNA3D failed to clone the effect".to_string(),
+            ));
+        }
+
+        Ok(Self::from_raw(device, raw, data))
+    }
+
+    pub fn raw(&self) -> *mut Effect {
+        self.raw
+    }
+
+    /// MojoShader data, e.g. for [`mojo::set_param`]
+    pub fn data(&self) -> *mut mojo::Effect {
+        self.data
+    }
+
+    pub fn technique_count(&self) -> u32 {
+        unsafe { (*self.data).technique_count.max(0) as u32 }
+    }
+
+    /// Technique at the `index` if it's in bounds
+    pub fn technique(&self, index: u32) -> Option<Technique> {
+        if index < self.technique_count() {
+            Some(Technique {
+                effect_id: self.id,
+                index,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Technique with the `name` if any
+    pub fn technique_by_name(&self, name: &str) -> Option<Technique> {
+        (0..self.technique_count()).find_map(|i| {
+            let tech = unsafe { &*(*self.data).techniques.add(i as usize) };
+            if tech.name.is_null() {
+                return None;
+            }
+            let tech_name = unsafe { CStr::from_ptr(tech.name) };
+            if tech_name.to_bytes() == name.as_bytes() {
+                self.technique(i)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Sets the technique used by future [`Device::apply_effect`] calls
+    ///
+    /// Returns an error if the technique was taken from another effect.
+    pub fn set_technique(&self, technique: Technique) -> Result<()> {
+        self::validate_technique(self.id, self.technique_count(), technique)?;
+
+        let tech = unsafe { (*self.data).techniques.add(technique.index as usize) };
+        self.device.set_effect_technique(self.raw, tech);
+        Ok(())
+    }
+}
+
+/// Passes
+#[cfg(feature = "mojoshader")]
+impl EffectHandle {
+    /// Applies the current pass, restoring the previous shader program when the guard is dropped
+    ///
+    /// Wraps [`Device::begin_pass_restore`] and [`Device::end_pass_restore`]. The `state_changes`
+    /// are filled by the effect and borrowed while the guard is alive, as FNA3D requires.
+    ///
+    /// ```no_run
+    /// # fn f(device: &fna3d::Device, effect: &fna3d::res::EffectHandle) {
+    /// let mut state_changes = fna3d::utils::no_change_effect();
+    /// {
+    ///     let _scope = effect.pass_restore_scope(device, &mut state_changes);
+    ///     // draw calls; early returns are fine
+    /// }
+    /// // the previous shader program is restored here
+    /// # }
+    /// ```
+    pub fn pass_restore_scope<'a>(
+        &'a self,
+        device: &'a Device,
+        state_changes: &'a mut mojo::EffectStateChanges,
+    ) -> PassRestoreScope<'a> {
+        device.begin_pass_restore(self.raw, state_changes);
+        PassRestoreScope {
+            device,
+            effect: self,
+            _state_changes: PhantomData,
+        }
+    }
+}
+
+/// Calls [`Device::end_pass_restore`] on drop. Created with [`EffectHandle::pass_restore_scope`]
+#[cfg(feature = "mojoshader")]
+#[derive(Debug)]
+pub struct PassRestoreScope<'a> {
+    device: &'a Device,
+    effect: &'a EffectHandle,
+    /// Written by FNA3D while the pass is applied
+    _state_changes: PhantomData<&'a mut mojo::EffectStateChanges>,
+}
+
+#[cfg(feature = "mojoshader")]
+impl<'a> Drop for PassRestoreScope<'a> {
+    fn drop(&mut self) {
+        self.device.end_pass_restore(self.effect.raw);
+    }
+}
+
+#[cfg(feature = "mojoshader")]
+impl<'a> PassRestoreScope<'a> {
+    pub fn effect(&self) -> &EffectHandle {
+        self.effect
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_region() {
+        let color = enums::SurfaceFormat::Color;
+        assert!(validate_region(color, [64, 32], [60, 0, 4, 32]).is_ok());
+        assert_eq!(
+            validate_region(color, [64, 32], [61, 0, 4, 1]),
+            Err(ResourceError::OutOfBounds {
+                rect: [61, 0, 4, 1],
+                size: [64, 32]
+            })
+        );
+        assert!(validate_region(color, [64, 32], [u32::MAX, 0, 2, 1]).is_err());
+
+        let dxt = enums::SurfaceFormat::Dxt1;
+        assert!(validate_region(dxt, [10, 10], [4, 4, 6, 4]).is_ok());
+        assert!(validate_region(dxt, [10, 10], [2, 0, 4, 4]).is_err());
+        assert_eq!(region_layout(dxt, 10, 10), (3 * 8, 3));
+
+        assert_eq!(level_size(256, 64, 2), (64, 16));
+        assert_eq!(level_size(256, 64, 7), (2, 1));
+        assert_eq!(level_size(256, 64, 40), (1, 1));
+    }
+
+    #[test]
+    fn test_checkerboard_pixels() {
+        let colors = [Color::rgb(255, 0, 255), Color::rgb(0, 0, 0)];
+        let pixels = checkerboard_pixels(16, colors);
+        assert_eq!(pixels.len(), 16 * 16 * 4);
+        // 2x2 pixel cells
+        assert_eq!(&pixels[0..4], &[255, 0, 255, 255]);
+        assert_eq!(&pixels[4 * 4..5 * 4], &[255, 0, 255, 255]);
+        assert_eq!(&pixels[2 * 4..3 * 4], &[0, 0, 0, 255]);
+        assert_eq!(&pixels[(16 * 2) * 4..(16 * 2 + 1) * 4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_resize_policy() {
+        assert_eq!(ResizePolicy::Fixed.size_for((1280, 720)), None);
+        assert_eq!(
+            ResizePolicy::MatchBackbuffer.size_for((1280, 720)),
+            Some((1280, 720))
+        );
+        assert_eq!(
+            ResizePolicy::ScaleOfBackbuffer(0.5).size_for((1280, 721)),
+            Some((640, 361))
+        );
+        assert_eq!(
+            ResizePolicy::ScaleOfBackbuffer(0.25).size_for((2, 0)),
+            Some((1, 1))
+        );
+    }
+
+    #[test]
+    fn test_validate_depth() {
+        use enums::DepthFormat;
+
+        assert!(validate_depth(DepthFormat::None, 0).is_ok());
+        assert!(validate_depth(DepthFormat::None, 3).is_ok());
+        assert!(validate_depth(DepthFormat::D24S8, 0).is_ok());
+        assert!(validate_depth(DepthFormat::D16, 4).is_ok());
+
+        assert_eq!(
+            validate_depth(DepthFormat::D24S8, 3),
+            Err(ResourceError::UnsupportedDepthFormat {
+                depth_format: DepthFormat::D24S8,
+                multi_sample_count: 3,
+            })
+        );
+        assert!(validate_depth(DepthFormat::D24, 1 << 31).is_err());
+    }
+
+    #[test]
+    fn test_write_fence() {
+        use enums::SetDataOptions::{Discard, NoOverwrite};
+        let n = 2;
+        let mut fence = WriteFence::default();
+
+        // frame 0: fill the buffer
+        assert_eq!(fence.plan(0, 8, 6, 0, n), (0, NoOverwrite));
+        fence.record(0, 6, NoOverwrite, 0, n);
+        // the beginning is in flight, so it's orphaned
+        assert_eq!(fence.plan(6, 8, 4, 1, n), (0, Discard));
+        assert_eq!(fence.plan(6, 8, 2, 1, n), (6, NoOverwrite));
+        fence.record(6, 8, NoOverwrite, 1, n);
+
+        // frame 2: the writes of frame 0 are out of flight, so the buffer is reused as a ring
+        assert_eq!(fence.plan(8, 8, 4, 2, n), (0, NoOverwrite));
+        fence.record(0, 4, NoOverwrite, 2, n);
+        // [6, 8) of frame 1 is still in flight
+        assert_eq!(fence.plan(4, 8, 4, 2, n), (0, Discard));
+        assert_eq!(fence.plan(4, 8, 2, 2, n), (4, NoOverwrite));
+
+        fence.record(0, 3, Discard, 2, n);
+        assert_eq!(fence.spans, vec![(0, 3, 2)]);
+    }
+
+    #[test]
+    fn test_buffer_layout() {
+        assert_eq!(buffer_layout::<[f32; 4]>(4), Ok((4, 64)));
+        assert_eq!(
+            buffer_layout::<[f32; 4]>(u32::MAX as usize / 16 + 1),
+            Err(ResourceError::BufferTooLarge {
+                capacity: u32::MAX as usize / 16 + 1,
+                stride: 16
+            })
+        );
+        assert!(buffer_layout::<u16>(usize::MAX).is_err());
+        assert!(buffer_layout::<()>(u32::MAX as usize + 1).is_err());
+    }
+
+    #[test]
+    fn test_validate_yuv_planes() {
+        // 4x3 -> 2x2
+        assert!(validate_yuv_planes([4, 3], [2, 2], 12 + 8).is_ok());
+        assert_eq!(
+            validate_yuv_planes([4, 3], [2, 2], 12 + 4),
+            Err(ResourceError::DataLength {
+                expected: 20,
+                actual: 16
+            })
+        );
+        assert_eq!(
+            validate_yuv_planes([4, 4], [4, 4], 48),
+            Err(ResourceError::YuvSubsampling {
+                y_size: [4, 4],
+                uv_size: [4, 4]
+            })
+        );
+    }
+
+    #[cfg(feature = "mojoshader")]
+    #[test]
+    fn test_validate_technique() {
+        let tech = Technique {
+            effect_id: 1,
+            index: 1,
+        };
+        assert!(validate_technique(1, 2, tech).is_ok());
+        assert_eq!(
+            validate_technique(2, 2, tech),
+            Err(ResourceError::ForeignTechnique)
+        );
+        assert_eq!(
+            validate_technique(1, 1, tech),
+            Err(ResourceError::TechniqueOutOfBounds { index: 1, count: 1 })
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! `Send` wrappers of resource handles (`send` feature)
+//!
+//! FNA3D resources are raw pointers that must only be used on the device thread. Asset pipelines
+//! still want to pass them around, e.g. a loader thread holds on to a texture while decoding its
+//! next mip level. [`SendHandle`] can be sent to other threads but is only "opened" back into the
+//! raw handle on the device thread, which is checked at runtime.
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, texture: *mut fna3d::Texture) {
+//! use fna3d::send::SendTexture;
+//!
+//! let handle = SendTexture::new(device, texture);
+//! let handle = std::thread::spawn(move || {
+//!     // can't be opened here
+//!     handle
+//! })
+//! .join()
+//! .unwrap();
+//!
+//! let texture = handle.open(device).unwrap();
+//! # }
+//! ```
+//!
+//! # Dispose
+//!
+//! A handle doesn't own the resource. Dropping it on another thread leaks the resource, so bring
+//! it back to the device thread and dispose it there.
+
+use std::{
+    fmt,
+    thread::{self, ThreadId},
+};
+
+use crate::fna3d::{fna3d_device::Device, fna3d_structs::*};
+
+pub type SendTexture = SendHandle<Texture>;
+pub type SendBuffer = SendHandle<Buffer>;
+pub type SendRenderbuffer = SendHandle<Renderbuffer>;
+pub type SendEffect = SendHandle<Effect>;
+pub type SendQuery = SendHandle<Query>;
+
+/// Raw resource handle that is `Send`. See the module document
+pub struct SendHandle<T> {
+    raw: *mut T,
+    /// Thread of the device the resource belongs to
+    thread: ThreadId,
+}
+
+// the pointer is never dereferenced except on the device thread
+unsafe impl<T> Send for SendHandle<T> {}
+
+impl<T> fmt::Debug for SendHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendHandle")
+            .field("raw", &self.raw)
+            .field("thread", &self.thread)
+            .finish()
+    }
+}
+
+impl<T> SendHandle<T> {
+    /// Wraps a resource of the `device`
+    pub fn new(device: &Device, raw: *mut T) -> Self {
+        Self {
+            raw,
+            thread: device.thread_id(),
+        }
+    }
+
+    /// Thread where the handle can be opened
+    pub fn device_thread(&self) -> ThreadId {
+        self.thread
+    }
+
+    /// Returns the raw handle if called on the thread of the `device`
+    ///
+    /// The handle is given back in the error otherwise.
+    pub fn open(self, device: &Device) -> Result<*mut T, WrongThread<T>> {
+        if device.thread_id() != self.thread {
+            log::warn!("tried to open a resource handle with a device of another thread");
+        }
+        self.open_on(device.thread_id())
+    }
+
+    fn open_on(self, device_thread: ThreadId) -> Result<*mut T, WrongThread<T>> {
+        let current = thread::current().id();
+        if current == device_thread && current == self.thread {
+            Ok(self.raw)
+        } else {
+            Err(WrongThread {
+                handle: self,
+                current,
+            })
+        }
+    }
+}
+
+/// [`SendHandle::open`] was called outside of the device thread
+pub struct WrongThread<T> {
+    pub handle: SendHandle<T>,
+    pub current: ThreadId,
+}
+
+impl<T> fmt::Debug for WrongThread<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WrongThread")
+            .field("handle", &self.handle)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+impl<T> fmt::Display for WrongThread<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "resource handle of device thread {:?} was opened on thread {:?}",
+            self.handle.thread, self.current
+        )
+    }
+}
+
+impl<T> std::error::Error for WrongThread<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_open_on_device_thread() {
+        let main = thread::current().id();
+        let handle = SendTexture {
+            raw: std::ptr::null_mut(),
+            thread: main,
+        };
+
+        let handle = thread::spawn(move || match handle.open_on(main) {
+            Ok(_) => panic!("opened on another thread"),
+            Err(err) => err.handle,
+        })
+        .join()
+        .unwrap();
+
+        assert!(handle.open_on(main).is_ok());
+    }
+}
@@ -0,0 +1,196 @@
+//! Graphics settings for options menus
+//!
+//! [`GraphicsSettings`] can be (de)serialized with the `serde` feature enabled, so it can be loaded
+//! from a settings file as is.
+//!
+//! ```no_run
+//! # fn run(window: *mut std::ffi::c_void) {
+//! let mut settings = fna3d::settings::GraphicsSettings::default();
+//!
+//! // before creating the window
+//! let flags = settings.prepare_window_attributes();
+//! // .. create the window with the `flags`, then:
+//! let device = fna3d::Device::from_settings(window, &settings);
+//!
+//! // on change
+//! settings.vsync = false;
+//! settings.apply(&device);
+//! # }
+//! ```
+
+use std::os::raw::c_void;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::fna3d::{
+    fna3d_device::Device, fna3d_enums as enums, fna3d_functions as funcs,
+    fna3d_structs::PresentationParameters,
+};
+
+/// Graphics backend of FNA3D
+///
+/// FNA3D selects the backend in [`funcs::prepare_window_attributes`] reading the
+/// `FNA3D_FORCE_DRIVER` environment variable. It can't be changed at runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Backend {
+    /// Let FNA3D select the most suitable one
+    #[default]
+    Auto,
+    OpenGL,
+    Vulkan,
+    D3D11,
+    Metal,
+}
+
+impl Backend {
+    /// Value of `FNA3D_FORCE_DRIVER`
+    pub fn driver_name(&self) -> Option<&'static str> {
+        match self {
+            Backend::Auto => None,
+            Backend::OpenGL => Some("OpenGL"),
+            Backend::Vulkan => Some("Vulkan"),
+            Backend::D3D11 => Some("D3D11"),
+            Backend::Metal => Some("Metal"),
+        }
+    }
+}
+
+/// User-facing subset of [`PresentationParameters`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GraphicsSettings {
+    /// Backbuffer size. `None` to use the drawable size of the window
+    pub resolution: Option<[u32; 2]>,
+    pub vsync: bool,
+    /// Multi-sample count. `0` to disable MSAA. Clamped to the maximum the device supports
+    pub msaa: u32,
+    pub fullscreen: bool,
+    /// Only used on device creation
+    pub backend: Backend,
+    /// Enables debugging and backend validation features. Only used on device creation
+    pub debug: bool,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            resolution: None,
+            vsync: true,
+            msaa: 0,
+            fullscreen: false,
+            backend: Backend::Auto,
+            debug: false,
+        }
+    }
+}
+
+impl GraphicsSettings {
+    /// Sets `FNA3D_FORCE_DRIVER` (if any) and calls [`funcs::prepare_window_attributes`]
+    ///
+    /// Create your window with the returned flags.
+    pub fn prepare_window_attributes(&self) -> funcs::SdlWindowFlags {
+        if let Some(name) = self.backend.driver_name() {
+            std::env::set_var("FNA3D_FORCE_DRIVER", name);
+        }
+        funcs::prepare_window_attributes()
+    }
+
+    /// Creates [`PresentationParameters`] for the window (`SDL_Window*`)
+    pub fn to_params(&self, window: *mut c_void) -> PresentationParameters {
+        let mut params = crate::utils::default_params_from_window_handle(window);
+        self.write_params(&mut params);
+        params
+    }
+
+    fn write_params(&self, params: &mut PresentationParameters) {
+        if let Some([w, h]) = self.resolution {
+            params.backBufferWidth = w as i32;
+            params.backBufferHeight = h as i32;
+        }
+        params.multiSampleCount = self.msaa as i32;
+        params.isFullScreen = self.fullscreen as u8;
+        params.presentationInterval = if self.vsync {
+            enums::PresentInterval::Default as u32
+        } else {
+            enums::PresentInterval::Immediate as u32
+        };
+    }
+
+    /// Applies the settings to the device at runtime (resets the backbuffer)
+    ///
+    /// The `backend` and `debug` fields are ignored. Resize or toggle fullscreen of the OS window
+    /// before calling this.
+    pub fn apply(&self, device: &Device) {
+        let mut params = device.params();
+        self.write_params(&mut params);
+
+        if self.resolution.is_none() {
+            let (w, h) = funcs::get_drawable_size(params.deviceWindowHandle);
+            params.backBufferWidth = w as i32;
+            params.backBufferHeight = h as i32;
+        }
+
+        if params.multiSampleCount > 0 {
+            let fmt = num_traits::FromPrimitive::from_u32(params.backBufferFormat)
+                .unwrap_or(enums::SurfaceFormat::Color);
+            params.multiSampleCount =
+                device.get_max_multi_sample_count(fmt, params.multiSampleCount as u32);
+        }
+
+        device.reset_backbuffer(&params);
+    }
+}
+
+/// Backbuffer formats in order of preference: 10 bit color, then 8 bit color
+pub const DEFAULT_BACKBUFFER_FORMATS: &[(enums::SurfaceFormat, enums::DepthFormat)] = &[
+    (enums::SurfaceFormat::Rgba1010102, enums::DepthFormat::D24S8),
+    (enums::SurfaceFormat::Color, enums::DepthFormat::D24S8),
+];
+
+/// Resets the backbuffer with the first supported `(surface, depth)` format pair
+///
+/// FNA3D can't be asked if a backbuffer format is supported, so each candidate is tried with
+/// [`Device::reset_backbuffer`] and kept if [`Device::backbuffer_info`] reports the same formats
+/// back (backends replace formats they can't create). `SurfaceFormat::Color` with `D24S8` is the
+/// last resort, which every backend supports.
+///
+/// Returns the parameters in use. Call it at startup, not every frame: each try recreates the
+/// backbuffer.
+pub fn negotiate_backbuffer_format(
+    device: &Device,
+    preferences: &[(enums::SurfaceFormat, enums::DepthFormat)],
+) -> PresentationParameters {
+    for &(surface, depth) in preferences {
+        if !surface.is_renderable() {
+            log::warn!("skipping non-renderable backbuffer format {:?}", surface);
+            continue;
+        }
+
+        let mut params = device.params();
+        params.backBufferFormat = surface as u32;
+        params.depthStencilFormat = depth as u32;
+        device.reset_backbuffer(&params);
+
+        let info = device.backbuffer_info();
+        if info.surface_format == surface && info.depth_format == depth {
+            return params;
+        }
+
+        log::info!(
+            "backbuffer format {:?}/{:?} is not supported (got {:?}/{:?}); trying the next one",
+            surface,
+            depth,
+            info.surface_format,
+            info.depth_format
+        );
+    }
+
+    let mut params = device.params();
+    params.backBufferFormat = enums::SurfaceFormat::Color as u32;
+    params.depthStencilFormat = enums::DepthFormat::D24S8 as u32;
+    device.reset_backbuffer(&params);
+    params
+}
@@ -0,0 +1,345 @@
+//! Quad batcher, based on FNA's `SpriteBatch`
+//!
+//! [`SpriteBatch`] collects textured quads and draws them with as few draw calls as possible: one
+//! per run of quads sharing a texture.
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, texture: *mut fna3d::Texture) -> fna3d::res::Result<()> {
+//! use fna3d::{spritebatch::{SortMode, SpriteBatch}, Color};
+//!
+//! let mut batch = SpriteBatch::new(device)?;
+//! batch.set_sort_mode(SortMode::Texture);
+//!
+//! // every frame
+//! batch.set_screen_size(1280, 720);
+//! batch.push(texture, [32.0, 32.0, 64.0, 64.0], [0.0, 0.0, 1.0, 1.0], Color::white());
+//! batch.flush();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Quads are drawn with the [`SPRITE_EFFECT`], the sampler state
+//! of [`SpriteBatch::set_sampler`] and the render states currently set to the device. Like
+//! `SpriteBatch`, textures are expected to have premultiplied alpha, so set
+//! [`BlendState::premultiplied`](crate::BlendState::premultiplied) beforehand.
+
+use std::{fmt, mem};
+
+use crate::{
+    defaults::SPRITE_EFFECT,
+    fna3d::{fna3d_device::Device, fna3d_enums as enums, fna3d_structs::*},
+    mojo::{self, EffectData},
+    res,
+    vertex::{self, VertexLayout},
+    Color,
+};
+
+/// Number of quads drawn with one upload. The batch is flushed when it's full
+pub const MAX_QUADS: usize = 2048;
+
+/// Vertex of [`SpriteBatch`] (the input of `SpriteEffect`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteVertex {
+    /// Position in pixels. `z` is passed to the depth buffer
+    pub pos: [f32; 3],
+    pub color: Color,
+    /// Normalized texture coordinates
+    pub uv: [f32; 2],
+}
+
+impl Default for SpriteVertex {
+    fn default() -> Self {
+        Self {
+            pos: [0.0; 3],
+            color: Color::white(),
+            uv: [0.0; 2],
+        }
+    }
+}
+
+impl VertexLayout for SpriteVertex {
+    const ELEMENTS: &'static [VertexElement] = &[
+        vertex::element(
+            0,
+            enums::VertexElementFormat::Vector3,
+            enums::VertexElementUsage::Position,
+            0,
+        ),
+        vertex::element(
+            12,
+            enums::VertexElementFormat::Color,
+            enums::VertexElementUsage::Color,
+            0,
+        ),
+        vertex::element(
+            16,
+            enums::VertexElementFormat::Vector2,
+            enums::VertexElementUsage::TextureCoordinate,
+            0,
+        ),
+    ];
+}
+
+/// Vertices of a quad: top-left, top-right, bottom-left and bottom-right
+pub type Quad = [SpriteVertex; 4];
+
+/// Quad from destination and UV rectangles (`[x, y, w, h]`)
+pub fn quad(dst: [f32; 4], uv: [f32; 4], color: Color) -> Quad {
+    let [x, y, w, h] = dst;
+    let [u, v, uw, vh] = uv;
+    let vtx = |pos: [f32; 2], uv: [f32; 2]| SpriteVertex {
+        pos: [pos[0], pos[1], 0.0],
+        color,
+        uv,
+    };
+
+    [
+        vtx([x, y], [u, v]),
+        vtx([x + w, y], [u + uw, v]),
+        vtx([x, y + h], [u, v + vh]),
+        vtx([x + w, y + h], [u + uw, v + vh]),
+    ]
+}
+
+/// When and in which order quads are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortMode {
+    /// Drawn on flush in push order
+    #[default]
+    Deferred,
+    /// Drawn on every push. Slow, but render states can be changed between quads
+    Immediate,
+    /// Drawn on flush, sorted by texture (stable). Minimizes draw calls for opaque sprites
+    Texture,
+}
+
+/// Batches textured quads. See the module document
+pub struct SpriteBatch {
+    device: Device,
+    effect: EffectData,
+    vbuf: res::VertexBuffer<Quad>,
+    ibuf: res::IndexBuffer<u16>,
+    quads: Vec<Quad>,
+    /// Texture of each quad
+    textures: Vec<*mut Texture>,
+    /// Reused buffers for sorting
+    order: Vec<usize>,
+    sorted_quads: Vec<Quad>,
+    sorted_textures: Vec<*mut Texture>,
+    sort_mode: SortMode,
+    sampler: SamplerState,
+}
+
+impl fmt::Debug for SpriteBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpriteBatch")
+            .field("effect", &self.effect)
+            .field("vbuf", &self.vbuf)
+            .field("ibuf", &self.ibuf)
+            .field("n_quads", &self.quads.len())
+            .field("sort_mode", &self.sort_mode)
+            .field("sampler", &self.sampler)
+            .finish()
+    }
+}
+
+impl SpriteBatch {
+    /// Allocates buffers for [`MAX_QUADS`] and loads the sprite effect. The projection is set to
+    /// the backbuffer size
+    pub fn new(device: &Device) -> res::Result<Self> {
+        let mut effect = EffectData::from_bytes(device, SPRITE_EFFECT)?;
+        let (w, h) = device.get_backbuffer_size();
+        let proj = mojo::pixel_projection(w, h, &device.clip_space_info());
+        effect
+            .set_matrix("MatrixTransform", &proj)
+            .map_err(|e| res::ResourceError::InvalidEffect(e.to_string()))?;
+
+        let vbuf = res::VertexBuffer::new(device, MAX_QUADS)?;
+
+        let indices = self::quad_indices(MAX_QUADS);
+        let mut ibuf = res::IndexBuffer::new(device, indices.len())?;
+        ibuf.overwrite_all(&indices)?;
+
+        Ok(Self {
+            device: device.clone(),
+            effect,
+            vbuf,
+            ibuf,
+            quads: Vec::with_capacity(MAX_QUADS),
+            textures: Vec::with_capacity(MAX_QUADS),
+            order: Vec::with_capacity(MAX_QUADS),
+            sorted_quads: Vec::with_capacity(MAX_QUADS),
+            sorted_textures: Vec::with_capacity(MAX_QUADS),
+            sort_mode: SortMode::default(),
+            sampler: SamplerState::default(),
+        })
+    }
+
+    pub fn sort_mode(&self) -> SortMode {
+        self.sort_mode
+    }
+
+    /// Flushes the batch and sets the sort mode
+    pub fn set_sort_mode(&mut self, mode: SortMode) {
+        self.flush();
+        self.sort_mode = mode;
+    }
+
+    pub fn sampler(&self) -> &SamplerState {
+        &self.sampler
+    }
+
+    /// Flushes the batch and sets the sampler state of the texture slot `0`
+    pub fn set_sampler(&mut self, sampler: SamplerState) {
+        self.flush();
+        self.sampler = sampler;
+    }
+
+    /// Flushes the batch and sets the projection matrix (column-major)
+    pub fn set_transform(&mut self, mat: &[f32; 16]) {
+        self.flush();
+        // `SpriteEffect` has `MatrixTransform`
+        self.effect.set_matrix("MatrixTransform", mat).unwrap();
+    }
+
+    /// [`SpriteBatch::set_transform`] with [`mojo::pixel_projection`] (origin at top-left)
+    pub fn set_screen_size(&mut self, w: u32, h: u32) {
+        let proj = mojo::pixel_projection(w, h, &self.device.clip_space_info());
+        self.set_transform(&proj);
+    }
+
+    /// Number of quads waiting for [`SpriteBatch::flush`]
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    /// Pushes a quad from destination and UV rectangles (`[x, y, w, h]`)
+    pub fn push(&mut self, texture: *mut Texture, dst: [f32; 4], uv: [f32; 4], color: Color) {
+        self.push_quad(texture, &self::quad(dst, uv, color));
+    }
+
+    /// Pushes a quad of vertices in the order of [`Quad`]
+    pub fn push_quad(&mut self, texture: *mut Texture, quad: &Quad) {
+        if self.quads.len() >= MAX_QUADS {
+            self.flush();
+        }
+
+        self.quads.push(*quad);
+        self.textures.push(texture);
+
+        if self.sort_mode == SortMode::Immediate {
+            self.flush();
+        }
+    }
+
+    /// Draws the pushed quads
+    pub fn flush(&mut self) {
+        if self.quads.is_empty() {
+            return;
+        }
+
+        if self.sort_mode == SortMode::Texture {
+            self.sort_by_texture();
+        }
+
+        // `push_quad` keeps the quads within `MAX_QUADS`
+        self.vbuf.overwrite_all(&self.quads).unwrap();
+
+        self.effect.apply_pass(&self.device, 0);
+
+        let binding = VertexBufferBinding {
+            vertexBuffer: self.vbuf.raw(),
+            vertexDeclaration: SpriteVertex::DECLARATION,
+            vertexOffset: 0,
+            instanceFrequency: 0,
+        };
+
+        for (lo, hi) in self::texture_runs(&self.textures) {
+            let n_quads = (hi - lo) as u32;
+            let base_vtx = 4 * lo as u32;
+
+            self.device
+                .verify_sampler(0, self.textures[lo], &self.sampler);
+            self.device
+                .apply_vertex_buffer_bindings(&[binding], true, base_vtx);
+            self.device.draw_indexed_primitives(
+                enums::PrimitiveType::TriangleList,
+                base_vtx,
+                0,
+                4 * n_quads,
+                0,
+                2 * n_quads,
+                self.ibuf.raw(),
+                self.ibuf.element_size(),
+            );
+        }
+
+        self.quads.clear();
+        self.textures.clear();
+    }
+
+    fn sort_by_texture(&mut self) {
+        let (quads, textures) = (&self.quads, &self.textures);
+        self.order.clear();
+        self.order.extend(0..quads.len());
+        self.order.sort_by_key(|&i| textures[i] as usize);
+
+        self.sorted_quads.clear();
+        self.sorted_quads
+            .extend(self.order.iter().map(|&i| quads[i]));
+        self.sorted_textures.clear();
+        self.sorted_textures
+            .extend(self.order.iter().map(|&i| textures[i]));
+        mem::swap(&mut self.quads, &mut self.sorted_quads);
+        mem::swap(&mut self.textures, &mut self.sorted_textures);
+    }
+}
+
+/// `[lo, hi)` quad spans sharing a texture
+fn texture_runs(textures: &[*mut Texture]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut lo = 0;
+    for i in 1..=textures.len() {
+        if i == textures.len() || textures[i] != textures[lo] {
+            runs.push((lo, i));
+            lo = i;
+        }
+    }
+    runs
+}
+
+/// Two triangles per quad, for vertices in the order of [`Quad`]
+fn quad_indices(n_quads: usize) -> Vec<u16> {
+    (0..n_quads as u16)
+        .flat_map(|q| {
+            let v = q * 4;
+            [v, v + 1, v + 2, v + 3, v + 2, v + 1]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sprite_batch_layout() {
+        assert_eq!(mem::size_of::<SpriteVertex>(), 24);
+        assert_eq!(quad_indices(2), vec![0, 1, 2, 3, 2, 1, 4, 5, 6, 7, 6, 5]);
+        // the last vertex fits in `u16`
+        assert!(4 * MAX_QUADS <= u16::MAX as usize + 1);
+
+        let q = quad([1.0, 2.0, 3.0, 4.0], [0.0, 0.0, 0.5, 1.0], Color::white());
+        assert_eq!(q[3].pos, [4.0, 6.0, 0.0]);
+        assert_eq!(q[1].uv, [0.5, 0.0]);
+
+        let (a, b) = (8 as *mut Texture, 16 as *mut Texture);
+        assert_eq!(texture_runs(&[a, a, b, a]), vec![(0, 2), (2, 3), (3, 4)]);
+        assert!(texture_runs(&[]).is_empty());
+    }
+}
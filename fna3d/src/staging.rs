@@ -0,0 +1,151 @@
+//! Reused CPU buffer for uploads
+//!
+//! Data that has to be converted before an upload (font atlases expanded to RGBA, rows re-packed
+//! from a larger image, ..) is written into a [`StagingBelt`] instead of a new `Vec` every time.
+//! The belt only grows, so after the first few frames uploads don't allocate:
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device, texture: *mut fna3d::Texture, alpha: &[u8], w: u32, h: u32) {
+//! use fna3d::staging::StagingBelt;
+//!
+//! let mut belt = StagingBelt::new();
+//! // every frame
+//! belt.clear();
+//! for &a in alpha {
+//!     belt.push(&[a, a, a, a]);
+//! }
+//! device.set_texture_data_2d(texture, 0, 0, w, h, 0, belt.as_bytes());
+//! # }
+//! ```
+//!
+//! [`StagingBelt::grow_count`] and [`StagingBelt::bytes_written`] tell how much it allocates and
+//! copies.
+//!
+//! Data that is already in the upload format doesn't need a belt. For example, the `fna3d-imgui`
+//! renderer uploads the vertex/index slices of ImGUI draw lists and the RGBA32 font atlas as they
+//! are.
+
+use std::{fmt, mem, slice};
+
+/// Grow-only byte buffer with typed appends. See the module document
+#[derive(Clone, Default)]
+pub struct StagingBelt {
+    buf: Vec<u8>,
+    /// Number of reallocations
+    grow_count: u32,
+    /// Bytes appended since creation
+    bytes_written: u64,
+}
+
+impl fmt::Debug for StagingBelt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StagingBelt")
+            .field("len", &self.buf.len())
+            .field("capacity", &self.buf.capacity())
+            .field("grow_count", &self.grow_count)
+            .field("bytes_written", &self.bytes_written)
+            .finish()
+    }
+}
+
+impl StagingBelt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates `bytes` up front
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(bytes),
+            ..Default::default()
+        }
+    }
+
+    /// Empties the belt, keeping the memory
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Reserves space for `additional` more bytes, counting a reallocation if needed
+    pub fn reserve(&mut self, additional: usize) {
+        if self.buf.len() + additional > self.buf.capacity() {
+            self.buf.reserve(additional);
+            self.grow_count += 1;
+        }
+    }
+
+    /// Appends the bytes of a value
+    ///
+    /// `T` should be a plain type without padding (pixels, vertices, indices), like the data
+    /// given to `Device::set_vertex_buffer_data`.
+    pub fn push<T: Copy>(&mut self, value: &T) {
+        self.extend(slice::from_ref(value));
+    }
+
+    /// Appends the bytes of values. See [`StagingBelt::push`]
+    pub fn extend<T: Copy>(&mut self, values: &[T]) {
+        let bytes = unsafe {
+            slice::from_raw_parts(values.as_ptr() as *const u8, mem::size_of_val(values))
+        };
+        self.extend_bytes(bytes);
+    }
+
+    pub fn extend_bytes(&mut self, bytes: &[u8]) {
+        self.reserve(bytes.len());
+        self.buf.extend_from_slice(bytes);
+        self.bytes_written += bytes.len() as u64;
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Number of times the belt reallocated
+    pub fn grow_count(&self) -> u32 {
+        self.grow_count
+    }
+
+    /// Total bytes appended since creation
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_staging_belt() {
+        let mut belt = StagingBelt::new();
+        belt.push(&[1u8, 2, 3, 4]);
+        assert_eq!(belt.grow_count(), 1);
+        belt.extend(&[0x0605u16]);
+        assert_eq!(&belt.as_bytes()[0..4], &[1, 2, 3, 4]);
+        assert_eq!(belt.len(), 6);
+
+        // reused without reallocation
+        belt.clear();
+        belt.reserve(6);
+        let grows = belt.grow_count();
+        belt.extend_bytes(&[0; 6]);
+        assert_eq!(belt.grow_count(), grows);
+        assert_eq!(belt.bytes_written(), 12);
+
+        let mut belt = StagingBelt::with_capacity(8);
+        belt.extend(&[0.0f32, 1.0]);
+        assert_eq!(belt.grow_count(), 0);
+    }
+}
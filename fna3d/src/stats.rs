@@ -0,0 +1,206 @@
+//! Rendering statistics
+//!
+//! [`crate::Device`] counts draw calls by itself ([`crate::Device::draw_stats`]).
+//! [`PassStatsRecorder`] splits them into named passes and optionally counts the pixels written in
+//! each pass with an occlusion query spanning the pass.
+//!
+//! # Overdraw estimation
+//!
+//! FNA3D has no timestamp queries, but pixel counts are a cheap hint of fill rate: divide the
+//! pixel count of a pass by the number of pixels of the render target to get the average overdraw
+//! of the pass.
+//!
+//! ```no_run
+//! # fn f(device: &fna3d::Device) {
+//! use fna3d::stats::PassStatsRecorder;
+//!
+//! let mut recorder = PassStatsRecorder::new(device, true);
+//!
+//! // every frame
+//! recorder.begin_pass("sprites");
+//! // draw calls
+//! recorder.end_pass();
+//!
+//! // results of previous frames, without waiting for the GPU
+//! for pass in recorder.collect() {
+//!     log::debug!("{}: {:?} {:?}", pass.name, pass.draws, pass.pixels);
+//! }
+//! # }
+//! ```
+
+use crate::fna3d::{fna3d_device::Device, fna3d_structs::Query};
+
+/// Draw calls and primitives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawStats {
+    pub draw_calls: u32,
+    /// Primitives (e.g. triangles) of all the draw calls, including instances
+    pub primitives: u64,
+}
+
+impl DrawStats {
+    /// Difference from an earlier snapshot
+    pub fn since(&self, before: &Self) -> Self {
+        Self {
+            draw_calls: self.draw_calls.wrapping_sub(before.draw_calls),
+            primitives: self.primitives.wrapping_sub(before.primitives),
+        }
+    }
+}
+
+/// Statistics of a pass recorded with [`PassStatsRecorder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassStats {
+    pub name: &'static str,
+    pub draws: DrawStats,
+    /// Pixels written in the pass if pixel counting is enabled
+    pub pixels: Option<u32>,
+}
+
+/// Pass waiting for its query
+#[derive(Debug)]
+struct PendingPass {
+    name: &'static str,
+    draws: DrawStats,
+    query: *mut Query,
+}
+
+/// Records per-pass [`PassStats`]. See the module document
+#[derive(Debug)]
+pub struct PassStatsRecorder {
+    device: Device,
+    count_pixels: bool,
+    /// `(name, snapshot, query)` of the current pass
+    current: Option<(&'static str, DrawStats, *mut Query)>,
+    pending: Vec<PendingPass>,
+    /// Finished passes (waiting for [`PassStatsRecorder::collect`])
+    done: Vec<PassStats>,
+    /// Reused queries
+    pool: Vec<*mut Query>,
+}
+
+impl Drop for PassStatsRecorder {
+    fn drop(&mut self) {
+        let current = self.current.take().map(|(_, _, q)| q);
+        let pending = self.pending.drain(..).map(|p| p.query);
+        for query in current
+            .into_iter()
+            .chain(pending)
+            .chain(self.pool.drain(..))
+        {
+            if !query.is_null() {
+                self.device.add_dispose_query(query);
+            }
+        }
+    }
+}
+
+impl PassStatsRecorder {
+    /// * `count_pixels`: Wrap each pass with an occlusion query
+    pub fn new(device: &Device, count_pixels: bool) -> Self {
+        Self {
+            device: device.clone(),
+            count_pixels,
+            current: None,
+            pending: Vec::new(),
+            done: Vec::new(),
+            pool: Vec::new(),
+        }
+    }
+
+    pub fn counts_pixels(&self) -> bool {
+        self.count_pixels
+    }
+
+    /// Starts a pass. The previous pass is ended if it's not yet
+    pub fn begin_pass(&mut self, name: &'static str) {
+        if self.current.is_some() {
+            log::warn!("pass stats: `begin_pass` without `end_pass`");
+            self.end_pass();
+        }
+
+        let query = if self.count_pixels {
+            let query = self
+                .pool
+                .pop()
+                .unwrap_or_else(|| self.device.create_query());
+            self.device.query_begin(query);
+            query
+        } else {
+            std::ptr::null_mut()
+        };
+
+        self.current = Some((name, self.device.draw_stats(), query));
+    }
+
+    /// Ends the current pass
+    pub fn end_pass(&mut self) {
+        let (name, before, query) = match self.current.take() {
+            Some(current) => current,
+            None => {
+                log::warn!("pass stats: `end_pass` without `begin_pass`");
+                return;
+            }
+        };
+
+        let draws = self.device.draw_stats().since(&before);
+        if query.is_null() {
+            self.done.push(PassStats {
+                name,
+                draws,
+                pixels: None,
+            });
+        } else {
+            self.device.query_end(query);
+            self.pending.push(PendingPass { name, draws, query });
+        }
+    }
+
+    /// Returns finished passes in order
+    ///
+    /// Pixel counts are read only if the GPU is done with them, so this never stalls. Passes
+    /// still in flight are returned by later calls.
+    pub fn collect(&mut self) -> Vec<PassStats> {
+        let n_ready = self
+            .pending
+            .iter()
+            .take_while(|p| self.device.query_complete(p.query))
+            .count();
+
+        for pass in self.pending.drain(..n_ready) {
+            let pixels = self.device.query_pixel_count(pass.query).max(0) as u32;
+            self.pool.push(pass.query);
+            self.done.push(PassStats {
+                name: pass.name,
+                draws: pass.draws,
+                pixels: Some(pixels),
+            });
+        }
+
+        std::mem::take(&mut self.done)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_draw_stats_since() {
+        let before = DrawStats {
+            draw_calls: 3,
+            primitives: 100,
+        };
+        let after = DrawStats {
+            draw_calls: 5,
+            primitives: 160,
+        };
+        assert_eq!(
+            after.since(&before),
+            DrawStats {
+                draw_calls: 2,
+                primitives: 60
+            }
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Vertex declarations from struct definitions
+//!
+//! [`VertexLayout`] provides the [`VertexDeclaration`] of a vertex type. Derive it (with the
+//! `derive` feature) instead of computing offsets and strides by hand:
+//!
+//! ```ignore
+//! use fna3d::{vertex::VertexLayout, Color};
+//!
+//! #[repr(C)]
+//! #[derive(Debug, Clone, Copy, fna3d::vertex::Vertex)]
+//! pub struct MyVertex {
+//!     #[vertex(Position)]
+//!     pub pos: [f32; 3],
+//!     #[vertex(Color)]
+//!     pub color: Color,
+//!     #[vertex(TextureCoordinate, 0)]
+//!     pub uv: [f32; 2],
+//! }
+//!
+//! let binding = fna3d::VertexBufferBinding {
+//!     vertexBuffer: vbuf,
+//!     vertexDeclaration: MyVertex::DECLARATION,
+//!     vertexOffset: 0,
+//!     instanceFrequency: 0,
+//! };
+//! ```
+//!
+//! Each field takes `#[vertex(<VertexElementUsage>)]` or `#[vertex(<VertexElementUsage>, <usage
+//! index>)]`. The element format is decided by the field type ([`VertexAttribute`]). The struct
+//! has to be `#[repr(C)]` so that the field order is kept.
+
+use std::mem;
+
+use crate::{
+    fna3d::{fna3d_enums as enums, fna3d_structs::*},
+    Color,
+};
+
+#[cfg(feature = "derive")]
+pub use fna3d_derive::Vertex;
+
+/// Field type of a vertex, mapped to a [`enums::VertexElementFormat`]
+pub trait VertexAttribute {
+    const FORMAT: enums::VertexElementFormat;
+}
+
+macro_rules! impl_attribute {
+    ($($ty:ty => $format:ident,)*) => {
+        $(
+            impl VertexAttribute for $ty {
+                const FORMAT: enums::VertexElementFormat = enums::VertexElementFormat::$format;
+            }
+        )*
+    };
+}
+
+impl_attribute!(
+    f32 => Single,
+    [f32; 2] => Vector2,
+    [f32; 3] => Vector3,
+    [f32; 4] => Vector4,
+    Color => Color,
+    [u8; 4] => Byte4,
+    [i16; 2] => Short2,
+    [i16; 4] => Short4,
+);
+
+/// Vertex type with a [`VertexDeclaration`]
+///
+/// Implement [`VertexLayout::ELEMENTS`] (or derive it); the declaration is made from it.
+pub trait VertexLayout: Sized {
+    /// Elements in the field order, with byte offsets
+    const ELEMENTS: &'static [VertexElement];
+
+    const DECLARATION: VertexDeclaration = VertexDeclaration {
+        vertexStride: mem::size_of::<Self>() as i32,
+        elementCount: Self::ELEMENTS.len() as i32,
+        elements: Self::ELEMENTS.as_ptr() as *mut _,
+    };
+}
+
+/// Element of a field, for implementing [`VertexLayout`] by hand
+pub const fn element(
+    offset: usize,
+    format: enums::VertexElementFormat,
+    usage: enums::VertexElementUsage,
+    usage_index: u32,
+) -> VertexElement {
+    VertexElement {
+        offset: offset as i32,
+        vertexElementFormat: format as u32,
+        vertexElementUsage: usage as u32,
+        usageIndex: usage_index as i32,
+    }
+}
@@ -0,0 +1,80 @@
+//! SDL2 window helpers (`sdl2` feature)
+//!
+//! Complements [`crate::prepare_window_attributes`]: creates windows, enumerates display modes
+//! and sets up exclusive fullscreen.
+//!
+//! ```no_run
+//! # fn run(window: &mut sdl2::video::Window) -> Result<(), String> {
+//! let modes = fna3d::win::display_modes(0)?;
+//! // e.g. let the user choose one in the options menu
+//! let params = fna3d::win::exclusive_fullscreen_params(window, &modes[0])?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use sdl2::video::DisplayMode;
+
+use sdl2::{sys, video::FullscreenType};
+
+use crate::fna3d::{fna3d_functions::SdlWindowFlags, fna3d_structs::PresentationParameters};
+
+/// Creates a centered window with the flags, e.g. [`crate::prepare_window_attributes`] OR-ed with
+/// your own ones
+///
+/// The flags are validated first.
+pub fn create_window(
+    video: &sdl2::VideoSubsystem,
+    title: &str,
+    size: (u32, u32),
+    flags: SdlWindowFlags,
+) -> Result<sdl2::video::Window, String> {
+    flags.validate().map_err(|e| e.to_string())?;
+
+    video
+        .window(title, size.0, size.1)
+        .set_window_flags(flags.bits())
+        .position_centered()
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Display modes (resolution, refresh rate and pixel format) of the display
+///
+/// SDL sorts them from the largest resolution and highest refresh rate. The SDL video subsystem
+/// has to be initialized.
+pub fn display_modes(display_index: i32) -> Result<Vec<DisplayMode>, String> {
+    let n = unsafe { sys::SDL_GetNumDisplayModes(display_index) };
+    if n < 0 {
+        return Err(sdl2::get_error());
+    }
+
+    (0..n)
+        .map(|i| {
+            let mut raw = std::mem::MaybeUninit::uninit();
+            let res = unsafe { sys::SDL_GetDisplayMode(display_index, i, raw.as_mut_ptr()) };
+            if res == 0 {
+                Ok(DisplayMode::from_ll(unsafe { &raw.assume_init() }))
+            } else {
+                Err(sdl2::get_error())
+            }
+        })
+        .collect()
+}
+
+/// Switches the window to exclusive fullscreen at the `mode` and returns the parameters for it
+///
+/// Pass the parameters to [`crate::Device::reset_backbuffer`] (or
+/// [`crate::Device::from_params`] before creating the device).
+pub fn exclusive_fullscreen_params(
+    window: &mut sdl2::video::Window,
+    mode: &DisplayMode,
+) -> Result<PresentationParameters, String> {
+    window.set_display_mode(*mode)?;
+    window.set_fullscreen(FullscreenType::True)?;
+
+    let mut params = crate::utils::default_params_from_window_handle(window.raw() as *mut _);
+    params.backBufferWidth = mode.w;
+    params.backBufferHeight = mode.h;
+    params.isFullScreen = 1;
+    Ok(params)
+}
@@ -0,0 +1,145 @@
+//! Driving FNA3D with a [winit] window (`winit` feature)
+//!
+//! FNA3D only renders to SDL windows (`deviceWindowHandle` is an `SDL_Window*`). This module wraps
+//! the native window of winit with `SDL_CreateWindowFrom`, so that FNA3D can render to it while
+//! winit owns the window and the event loop.
+//!
+//! # Path
+//!
+//! 1. Call [`crate::prepare_window_attributes`] to select the backend, before anything else.
+//! 2. Create the winit window.
+//! 3. Call [`presentation_params`] and create the device with [`crate::Device::from_params`].
+//!    Keep the returned [`ForeignWindow`] alive while the device is used.
+//! 4. On `WindowEvent::Resized`, reset the backbuffer with the new physical size.
+//! 5. Present with `device.swap_buffers(None, None, foreign.raw())`.
+//!
+//! # Backend support
+//!
+//! * OpenGL: Windows and X11. Requires SDL 2.0.16+ for `SDL_VIDEO_FOREIGN_WINDOW_OPENGL`, which is
+//!   set by this module.
+//! * Vulkan: Windows and X11 with SDL 2.0.16+ (`SDL_VIDEO_FOREIGN_WINDOW_VULKAN`).
+//! * Metal: macOS (`NSWindow`).
+//! * Wayland: not supported by `SDL_CreateWindowFrom`. Use SDL for windowing there.
+//!
+//! Input still comes from winit; SDL doesn't pump events of foreign windows.
+//!
+//! [winit]: https://github.com/rust-windowing/winit
+
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_void},
+};
+
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+use crate::fna3d::fna3d_structs::PresentationParameters;
+
+#[link(name = "SDL2")]
+extern "C" {
+    fn SDL_InitSubSystem(flags: u32) -> c_int;
+    fn SDL_QuitSubSystem(flags: u32);
+    fn SDL_SetHint(name: *const c_char, value: *const c_char) -> c_int;
+    fn SDL_CreateWindowFrom(data: *const c_void) -> *mut c_void;
+    fn SDL_DestroyWindow(window: *mut c_void);
+    fn SDL_GetError() -> *const c_char;
+}
+
+const SDL_INIT_VIDEO: u32 = 0x0000_0020;
+
+fn sdl_error() -> String {
+    unsafe { CStr::from_ptr(SDL_GetError()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// `SDL_Window*` wrapping a native window of winit. Destroyed on drop
+///
+/// It doesn't own the native window; drop it before the winit window (and after the device).
+#[derive(Debug)]
+pub struct ForeignWindow {
+    raw: *mut c_void,
+}
+
+impl Drop for ForeignWindow {
+    fn drop(&mut self) {
+        unsafe {
+            SDL_DestroyWindow(self.raw);
+            SDL_QuitSubSystem(SDL_INIT_VIDEO);
+        }
+    }
+}
+
+impl ForeignWindow {
+    /// Wraps the native window with `SDL_CreateWindowFrom`
+    pub fn new(window: &winit::window::Window) -> Result<Self, String> {
+        let native = self::native_handle(window.raw_window_handle())?;
+
+        unsafe {
+            if SDL_InitSubSystem(SDL_INIT_VIDEO) != 0 {
+                return Err(self::sdl_error());
+            }
+
+            // let SDL set up the pixel format / surface for the backend
+            for hint in [
+                &b"SDL_VIDEO_FOREIGN_WINDOW_OPENGL\0"[..],
+                &b"SDL_VIDEO_FOREIGN_WINDOW_VULKAN\0"[..],
+            ]
+            .iter()
+            {
+                SDL_SetHint(hint.as_ptr() as *const _, b"1\0".as_ptr() as *const _);
+            }
+
+            let raw = SDL_CreateWindowFrom(native);
+            if raw.is_null() {
+                let err = self::sdl_error();
+                SDL_QuitSubSystem(SDL_INIT_VIDEO);
+                return Err(err);
+            }
+
+            Ok(Self { raw })
+        }
+    }
+
+    /// `SDL_Window*` for `deviceWindowHandle` and [`crate::Device::swap_buffers`]
+    pub fn raw(&self) -> *mut c_void {
+        self.raw
+    }
+}
+
+/// Native window pointer accepted by `SDL_CreateWindowFrom`
+fn native_handle(handle: RawWindowHandle) -> Result<*const c_void, String> {
+    match handle {
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Windows(h) => Ok(h.hwnd as *const c_void),
+        #[cfg(target_os = "macos")]
+        RawWindowHandle::MacOS(h) => Ok(h.ns_window as *const c_void),
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        RawWindowHandle::Xlib(h) => Ok(h.window as usize as *const c_void),
+        _ => Err(format!(
+            "unsupported window handle for SDL_CreateWindowFrom: {:?}",
+            handle
+        )),
+    }
+}
+
+/// Creates a [`ForeignWindow`] and [`PresentationParameters`] for the winit window
+///
+/// The backbuffer is sized to the physical (pixel) size of the window.
+pub fn presentation_params(
+    window: &winit::window::Window,
+) -> Result<(PresentationParameters, ForeignWindow), String> {
+    let foreign = ForeignWindow::new(window)?;
+
+    let mut params = crate::utils::default_params_from_window_handle(foreign.raw());
+    let size = window.inner_size();
+    params.backBufferWidth = size.width as i32;
+    params.backBufferHeight = size.height as i32;
+
+    Ok((params, foreign))
+}
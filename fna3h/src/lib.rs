@@ -19,10 +19,12 @@ pub mod tex {
 pub mod buf {
     //! GPU buffer
     //!
-    //! TODO: provide with `derive` macro for vertices
+    //! Vertex declarations can be derived with `fna3d`'s `derive` feature. See [`fna3d::vertex`].
 
     pub use fna3d::{Buffer, BufferUsage, SetDataOptions};
 
+    pub use fna3d::vertex::{VertexAttribute, VertexLayout};
+
     pub use fna3d::{
         IndexElementSize, VertexDeclaration, VertexElement, VertexElementFormat, VertexElementUsage,
     };